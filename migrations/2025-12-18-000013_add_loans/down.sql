@@ -0,0 +1,3 @@
+-- Drop loans and borrowers tables
+DROP TABLE IF EXISTS loans;
+DROP TABLE IF EXISTS borrowers;
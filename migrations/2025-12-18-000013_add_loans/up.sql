@@ -0,0 +1,29 @@
+-- Add borrowers and loans tables for tracking who has taken files/items out
+CREATE TABLE IF NOT EXISTS borrowers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    department TEXT NOT NULL DEFAULT '',
+    contact TEXT NOT NULL DEFAULT '',
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS loans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    borrower_id INTEGER NOT NULL,
+    file_id INTEGER,
+    item_id INTEGER,
+    purpose TEXT NOT NULL DEFAULT '',
+    loan_date TEXT NOT NULL,
+    due_date TEXT NOT NULL,
+    return_date TEXT,
+    approval_status TEXT NOT NULL DEFAULT 'pending',
+    approved_by TEXT NOT NULL DEFAULT '',
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (borrower_id) REFERENCES borrowers(id),
+    FOREIGN KEY (file_id) REFERENCES files(id),
+    FOREIGN KEY (item_id) REFERENCES items(id)
+);
@@ -0,0 +1,3 @@
+-- Remove hash chain columns from audit_log
+ALTER TABLE audit_log DROP COLUMN hash;
+ALTER TABLE audit_log DROP COLUMN prev_hash;
@@ -0,0 +1,3 @@
+-- Chain audit_log entries: each entry stores the hash of the previous one
+ALTER TABLE audit_log ADD COLUMN prev_hash TEXT NOT NULL DEFAULT '';
+ALTER TABLE audit_log ADD COLUMN hash TEXT NOT NULL DEFAULT '';
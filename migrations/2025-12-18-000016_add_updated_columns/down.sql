@@ -0,0 +1,40 @@
+-- Remove last modification columns
+ALTER TABLE fond_classifications DROP COLUMN updated_machine;
+ALTER TABLE fond_classifications DROP COLUMN updated_by;
+ALTER TABLE fond_classifications DROP COLUMN updated_at;
+
+ALTER TABLE schemas DROP COLUMN updated_machine;
+ALTER TABLE schemas DROP COLUMN updated_by;
+ALTER TABLE schemas DROP COLUMN updated_at;
+
+ALTER TABLE schema_items DROP COLUMN updated_machine;
+ALTER TABLE schema_items DROP COLUMN updated_by;
+ALTER TABLE schema_items DROP COLUMN updated_at;
+
+ALTER TABLE fonds DROP COLUMN updated_machine;
+ALTER TABLE fonds DROP COLUMN updated_by;
+ALTER TABLE fonds DROP COLUMN updated_at;
+
+ALTER TABLE fond_schemas DROP COLUMN updated_machine;
+ALTER TABLE fond_schemas DROP COLUMN updated_by;
+ALTER TABLE fond_schemas DROP COLUMN updated_at;
+
+ALTER TABLE series DROP COLUMN updated_machine;
+ALTER TABLE series DROP COLUMN updated_by;
+ALTER TABLE series DROP COLUMN updated_at;
+
+ALTER TABLE files DROP COLUMN updated_machine;
+ALTER TABLE files DROP COLUMN updated_by;
+ALTER TABLE files DROP COLUMN updated_at;
+
+ALTER TABLE items DROP COLUMN updated_machine;
+ALTER TABLE items DROP COLUMN updated_by;
+ALTER TABLE items DROP COLUMN updated_at;
+
+ALTER TABLE borrowers DROP COLUMN updated_machine;
+ALTER TABLE borrowers DROP COLUMN updated_by;
+ALTER TABLE borrowers DROP COLUMN updated_at;
+
+ALTER TABLE loans DROP COLUMN updated_machine;
+ALTER TABLE loans DROP COLUMN updated_by;
+ALTER TABLE loans DROP COLUMN updated_at;
@@ -0,0 +1,40 @@
+-- Track the last modification (time, user, machine) on every entity table
+ALTER TABLE fond_classifications ADD COLUMN updated_at TEXT;
+ALTER TABLE fond_classifications ADD COLUMN updated_by TEXT;
+ALTER TABLE fond_classifications ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE schemas ADD COLUMN updated_at TEXT;
+ALTER TABLE schemas ADD COLUMN updated_by TEXT;
+ALTER TABLE schemas ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE schema_items ADD COLUMN updated_at TEXT;
+ALTER TABLE schema_items ADD COLUMN updated_by TEXT;
+ALTER TABLE schema_items ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE fonds ADD COLUMN updated_at TEXT;
+ALTER TABLE fonds ADD COLUMN updated_by TEXT;
+ALTER TABLE fonds ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE fond_schemas ADD COLUMN updated_at TEXT;
+ALTER TABLE fond_schemas ADD COLUMN updated_by TEXT;
+ALTER TABLE fond_schemas ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE series ADD COLUMN updated_at TEXT;
+ALTER TABLE series ADD COLUMN updated_by TEXT;
+ALTER TABLE series ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE files ADD COLUMN updated_at TEXT;
+ALTER TABLE files ADD COLUMN updated_by TEXT;
+ALTER TABLE files ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE items ADD COLUMN updated_at TEXT;
+ALTER TABLE items ADD COLUMN updated_by TEXT;
+ALTER TABLE items ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE borrowers ADD COLUMN updated_at TEXT;
+ALTER TABLE borrowers ADD COLUMN updated_by TEXT;
+ALTER TABLE borrowers ADD COLUMN updated_machine TEXT;
+
+ALTER TABLE loans ADD COLUMN updated_at TEXT;
+ALTER TABLE loans ADD COLUMN updated_by TEXT;
+ALTER TABLE loans ADD COLUMN updated_machine TEXT;
@@ -0,0 +1,3 @@
+-- Drop checksum manifest and fixity run history
+DROP TABLE IF EXISTS fixity_runs;
+DROP TABLE IF EXISTS item_checksums;
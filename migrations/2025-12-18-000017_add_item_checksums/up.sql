@@ -0,0 +1,31 @@
+-- Add per-file checksum manifest for item payloads and fixity run history
+CREATE TABLE IF NOT EXISTS item_checksums (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    relative_path TEXT NOT NULL,
+    algorithm TEXT NOT NULL DEFAULT 'sha256',
+    checksum TEXT NOT NULL,
+    file_size BIGINT NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT,
+    updated_by TEXT,
+    updated_machine TEXT,
+    UNIQUE (item_id, relative_path),
+    FOREIGN KEY (item_id) REFERENCES items(id)
+);
+
+CREATE TABLE IF NOT EXISTS fixity_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    checked_count INTEGER NOT NULL DEFAULT 0,
+    changed_count INTEGER NOT NULL DEFAULT 0,
+    missing_count INTEGER NOT NULL DEFAULT 0,
+    new_count INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT,
+    updated_by TEXT,
+    updated_machine TEXT
+);
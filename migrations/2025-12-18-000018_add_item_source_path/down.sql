@@ -0,0 +1,2 @@
+-- Drop item provenance
+ALTER TABLE items DROP COLUMN source_path;
@@ -0,0 +1,2 @@
+-- Keep the original location of payloads copied or moved into the library
+ALTER TABLE items ADD COLUMN source_path TEXT;
@@ -0,0 +1,2 @@
+-- Drop localized names
+DROP TABLE IF EXISTS translations;
@@ -0,0 +1,15 @@
+-- Localized names of classifications and schema items, one row per entity and locale
+CREATE TABLE IF NOT EXISTS translations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity_type TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    locale TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT,
+    updated_by TEXT,
+    updated_machine TEXT,
+    UNIQUE (entity_type, entity_id, locale)
+);
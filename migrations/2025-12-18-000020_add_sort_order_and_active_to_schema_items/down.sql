@@ -0,0 +1,3 @@
+-- Remove ordering and activation from schema_items
+ALTER TABLE schema_items DROP COLUMN sort_order;
+ALTER TABLE schema_items DROP COLUMN active;
@@ -0,0 +1,3 @@
+-- Order schema items within their schema and allow retiring them without deleting
+ALTER TABLE schema_items ADD COLUMN active BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE schema_items ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Remove schema item nesting and dimension levels
+ALTER TABLE fond_schemas DROP COLUMN item_level;
+ALTER TABLE schema_items DROP COLUMN parent_id;
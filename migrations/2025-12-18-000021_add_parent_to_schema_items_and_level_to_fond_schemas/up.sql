@@ -0,0 +1,3 @@
+-- Nest schema items under a parent item of the same schema, and let a fond dimension expand to a chosen level
+ALTER TABLE schema_items ADD COLUMN parent_id INTEGER REFERENCES schema_items(id);
+ALTER TABLE fond_schemas ADD COLUMN item_level INTEGER;
@@ -0,0 +1,15 @@
+-- Record the schema items each series was generated from, so usage checks do not depend on parsing series numbers
+CREATE TABLE IF NOT EXISTS series_schema_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    series_id INTEGER NOT NULL,
+    schema_item_id INTEGER NOT NULL,
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT,
+    updated_by TEXT,
+    updated_machine TEXT,
+    UNIQUE (series_id, schema_item_id),
+    FOREIGN KEY (series_id) REFERENCES series(id),
+    FOREIGN KEY (schema_item_id) REFERENCES schema_items(id)
+);
@@ -0,0 +1,32 @@
+-- Make schema item numbers unique across the whole schema again (fails if nested items share a number)
+PRAGMA foreign_keys = OFF;
+
+DROP INDEX IF EXISTS idx_schema_items_top_level_no;
+
+CREATE TABLE schema_items_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schema_id INTEGER NOT NULL,
+    item_no TEXT NOT NULL,
+    item_name TEXT NOT NULL,
+    parent_id INTEGER,
+    active BOOLEAN NOT NULL DEFAULT 1,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT,
+    updated_by TEXT,
+    updated_machine TEXT,
+    UNIQUE (schema_id, item_no),
+    FOREIGN KEY (schema_id) REFERENCES schemas(id),
+    FOREIGN KEY (parent_id) REFERENCES schema_items(id)
+);
+
+INSERT INTO schema_items_old (id, schema_id, item_no, item_name, parent_id, active, sort_order, created_by, created_machine, created_at, updated_at, updated_by, updated_machine)
+SELECT id, schema_id, item_no, item_name, parent_id, active, sort_order, created_by, created_machine, created_at, updated_at, updated_by, updated_machine
+FROM schema_items;
+
+DROP TABLE schema_items;
+ALTER TABLE schema_items_old RENAME TO schema_items;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,34 @@
+-- Make schema item numbers unique under their parent instead of across the whole schema
+-- Note: SQLite doesn't support DROP CONSTRAINT directly, so we need to recreate the table
+PRAGMA foreign_keys = OFF;
+
+CREATE TABLE schema_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schema_id INTEGER NOT NULL,
+    item_no TEXT NOT NULL,
+    item_name TEXT NOT NULL,
+    parent_id INTEGER,
+    active BOOLEAN NOT NULL DEFAULT 1,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_machine TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT,
+    updated_by TEXT,
+    updated_machine TEXT,
+    UNIQUE (schema_id, parent_id, item_no),
+    FOREIGN KEY (schema_id) REFERENCES schemas(id),
+    FOREIGN KEY (parent_id) REFERENCES schema_items(id)
+);
+
+INSERT INTO schema_items_new (id, schema_id, item_no, item_name, parent_id, active, sort_order, created_by, created_machine, created_at, updated_at, updated_by, updated_machine)
+SELECT id, schema_id, item_no, item_name, parent_id, active, sort_order, created_by, created_machine, created_at, updated_at, updated_by, updated_machine
+FROM schema_items;
+
+DROP TABLE schema_items;
+ALTER TABLE schema_items_new RENAME TO schema_items;
+
+-- UNIQUE does not compare NULLs, so top-level item numbers get a partial unique index
+CREATE UNIQUE INDEX IF NOT EXISTS idx_schema_items_top_level_no ON schema_items(schema_id, item_no) WHERE parent_id IS NULL;
+
+PRAGMA foreign_keys = ON;
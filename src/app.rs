@@ -3,7 +3,7 @@ use std::rc::Rc;
 
 use fonds_pod_lib::services::SettingsService;
 use fonds_pod_lib::viewmodels::{
-    AboutViewModel, FondClassificationViewModel, FondViewModel, HomeViewModel, SchemaViewModel, SchemaItemViewModel, SettingsViewModel,
+    AboutViewModel, FondClassificationViewModel, FondViewModel, HomeViewModel, LoanViewModel, SchemaViewModel, SchemaItemViewModel, SettingsViewModel,
 };
 use fonds_pod_lib::AppWindow;
 use fonds_pod_lib::CrudViewModelBase;
@@ -20,6 +20,7 @@ pub struct App {
     pub fond_classification_vm: SharedVm<FondClassificationViewModel>,
     pub schema_vm: SharedVm<SchemaViewModel>,
     pub schema_item_vm: SharedVm<SchemaItemViewModel>,
+    pub loan_vm: SharedVm<LoanViewModel>,
 }
 
 impl App {
@@ -85,6 +86,12 @@ impl App {
         let schema_item_vm = Rc::new(RefCell::new(Self::initialize_schema_item_vm(&settings_service)));
         schema_item_vm.borrow().load();
 
+        // Initialize Loan ViewModel
+        let loan_vm = Rc::new(RefCell::new(LoanViewModel::new(Self::get_database_connection(&settings_service))));
+        if let Err(e) = loan_vm.borrow_mut().load() {
+            log::error!("App: Failed to load loans: {}", e);
+        }
+
         App {
             settings_vm,
             about_vm,
@@ -93,6 +100,7 @@ impl App {
             fond_classification_vm,
             schema_vm,
             schema_item_vm,
+            loan_vm,
         }
     }
 
@@ -168,6 +176,7 @@ impl App {
             ui_handle,
         );
         SchemaItemViewModel::setup_callbacks(Rc::clone(&self.schema_item_vm), ui_handle);
+        LoanViewModel::setup_callbacks(Rc::clone(&self.loan_vm), ui_handle);
 
         // Initial load for Fond VM
         let items = self.fond_vm.borrow().get_items();
@@ -252,6 +261,7 @@ impl App {
             let fond_classification_vm = Rc::clone(&self.fond_classification_vm);
             let schema_vm = Rc::clone(&self.schema_vm);
             let schema_item_vm = Rc::clone(&self.schema_item_vm);
+            let loan_vm = Rc::clone(&self.loan_vm);
             let home_vm = Rc::clone(&self.home_vm);
             let ui_weak = ui_handle.as_weak();
             move |page_name| {
@@ -300,6 +310,11 @@ impl App {
                                         let schema_item_items = schema_item_vm.borrow().get_items();
                                         ui.set_detail_list_items(schema_item_items);
                                     }
+                                    "loans" => {
+                                        let mut vm = loan_vm.borrow_mut();
+                                        vm.update_connection(new_conn);
+                                        vm.init_ui(&ui);
+                                    }
                                     _ => {}
                                 }
                             }
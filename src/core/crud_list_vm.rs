@@ -1,4 +1,5 @@
-use crate::{core::GenericRepository, Creatable, CrudListItem};
+use crate::core::{Activeable, ActiveableRepository, GenericRepository};
+use crate::{Creatable, CrudListItem};
 use slint::{Model, ModelRc, VecModel};
 use std::cell::RefCell;
 use std::marker::PhantomData;
@@ -96,23 +97,7 @@ where
     }
 
     pub fn load(&self) {
-        let items: Vec<T> = {
-            use std::any::Any;
-            let mut repo = self.repo.borrow_mut();
-            // Check if it's FondClassificationsRepository
-            if let Some(activeable_repo) = (&mut *repo as &mut dyn Any)
-                .downcast_mut::<crate::persistence::FondClassificationsRepository>(
-            ) {
-                // For FondClassificationsRepository, load only top-level items (parent_id is NULL)
-                // UI will handle styling based on active status
-                let result: Vec<crate::models::fond_classification::FondClassification> =
-                    activeable_repo.find_by_parent_id(None).unwrap_or_default();
-                // Unsafe cast - we know this is safe because T is FondClassification in this context
-                unsafe { std::mem::transmute(result) }
-            } else {
-                repo.find_all().unwrap_or_default()
-            }
-        };
+        let items: Vec<T> = self.repo.borrow_mut().find_all().unwrap_or_default();
         let crud_items: Vec<CrudListItem> =
             items.iter().map(|item| item.to_crud_list_item()).collect();
         self.items.set_vec(crud_items);
@@ -147,61 +132,42 @@ where
         }
     }
 
+    pub fn get_items(&self) -> ModelRc<CrudListItem> {
+        ModelRc::from(self.items.clone())
+    }
+}
+
+impl<T, R> CrudViewModel<T, R>
+where
+    T: Creatable + Activeable + Clone + Default + ToCrudListItem + 'static,
+    R: ActiveableRepository<T> + 'static,
+{
     pub fn activate(&self, id: i32) {
-        use std::any::Any;
-        {
-            let mut repo = self.repo.borrow_mut();
-            // Check if it's FondClassificationsRepository
-            if let Some(activeable_repo) = (&mut *repo as &mut dyn Any)
-                .downcast_mut::<crate::persistence::FondClassificationsRepository>(
-            ) {
-                use crate::core::ActiveableRepository;
-                if activeable_repo.activate(id).is_ok() {
-                    // 重新加载数据 - 需要在borrow结束后调用
-                    drop(repo);
-                    self.load();
-                    return;
-                }
-            }
+        if let Err(e) = self.repo.borrow_mut().activate(id) {
+            log::error!("Failed to activate {}: {}", id, e);
         }
-        // 如果不是activeable repo，什么都不做
+        self.load();
     }
 
     pub fn deactivate(&self, id: i32) {
-        use std::any::Any;
-        {
-            let mut repo = self.repo.borrow_mut();
-            // Check if it's FondClassificationsRepository
-            if let Some(activeable_repo) = (&mut *repo as &mut dyn Any)
-                .downcast_mut::<crate::persistence::FondClassificationsRepository>(
-            ) {
-                use crate::core::ActiveableRepository;
-                if activeable_repo.deactivate(id).is_ok() {
-                    // 重新加载数据 - 需要在borrow结束后调用
-                    drop(repo);
-                    self.load();
-                    return;
-                }
-            }
+        if let Err(e) = self.repo.borrow_mut().deactivate(id) {
+            log::error!("Failed to deactivate {}: {}", id, e);
         }
-        // 如果不是activeable repo，什么都不做
-    }
-
-    pub fn get_items(&self) -> ModelRc<CrudListItem> {
-        ModelRc::from(self.items.clone())
+        self.load();
     }
 }
 
 /// 为支持activeable的CrudViewModel自动生成ActiveableCrudViewModel实现
 ///
 /// 此宏需要：
-/// - 一个包含 `inner: CrudViewModel<T, R>` 字段的结构体
+/// - 一个包含 `inner: CrudViewModel<T, R>` 或 `inner: TreeCrudViewModel<T, R>` 字段的结构体，
+///   且仓储实现了 `ActiveableRepository<T>`
 /// - 必须提供 `create_default()` 方法来生成新实体
 ///
 /// # 示例
 /// ```ignore
 /// pub struct FondClassificationViewModel {
-///     inner: CrudViewModel<FondClassification, FondClassificationsRepository>,
+///     inner: TreeCrudViewModel<FondClassification, FondClassificationsRepository>,
 /// }
 ///
 /// impl FondClassificationViewModel {
@@ -383,26 +349,6 @@ macro_rules! impl_crud_vm_base {
                     Err("无效索引".to_string())
                 }
             }
-
-            fn activate(&self, id: i32) {
-                log::info!("{}: Activating item with id {}", Self::vm_name(), id);
-                self.inner.activate(id);
-                log::info!(
-                    "{}: Activated item, current count: {}",
-                    Self::vm_name(),
-                    self.inner.items.row_count()
-                );
-            }
-
-            fn deactivate(&self, id: i32) {
-                log::info!("{}: Deactivating item with id {}", Self::vm_name(), id);
-                self.inner.deactivate(id);
-                log::info!(
-                    "{}: Deactivated item, current count: {}",
-                    Self::vm_name(),
-                    self.inner.items.row_count()
-                );
-            }
         }
     };
 }
@@ -46,6 +46,36 @@ pub trait Creatable {
     }
 }
 
+// ============================================================================
+// 当前用户 / 机器
+// ============================================================================
+
+/// 获取当前操作系统用户名
+pub fn current_user() -> Result<String, Box<dyn Error>> {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("USERNAME").map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::env::var("USER").or_else(|_| std::env::var("LOGNAME")).map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+}
+
+/// 获取当前机器名
+pub fn current_machine() -> Result<String, Box<dyn Error>> {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("COMPUTERNAME").map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::process::Command;
+        let output = Command::new("hostname").output().map_err(|e| Box::new(e) as Box<dyn Error>)?;
+        String::from_utf8(output.stdout).map(|s| s.trim().to_string()).map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+}
+
 // ============================================================================
 // Repository Trait - 通用仓储接口
 // ============================================================================
@@ -57,28 +87,12 @@ pub trait Creatable {
 pub trait GenericRepository<E: Creatable> {
     /// 获取当前用户名（默认实现返回 Windows 用户名）
     fn get_current_user() -> Result<String, Box<dyn Error>> {
-        #[cfg(target_os = "windows")]
-        {
-            std::env::var("USERNAME").map_err(|e| Box::new(e) as Box<dyn Error>)
-        }
-        #[cfg(not(target_os = "windows"))]
-        {
-            std::env::var("USER").or_else(|_| std::env::var("LOGNAME")).map_err(|e| Box::new(e) as Box<dyn Error>)
-        }
+        current_user()
     }
 
     /// 获取当前机器名（默认实现返回计算机名）
     fn get_current_machine() -> Result<String, Box<dyn Error>> {
-        #[cfg(target_os = "windows")]
-        {
-            std::env::var("COMPUTERNAME").map_err(|e| Box::new(e) as Box<dyn Error>)
-        }
-        #[cfg(not(target_os = "windows"))]
-        {
-            use std::process::Command;
-            let output = Command::new("hostname").output().map_err(|e| Box::new(e) as Box<dyn Error>)?;
-            String::from_utf8(output.stdout).map(|s| s.trim().to_string()).map_err(|e| Box::new(e) as Box<dyn Error>)
-        }
+        current_machine()
     }
 
     /// 插入记录（不修改时间戳），返回插入后的 id
@@ -192,6 +206,9 @@ macro_rules! impl_creatable {
 /// - 表必须有 `id` 列作为自增主键
 /// - 实体类型必须实现 Creatable trait
 /// - 插入时自动排除 id 列（由数据库自动生成）
+/// - 实体类型必须实现 Serialize，insert / update / delete 会在同一事务中写入 audit_log
+/// - 写入提交后刷新对应目录的 sidecar（见 `persistence::sidecars`）
+/// - 实体类型必须实现 Updatable，修改时自动填写 updated_at、updated_by 和 updated_machine
 ///
 /// # 参数
 /// - `$repo`: 仓储结构体名称
@@ -234,19 +251,91 @@ macro_rules! impl_repository {
             pub fn update_connection(&mut self, new_conn: Rc<RefCell<diesel::SqliteConnection>>) {
                 self.conn = new_conn;
             }
+
+            /// 仓储当前使用的数据库连接（供需要跨仓储操作的服务使用）
+            pub fn connection(&self) -> Rc<RefCell<diesel::SqliteConnection>> {
+                Rc::clone(&self.conn)
+            }
+
+            /// 在事务中执行写操作，并把变更前后的实体 JSON 写入审计日志
+            ///
+            /// - `id`: 被修改记录的 id，插入时为 None
+            /// - `write`: 实际的写操作，返回被写入记录的 id
+            fn write_with_audit<F>(
+                &mut self,
+                action: crate::models::audit_log::AuditAction,
+                id: Option<i32>,
+                write: F,
+            ) -> Result<i32, Box<dyn std::error::Error>>
+            where
+                F: FnOnce(&mut diesel::SqliteConnection) -> diesel::QueryResult<i32>,
+            {
+                use diesel::prelude::*;
+                let user = <Self as crate::core::GenericRepository<$entity>>::get_current_user().unwrap_or_default();
+                let machine = <Self as crate::core::GenericRepository<$entity>>::get_current_machine().unwrap_or_default();
+
+                let mut conn = self.conn.borrow_mut();
+                let mut removed = None;
+                let id = conn.transaction::<i32, Box<dyn std::error::Error>, _>(|conn| {
+                    let before = match id {
+                        Some(id) => $table::table.filter($table::id.eq(id)).first::<$entity>(conn).optional()?,
+                        None => None,
+                    };
+                    if action == crate::models::audit_log::AuditAction::Delete {
+                        removed = before.as_ref().map(serde_json::to_value).transpose()?;
+                    }
+                    let id = write(conn)?;
+
+                    // 修改时自动填写 updated_at / updated_by / updated_machine
+                    if action == crate::models::audit_log::AuditAction::Update {
+                        use chrono::Timelike;
+                        let now = chrono::Local::now().naive_local();
+                        let now_ms = now.with_nanosecond((now.nanosecond() / 1_000_000) * 1_000_000).unwrap();
+                        diesel::update($table::table.filter($table::id.eq(id)))
+                            .set((
+                                $table::updated_at.eq(Some(now_ms)),
+                                $table::updated_by.eq(Some(&user)),
+                                $table::updated_machine.eq(Some(&machine)),
+                            ))
+                            .execute(conn)?;
+                    }
+
+                    let after = $table::table.filter($table::id.eq(id)).first::<$entity>(conn).optional()?;
+
+                    // 没有实际影响任何记录时不写审计日志
+                    if before.is_some() || after.is_some() {
+                        let entry = crate::models::audit_log::NewAuditLog::from_change(
+                            stringify!($table),
+                            id,
+                            action,
+                            before.as_ref(),
+                            after.as_ref(),
+                            &user,
+                            &machine,
+                        )?;
+                        crate::persistence::audit_log_repository::AuditLogRepository::record(conn, entry)?;
+                    }
+                    Ok(id)
+                })?;
+
+                // 提交后刷新受影响目录的 sidecar，供数据库丢失时重建
+                crate::persistence::sidecars::after_write(&mut conn, stringify!($table), id, removed.as_ref());
+                Ok(id)
+            }
         }
 
         impl crate::core::GenericRepository<$entity> for $repo {
             fn insert(&mut self, entity: &$entity) -> Result<i32, Box<dyn std::error::Error>> {
                 use diesel::prelude::*;
-                // 插入时只插入指定列，排除 id（由数据库自增）
-                diesel::insert_into($table::table)
-                    .values(( $( $table::$insert_col.eq(&entity.$insert_col), )* ))
-                    .execute(&mut *self.conn.borrow_mut())?;
-                // 获取最后插入的 id
-                let last_id: i32 = diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("last_insert_rowid()"))
-                    .get_result(&mut *self.conn.borrow_mut())?;
-                Ok(last_id)
+                self.write_with_audit(crate::models::audit_log::AuditAction::Insert, None, |conn| {
+                    // 插入时只插入指定列，排除 id（由数据库自增）
+                    diesel::insert_into($table::table)
+                        .values(( $( $table::$insert_col.eq(&entity.$insert_col), )* ))
+                        .execute(conn)?;
+                    // 获取最后插入的 id
+                    diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("last_insert_rowid()"))
+                        .get_result(conn)
+                })
             }
 
             fn find_by_id(&mut self, id: i32) -> Result<Option<$entity>, Box<dyn std::error::Error>> {
@@ -267,18 +356,27 @@ macro_rules! impl_repository {
             fn update(&mut self, entity: &$entity) -> Result<(), Box<dyn std::error::Error>> {
                 use diesel::prelude::*;
                 use crate::core::Creatable;
-                diesel::update($table::table.filter($table::id.eq(entity.id())))
-                    .set(( $( $table::$update_col.eq(&entity.$update_col), )* ))
-                    .execute(&mut *self.conn.borrow_mut())?;
+                let id = entity.id();
+                self.write_with_audit(crate::models::audit_log::AuditAction::Update, Some(id), |conn| {
+                    diesel::update($table::table.filter($table::id.eq(id)))
+                        .set(( $( $table::$update_col.eq(&entity.$update_col), )* ))
+                        .execute(conn)
+                        .map(|_| id)
+                })?;
                 Ok(())
             }
 
             fn delete(&mut self, id: i32) -> Result<(), Box<dyn std::error::Error>> {
                 use diesel::prelude::*;
-                diesel::delete($table::table.filter($table::id.eq(id)))
-                    .execute(&mut *self.conn.borrow_mut())?;
+                self.write_with_audit(crate::models::audit_log::AuditAction::Delete, Some(id), |conn| {
+                    diesel::delete($table::table.filter($table::id.eq(id)))
+                        .execute(conn)
+                        .map(|_| id)
+                })?;
                 Ok(())
             }
         }
+
+        impl crate::core::UpdatableRepository<$entity> for $repo {}
     };
 }
@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use crate::core::{Activeable, ActiveableRepository, Creatable, GenericRepository, Sortable};
+
+// ============================================================================
+// Core Trait - Hierarchical（约定实体有 parent_id 字段）
+// ============================================================================
+
+/// Hierarchical trait - 约定实体必须有 parent_id 字段
+///
+/// 约定：
+/// - `parent_id`: Option<i32> 类型，引用同一张表的 id，顶级记录为 None
+pub trait Hierarchical {
+    /// 获取 parent_id
+    fn parent_id(&self) -> Option<i32>;
+
+    /// 设置 parent_id
+    fn set_parent_id(&mut self, parent_id: Option<i32>);
+}
+
+// ============================================================================
+// Repository Trait - 树形仓储接口
+// ============================================================================
+
+/// 树形仓储 trait
+///
+/// 所有方法都有默认实现：树在内存中由 `find_all` 的结果构建（层级表通常只有几百条记录），
+/// 写操作逐条经过 `update` / `delete`，因此每个变更都会写入审计日志。
+///
+/// 泛型参数:
+/// - E: 实体类型（实现 Creatable、Hierarchical 和 Sortable）
+pub trait HierarchicalRepository<E: Creatable + Hierarchical + Sortable + Clone>: GenericRepository<E> {
+    /// 查找 `parent_id` 的直接子记录，按 sort_order 排序（None 为顶级记录）
+    fn find_children(&mut self, parent_id: Option<i32>) -> Result<Vec<E>, Box<dyn Error>> {
+        let mut children = self.find_by_predicate(|e| e.parent_id() == parent_id)?;
+        children.sort_by_key(|e| (e.sort_order(), e.id()));
+        Ok(children)
+    }
+
+    /// 按树的先序列出 `parent_id` 下的所有记录及其深度（直接子记录深度为 0）
+    ///
+    /// 同级按 sort_order 排序；`parent_id` 为 None 时列出整棵树。
+    fn find_tree(&mut self, parent_id: Option<i32>) -> Result<Vec<(usize, E)>, Box<dyn Error>> {
+        let mut children: HashMap<Option<i32>, Vec<E>> = HashMap::new();
+        for entity in self.find_all()? {
+            children.entry(entity.parent_id()).or_default().push(entity);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|e| (e.sort_order(), e.id()));
+        }
+
+        // 显式栈遍历；visited 防止损坏数据中的循环导致死循环
+        let mut result = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<(usize, E)> = children.remove(&parent_id).unwrap_or_default().into_iter().rev().map(|e| (0, e)).collect();
+        while let Some((depth, entity)) = stack.pop() {
+            if !visited.insert(entity.id()) {
+                continue;
+            }
+            if let Some(kids) = children.remove(&Some(entity.id())) {
+                stack.extend(kids.into_iter().rev().map(|e| (depth + 1, e)));
+            }
+            result.push((depth, entity));
+        }
+        Ok(result)
+    }
+
+    /// 查找记录的所有下级记录（先序）
+    fn find_descendants(&mut self, id: i32) -> Result<Vec<E>, Box<dyn Error>> {
+        Ok(self.find_tree(Some(id))?.into_iter().map(|(_, e)| e).collect())
+    }
+
+    /// 查找记录的所有上级记录，从顶级记录开始
+    fn find_ancestors(&mut self, id: i32) -> Result<Vec<E>, Box<dyn Error>> {
+        let mut ancestors: Vec<E> = Vec::new();
+        let mut current = self.find_by_id(id)?.ok_or("记录不存在")?;
+        while let Some(parent_id) = current.parent_id() {
+            if parent_id == id || ancestors.iter().any(|a| a.id() == parent_id) {
+                return Err(format!("记录 {} 的上级关系存在循环", id).into());
+            }
+            current = self.find_by_id(parent_id)?.ok_or("上级记录不存在")?;
+            ancestors.push(current.clone());
+        }
+        ancestors.reverse();
+        Ok(ancestors)
+    }
+
+    /// 把记录移动到 `new_parent_id` 下的第 `position` 个位置（连同其下级记录）
+    ///
+    /// 不允许移动到自身或其下级记录之下；新的同级记录重新编排 sort_order。
+    fn move_subtree(&mut self, id: i32, new_parent_id: Option<i32>, position: usize) -> Result<(), Box<dyn Error>> {
+        let mut moved = self.find_by_id(id)?.ok_or("记录不存在")?;
+        if let Some(parent_id) = new_parent_id {
+            if parent_id == id || self.find_ancestors(parent_id)?.iter().any(|a| a.id() == id) {
+                return Err("不能移动到自身或其下级之下".into());
+            }
+        }
+
+        let mut siblings: Vec<E> = self.find_children(new_parent_id)?.into_iter().filter(|e| e.id() != id).collect();
+        moved.set_parent_id(new_parent_id);
+        siblings.insert(position.min(siblings.len()), moved);
+        for (index, mut sibling) in siblings.into_iter().enumerate() {
+            let original = (sibling.parent_id(), sibling.sort_order());
+            sibling.set_sort_order(index as i32);
+            if sibling.id() == id || original != (sibling.parent_id(), sibling.sort_order()) {
+                self.update(&sibling)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 在同级记录中上移（负数）或下移（正数）
+    fn move_among_siblings(&mut self, id: i32, offset: i32) -> Result<(), Box<dyn Error>> {
+        let entity = self.find_by_id(id)?.ok_or("记录不存在")?;
+        let siblings = self.find_children(entity.parent_id())?;
+        let index = siblings.iter().position(|e| e.id() == id).unwrap_or(0) as i32;
+        let target = (index + offset).clamp(0, siblings.len() as i32 - 1);
+        if target != index {
+            self.move_subtree(id, entity.parent_id(), target as usize)?;
+        }
+        Ok(())
+    }
+
+    /// 删除记录及其所有下级记录（从叶子开始），返回删除的数量
+    fn delete_subtree(&mut self, id: i32) -> Result<usize, Box<dyn Error>> {
+        let descendants = self.find_descendants(id)?;
+        for descendant in descendants.iter().rev() {
+            self.delete(descendant.id())?;
+        }
+        self.delete(id)?;
+        Ok(descendants.len() + 1)
+    }
+
+    /// 启用记录及其所有上级记录（否则启用的记录在树中不可见）
+    fn activate_with_ancestors(&mut self, id: i32) -> Result<(), Box<dyn Error>>
+    where
+        Self: ActiveableRepository<E>,
+        E: Activeable,
+    {
+        for ancestor in self.find_ancestors(id)?.into_iter().filter(|a| !a.active()) {
+            self.activate(ancestor.id())?;
+        }
+        self.activate(id)
+    }
+
+    /// 停用记录及其所有下级记录
+    fn deactivate_subtree(&mut self, id: i32) -> Result<(), Box<dyn Error>>
+    where
+        Self: ActiveableRepository<E>,
+        E: Activeable,
+    {
+        self.deactivate(id)?;
+        for descendant in self.find_descendants(id)?.into_iter().filter(|d| d.active()) {
+            self.deactivate(descendant.id())?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// 宏：自动实现 Hierarchical
+// ============================================================================
+
+/// 为实体自动实现 Hierarchical trait
+///
+/// 约定实体必须有 `parent_id: Option<i32>` 字段
+///
+/// # 示例
+/// ```ignore
+/// impl_hierarchical!(FondClassification);
+/// ```
+#[macro_export]
+macro_rules! impl_hierarchical {
+    ($entity:ty) => {
+        impl $crate::core::Hierarchical for $entity {
+            fn parent_id(&self) -> Option<i32> {
+                self.parent_id
+            }
+
+            fn set_parent_id(&mut self, parent_id: Option<i32>) {
+                self.parent_id = parent_id;
+            }
+        }
+    };
+}
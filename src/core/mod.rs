@@ -1,11 +1,17 @@
 #[macro_use]
 pub mod crud_list_vm;
+pub mod tree_crud_vm;
 
 pub mod generic_repository;
 pub mod activeable_repository;
 pub mod sortable_repository;
+pub mod updatable_repository;
+pub mod hierarchical_repository;
 
-pub use generic_repository::{Creatable, GenericRepository};
+pub use generic_repository::{Creatable, GenericRepository, current_user, current_machine};
 pub use activeable_repository::{Activeable, ActiveableRepository};
 pub use sortable_repository::{Sortable, SortableRepository};
+pub use updatable_repository::{Updatable, UpdatableRepository};
+pub use hierarchical_repository::{Hierarchical, HierarchicalRepository};
 pub use crud_list_vm::{CrudViewModel, ToCrudListItem, CrudViewModelBase};
+pub use tree_crud_vm::{TreeCrudViewModel, TreeMove, TreeScope};
@@ -160,9 +160,12 @@ macro_rules! impl_sortable_repository {
         impl crate::core::SortableRepository<$entity> for $repo {
             fn update_sort_order(&mut self, id: i32, sort_order: i32) -> Result<(), Box<dyn std::error::Error>> {
                 use diesel::prelude::*;
-                diesel::update($table::table.filter($table::id.eq(id)))
-                    .set($table::sort_order.eq(sort_order))
-                    .execute(&mut *self.conn.borrow_mut())?;
+                self.write_with_audit(crate::models::audit_log::AuditAction::Update, Some(id), |conn| {
+                    diesel::update($table::table.filter($table::id.eq(id)))
+                        .set($table::sort_order.eq(sort_order))
+                        .execute(conn)
+                        .map(|_| id)
+                })?;
                 Ok(())
             }
         }
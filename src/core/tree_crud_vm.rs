@@ -0,0 +1,275 @@
+use crate::core::{Activeable, ActiveableRepository, Creatable, Hierarchical, HierarchicalRepository, Sortable, ToCrudListItem};
+use crate::CrudListItem;
+use slint::{ModelRc, VecModel};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+/// 树形列表显示的范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeScope {
+    /// 某个上级记录的直接子记录（None 为顶级记录），不缩进
+    Level(Option<i32>),
+    /// 某条记录及其全部下级记录，第 0 行是该记录本身
+    Subtree(i32),
+    /// 整棵树
+    All,
+}
+
+/// 树中记录的调整操作，与列表快捷按钮的顺序一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeMove {
+    Up,
+    Down,
+    /// 升一级：成为原上级记录的下一个同级记录
+    Outdent,
+    /// 降一级：归入上一个同级记录，排在最后
+    Indent,
+}
+
+impl TreeMove {
+    pub fn from_action(action: i32) -> Option<Self> {
+        match action {
+            0 => Some(Self::Up),
+            1 => Some(Self::Down),
+            2 => Some(Self::Outdent),
+            3 => Some(Self::Indent),
+            _ => None,
+        }
+    }
+}
+
+/// 列表中的一行
+#[derive(Debug, Clone, Copy)]
+struct TreeRow {
+    id: i32,
+    depth: usize,
+}
+
+/// 树形列表中按深度缩进的标题
+pub fn tree_title(depth: usize, title: &str) -> String {
+    if depth == 0 {
+        title.to_string()
+    } else {
+        format!("{}└ {}", "　".repeat(depth - 1), title)
+    }
+}
+
+/// 树形 CRUD ViewModel：把层级实体显示为缩进的 CrudList
+///
+/// 与 `CrudViewModel` 提供相同的 load / add / delete / activate 接口，可直接用于
+/// `impl_activeable_crud_vm_base!`；删除、停用会带上下级记录，启用会带上上级记录。
+pub struct TreeCrudViewModel<T, R>
+where
+    T: Creatable + Hierarchical + Sortable + Clone + Default + ToCrudListItem + 'static,
+    R: HierarchicalRepository<T> + 'static,
+{
+    pub items: Rc<VecModel<CrudListItem>>,
+    rows: RefCell<Vec<TreeRow>>,
+    scope: Cell<TreeScope>,
+    /// 替代 `to_crud_list_item` 标题的显示名称（例如当前语言的翻译）
+    display_names: RefCell<HashMap<i32, String>>,
+    repo: Rc<RefCell<R>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, R> TreeCrudViewModel<T, R>
+where
+    T: Creatable + Hierarchical + Sortable + Clone + Default + ToCrudListItem + 'static,
+    R: HierarchicalRepository<T> + 'static,
+{
+    pub fn new(repo: Rc<RefCell<R>>, scope: TreeScope) -> Self {
+        Self {
+            items: Rc::new(VecModel::default()),
+            rows: RefCell::new(Vec::new()),
+            scope: Cell::new(scope),
+            display_names: RefCell::new(HashMap::new()),
+            repo,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn get_repo(&self) -> Rc<RefCell<R>> {
+        self.repo.clone()
+    }
+
+    pub fn get_items(&self) -> ModelRc<CrudListItem> {
+        ModelRc::from(self.items.clone())
+    }
+
+    pub fn scope(&self) -> TreeScope {
+        self.scope.get()
+    }
+
+    /// 切换显示范围并重新加载
+    pub fn set_scope(&self, scope: TreeScope) {
+        self.scope.set(scope);
+        self.load();
+    }
+
+    /// 设置记录的显示名称，下次加载时生效；没有列出的记录显示原标题
+    pub fn set_display_names(&self, names: HashMap<i32, String>) {
+        *self.display_names.borrow_mut() = names;
+    }
+
+    /// 清空列表（例如没有选中上级记录时）
+    pub fn clear(&self) {
+        self.rows.borrow_mut().clear();
+        self.items.set_vec(Vec::new());
+    }
+
+    pub fn load(&self) {
+        let rows: Vec<(usize, T)> = {
+            let mut repo = self.repo.borrow_mut();
+            match self.scope.get() {
+                TreeScope::Level(parent_id) => repo.find_children(parent_id).unwrap_or_default().into_iter().map(|e| (0, e)).collect(),
+                TreeScope::Subtree(root_id) => match repo.find_by_id(root_id).ok().flatten() {
+                    Some(root) => std::iter::once((0, root))
+                        .chain(repo.find_tree(Some(root_id)).unwrap_or_default().into_iter().map(|(depth, e)| (depth + 1, e)))
+                        .collect(),
+                    None => Vec::new(),
+                },
+                TreeScope::All => repo.find_tree(None).unwrap_or_default(),
+            }
+        };
+        *self.rows.borrow_mut() = rows.iter().map(|(depth, e)| TreeRow { id: e.id(), depth: *depth }).collect();
+        let display_names = self.display_names.borrow();
+        let crud_items: Vec<CrudListItem> = rows
+            .iter()
+            .map(|(depth, e)| {
+                let mut item = e.to_crud_list_item();
+                let title = match display_names.get(&e.id()) {
+                    Some(name) => tree_title(*depth, name),
+                    None => tree_title(*depth, &item.title),
+                };
+                item.title = title.into();
+                item
+            })
+            .collect();
+        self.items.set_vec(crud_items);
+    }
+
+    /// 第 `index` 行的记录 id
+    pub fn id_at(&self, index: usize) -> Option<i32> {
+        self.rows.borrow().get(index).map(|row| row.id)
+    }
+
+    /// 第 `index` 行的深度（Subtree 范围中第 0 行为 0）
+    pub fn depth_at(&self, index: usize) -> Option<usize> {
+        self.rows.borrow().get(index).map(|row| row.depth)
+    }
+
+    /// 记录所在的行号
+    pub fn index_of(&self, id: i32) -> Option<usize> {
+        self.rows.borrow().iter().position(|row| row.id == id)
+    }
+
+    /// 在当前范围的根下添加记录（Level 范围的上级、Subtree 范围的根记录，All 范围为顶级）
+    pub fn add(&self, item: &mut T) {
+        let parent_id = match self.scope.get() {
+            TreeScope::Level(parent_id) => parent_id,
+            TreeScope::Subtree(root_id) => Some(root_id),
+            TreeScope::All => None,
+        };
+        if let Ok(id) = self.create_under(parent_id, item.clone()) {
+            item.set_id(id);
+            self.load();
+        }
+    }
+
+    /// 在第 `index` 行的记录下添加子记录；没有该行时加到当前范围的根下
+    pub fn add_child(&self, index: usize, item: T) -> Result<i32, String> {
+        let parent_id = match (self.id_at(index), self.scope.get()) {
+            (Some(id), _) => Some(id),
+            (None, TreeScope::Level(parent_id)) => parent_id,
+            (None, TreeScope::Subtree(root_id)) => Some(root_id),
+            (None, TreeScope::All) => None,
+        };
+        let id = self.create_under(parent_id, item)?;
+        self.load();
+        Ok(id)
+    }
+
+    /// 创建记录，排在同级记录的最后
+    fn create_under(&self, parent_id: Option<i32>, mut item: T) -> Result<i32, String> {
+        let mut repo = self.repo.borrow_mut();
+        let siblings = repo.find_children(parent_id).map_err(|e| e.to_string())?;
+        item.set_parent_id(parent_id);
+        item.set_sort_order(siblings.last().map_or(0, |last| last.sort_order() + 1));
+        repo.create(item).map_err(|e| format!("添加失败: {}", e))
+    }
+
+    /// 移动第 `index` 行的记录（连同其下级记录）
+    ///
+    /// 返回移动后所在顶级记录的 id 和被移动记录的 id，用于重新选中。
+    pub fn move_node(&self, index: usize, movement: TreeMove) -> Result<(i32, i32), String> {
+        let id = self.id_at(index).ok_or("项目未找到")?;
+        {
+            let mut repo = self.repo.borrow_mut();
+            let node = repo.find_by_id(id).map_err(|e| e.to_string())?.ok_or("项目未找到")?;
+            let result = match movement {
+                TreeMove::Up => repo.move_among_siblings(id, -1),
+                TreeMove::Down => repo.move_among_siblings(id, 1),
+                TreeMove::Outdent => {
+                    let parent_id = node.parent_id().ok_or("顶级记录不能再升级")?;
+                    let parent = repo.find_by_id(parent_id).map_err(|e| e.to_string())?.ok_or("上级记录不存在")?;
+                    let siblings = repo.find_children(parent.parent_id()).map_err(|e| e.to_string())?;
+                    let position = siblings.iter().position(|e| e.id() == parent_id).unwrap_or(0);
+                    repo.move_subtree(id, parent.parent_id(), position + 1)
+                }
+                TreeMove::Indent => {
+                    let siblings = repo.find_children(node.parent_id()).map_err(|e| e.to_string())?;
+                    let position = siblings.iter().position(|e| e.id() == id).unwrap_or(0);
+                    let previous = position.checked_sub(1).map(|i| siblings[i].id()).ok_or("没有可归入的上一个同级记录")?;
+                    repo.move_subtree(id, Some(previous), usize::MAX)
+                }
+            };
+            result.map_err(|e| format!("移动失败: {}", e))?;
+        }
+        let top_id = self.repo.borrow_mut().find_ancestors(id).map_err(|e| e.to_string())?.first().map_or(id, |top| top.id());
+        self.load();
+        Ok((top_id, id))
+    }
+
+    /// 第 `index` 行记录的下级记录数量
+    pub fn descendant_count(&self, index: usize) -> usize {
+        self.id_at(index)
+            .and_then(|id| self.repo.borrow_mut().find_descendants(id).ok())
+            .map_or(0, |d| d.len())
+    }
+
+    /// 删除第 `index` 行的记录及其下级记录，返回删除的数量
+    pub fn delete_subtree(&self, index: usize) -> Result<usize, String> {
+        let id = self.id_at(index).ok_or("项目未找到")?;
+        let count = self.repo.borrow_mut().delete_subtree(id).map_err(|e| format!("删除失败: {}", e))?;
+        self.load();
+        Ok(count)
+    }
+
+    pub fn delete(&self, index: usize) -> Result<(), String> {
+        self.delete_subtree(index).map(|_| ())
+    }
+}
+
+impl<T, R> TreeCrudViewModel<T, R>
+where
+    T: Creatable + Hierarchical + Sortable + Activeable + Clone + Default + ToCrudListItem + 'static,
+    R: HierarchicalRepository<T> + ActiveableRepository<T> + 'static,
+{
+    /// 启用记录及其上级记录
+    pub fn activate(&self, id: i32) {
+        if let Err(e) = self.repo.borrow_mut().activate_with_ancestors(id) {
+            log::error!("Failed to activate {} with its ancestors: {}", id, e);
+        }
+        self.load();
+    }
+
+    /// 停用记录及其下级记录
+    pub fn deactivate(&self, id: i32) {
+        if let Err(e) = self.repo.borrow_mut().deactivate_subtree(id) {
+            log::error!("Failed to deactivate {} with its descendants: {}", id, e);
+        }
+        self.load();
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use std::error::Error;
+use crate::core::{Creatable, GenericRepository};
+
+// ============================================================================
+// Core Trait - Updatable（约定实体有 updated_at、updated_by 和 updated_machine 字段）
+// ============================================================================
+
+/// Updatable trait - Creatable 的伴随 trait，记录最后一次修改
+///
+/// 约定：
+/// - `updated_at`: Option<NaiveDateTime> 类型，最后修改时间，从未修改时为 None
+/// - `updated_by`: Option<String> 类型，最后修改者用户名
+/// - `updated_machine`: Option<String> 类型，最后修改机器名
+///
+/// 这些字段由 impl_repository! 系列宏在 update / activate / deactivate / update_sort_order 时自动填写，
+/// 不需要（也不应该）由调用方设置
+pub trait Updatable: Creatable {
+    /// 获取 updated_at 最后修改时间
+    fn updated_at(&self) -> Option<NaiveDateTime>;
+
+    /// 获取 updated_by 最后修改者
+    fn updated_by(&self) -> Option<&str>;
+
+    /// 获取 updated_machine 最后修改机器名
+    fn updated_machine(&self) -> Option<&str>;
+
+    /// 最后变动时间（从未修改时为创建时间）
+    fn last_changed_at(&self) -> NaiveDateTime {
+        self.updated_at().unwrap_or_else(|| self.created_at())
+    }
+
+    /// 用于列表和详情显示的修改信息，从未修改时为空字符串
+    fn change_summary(&self) -> String {
+        match self.updated_at() {
+            Some(at) => format!("✎ {} {}", at.format("%Y-%m-%d %H:%M"), self.updated_by().unwrap_or("")).trim_end().to_string(),
+            None => String::new(),
+        }
+    }
+}
+
+// ============================================================================
+// Repository Trait - 可追踪修改仓储接口
+// ============================================================================
+
+/// 可追踪修改仓储 trait
+///
+/// 泛型参数:
+/// - E: 实体类型（实现 Updatable）
+pub trait UpdatableRepository<E: Updatable>: GenericRepository<E> {
+    /// 查找在指定时间之后新增或修改的记录，按最后变动时间倒序
+    fn find_changed_since(&mut self, since: NaiveDateTime) -> Result<Vec<E>, Box<dyn Error>> {
+        let mut changed = self.find_by_predicate(|e| e.last_changed_at() >= since)?;
+        changed.sort_by_key(|e| std::cmp::Reverse(e.last_changed_at()));
+        Ok(changed)
+    }
+
+    /// 最近变动的若干条记录，按最后变动时间倒序
+    fn find_recently_changed(&mut self, limit: usize) -> Result<Vec<E>, Box<dyn Error>> {
+        let mut all = self.find_all()?;
+        all.sort_by_key(|e| std::cmp::Reverse(e.last_changed_at()));
+        all.truncate(limit);
+        Ok(all)
+    }
+}
+
+// ============================================================================
+// 宏：自动实现 Updatable
+// ============================================================================
+
+/// 为实体自动实现 Updatable trait
+///
+/// 约定实体必须有 `updated_at: Option<NaiveDateTime>`、`updated_by: Option<String>` 和 `updated_machine: Option<String>` 字段
+///
+/// # 示例
+/// ```ignore
+/// impl_updatable!(Schema);
+/// ```
+#[macro_export]
+macro_rules! impl_updatable {
+    ($entity:ty) => {
+        impl $crate::core::Updatable for $entity {
+            fn updated_at(&self) -> Option<chrono::NaiveDateTime> {
+                self.updated_at
+            }
+
+            fn updated_by(&self) -> Option<&str> {
+                self.updated_by.as_deref()
+            }
+
+            fn updated_machine(&self) -> Option<&str> {
+                self.updated_machine.as_deref()
+            }
+        }
+    };
+}
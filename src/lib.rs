@@ -7,8 +7,8 @@ pub mod viewmodels;
 // Re-export core traits for convenience
 pub use core::crud_list_vm::ActiveableCrudViewModel;
 pub use core::{
-    Activeable, ActiveableRepository, Creatable, CrudViewModelBase, GenericRepository, Sortable,
-    SortableRepository,
+    Activeable, ActiveableRepository, Creatable, CrudViewModelBase, GenericRepository, Hierarchical,
+    HierarchicalRepository, Sortable, SortableRepository, Updatable, UpdatableRepository,
 };
 
 // Re-export viewmodels
@@ -1,5 +1,6 @@
 /// Application settings model
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArchiveLibrary {
@@ -7,12 +8,73 @@ pub struct ArchiveLibrary {
     pub path: String,
 }
 
+/// How item payloads picked on disk are brought into a library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IngestMode {
+    /// Copy into `<library>/<fond_no>/<file_no>/<item_no>/`, leaving the original untouched
+    #[default]
+    Copy,
+    /// Copy, verify, then delete the original
+    Move,
+    /// Only record the original path (the archive depends on files outside the library)
+    Link,
+}
+
+/// Scheduled backup of the open library
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// Days between scheduled backups, 0 disables the schedule
+    pub interval_days: u32,
+    /// Number of scheduled backups kept per library, older ones are deleted
+    pub retention: usize,
+    /// Folder for scheduled backups, None uses the per-user data directory
+    pub directory: Option<String>,
+    /// Also archive payload directories, not only the database
+    pub include_payloads: bool,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            interval_days: 7,
+            retention: 10,
+            directory: None,
+            include_payloads: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub language: String,
     pub theme: String,
     pub archive_libraries: Vec<ArchiveLibrary>,
     pub last_opened_library: Option<String>,
+    /// Days between scheduled fixity checks of item payloads, 0 disables the schedule
+    #[serde(default = "default_fixity_interval_days")]
+    pub fixity_interval_days: u32,
+    /// How new item payloads are stored; linking must be chosen explicitly
+    #[serde(default)]
+    pub ingest_mode: IngestMode,
+    /// Checksum algorithm recorded for new item payloads (`sha256`, or `blake3` when compiled in)
+    #[serde(default = "default_checksum_algorithm")]
+    pub checksum_algorithm: String,
+    /// Scheduled backup settings
+    #[serde(default)]
+    pub backup: BackupSettings,
+    /// Catalogue export columns per level (`fond`, `series`, `file`); levels not listed use the defaults
+    #[serde(default)]
+    pub catalogue_columns: BTreeMap<String, Vec<String>>,
+}
+
+fn default_fixity_interval_days() -> u32 {
+    30
+}
+
+fn default_checksum_algorithm() -> String {
+    "sha256".to_string()
 }
 
 impl Default for AppSettings {
@@ -22,6 +84,11 @@ impl Default for AppSettings {
             theme: "light".to_string(),
             archive_libraries: Vec::new(),
             last_opened_library: None,
+            fixity_interval_days: default_fixity_interval_days(),
+            ingest_mode: IngestMode::default(),
+            checksum_algorithm: default_checksum_algorithm(),
+            backup: BackupSettings::default(),
+            catalogue_columns: BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,212 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::impl_creatable;
+
+table! {
+    audit_log (id) {
+        id -> Integer,
+        entity_type -> Text,
+        entity_id -> Integer,
+        action -> Text,
+        before_json -> Nullable<Text>,
+        after_json -> Nullable<Text>,
+        prev_hash -> Text,
+        hash -> Text,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+/// 审计动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// 新增记录
+    Insert,
+    /// 修改记录（包括激活/停用、调整排序）
+    Update,
+    /// 删除记录
+    Delete,
+    /// 将日志启用前已存在的记录纳入日志（只有变更后 JSON）
+    Snapshot,
+}
+
+impl AuditAction {
+    /// 数据库中保存的动作字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Insert => "insert",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// AuditLog 实体（审计日志，只追加不修改）
+///
+/// 由通用仓储宏在 insert / update / delete 时与业务写操作在同一事务中写入。
+/// 每条日志都包含上一条日志的哈希，构成哈希链，绕过应用直接修改数据库可被校验发现
+///
+/// 约定：
+/// - `id`: 自增主键
+/// - `entity_type`: 实体类型，即表名（如 "files"、"fond_classifications"）
+/// - `entity_id`: 实体 id
+/// - `action`: 动作（insert / update / delete）
+/// - `before_json`: 变更前的实体 JSON，新增时为空
+/// - `after_json`: 变更后的实体 JSON，删除时为空
+/// - `prev_hash`: 上一条日志的哈希，第一条为空字符串
+/// - `hash`: 本条日志的 SHA-256 哈希（十六进制），覆盖 prev_hash 及其余所有字段
+/// - `created_by`: 操作用户
+/// - `created_machine`: 操作机器
+/// - `created_at`: 操作时间
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = audit_log)]
+pub struct AuditLog {
+    pub id: i32,
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub action: String,
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl_creatable!(AuditLog);
+
+impl AuditLog {
+    /// 根据当前字段重新计算哈希，用于校验哈希链
+    pub fn compute_hash(&self) -> String {
+        HashedFields {
+            prev_hash: &self.prev_hash,
+            entity_type: &self.entity_type,
+            entity_id: self.entity_id,
+            action: &self.action,
+            before_json: self.before_json.as_deref(),
+            after_json: self.after_json.as_deref(),
+            created_by: &self.created_by,
+            created_machine: &self.created_machine,
+            created_at: self.created_at,
+        }
+        .hash()
+    }
+}
+
+/// 参与哈希的字段；写入（seal）和校验（compute_hash）共用同一份字段列表
+struct HashedFields<'a> {
+    prev_hash: &'a str,
+    entity_type: &'a str,
+    entity_id: i32,
+    action: &'a str,
+    before_json: Option<&'a str>,
+    after_json: Option<&'a str>,
+    created_by: &'a str,
+    created_machine: &'a str,
+    created_at: NaiveDateTime,
+}
+
+impl HashedFields<'_> {
+    fn hash(&self) -> String {
+        chain_hash(&[
+            self.prev_hash,
+            self.entity_type,
+            &self.entity_id.to_string(),
+            self.action,
+            self.before_json.unwrap_or(""),
+            self.after_json.unwrap_or(""),
+            self.created_by,
+            self.created_machine,
+            &format_timestamp(self.created_at),
+        ])
+    }
+}
+
+/// 哈希中使用的时间格式（毫秒精度，与数据库中保存的精度一致）
+fn format_timestamp(dt: NaiveDateTime) -> String {
+    dt.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+/// 对各字段做 SHA-256，字段之间用单元分隔符隔开，避免拼接歧义
+fn chain_hash(parts: &[&str]) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for (index, part) in parts.iter().enumerate() {
+        if index > 0 {
+            hasher.update([0x1f]);
+        }
+        hasher.update(part.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// NewAuditLog 用于插入审计日志
+/// 不包含 id 字段，让 SQLite 自动生成
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditLog {
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub action: String,
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl NewAuditLog {
+    /// 根据变更前后的实体构造审计日志，时间精度到毫秒
+    pub fn from_change<T: Serialize>(
+        entity_type: &str,
+        entity_id: i32,
+        action: AuditAction,
+        before: Option<&T>,
+        after: Option<&T>,
+        user: &str,
+        machine: &str,
+    ) -> Result<Self, serde_json::Error> {
+        use chrono::Timelike;
+        let now = chrono::Local::now().naive_local();
+        let now_ms = now.with_nanosecond((now.nanosecond() / 1_000_000) * 1_000_000).unwrap();
+        Ok(NewAuditLog {
+            entity_type: entity_type.to_string(),
+            entity_id,
+            action: action.as_str().to_string(),
+            before_json: before.map(serde_json::to_string).transpose()?,
+            after_json: after.map(serde_json::to_string).transpose()?,
+            prev_hash: String::new(),
+            hash: String::new(),
+            created_by: user.to_string(),
+            created_machine: machine.to_string(),
+            created_at: now_ms,
+        })
+    }
+
+    /// 接到哈希链末尾：记录上一条日志的哈希并计算本条哈希
+    pub fn seal(&mut self, prev_hash: &str) {
+        self.prev_hash = prev_hash.to_string();
+        self.hash = HashedFields {
+            prev_hash,
+            entity_type: &self.entity_type,
+            entity_id: self.entity_id,
+            action: &self.action,
+            before_json: self.before_json.as_deref(),
+            after_json: self.after_json.as_deref(),
+            created_by: &self.created_by,
+            created_machine: &self.created_machine,
+            created_at: self.created_at,
+        }
+        .hash();
+    }
+}
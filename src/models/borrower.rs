@@ -0,0 +1,57 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::{impl_creatable, impl_updatable};
+
+table! {
+    borrowers (id) {
+        id -> Integer,
+        name -> Text,
+        department -> Text,
+        contact -> Text,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
+    }
+}
+
+/// Borrower 实体（借阅人）
+///
+/// 约定：
+/// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
+/// - `name`: 借阅人姓名
+/// - `department`: 所属部门，可为空字符串
+/// - `contact`: 联系方式，可为空字符串
+/// - `created_at`: 创建时间，由仓储自动设置
+/// - `created_by`: 创建者，由仓储自动设置
+/// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
+///
+/// 使用示例：
+/// ```ignore
+/// repo.create(Borrower {
+///     name: "张三".into(),
+///     department: "办公室".into(),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = borrowers)]
+pub struct Borrower {
+    pub id: i32,
+    pub name: String,
+    pub department: String,
+    pub contact: String,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+impl_creatable!(Borrower);
+impl_updatable!(Borrower);
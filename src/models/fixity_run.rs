@@ -0,0 +1,53 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::{impl_creatable, impl_updatable};
+
+table! {
+    fixity_runs (id) {
+        id -> Integer,
+        checked_count -> Integer,
+        changed_count -> Integer,
+        missing_count -> Integer,
+        new_count -> Integer,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
+    }
+}
+
+/// FixityRun 实体（完整性校验运行记录）
+///
+/// 每次全库重新计算校验值后写入一条，用于判断定期校验是否到期
+///
+/// 约定：
+/// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
+/// - `checked_count`: 校验的文件数
+/// - `changed_count`: 内容变化的文件数
+/// - `missing_count`: 缺失的文件数
+/// - `new_count`: 清单外新增的文件数
+/// - `created_at`: 运行时间，由仓储自动设置
+/// - `created_by`: 运行者，由仓储自动设置
+/// - `created_machine`: 运行机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = fixity_runs)]
+pub struct FixityRun {
+    pub id: i32,
+    pub checked_count: i32,
+    pub changed_count: i32,
+    pub missing_count: i32,
+    pub new_count: i32,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+impl_creatable!(FixityRun);
+impl_updatable!(FixityRun);
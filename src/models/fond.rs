@@ -1,7 +1,7 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
-use crate::{impl_creatable};
+use crate::{impl_creatable, impl_updatable};
 
 table! {
     fonds (id) {
@@ -12,6 +12,9 @@ table! {
         created_by -> Text,
         created_machine -> Text,
         created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
     }
 }
 
@@ -25,6 +28,7 @@ table! {
 /// - `created_at`: 创建时间，由仓储自动设置
 /// - `created_by`: 创建者，由仓储自动设置
 /// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
 ///
 /// 使用示例：
 /// ```ignore
@@ -45,11 +49,15 @@ pub struct Fond {
     pub created_by: String,
     pub created_machine: String,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
 }
 
 impl_creatable!(Fond);
+impl_updatable!(Fond);
 
-use crate::core::ToCrudListItem;
+use crate::core::{ToCrudListItem, Updatable};
 use crate::CrudListItem;
 
 impl ToCrudListItem for Fond {
@@ -59,6 +67,7 @@ impl ToCrudListItem for Fond {
             title: self.name.clone().into(),
             subtitle: self.fond_no.clone().into(),
             active: true,
+            detail: self.change_summary().into(),
         }
     }
 }
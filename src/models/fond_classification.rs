@@ -1,4 +1,4 @@
-use crate::{impl_activeable, impl_creatable, impl_sortable};
+use crate::{impl_activeable, impl_creatable, impl_hierarchical, impl_updatable, impl_sortable};
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,9 @@ table! {
         created_by -> Text,
         created_machine -> Text,
         created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
     }
 }
 
@@ -29,6 +32,7 @@ table! {
 /// - `created_at`: 创建时间，由仓储自动设置
 /// - `created_by`: 创建者，由仓储自动设置
 /// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
 ///
 /// 使用示例：
 /// ```ignore
@@ -53,13 +57,30 @@ pub struct FondClassification {
     pub created_by: String,
     pub created_machine: String,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
 }
 
 impl_creatable!(FondClassification);
+impl_updatable!(FondClassification);
 impl_activeable!(FondClassification);
 impl_sortable!(FondClassification);
+impl_hierarchical!(FondClassification);
 
-use crate::core::ToCrudListItem;
+impl crate::models::translation::Translatable for FondClassification {
+    const ENTITY_TYPE: &'static str = "fond_classifications";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+}
+
+use crate::core::{ToCrudListItem, Updatable};
 use crate::CrudListItem;
 
 impl ToCrudListItem for FondClassification {
@@ -69,17 +90,25 @@ impl ToCrudListItem for FondClassification {
             title: self.name.clone().into(),
             subtitle: self.code.clone().into(),
             active: self.active,
+            detail: self.change_summary().into(),
         }
     }
 }
 
 /// 用于JSON导入导出的分类结构
+///
+/// `replaces` 列出被本分类取代的旧代码：合并导入时，引用旧代码的全宗改为引用本分类。
+/// `names` 是各语言的名称（语言代码 -> 名称），`name` 是没有对应翻译时显示的名称。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassificationJson {
     pub code: String,
     pub name: String,
     pub active: bool,
     pub children: Vec<ClassificationJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaces: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub names: std::collections::BTreeMap<String, String>,
 }
 
 impl ClassificationJson {
@@ -90,6 +119,8 @@ impl ClassificationJson {
             name: classification.name.clone(),
             active: classification.active,
             children: Vec::new(),
+            replaces: Vec::new(),
+            names: std::collections::BTreeMap::new(),
         }
     }
 
@@ -105,6 +136,9 @@ impl ClassificationJson {
             created_by: "import".to_string(),
             created_machine: std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string()),
             created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+            updated_by: None,
+            updated_machine: None,
         }
     }
 }
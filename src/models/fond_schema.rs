@@ -1,7 +1,7 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
-use crate::impl_creatable;
+use crate::{impl_creatable, impl_updatable};
 use crate::core::Sortable;
 
 table! {
@@ -10,10 +10,14 @@ table! {
         fond_id -> Integer,
         schema_id -> Integer,
         schema_item_id -> Nullable<Integer>,
+        item_level -> Nullable<Integer>,
         sort_order -> Integer,
         created_by -> Text,
         created_machine -> Text,
         created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
     }
 }
 
@@ -23,11 +27,13 @@ table! {
 /// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
 /// - `fond_id`: 全宗ID
 /// - `schema_id`: 方案ID
-/// - `schema_item_id`: 方案项ID，对于动态schema（如Year）可以为NULL
+/// - `schema_item_id`: 方案项ID，对于动态schema（如Year）可以为NULL；指定时只生成该条目及其下级条目的案卷
+/// - `item_level`: 多级方案展开到的层级（0 为顶级条目），比该层浅的叶子条目也包括在内；为NULL时展开到叶子条目
 /// - `sort_order`: 排序顺序，数字越小越靠前
 /// - `created_at`: 创建时间，由仓储自动设置
 /// - `created_by`: 创建者，由仓储自动设置
 /// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
 ///
 /// 使用示例：
 /// ```ignore
@@ -35,6 +41,7 @@ table! {
 ///     fond_id: 1,
 ///     schema_id: 1,
 ///     schema_item_id: Some(1),  // or None for dynamic schemas
+///     item_level: None,         // expand to leaf items
 ///     sort_order: 1,
 ///     ..Default::default()
 /// });
@@ -46,13 +53,19 @@ pub struct FondSchema {
     pub fond_id: i32,
     pub schema_id: i32,
     pub schema_item_id: Option<i32>,
+    #[serde(default)]
+    pub item_level: Option<i32>,
     pub sort_order: i32,
     pub created_by: String,
     pub created_machine: String,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
 }
 
 impl_creatable!(FondSchema);
+impl_updatable!(FondSchema);
 
 impl Sortable for FondSchema {
     fn sort_order(&self) -> i32 {
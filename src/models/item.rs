@@ -1,7 +1,7 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
-use crate::{impl_creatable};
+use crate::{impl_creatable, impl_updatable};
 
 table! {
     items (id) {
@@ -10,9 +10,13 @@ table! {
         item_no -> Text,
         name -> Text,
         path -> Nullable<Text>,
+        source_path -> Nullable<Text>,
         created_by -> Text,
         created_machine -> Text,
         created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
     }
 }
 
@@ -24,9 +28,11 @@ table! {
 /// - `item_no`: 档案项号，唯一标识
 /// - `name`: 档案项名称
 /// - `path`: 文件路径，可为空
+/// - `source_path`: 导入前的原始路径（来源记录），仅链接模式下与 `path` 相同
 /// - `created_at`: 创建时间，由仓储自动设置
 /// - `created_by`: 创建者，由仓储自动设置
 /// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
 ///
 /// 使用示例：
 /// ```ignore
@@ -46,9 +52,14 @@ pub struct Item {
     pub item_no: String,
     pub name: String,
     pub path: Option<String>,
+    pub source_path: Option<String>,
     pub created_by: String,
     pub created_machine: String,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
 }
 
 impl_creatable!(Item);
+impl_updatable!(Item);
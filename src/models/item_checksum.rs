@@ -0,0 +1,56 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::{impl_creatable, impl_updatable};
+
+table! {
+    item_checksums (id) {
+        id -> Integer,
+        item_id -> Integer,
+        relative_path -> Text,
+        algorithm -> Text,
+        checksum -> Text,
+        file_size -> BigInt,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
+    }
+}
+
+/// ItemChecksum 实体（档案项文件校验清单）
+///
+/// 档案项的 path 指向单个文件时，清单只有一条记录；指向文件夹时，文件夹内每个文件各一条
+///
+/// 约定：
+/// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
+/// - `item_id`: 所属档案项 id，外键引用
+/// - `relative_path`: 文件相对档案项路径的位置（以 `/` 分隔）；单个文件时为文件名
+/// - `algorithm`: 校验算法（sha256 / blake3）
+/// - `checksum`: 十六进制校验值
+/// - `file_size`: 文件大小（字节）
+/// - `created_at`: 创建时间，由仓储自动设置
+/// - `created_by`: 创建者，由仓储自动设置
+/// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = item_checksums)]
+pub struct ItemChecksum {
+    pub id: i32,
+    pub item_id: i32,
+    pub relative_path: String,
+    pub algorithm: String,
+    pub checksum: String,
+    pub file_size: i64,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+impl_creatable!(ItemChecksum);
+impl_updatable!(ItemChecksum);
@@ -0,0 +1,125 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::{impl_creatable, impl_updatable};
+
+table! {
+    loans (id) {
+        id -> Integer,
+        borrower_id -> Integer,
+        file_id -> Nullable<Integer>,
+        item_id -> Nullable<Integer>,
+        purpose -> Text,
+        loan_date -> Timestamp,
+        due_date -> Timestamp,
+        return_date -> Nullable<Timestamp>,
+        approval_status -> Text,
+        approved_by -> Text,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
+    }
+}
+
+/// 借阅审批状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalStatus {
+    /// 待审批
+    Pending,
+    /// 已批准
+    Approved,
+    /// 已驳回
+    Rejected,
+}
+
+impl ApprovalStatus {
+    /// 数据库中保存的状态字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+        }
+    }
+
+    /// 从数据库状态字符串解析，未知值视为待审批
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "approved" => ApprovalStatus::Approved,
+            "rejected" => ApprovalStatus::Rejected,
+            _ => ApprovalStatus::Pending,
+        }
+    }
+}
+
+/// Loan 实体（借阅记录）
+///
+/// 约定：
+/// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
+/// - `borrower_id`: 借阅人 id，外键引用
+/// - `file_id` / `item_id`: 借出的案卷或文件，二者有且只有一个
+/// - `purpose`: 借阅目的
+/// - `loan_date`: 借出日期
+/// - `due_date`: 应还日期
+/// - `return_date`: 归还日期，未归还时为空
+/// - `approval_status`: 审批状态（pending / approved / rejected）
+/// - `approved_by`: 审批人，未审批时为空字符串
+/// - `created_at`: 创建时间，由仓储自动设置
+/// - `created_by`: 创建者，由仓储自动设置
+/// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
+///
+/// 使用示例：
+/// ```ignore
+/// repo.create(Loan {
+///     borrower_id: 1,
+///     file_id: Some(1),
+///     purpose: "编史修志".into(),
+///     due_date: due,
+///     approval_status: ApprovalStatus::Pending.as_str().into(),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = loans)]
+pub struct Loan {
+    pub id: i32,
+    pub borrower_id: i32,
+    pub file_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub purpose: String,
+    pub loan_date: NaiveDateTime,
+    pub due_date: NaiveDateTime,
+    pub return_date: Option<NaiveDateTime>,
+    pub approval_status: String,
+    pub approved_by: String,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+impl_creatable!(Loan);
+impl_updatable!(Loan);
+
+impl Loan {
+    /// 审批状态
+    pub fn status(&self) -> ApprovalStatus {
+        ApprovalStatus::parse(&self.approval_status)
+    }
+
+    /// 是否仍处于借出状态（未归还且未被驳回）
+    pub fn is_open(&self) -> bool {
+        self.return_date.is_none() && self.status() != ApprovalStatus::Rejected
+    }
+
+    /// 在给定时间是否已逾期
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        self.is_open() && self.due_date < now
+    }
+}
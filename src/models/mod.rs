@@ -4,9 +4,16 @@ pub mod fond_classification;
 pub mod fond;
 pub mod fond_schema;
 pub mod series;
+pub mod series_schema_item;
 pub mod file;
 pub mod item;
 pub mod sequence;
+pub mod borrower;
+pub mod loan;
+pub mod audit_log;
+pub mod item_checksum;
+pub mod fixity_run;
+pub mod translation;
 pub mod app_settings;
 
 pub use fond::Fond;
\ No newline at end of file
@@ -1,8 +1,8 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
-use crate::{impl_creatable, impl_sortable};
-use crate::core::ToCrudListItem;
+use crate::{impl_creatable, impl_updatable, impl_sortable};
+use crate::core::{ToCrudListItem, Updatable};
 use crate::CrudListItem;
 
 table! {
@@ -14,6 +14,9 @@ table! {
         created_by -> Text,
         created_machine -> Text,
         created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
     }
 }
 
@@ -25,6 +28,7 @@ table! {
 /// - `created_at`: 创建时间，由仓储自动设置
 /// - `created_by`: 创建者，由仓储自动设置
 /// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
 /// 
 /// 使用示例：
 /// ```ignore
@@ -40,9 +44,13 @@ pub struct Schema {
     pub created_by: String,
     pub created_machine: String,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
 }
 
 impl_creatable!(Schema);
+impl_updatable!(Schema);
 impl_sortable!(Schema);
 
 impl ToCrudListItem for Schema {
@@ -52,6 +60,32 @@ impl ToCrudListItem for Schema {
             title: self.name.clone().into(),
             subtitle: self.schema_no.clone().into(),
             active: true,
+            detail: self.change_summary().into(),
+        }
+    }
+}
+
+/// 用于JSON/CSV导入导出的方案结构
+///
+/// 导入时方案按 `schema_no`、条目按 `item_no` 匹配；条目按文件中的顺序排列。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaJson {
+    pub schema_no: String,
+    pub name: String,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub items: Vec<crate::models::schema_item::SchemaItemJson>,
+}
+
+impl SchemaJson {
+    /// 从Schema创建SchemaJson（不含条目）
+    pub fn from_schema(schema: &Schema) -> Self {
+        Self {
+            schema_no: schema.schema_no.clone(),
+            name: schema.name.clone(),
+            sort_order: schema.sort_order,
+            items: Vec::new(),
         }
     }
 }
@@ -1,8 +1,8 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
-use crate::impl_creatable;
-use crate::core::ToCrudListItem;
+use crate::{impl_activeable, impl_creatable, impl_hierarchical, impl_sortable, impl_updatable};
+use crate::core::{ToCrudListItem, Updatable};
 use crate::CrudListItem;
 
 table! {
@@ -11,9 +11,15 @@ table! {
         schema_id -> Integer,
         item_no -> Text,
         item_name -> Text,
+        parent_id -> Nullable<Integer>,
+        active -> Bool,
+        sort_order -> Integer,
         created_by -> Text,
         created_machine -> Text,
         created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
     }
 }
 
@@ -22,27 +28,84 @@ table! {
 /// 约定：
 /// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
 /// - `schema_id`: 关联的 Schema id
+/// - `item_no`: 条目编号，在同一上级条目下唯一（顶级条目在方案内唯一）
+/// - `parent_id`: 上级条目ID，可为空（同一方案内的条目）；案卷号中的维度值由从顶级条目到本条目的编号依次拼接
+/// - `active`: 是否启用；停用的条目不再用于生成新案卷，已有案卷保持不变
+/// - `sort_order`: 同级条目间的排序顺序，数字越小越靠前
 /// - `created_at`: 创建时间，由仓储自动设置
 /// - `created_by`: 创建者，由仓储自动设置
 /// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
 /// 
 /// 使用示例：
 /// ```ignore
 /// repo.create(SchemaItem { schema_id: 1, item_no: "01".into(), item_name: "Name".into(), ..Default::default() });
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+///
+/// 与其他实体不同，`Default` 的 `active` 为 true：新建条目默认启用。
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
 #[diesel(table_name = schema_items)]
 pub struct SchemaItem {
     pub id: i32,
     pub schema_id: i32,
     pub item_no: String,
     pub item_name: String,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    /// 旧的 sidecar 中没有此字段，按启用处理
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default)]
+    pub sort_order: i32,
     pub created_by: String,
     pub created_machine: String,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl Default for SchemaItem {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            schema_id: 0,
+            item_no: String::new(),
+            item_name: String::new(),
+            parent_id: None,
+            active: true,
+            sort_order: 0,
+            created_by: String::new(),
+            created_machine: String::new(),
+            created_at: NaiveDateTime::default(),
+            updated_at: None,
+            updated_by: None,
+            updated_machine: None,
+        }
+    }
 }
 
 impl_creatable!(SchemaItem);
+impl_updatable!(SchemaItem);
+impl_activeable!(SchemaItem);
+impl_sortable!(SchemaItem);
+impl_hierarchical!(SchemaItem);
+
+impl crate::models::translation::Translatable for SchemaItem {
+    const ENTITY_TYPE: &'static str = "schema_items";
+
+    fn name(&self) -> &str {
+        &self.item_name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.item_name = name;
+    }
+}
 
 impl ToCrudListItem for SchemaItem {
     fn to_crud_list_item(&self) -> CrudListItem {
@@ -50,7 +113,38 @@ impl ToCrudListItem for SchemaItem {
             id: self.id,
             title: self.item_name.clone().into(),
             subtitle: self.item_no.clone().into(),
-            active: true,
+            active: self.active,
+            detail: self.change_summary().into(),
+        }
+    }
+}
+
+/// 用于JSON/CSV导入导出的方案条目结构
+///
+/// `names` 是各语言的名称（语言代码 -> 名称），`item_name` 是没有对应翻译时显示的名称。
+/// 条目按树的先序导出，上级条目在前；`parent_no` 是上级条目的编号，顶级条目为空。
+/// `active` 缺省为启用。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaItemJson {
+    pub item_no: String,
+    pub item_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_no: Option<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub names: std::collections::BTreeMap<String, String>,
+}
+
+impl SchemaItemJson {
+    /// 从SchemaItem创建SchemaItemJson（不含翻译和上级条目编号）
+    pub fn from_schema_item(item: &SchemaItem) -> Self {
+        Self {
+            item_no: item.item_no.clone(),
+            item_name: item.item_name.clone(),
+            parent_no: None,
+            active: item.active,
+            names: std::collections::BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,47 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::{impl_creatable, impl_updatable};
+
+table! {
+    series_schema_items (id) {
+        id -> Integer,
+        series_id -> Integer,
+        schema_item_id -> Integer,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
+    }
+}
+
+/// SeriesSchemaItem 实体（系列生成时使用的方案条目）
+///
+/// 生成系列时为每个非 Year 维度记录一条，删除方案条目前据此检查是否仍被系列使用
+///
+/// 约定：
+/// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
+/// - `series_id`: 系列 id，外键引用
+/// - `schema_item_id`: 方案条目 id，外键引用
+/// - `created_at`: 创建时间，由仓储自动设置
+/// - `created_by`: 创建者，由仓储自动设置
+/// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = series_schema_items)]
+pub struct SeriesSchemaItem {
+    pub id: i32,
+    pub series_id: i32,
+    pub schema_item_id: i32,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+impl_creatable!(SeriesSchemaItem);
+impl_updatable!(SeriesSchemaItem);
@@ -0,0 +1,66 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use crate::core::Creatable;
+use crate::{impl_creatable, impl_updatable};
+
+table! {
+    translations (id) {
+        id -> Integer,
+        entity_type -> Text,
+        entity_id -> Integer,
+        locale -> Text,
+        name -> Text,
+        created_by -> Text,
+        created_machine -> Text,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        updated_by -> Nullable<Text>,
+        updated_machine -> Nullable<Text>,
+    }
+}
+
+/// Translation 实体（分类、方案条目名称的多语言翻译）
+///
+/// 实体自身的名称列是未翻译时显示的名称；每个实体每种语言最多一条翻译
+///
+/// 约定：
+/// - `id`: 自增主键，创建时设为 0（由数据库自动生成）
+/// - `entity_type`: 被翻译实体的表名，与审计日志的 entity_type 相同（如 `fond_classifications`）
+/// - `entity_id`: 被翻译实体的 id
+/// - `locale`: 语言代码，与设置中的界面语言相同（如 `en_US`）
+/// - `name`: 该语言的名称
+/// - `created_at`: 创建时间，由仓储自动设置
+/// - `created_by`: 创建者，由仓储自动设置
+/// - `created_machine`: 创建机器，由仓储自动设置
+/// - `updated_at` / `updated_by` / `updated_machine`: 最后修改时间、修改者和机器，由仓储在修改时自动设置
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Default)]
+#[diesel(table_name = translations)]
+pub struct Translation {
+    pub id: i32,
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub locale: String,
+    pub name: String,
+    pub created_by: String,
+    pub created_machine: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub updated_by: Option<String>,
+    pub updated_machine: Option<String>,
+}
+
+impl_creatable!(Translation);
+impl_updatable!(Translation);
+
+/// 名称可以翻译的实体
+pub trait Translatable: Creatable {
+    /// 实体的表名，用作 translations.entity_type
+    const ENTITY_TYPE: &'static str;
+
+    /// 未翻译时的名称
+    fn name(&self) -> &str;
+
+    /// 替换名称（仅用于显示，不写回数据库）
+    fn set_name(&mut self, name: String);
+}
@@ -0,0 +1,125 @@
+use crate::models::audit_log::{audit_log, AuditLog, NewAuditLog};
+use std::cell::RefCell;
+use std::rc::Rc;
+use diesel::prelude::*;
+use chrono::NaiveDateTime;
+
+/// 审计日志查询条件，字段为空表示不限制
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    /// 实体类型（表名）
+    pub entity_type: Option<String>,
+    /// 实体 id
+    pub entity_id: Option<i32>,
+    /// 动作（insert / update / delete）
+    pub action: Option<String>,
+    /// 操作用户
+    pub user: Option<String>,
+    /// 起始时间（含）
+    pub from: Option<NaiveDateTime>,
+    /// 结束时间（不含）
+    pub to: Option<NaiveDateTime>,
+    /// 最多返回条数
+    pub limit: Option<i64>,
+}
+
+/// AuditLogRepository - 审计日志仓储
+///
+/// 审计日志只追加不修改，因此不提供 update / delete，
+/// 也不使用 impl_repository! 宏（宏生成的写操作本身会写审计日志）
+pub struct AuditLogRepository {
+    conn: Rc<RefCell<diesel::SqliteConnection>>,
+}
+
+impl AuditLogRepository {
+    pub fn new(conn: Rc<RefCell<diesel::SqliteConnection>>) -> Self {
+        AuditLogRepository { conn }
+    }
+
+    pub fn update_connection(&mut self, new_conn: Rc<RefCell<diesel::SqliteConnection>>) {
+        self.conn = new_conn;
+    }
+
+    /// 在给定连接上写入一条审计日志，并接到哈希链末尾
+    ///
+    /// 由仓储宏在写操作的事务内调用，因此直接接收连接而不是仓储实例
+    pub fn record(conn: &mut diesel::SqliteConnection, mut entry: NewAuditLog) -> QueryResult<usize> {
+        let prev_hash = Self::last_hash(conn)?;
+        entry.seal(&prev_hash);
+        diesel::insert_into(audit_log::table).values(&entry).execute(conn)
+    }
+
+    /// 哈希链末尾的哈希，日志为空时为空字符串
+    pub fn last_hash(conn: &mut diesel::SqliteConnection) -> QueryResult<String> {
+        let hash = audit_log::table
+            .select(audit_log::hash)
+            .order(audit_log::id.desc())
+            .first::<String>(conn)
+            .optional()?;
+        Ok(hash.unwrap_or_default())
+    }
+
+    /// 最新的一条审计日志
+    pub fn find_last(&mut self) -> Result<Option<AuditLog>, Box<dyn std::error::Error>> {
+        audit_log::table
+            .order(audit_log::id.desc())
+            .first::<AuditLog>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+
+    /// 根据 id 查找审计日志
+    pub fn find_by_id(&mut self, id: i32) -> Result<Option<AuditLog>, Box<dyn std::error::Error>> {
+        audit_log::table
+            .filter(audit_log::id.eq(id))
+            .first::<AuditLog>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+
+    /// 按条件查询审计日志，按时间先后排序
+    pub fn query(&mut self, filter: &AuditLogFilter) -> Result<Vec<AuditLog>, Box<dyn std::error::Error>> {
+        let mut query = audit_log::table.into_boxed();
+
+        if let Some(entity_type) = &filter.entity_type {
+            query = query.filter(audit_log::entity_type.eq(entity_type.clone()));
+        }
+        if let Some(entity_id) = filter.entity_id {
+            query = query.filter(audit_log::entity_id.eq(entity_id));
+        }
+        if let Some(action) = &filter.action {
+            query = query.filter(audit_log::action.eq(action.clone()));
+        }
+        if let Some(user) = &filter.user {
+            query = query.filter(audit_log::created_by.eq(user.clone()));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(audit_log::created_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(audit_log::created_at.lt(to));
+        }
+        if let Some(limit) = filter.limit {
+            query = query.limit(limit);
+        }
+
+        query
+            .order(audit_log::id.asc())
+            .load::<AuditLog>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+
+    /// 查询某个实体的完整变更历史
+    pub fn find_by_entity(&mut self, entity_type: &str, entity_id: i32) -> Result<Vec<AuditLog>, Box<dyn std::error::Error>> {
+        self.query(&AuditLogFilter {
+            entity_type: Some(entity_type.to_string()),
+            entity_id: Some(entity_id),
+            ..Default::default()
+        })
+    }
+
+    /// 查询全部审计日志
+    pub fn find_all(&mut self) -> Result<Vec<AuditLog>, Box<dyn std::error::Error>> {
+        self.query(&AuditLogFilter::default())
+    }
+}
@@ -0,0 +1,11 @@
+use crate::models::borrower::{borrowers, Borrower};
+use crate::impl_repository;
+
+// 使用宏自动生成 BorrowersRepository 和 GenericRepository 实现
+impl_repository!(
+    BorrowersRepository,                                   // 仓储名
+    Borrower,                                              // 实体类型
+    borrowers,                                             // 表模块
+    { name, department, contact, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { name, department, contact }                          // 更新列
+);
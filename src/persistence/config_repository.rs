@@ -24,6 +24,14 @@ impl FileConfigRepository {
         }
     }
 
+    /// Directory of saved library templates, next to `settings.json`
+    pub fn templates_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .map(|dir| dir.join("templates"))
+            .unwrap_or_else(|| PathBuf::from("templates"))
+    }
+
     /// Load settings from configuration file
     pub fn load(&self) -> Result<AppSettings, Box<dyn Error>> {
         if !self.config_path.exists() {
@@ -9,3 +9,16 @@ impl_repository!(
     { series_id, name, file_no, path, created_at, created_by, created_machine }, // 插入列（排除 id）
     { series_id, name, file_no, path }                       // 更新列
 );
+
+// 自定义方法实现
+impl FilesRepository {
+    /// 根据文件编号查找文件
+    pub fn find_by_file_no(&mut self, file_no_param: &str) -> Result<Option<File>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        files::table
+            .filter(files::file_no.eq(file_no_param))
+            .first::<File>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+}
@@ -0,0 +1,24 @@
+use crate::models::fixity_run::{fixity_runs, FixityRun};
+use crate::impl_repository;
+
+// 使用宏自动生成 FixityRunsRepository 和 GenericRepository 实现
+impl_repository!(
+    FixityRunsRepository,                                  // 仓储名
+    FixityRun,                                             // 实体类型
+    fixity_runs,                                           // 表模块
+    { checked_count, changed_count, missing_count, new_count, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { checked_count, changed_count, missing_count, new_count } // 更新列
+);
+
+// 自定义方法实现
+impl FixityRunsRepository {
+    /// 最近一次校验运行
+    pub fn find_last(&mut self) -> Result<Option<FixityRun>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        fixity_runs::table
+            .order(fixity_runs::id.desc())
+            .first::<FixityRun>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+}
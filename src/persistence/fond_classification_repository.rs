@@ -11,6 +11,9 @@ impl_activeable_sortable_repository!(
     sort_order                                             // 排序字段
 );
 
+// 分类树：祖先、后代、移动子树等由 HierarchicalRepository 的默认实现提供
+impl crate::core::HierarchicalRepository<FondClassification> for FondClassificationsRepository {}
+
 // 自定义方法实现
 impl FondClassificationsRepository {
     /// 根据父级ID查找子分类
@@ -30,12 +33,17 @@ impl FondClassificationsRepository {
             .map_err(|e| e.into())
     }
 
-    /// 删除所有分类数据
+    /// 删除所有分类数据（逐条删除，以便每条记录都写入审计日志；下级分类先于上级删除）
     pub fn delete_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        use diesel::prelude::*;
-        use crate::models::fond_classification::fond_classifications::dsl::*;
+        use crate::core::{Creatable, GenericRepository, HierarchicalRepository};
 
-        diesel::delete(fond_classifications).execute(&mut *self.conn.borrow_mut())?;
+        for (_, classification) in self.find_tree(None)?.into_iter().rev() {
+            self.delete(classification.id())?;
+        }
+        // 上级已不存在的分类不在树中
+        for classification in self.find_all()? {
+            self.delete(classification.id())?;
+        }
         Ok(())
     }
 }
\ No newline at end of file
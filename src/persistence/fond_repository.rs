@@ -9,3 +9,16 @@ impl_repository!(
     { fond_no, fond_classification_code, name, created_at, created_by, created_machine }, // 插入列（排除 id）
     { fond_no, fond_classification_code, name }            // 更新列
 );
+
+// 自定义方法实现
+impl FondsRepository {
+    /// 根据全宗号查找全宗
+    pub fn find_by_fond_no(&mut self, fond_no_param: &str) -> Result<Option<Fond>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        fonds::table
+            .filter(fonds::fond_no.eq(fond_no_param))
+            .first::<Fond>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+}
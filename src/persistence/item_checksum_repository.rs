@@ -0,0 +1,24 @@
+use crate::models::item_checksum::{item_checksums, ItemChecksum};
+use crate::impl_repository;
+
+// 使用宏自动生成 ItemChecksumsRepository 和 GenericRepository 实现
+impl_repository!(
+    ItemChecksumsRepository,                               // 仓储名
+    ItemChecksum,                                          // 实体类型
+    item_checksums,                                        // 表模块
+    { item_id, relative_path, algorithm, checksum, file_size, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { item_id, relative_path, algorithm, checksum, file_size } // 更新列
+);
+
+// 自定义方法实现
+impl ItemChecksumsRepository {
+    /// 查找档案项的校验清单，按相对路径排序
+    pub fn find_by_item_id(&mut self, item_id_param: i32) -> Result<Vec<ItemChecksum>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        item_checksums::table
+            .filter(item_checksums::item_id.eq(item_id_param))
+            .order(item_checksums::relative_path.asc())
+            .load::<ItemChecksum>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+}
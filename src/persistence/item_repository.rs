@@ -6,6 +6,19 @@ impl_repository!(
     ItemsRepository,                                      // 仓储名
     Item,                                                  // 实体类型
     items,                                                 // 表模块
-    { file_id, item_no, name, path, created_at, created_by, created_machine }, // 插入列（排除 id）
-    { file_id, item_no, name, path }                       // 更新列
+    { file_id, item_no, name, path, source_path, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { file_id, item_no, name, path, source_path }          // 更新列
 );
+
+// 自定义方法实现
+impl ItemsRepository {
+    /// 根据档案项号查找档案项
+    pub fn find_by_item_no(&mut self, item_no_param: &str) -> Result<Option<Item>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        items::table
+            .filter(items::item_no.eq(item_no_param))
+            .first::<Item>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+}
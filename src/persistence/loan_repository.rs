@@ -0,0 +1,46 @@
+use crate::models::loan::{loans, Loan};
+use crate::impl_repository;
+
+// 使用宏自动生成 LoansRepository 和 GenericRepository 实现
+impl_repository!(
+    LoansRepository,                                       // 仓储名
+    Loan,                                                  // 实体类型
+    loans,                                                 // 表模块
+    { borrower_id, file_id, item_id, purpose, loan_date, due_date, return_date, approval_status, approved_by, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { borrower_id, file_id, item_id, purpose, loan_date, due_date, return_date, approval_status, approved_by } // 更新列
+);
+
+// 自定义方法实现
+impl LoansRepository {
+    /// 查找案卷尚未归还的借阅记录（驳回的申请不计）
+    pub fn find_open_by_file_id(&mut self, file_id_param: i32) -> Result<Vec<Loan>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        loans::table
+            .filter(loans::file_id.eq(file_id_param))
+            .filter(loans::return_date.is_null())
+            .filter(loans::approval_status.ne("rejected"))
+            .load::<Loan>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+
+    /// 查找文件尚未归还的借阅记录（驳回的申请不计）
+    pub fn find_open_by_item_id(&mut self, item_id_param: i32) -> Result<Vec<Loan>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        loans::table
+            .filter(loans::item_id.eq(item_id_param))
+            .filter(loans::return_date.is_null())
+            .filter(loans::approval_status.ne("rejected"))
+            .load::<Loan>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+
+    /// 查找借阅人的全部借阅记录，按借出日期倒序
+    pub fn find_by_borrower_id(&mut self, borrower_id_param: i32) -> Result<Vec<Loan>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        loans::table
+            .filter(loans::borrower_id.eq(borrower_id_param))
+            .order(loans::loan_date.desc())
+            .load::<Loan>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+}
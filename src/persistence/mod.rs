@@ -5,27 +5,43 @@ pub mod fond_classification_repository;
 pub mod fond_repository;
 pub mod fond_schema_repository;
 pub mod series_repository;
+pub mod series_schema_item_repository;
 pub mod file_repository;
 pub mod item_repository;
 pub mod sequences_repository;
+pub mod borrower_repository;
+pub mod loan_repository;
+pub mod audit_log_repository;
+pub mod item_checksum_repository;
+pub mod fixity_run_repository;
 pub mod config_repository;
+pub mod translation_repository;
+pub mod sidecars;
 
 // Re-export core traits for convenience
 pub use crate::core::generic_repository::{Creatable, GenericRepository};
 pub use crate::core::sortable_repository::{Sortable, SortableRepository};
 pub use crate::core::activeable_repository::{Activeable, ActiveableRepository};
+pub use crate::core::hierarchical_repository::{Hierarchical, HierarchicalRepository};
 pub use fond_classification_repository::FondClassificationsRepository;
 pub use fond_repository::FondsRepository;
 pub use fond_schema_repository::FondSchemasRepository;
 pub use series_repository::SeriesRepository;
+pub use series_schema_item_repository::SeriesSchemaItemsRepository;
 pub use file_repository::FilesRepository;
 pub use item_repository::ItemsRepository;
 pub use sequences_repository::SequencesRepository;
+pub use borrower_repository::BorrowersRepository;
+pub use loan_repository::LoansRepository;
+pub use audit_log_repository::{AuditLogRepository, AuditLogFilter};
+pub use item_checksum_repository::ItemChecksumsRepository;
+pub use fixity_run_repository::FixityRunsRepository;
 pub use config_repository::FileConfigRepository;
+pub use translation_repository::TranslationsRepository;
 pub use schema_repository::SchemaRepository;
 
 use diesel::sqlite::SqliteConnection;
-use diesel::Connection;
+use diesel::{Connection, RunQueryDsl};
 use std::error::Error;
 use std::path::Path;
 use std::rc::Rc;
@@ -35,6 +51,93 @@ use std::cell::RefCell;
 pub fn establish_connection(database_path: &Path) -> Result<Rc<RefCell<SqliteConnection>>, Box<dyn Error>> {
     let database_url = database_path.to_string_lossy().to_string();
     let mut connection = SqliteConnection::establish(&database_url)?;
+    // SQLite 默认不检查外键；每个连接都要单独打开
+    diesel::sql_query("PRAGMA foreign_keys = ON").execute(&mut connection)?;
     schema::init_schema(&mut connection)?;
     Ok(Rc::new(RefCell::new(connection)))
-}
\ No newline at end of file
+}
+
+/// Run several repository writes as one transaction
+///
+/// `Connection::transaction` would keep the shared connection borrowed while
+/// `work` runs, so the outer transaction is driven through the transaction
+/// manager instead; the repositories' own transactions become savepoints.
+/// The library sidecar is rewritten once when the outermost transaction ends,
+/// also after a rollback; other sidecars refreshed inside `work` are not
+/// restored on rollback.
+pub fn with_transaction<T>(
+    conn: &Rc<RefCell<SqliteConnection>>,
+    work: impl FnOnce() -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    use diesel::connection::TransactionManager;
+    type Manager = <SqliteConnection as Connection>::TransactionManager;
+
+    Manager::begin_transaction(&mut *conn.borrow_mut())?;
+    let result = match work() {
+        Ok(value) => Manager::commit_transaction(&mut *conn.borrow_mut()).map(|_| value).map_err(Into::into),
+        Err(e) => {
+            if let Err(rollback) = Manager::rollback_transaction(&mut *conn.borrow_mut()) {
+                log::error!("Failed to roll back transaction: {}", rollback);
+            }
+            Err(e)
+        }
+    };
+    sidecars::after_transaction(&mut conn.borrow_mut());
+    result
+}
+
+/// Copy a live database file with SQLite's online backup API
+///
+/// Opens its own connections, so the copy is a consistent snapshot even while
+/// the application keeps the library open.
+pub fn backup_database(source: &Path, destination: &Path) -> Result<(), Box<dyn Error>> {
+    use libsqlite3_sys as ffi;
+    use std::ffi::{CStr, CString};
+
+    fn open(path: &Path, flags: i32) -> Result<*mut ffi::sqlite3, Box<dyn Error>> {
+        let c_path = CString::new(path.to_string_lossy().as_bytes())?;
+        let mut db = std::ptr::null_mut();
+        // SAFETY: c_path outlives the call; db is closed by the caller (or here on failure)
+        let rc = unsafe { ffi::sqlite3_open_v2(c_path.as_ptr(), &mut db, flags, std::ptr::null()) };
+        if rc != ffi::SQLITE_OK {
+            unsafe { ffi::sqlite3_close(db) };
+            return Err(format!("无法打开数据库 {} (SQLite 错误 {})", path.display(), rc).into());
+        }
+        Ok(db)
+    }
+
+    let src = open(source, ffi::SQLITE_OPEN_READONLY)?;
+    let dst = match open(destination, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE) {
+        Ok(dst) => dst,
+        Err(e) => {
+            unsafe { ffi::sqlite3_close(src) };
+            return Err(e);
+        }
+    };
+
+    let main = CString::new("main")?;
+    // SAFETY: both handles are open until the close calls below; the backup
+    // object is always released with sqlite3_backup_finish
+    let result = unsafe {
+        let backup = ffi::sqlite3_backup_init(dst, main.as_ptr(), src, main.as_ptr());
+        let rc = if backup.is_null() {
+            ffi::sqlite3_errcode(dst)
+        } else {
+            // 其他连接持有写锁时稍后重试
+            while let ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED = ffi::sqlite3_backup_step(backup, -1) {
+                ffi::sqlite3_sleep(50);
+            }
+            ffi::sqlite3_backup_finish(backup)
+        };
+        let result = if rc == ffi::SQLITE_OK {
+            Ok(())
+        } else {
+            let message = CStr::from_ptr(ffi::sqlite3_errmsg(dst)).to_string_lossy().to_string();
+            Err(format!("数据库备份失败: {}", message).into())
+        };
+        ffi::sqlite3_close(dst);
+        ffi::sqlite3_close(src);
+        result
+    };
+    result
+}
@@ -17,6 +17,9 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
             FOREIGN KEY (parent_id) REFERENCES fond_classifications(id)
         )
         "#,
@@ -33,29 +36,17 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             sort_order INTEGER NOT NULL DEFAULT 0,
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
-            created_at TEXT NOT NULL
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT
         )
         "#,
     )
     .execute(conn)?;
 
     // Create schema_items table (id 自增主键)
-    sql_query(
-        r#"
-        CREATE TABLE IF NOT EXISTS schema_items (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            schema_id INTEGER NOT NULL,
-            item_no TEXT NOT NULL,
-            item_name TEXT NOT NULL,
-            created_by TEXT NOT NULL,
-            created_machine TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            UNIQUE (schema_id, item_no),
-            FOREIGN KEY (schema_id) REFERENCES schemas(id)
-        )
-        "#,
-    )
-    .execute(conn)?;
+    create_schema_items_table(conn, "schema_items")?;
 
     // Create fonds table
     sql_query(
@@ -67,7 +58,10 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             name TEXT NOT NULL,
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
-            created_at TEXT NOT NULL
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT
         )
         "#,
     )
@@ -81,10 +75,14 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             fond_id INTEGER NOT NULL,
             schema_id INTEGER NOT NULL,
             schema_item_id INTEGER,  -- Nullable for dynamic schemas like Year
+            item_level INTEGER,      -- NULL expands the dimension to its leaf items
             sort_order INTEGER NOT NULL,
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
             UNIQUE (fond_id, schema_id, schema_item_id),
             FOREIGN KEY (fond_id) REFERENCES fonds(id),
             FOREIGN KEY (schema_id) REFERENCES schemas(id),
@@ -105,12 +103,36 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
             FOREIGN KEY (fond_id) REFERENCES fonds(id)
         )
         "#,
     )
     .execute(conn)?;
 
+    // Create series_schema_items table (系列生成时使用的方案条目)
+    sql_query(
+        r#"
+        CREATE TABLE IF NOT EXISTS series_schema_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            series_id INTEGER NOT NULL,
+            schema_item_id INTEGER NOT NULL,
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
+            UNIQUE (series_id, schema_item_id),
+            FOREIGN KEY (series_id) REFERENCES series(id),
+            FOREIGN KEY (schema_item_id) REFERENCES schema_items(id)
+        )
+        "#,
+    )
+    .execute(conn)?;
+
     // Create files table
     sql_query(
         r#"
@@ -123,6 +145,9 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
             FOREIGN KEY (series_id) REFERENCES series(id)
         )
         "#,
@@ -138,9 +163,13 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
             item_no TEXT NOT NULL DEFAULT '',
             name TEXT NOT NULL,
             path TEXT,
+            source_path TEXT,
             created_by TEXT NOT NULL,
             created_machine TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
             FOREIGN KEY (file_id) REFERENCES files(id)
         )
         "#,
@@ -163,11 +192,152 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
     )
     .execute(conn)?;
 
-    // Insert default Year schema if not exists
+    // Create borrowers table
     sql_query(
         r#"
-        INSERT OR IGNORE INTO schemas (schema_no, name, sort_order, created_by, created_machine, created_at)
-        VALUES ('Year', 'Year', 0, 'system', 'system', CURRENT_TIMESTAMP)
+        CREATE TABLE IF NOT EXISTS borrowers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            department TEXT NOT NULL DEFAULT '',
+            contact TEXT NOT NULL DEFAULT '',
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT
+        )
+        "#,
+    )
+    .execute(conn)?;
+
+    // Create loans table (file_id / item_id 二者有且只有一个)
+    sql_query(
+        r#"
+        CREATE TABLE IF NOT EXISTS loans (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            borrower_id INTEGER NOT NULL,
+            file_id INTEGER,
+            item_id INTEGER,
+            purpose TEXT NOT NULL DEFAULT '',
+            loan_date TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            return_date TEXT,
+            approval_status TEXT NOT NULL DEFAULT 'pending',
+            approved_by TEXT NOT NULL DEFAULT '',
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
+            FOREIGN KEY (borrower_id) REFERENCES borrowers(id),
+            FOREIGN KEY (file_id) REFERENCES files(id),
+            FOREIGN KEY (item_id) REFERENCES items(id)
+        )
+        "#,
+    )
+    .execute(conn)?;
+
+    // Create item_checksums table (档案项文件校验清单)
+    sql_query(
+        r#"
+        CREATE TABLE IF NOT EXISTS item_checksums (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            relative_path TEXT NOT NULL,
+            algorithm TEXT NOT NULL DEFAULT 'sha256',
+            checksum TEXT NOT NULL,
+            file_size BIGINT NOT NULL DEFAULT 0,
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
+            UNIQUE (item_id, relative_path),
+            FOREIGN KEY (item_id) REFERENCES items(id)
+        )
+        "#,
+    )
+    .execute(conn)?;
+
+    // Create fixity_runs table (完整性校验运行记录)
+    sql_query(
+        r#"
+        CREATE TABLE IF NOT EXISTS fixity_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            checked_count INTEGER NOT NULL DEFAULT 0,
+            changed_count INTEGER NOT NULL DEFAULT 0,
+            missing_count INTEGER NOT NULL DEFAULT 0,
+            new_count INTEGER NOT NULL DEFAULT 0,
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT
+        )
+        "#,
+    )
+    .execute(conn)?;
+
+    // Create translations table (分类、方案条目名称的多语言翻译，每个实体每种语言一条)
+    sql_query(
+        r#"
+        CREATE TABLE IF NOT EXISTS translations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity_type TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            locale TEXT NOT NULL,
+            name TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
+            UNIQUE (entity_type, entity_id, locale)
+        )
+        "#,
+    )
+    .execute(conn)?;
+
+    // Create audit_log table (只追加，触发器禁止修改和删除)
+    sql_query(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity_type TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            before_json TEXT,
+            after_json TEXT,
+            prev_hash TEXT NOT NULL DEFAULT '',
+            hash TEXT NOT NULL DEFAULT '',
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(conn)?;
+    sql_query("CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id)").execute(conn)?;
+    sql_query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END
+        "#,
+    )
+    .execute(conn)?;
+    sql_query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END
         "#,
     )
     .execute(conn)?;
@@ -181,5 +351,124 @@ pub fn init_schema(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
     let _ = sql_query("ALTER TABLE series ADD COLUMN series_no TEXT NOT NULL DEFAULT ''").execute(conn);
     let _ = sql_query("ALTER TABLE items ADD COLUMN item_no TEXT NOT NULL DEFAULT ''").execute(conn);
 
+    // Add source_path (provenance of ingested payloads) to items if it doesn't exist
+    let _ = sql_query("ALTER TABLE items ADD COLUMN source_path TEXT").execute(conn);
+
+    // Add active / sort_order columns to schema_items if they don't exist
+    let _ = sql_query("ALTER TABLE schema_items ADD COLUMN active BOOLEAN NOT NULL DEFAULT 1").execute(conn);
+    let _ = sql_query("ALTER TABLE schema_items ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0").execute(conn);
+
+    // Add schema item nesting and dimension levels if they don't exist
+    let _ = sql_query("ALTER TABLE schema_items ADD COLUMN parent_id INTEGER REFERENCES schema_items(id)").execute(conn);
+    let _ = sql_query("ALTER TABLE fond_schemas ADD COLUMN item_level INTEGER").execute(conn);
+
+    // Add hash chain columns to audit_log if they don't exist
+    let _ = sql_query("ALTER TABLE audit_log ADD COLUMN prev_hash TEXT NOT NULL DEFAULT ''").execute(conn);
+    let _ = sql_query("ALTER TABLE audit_log ADD COLUMN hash TEXT NOT NULL DEFAULT ''").execute(conn);
+
+    // Add updated_at / updated_by / updated_machine columns to all entity tables if they don't exist
+    for table in [
+        "fond_classifications",
+        "schemas",
+        "schema_items",
+        "fonds",
+        "fond_schemas",
+        "series",
+        "files",
+        "items",
+        "borrowers",
+        "loans",
+    ] {
+        let _ = sql_query(format!("ALTER TABLE {} ADD COLUMN updated_at TEXT", table)).execute(conn);
+        let _ = sql_query(format!("ALTER TABLE {} ADD COLUMN updated_by TEXT", table)).execute(conn);
+        let _ = sql_query(format!("ALTER TABLE {} ADD COLUMN updated_machine TEXT", table)).execute(conn);
+    }
+
+    // Item numbers used to be unique per schema; nested items only need them unique under their parent
+    scope_schema_item_numbers(conn)?;
+    // UNIQUE 约束不比较 NULL，顶级条目的编号由部分唯一索引保证不重复
+    sql_query("CREATE UNIQUE INDEX IF NOT EXISTS idx_schema_items_top_level_no ON schema_items(schema_id, item_no) WHERE parent_id IS NULL")
+        .execute(conn)?;
+
+    // Insert default Year schema if not exists (after all columns exist)
+    let inserted = sql_query(
+        r#"
+        INSERT OR IGNORE INTO schemas (schema_no, name, sort_order, created_by, created_machine, created_at)
+        VALUES ('Year', 'Year', 0, 'system', 'system', CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(conn)?;
+
+    // 新建的 Year 记录不经过仓储，单独写入审计日志，使哈希链覆盖全部记录
+    if inserted > 0 {
+        use crate::models::audit_log::{AuditAction, NewAuditLog};
+        use crate::models::schema::{schemas, Schema};
+        use crate::persistence::audit_log_repository::AuditLogRepository;
+
+        let year = schemas::table
+            .filter(schemas::schema_no.eq("Year"))
+            .first::<Schema>(conn)?;
+        let entry = NewAuditLog::from_change("schemas", year.id, AuditAction::Snapshot, None, Some(&year), "system", "system")?;
+        AuditLogRepository::record(conn, entry)?;
+    }
+
     Ok(())
 }
+
+/// Create the schema_items table under `name`
+fn create_schema_items_table(conn: &mut SqliteConnection, name: &str) -> Result<(), Box<dyn Error>> {
+    sql_query(format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            schema_id INTEGER NOT NULL,
+            item_no TEXT NOT NULL,
+            item_name TEXT NOT NULL,
+            parent_id INTEGER,
+            active BOOLEAN NOT NULL DEFAULT 1,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            updated_machine TEXT,
+            UNIQUE (schema_id, parent_id, item_no),
+            FOREIGN KEY (schema_id) REFERENCES schemas(id),
+            FOREIGN KEY (parent_id) REFERENCES schema_items(id)
+        )
+        "#,
+        name
+    ))
+    .execute(conn)?;
+    Ok(())
+}
+
+/// Rebuild a schema_items table whose item numbers are unique per schema, so they are unique per parent
+///
+/// SQLite cannot drop a table constraint, so the rows are copied into a new table
+/// with foreign key checks off while the old table is replaced.
+fn scope_schema_item_numbers(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
+    #[derive(QueryableByName)]
+    struct TableSql {
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        sql: String,
+    }
+    const COLUMNS: &str = "id, schema_id, item_no, item_name, parent_id, active, sort_order, created_by, created_machine, created_at, updated_at, updated_by, updated_machine";
+
+    let tables = sql_query("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'schema_items'").load::<TableSql>(conn)?;
+    if !tables.iter().any(|t| t.sql.contains("UNIQUE (schema_id, item_no)")) {
+        return Ok(());
+    }
+    // 外键开关在事务内无效，先关闭再开始事务
+    sql_query("PRAGMA foreign_keys = OFF").execute(conn)?;
+    let rebuilt = conn.transaction(|conn| {
+        create_schema_items_table(conn, "schema_items_new")?;
+        sql_query(format!("INSERT INTO schema_items_new ({0}) SELECT {0} FROM schema_items", COLUMNS)).execute(conn)?;
+        sql_query("DROP TABLE schema_items").execute(conn)?;
+        sql_query("ALTER TABLE schema_items_new RENAME TO schema_items").execute(conn)?;
+        Ok::<_, Box<dyn Error>>(())
+    });
+    sql_query("PRAGMA foreign_keys = ON").execute(conn)?;
+    rebuilt
+}
@@ -1,21 +1,204 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use crate::core::{HierarchicalRepository, TreeMove};
 use crate::models::schema_item::{schema_items, SchemaItem};
-use crate::impl_repository;
+use crate::impl_activeable_sortable_repository;
 use crate::GenericRepository;
 
-// 使用宏自动生成 SchemaItemRepository 和 GenericRepository 实现
-impl_repository!(
+// 使用宏自动生成 SchemaItemRepository 和 ActiveableRepository + SortableRepository 实现
+impl_activeable_sortable_repository!(
     SchemaItemRepository,                                              // 仓储名
     SchemaItem,                                                        // 实体类型
     schema_items,                                                      // 表模块
-    { schema_id, item_no, item_name, created_at, created_by, created_machine }, // 插入列（排除 id）
-    { schema_id, item_no, item_name }                                  // 更新列
+    { schema_id, item_no, item_name, parent_id, active, sort_order, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { schema_id, item_no, item_name, parent_id, active, sort_order },  // 更新列
+    sort_order                                                         // 排序字段
 );
 
+// 条目树：祖先、后代、删除子树、连带启用停用等由 HierarchicalRepository 的默认实现提供；
+// 各方案的顶级条目都没有上级，所以同级条目的查找和移动使用下面按方案区分的方法
+impl HierarchicalRepository<SchemaItem> for SchemaItemRepository {}
+
 // 额外的自定义方法
 impl SchemaItemRepository {
-    /// Find all items belonging to a specific schema
+    /// Find all items belonging to a specific schema, in tree order
+    /// (each item is followed by its descendants, siblings by sort order)
     pub fn find_by_schema_id(&mut self, schema_id_val: i32) -> Result<Vec<SchemaItem>, Box<dyn Error>> {
-        self.find_by_predicate(|item| item.schema_id == schema_id_val)
+        Ok(self.find_tree_by_schema_id(schema_id_val)?.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// Items of a schema with their depth (0 for top-level items), in tree order
+    pub fn find_tree_by_schema_id(&mut self, schema_id_val: i32) -> Result<Vec<(usize, SchemaItem)>, Box<dyn Error>> {
+        Ok(self.find_tree(None)?.into_iter().filter(|(_, item)| item.schema_id == schema_id_val).collect())
+    }
+
+    /// Active items of a schema whose ancestors are active too, in tree order
+    pub fn find_active_by_schema_id(&mut self, schema_id_val: i32) -> Result<Vec<SchemaItem>, Box<dyn Error>> {
+        let items = self.find_by_schema_id(schema_id_val)?;
+        let by_id: HashMap<i32, &SchemaItem> = items.iter().map(|item| (item.id, item)).collect();
+        Ok(items.iter().filter(|item| path_of(&by_id, item).iter().all(|i| i.active)).cloned().collect())
+    }
+
+    /// Sort order for an item appended after every item of a schema
+    pub fn next_sort_order(&mut self, schema_id_val: i32) -> Result<i32, Box<dyn Error>> {
+        Ok(self.find_by_predicate(|item| item.schema_id == schema_id_val)?.iter().map(|item| item.sort_order + 1).max().unwrap_or(0))
+    }
+
+    /// Items of a schema under `parent_id` (None for top-level items), in order
+    pub fn find_siblings(&mut self, schema_id_val: i32, parent_id_val: Option<i32>) -> Result<Vec<SchemaItem>, Box<dyn Error>> {
+        let mut items = self.find_by_predicate(|item| item.schema_id == schema_id_val && item.parent_id == parent_id_val)?;
+        items.sort_by_key(|item| (item.sort_order, item.id));
+        Ok(items)
+    }
+
+    /// Move an item up (negative) or down (positive) among its siblings in the schema;
+    /// the siblings are renumbered from 0
+    pub fn move_within_schema(&mut self, id: i32, offset: i32) -> Result<(), Box<dyn Error>> {
+        let item = self.find_by_id(id)?.ok_or("条目不存在")?;
+        let siblings = self.find_siblings(item.schema_id, item.parent_id)?;
+        let index = siblings.iter().position(|i| i.id == id).unwrap_or(0) as i32;
+        let target = (index + offset).clamp(0, siblings.len() as i32 - 1);
+        if target != index {
+            self.move_to_parent(id, item.parent_id, target as usize)?;
+        }
+        Ok(())
+    }
+
+    /// Move an item with its descendants under `parent_id` of the same schema (None for top level),
+    /// at `position` among the new siblings
+    pub fn move_to_parent(&mut self, id: i32, parent_id_val: Option<i32>, position: usize) -> Result<(), Box<dyn Error>> {
+        let mut moved = self.find_by_id(id)?.ok_or("条目不存在")?;
+        if let Some(parent_id_val) = parent_id_val {
+            let parent = self.find_by_id(parent_id_val)?.ok_or("上级条目不存在")?;
+            if parent.schema_id != moved.schema_id {
+                return Err("上级条目必须属于同一方案".into());
+            }
+            if parent_id_val == id || self.find_ancestors(parent_id_val)?.iter().any(|a| a.id == id) {
+                return Err("不能移动到自身或其下级之下".into());
+            }
+        }
+
+        let mut siblings: Vec<SchemaItem> =
+            self.find_siblings(moved.schema_id, parent_id_val)?.into_iter().filter(|i| i.id != id).collect();
+        moved.parent_id = parent_id_val;
+        siblings.insert(position.min(siblings.len()), moved);
+        for (order, mut item) in siblings.into_iter().enumerate() {
+            if item.id == id || item.sort_order != order as i32 {
+                item.sort_order = order as i32;
+                self.update(&item)?;
+            }
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Move an item in the schema tree: up or down among its siblings, out of its parent
+    /// (placed right after it) or into the previous sibling (placed last)
+    pub fn move_in_tree(&mut self, id: i32, movement: TreeMove) -> Result<(), Box<dyn Error>> {
+        let item = self.find_by_id(id)?.ok_or("条目不存在")?;
+        match movement {
+            TreeMove::Up => self.move_within_schema(id, -1),
+            TreeMove::Down => self.move_within_schema(id, 1),
+            TreeMove::Outdent => {
+                let parent_id_val = item.parent_id.ok_or("顶级条目不能再升级")?;
+                let parent = self.find_by_id(parent_id_val)?.ok_or("上级条目不存在")?;
+                let siblings = self.find_siblings(parent.schema_id, parent.parent_id)?;
+                let position = siblings.iter().position(|i| i.id == parent_id_val).unwrap_or(0);
+                self.move_to_parent(id, parent.parent_id, position + 1)
+            }
+            TreeMove::Indent => {
+                let siblings = self.find_siblings(item.schema_id, item.parent_id)?;
+                let position = siblings.iter().position(|i| i.id == id).unwrap_or(0);
+                let previous = position.checked_sub(1).map(|i| siblings[i].id).ok_or("没有可归入的上一个同级条目")?;
+                self.move_to_parent(id, Some(previous), usize::MAX)
+            }
+        }
+    }
+
+    /// Path numbers of an item and of each of its descendants - the values a series
+    /// number carries for the dimension when the series uses the item
+    pub fn find_path_nos(&mut self, id: i32) -> Result<Vec<String>, Box<dyn Error>> {
+        let item = self.find_by_id(id)?.ok_or("条目不存在")?;
+        let items = self.find_by_schema_id(item.schema_id)?;
+        let by_id: HashMap<i32, &SchemaItem> = items.iter().map(|i| (i.id, i)).collect();
+        Ok(items
+            .iter()
+            .map(|i| path_of(&by_id, i))
+            .filter(|path| path.iter().any(|i| i.id == id))
+            .map(|path| path_no(&path))
+            .collect())
+    }
+
+    /// Every item of a schema with its path number, inactive items included - the values
+    /// a series number can carry for the schema, whatever level the dimension expanded to
+    pub fn find_path_numbered(&mut self, schema_id_val: i32) -> Result<Vec<(String, i32)>, Box<dyn Error>> {
+        let items = self.find_by_schema_id(schema_id_val)?;
+        let by_id: HashMap<i32, &SchemaItem> = items.iter().map(|i| (i.id, i)).collect();
+        Ok(items.iter().map(|i| (path_no(&path_of(&by_id, i)), i.id)).collect())
+    }
+
+    /// Values a fond dimension expands to, in tree order
+    ///
+    /// `level` None selects leaf items; `Some(n)` selects items at depth n (0 for top-level
+    /// items) and leaf items above it. With `root` only that item and its descendants are
+    /// considered. Items under an inactive item are skipped like inactive items. Each
+    /// returned item has `item_no` set to the numbers on its path from the top level
+    /// joined by `PATH_SEPARATOR`, and `item_name` to the names on that path joined by "/".
+    /// Two items with the same path number would give colliding series numbers, so that is
+    /// an error rather than a silently skipped series.
+    pub fn find_expanded(&mut self, schema_id_val: i32, root: Option<i32>, level: Option<i32>) -> Result<Vec<SchemaItem>, Box<dyn Error>> {
+        let tree = self.find_tree_by_schema_id(schema_id_val)?;
+        let by_id: HashMap<i32, &SchemaItem> = tree.iter().map(|(_, item)| (item.id, item)).collect();
+        let parents: HashSet<i32> = tree.iter().filter_map(|(_, item)| item.parent_id).collect();
+        let level = match root {
+            Some(root_id) => match tree.iter().find(|(_, item)| item.id == root_id) {
+                // 指定的条目比所选层级更深时，只生成该条目
+                Some((depth, _)) => level.map(|level| level.max(*depth as i32)),
+                None => return Ok(Vec::new()),
+            },
+            None => level,
+        };
+
+        let mut expanded = Vec::new();
+        for (depth, item) in &tree {
+            let leaf = !parents.contains(&item.id);
+            let selected = match level {
+                None => leaf,
+                Some(level) => *depth as i32 == level || ((*depth as i32) < level && leaf),
+            };
+            let path = path_of(&by_id, item);
+            if !selected || !path.iter().all(|i| i.active) || root.is_some_and(|root_id| !path.iter().any(|i| i.id == root_id)) {
+                continue;
+            }
+            let item_no = path_no(&path);
+            if expanded.iter().any(|i: &SchemaItem| i.item_no == item_no) {
+                return Err(format!("条目编号路径 {} 重复，请修改同级条目的编号", item_no).into());
+            }
+            expanded.push(SchemaItem {
+                item_no,
+                item_name: path.iter().map(|i| i.item_name.as_str()).collect::<Vec<_>>().join("/"),
+                ..(*item).clone()
+            });
+        }
+        Ok(expanded)
+    }
+}
+
+/// Items from the top level down to `item`; stops at a missing parent or a cycle
+fn path_of<'a>(by_id: &HashMap<i32, &'a SchemaItem>, item: &'a SchemaItem) -> Vec<&'a SchemaItem> {
+    let mut path = vec![item];
+    while let Some(parent) = path.last().and_then(|i| i.parent_id).and_then(|id| by_id.get(&id)) {
+        if path.iter().any(|i| i.id == parent.id) {
+            break;
+        }
+        path.push(parent);
+    }
+    path.reverse();
+    path
+}
+
+/// Separator between the item numbers on a path, e.g. `01.A`
+pub const PATH_SEPARATOR: &str = ".";
+
+fn path_no(path: &[&SchemaItem]) -> String {
+    path.iter().map(|i| i.item_no.as_str()).collect::<Vec<_>>().join(PATH_SEPARATOR)
+}
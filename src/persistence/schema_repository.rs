@@ -15,7 +15,7 @@ impl_sortable_repository!(
 );
 
 impl SchemaRepository {
-    /// 自定义删除方法，防止删除code为Year的Schema
+    /// 自定义删除方法，防止删除code为Year的Schema和仍被全宗使用的Schema
     pub fn delete(&mut self, id: i32) -> Result<(), Box<dyn std::error::Error>> {
         // 首先检查是否是code为Year的Schema
         use diesel::prelude::*;
@@ -26,6 +26,16 @@ impl SchemaRepository {
         if schema.schema_no == "Year" {
             return Err("Cannot delete the schema with code 'Year'".into());
         }
+
+        // 被全宗用作维度的方案不能删除，否则这些全宗无法再生成案卷
+        use crate::models::fond_schema::fond_schemas;
+        let used_by = fond_schemas::table
+            .filter(fond_schemas::schema_id.eq(id))
+            .count()
+            .get_result::<i64>(&mut *self.conn.borrow_mut())?;
+        if used_by > 0 {
+            return Err(format!("方案 {} 仍被 {} 个全宗使用，不能删除", schema.schema_no, used_by).into());
+        }
         
         // 调用父类的delete
         <Self as crate::core::GenericRepository<Schema>>::delete(self, id)
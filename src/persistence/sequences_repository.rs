@@ -83,6 +83,16 @@ impl SequencesRepository {
         }
     }
 
+    /// 分配 `prefix` 下的下一个完整编号（`<prefix>-<序列号>`），跳过 `is_taken` 认为已被占用的编号
+    pub fn allocate(&mut self, prefix: &str, digits: i32, is_taken: impl Fn(&str) -> bool) -> Result<String, Box<dyn std::error::Error>> {
+        loop {
+            let candidate = format!("{}-{}", prefix, self.get_next_number(prefix, Some(digits))?);
+            if !is_taken(&candidate) {
+                return Ok(candidate);
+            }
+        }
+    }
+
     /// 重置序列（用于测试或初始化）
     pub fn reset_sequence(&mut self, prefix: &str, start_value: i32) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(mut seq) = self.find_by_prefix(prefix)? {
@@ -93,4 +103,28 @@ impl SequencesRepository {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// 使序列越过一个已使用的编号（如导入时保留的原编号），避免之后生成重复编号
+    ///
+    /// `number` 为完整编号，去掉 `prefix` 和连接符后的数字部分即序列值；不是该前缀下的数字编号时不做处理
+    pub fn advance_past(&mut self, prefix: &str, number: &str, digits: i32) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(value) = number
+            .strip_prefix(prefix)
+            .map(|rest| rest.trim_start_matches('-'))
+            .and_then(|rest| rest.parse::<i32>().ok())
+        else {
+            return Ok(());
+        };
+        match self.find_by_prefix(prefix)? {
+            Some(mut seq) if seq.next_value <= value => {
+                seq.next_value = value + 1;
+                self.update(&seq)?;
+            }
+            Some(_) => {}
+            None => {
+                self.insert(prefix, value + 1, digits)?;
+            }
+        }
+        Ok(())
+    }
+}
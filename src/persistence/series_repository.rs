@@ -9,3 +9,16 @@ impl_repository!(
     { fond_id, series_no, name, created_at, created_by, created_machine }, // 插入列（排除 id）
     { fond_id, series_no, name }                       // 更新列
 );
+
+// 自定义方法实现
+impl SeriesRepository {
+    /// 根据案卷号查找案卷
+    pub fn find_by_series_no(&mut self, series_no_param: &str) -> Result<Option<Series>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        series::table
+            .filter(series::series_no.eq(series_no_param))
+            .first::<Series>(&mut *self.conn.borrow_mut())
+            .optional()
+            .map_err(|e| e.into())
+    }
+}
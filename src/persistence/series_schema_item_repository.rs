@@ -0,0 +1,24 @@
+use crate::models::series_schema_item::{series_schema_items, SeriesSchemaItem};
+use crate::impl_repository;
+
+// 使用宏自动生成 SeriesSchemaItemsRepository 和 GenericRepository 实现
+impl_repository!(
+    SeriesSchemaItemsRepository,                           // 仓储名
+    SeriesSchemaItem,                                      // 实体类型
+    series_schema_items,                                   // 表模块
+    { series_id, schema_item_id, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { series_id, schema_item_id }                          // 更新列
+);
+
+// 自定义方法实现
+impl SeriesSchemaItemsRepository {
+    /// 查找系列生成时使用的方案条目
+    pub fn find_by_series_id(&mut self, series_id_param: i32) -> Result<Vec<SeriesSchemaItem>, Box<dyn std::error::Error>> {
+        use diesel::prelude::*;
+        series_schema_items::table
+            .filter(series_schema_items::series_id.eq(series_id_param))
+            .order(series_schema_items::id.asc())
+            .load::<SeriesSchemaItem>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+}
@@ -0,0 +1,312 @@
+/// Sidecars - JSON copies of the catalogue written next to the payloads
+///
+/// Every repository write refreshes the `.fondspod.json` of the affected
+/// directory, so a library can be rebuilt from its folders if `.fondspod.db`
+/// is lost:
+/// - `<library>/.fondspod.json`: fond classifications and schemas with their items, and their translated names
+/// - `<library>/<fond_no>/.fondspod.json`: the fond, its schema dimensions and its series
+/// - `<library>/<fond_no>/<file_no>/.fondspod.json`: the file and the number of its series
+/// - `<library>/<fond_no>/<file_no>/<item_no>/.fondspod.json`: the item and the number of its file
+///
+/// Sidecars are only written for library databases (a `.fondspod.db` inside
+/// the library folder); other databases such as in-memory ones are left alone.
+/// Writes to the library tables inside a transaction only mark the library
+/// sidecar stale; it is rewritten once when the outermost transaction ends.
+use crate::models::file::{files, File};
+use crate::models::fond::{fonds, Fond};
+use crate::models::fond_classification::{fond_classifications, FondClassification};
+use crate::models::fond_schema::{fond_schemas, FondSchema};
+use crate::models::item::{items, Item};
+use crate::models::schema::{schemas, Schema};
+use crate::models::schema_item::{schema_items, SchemaItem};
+use crate::models::series::{series, Series};
+use crate::models::translation::{translations, Translation};
+use crate::persistence::schema_item_repository::PATH_SEPARATOR;
+use crate::services::backup_service::DATABASE_NAME;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use diesel::connection::TransactionManager;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a sidecar
+pub const SIDECAR_NAME: &str = ".fondspod.json";
+
+/// Current sidecar format version
+pub const SIDECAR_VERSION: u32 = 1;
+
+/// A schema with its items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSidecar {
+    pub schema: Schema,
+    pub items: Vec<SchemaItem>,
+}
+
+/// A schema dimension of a fond, referring to the schema by number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FondSchemaSidecar {
+    pub schema_no: String,
+    /// Path number of the fixed schema item of the dimension, if any
+    pub item_no: Option<String>,
+    /// Level the dimension expands to; None for leaf items
+    #[serde(default)]
+    pub item_level: Option<i32>,
+    pub sort_order: i32,
+}
+
+/// Content of one sidecar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Sidecar {
+    Library {
+        classifications: Vec<FondClassification>,
+        schemas: Vec<SchemaSidecar>,
+        /// Translated names, referring to the classifications and schema items by id
+        #[serde(default)]
+        translations: Vec<Translation>,
+    },
+    Fond { fond: Fond, schemas: Vec<FondSchemaSidecar>, series: Vec<Series> },
+    File { series_no: String, file: File },
+    Item { file_no: String, item: Item },
+}
+
+/// Sidecar as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidecarDocument {
+    pub version: u32,
+    pub written_at: chrono::NaiveDateTime,
+    #[serde(flatten)]
+    pub sidecar: Sidecar,
+}
+
+impl SidecarDocument {
+    /// Read a sidecar file
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let document: SidecarDocument = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if document.version > SIDECAR_VERSION {
+            return Err(format!("不支持的 sidecar 版本 {}: {}", document.version, path.display()).into());
+        }
+        Ok(document)
+    }
+}
+
+#[derive(QueryableByName)]
+struct DatabaseFile {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    file: String,
+}
+
+/// Library folder of a connection, when it is a library database
+pub fn library_dir(conn: &mut SqliteConnection) -> Option<PathBuf> {
+    let databases = diesel::sql_query("PRAGMA database_list").load::<DatabaseFile>(conn).ok()?;
+    let main = PathBuf::from(&databases.into_iter().find(|d| d.name == "main")?.file);
+    if main.file_name()? != DATABASE_NAME {
+        return None;
+    }
+    main.parent().map(Path::to_path_buf)
+}
+
+thread_local! {
+    /// Libraries whose sidecar is rewritten when the current transaction ends
+    static STALE_LIBRARIES: RefCell<BTreeSet<PathBuf>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+fn in_transaction(conn: &mut SqliteConnection) -> bool {
+    type Manager = <SqliteConnection as Connection>::TransactionManager;
+    matches!(Manager::transaction_manager_status_mut(conn).transaction_depth(), Ok(Some(_)))
+}
+
+/// Rewrite the library sidecar marked stale by writes inside a transaction
+///
+/// Called by `with_transaction` after it committed or rolled back; does
+/// nothing while an outer transaction is still open.
+pub fn after_transaction(conn: &mut SqliteConnection) {
+    if in_transaction(conn) {
+        return;
+    }
+    let Some(library) = library_dir(conn) else { return };
+    if !STALE_LIBRARIES.with(|stale| stale.borrow_mut().remove(&library)) {
+        return;
+    }
+    if let Err(e) = write_library(conn, &library) {
+        log::warn!("Failed to refresh library sidecar of {}: {}", library.display(), e);
+    }
+}
+
+/// Refresh the sidecars touched by a repository write
+///
+/// Called by the repositories after the write committed; `removed` is the
+/// deleted row for deletes. Failures are logged and never undo the write.
+pub fn after_write(conn: &mut SqliteConnection, table: &str, id: i32, removed: Option<&Value>) {
+    let Some(library) = library_dir(conn) else { return };
+    if let Err(e) = refresh(conn, &library, table, id, removed) {
+        log::warn!("Failed to refresh sidecar for {} {}: {}", table, id, e);
+    }
+}
+
+fn refresh(conn: &mut SqliteConnection, library: &Path, table: &str, id: i32, removed: Option<&Value>) -> Result<(), Box<dyn Error>> {
+    let removed_field = |field: &str| removed.and_then(|row| row.get(field)).cloned();
+    let removed_id = |field: &str| removed_field(field).and_then(|v| v.as_i64()).map(|v| v as i32);
+    let removed_text = |field: &str| removed_field(field).and_then(|v| v.as_str().map(str::to_string));
+
+    let library_table = matches!(table, "fond_classifications" | "schemas" | "schema_items" | "translations");
+    if library_table || !library.join(SIDECAR_NAME).exists() {
+        if in_transaction(conn) {
+            STALE_LIBRARIES.with(|stale| stale.borrow_mut().insert(library.to_path_buf()));
+        } else {
+            write_library(conn, library)?;
+        }
+    }
+    match table {
+        "fond_classifications" | "schemas" | "schema_items" | "translations" => Ok(()),
+        "fonds" => match removed_text("fond_no") {
+            Some(fond_no) => remove(&library.join(fond_no)),
+            None => write_fond(conn, library, id),
+        },
+        "series" | "fond_schemas" => {
+            let fond_id = match removed_id("fond_id") {
+                Some(fond_id) => fond_id,
+                None if table == "series" => series::table.find(id).select(series::fond_id).first(conn)?,
+                None => fond_schemas::table.find(id).select(fond_schemas::fond_id).first(conn)?,
+            };
+            write_fond(conn, library, fond_id)
+        }
+        "files" => match (removed_id("series_id"), removed_text("file_no")) {
+            (Some(series_id), Some(file_no)) => remove(&fond_dir_of_series(conn, library, series_id)?.join(file_no)),
+            _ => write_file(conn, library, id),
+        },
+        "items" => match (removed_id("file_id"), removed_text("item_no")) {
+            (Some(file_id), Some(item_no)) => remove(&file_dir(conn, library, file_id)?.0.join(item_no)),
+            _ => write_item(conn, library, id),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Write every sidecar of a library; returns the number written
+///
+/// Used to add sidecars to libraries created before they existed.
+pub fn write_all(conn: &mut SqliteConnection) -> Result<usize, Box<dyn Error>> {
+    let library = library_dir(conn).ok_or("不是档案库数据库")?;
+    write_library(conn, &library)?;
+    let mut written = 1;
+    for fond_id in fonds::table.select(fonds::id).load::<i32>(conn)? {
+        write_fond(conn, &library, fond_id)?;
+        written += 1;
+    }
+    for file_id in files::table.select(files::id).load::<i32>(conn)? {
+        write_file(conn, &library, file_id)?;
+        written += 1;
+    }
+    for item_id in items::table.select(items::id).load::<i32>(conn)? {
+        write_item(conn, &library, item_id)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+fn write_library(conn: &mut SqliteConnection, library: &Path) -> Result<(), Box<dyn Error>> {
+    let classifications = fond_classifications::table.order(fond_classifications::id).load::<FondClassification>(conn)?;
+    let all_items = schema_items::table.order(schema_items::id).load::<SchemaItem>(conn)?;
+    let schemas = schemas::table
+        .order(schemas::id)
+        .load::<Schema>(conn)?
+        .into_iter()
+        .map(|schema| {
+            let items = all_items.iter().filter(|i| i.schema_id == schema.id).cloned().collect();
+            SchemaSidecar { schema, items }
+        })
+        .collect();
+    let translations = translations::table.order(translations::id).load::<Translation>(conn)?;
+    write(library, Sidecar::Library { classifications, schemas, translations })
+}
+
+/// Path number of a schema item: the item numbers from the top level down, as in series numbers
+fn schema_item_path_no(conn: &mut SqliteConnection, item_id: i32) -> Result<Option<String>, Box<dyn Error>> {
+    let mut numbers = Vec::new();
+    let mut visited = HashSet::new();
+    let mut next = Some(item_id);
+    while let Some(id) = next.filter(|id| visited.insert(*id)) {
+        let Some((item_no, parent_id)) = schema_items::table
+            .find(id)
+            .select((schema_items::item_no, schema_items::parent_id))
+            .first::<(String, Option<i32>)>(conn)
+            .optional()?
+        else {
+            break;
+        };
+        numbers.push(item_no);
+        next = parent_id;
+    }
+    if numbers.is_empty() {
+        return Ok(None);
+    }
+    numbers.reverse();
+    Ok(Some(numbers.join(PATH_SEPARATOR)))
+}
+
+fn write_fond(conn: &mut SqliteConnection, library: &Path, fond_id: i32) -> Result<(), Box<dyn Error>> {
+    let Some(fond) = fonds::table.find(fond_id).first::<Fond>(conn).optional()? else { return Ok(()) };
+    let mut schemas = Vec::new();
+    for fond_schema in fond_schemas::table.filter(fond_schemas::fond_id.eq(fond_id)).order(fond_schemas::sort_order).load::<FondSchema>(conn)? {
+        let schema_no = schemas::table.find(fond_schema.schema_id).select(schemas::schema_no).first::<String>(conn)?;
+        let item_no = match fond_schema.schema_item_id {
+            Some(item_id) => schema_item_path_no(conn, item_id)?,
+            None => None,
+        };
+        schemas.push(FondSchemaSidecar { schema_no, item_no, item_level: fond_schema.item_level, sort_order: fond_schema.sort_order });
+    }
+    let series = series::table.filter(series::fond_id.eq(fond_id)).order(series::id).load::<Series>(conn)?;
+    write(&library.join(&fond.fond_no), Sidecar::Fond { fond, schemas, series })
+}
+
+fn write_file(conn: &mut SqliteConnection, library: &Path, file_id: i32) -> Result<(), Box<dyn Error>> {
+    let Some(file) = files::table.find(file_id).first::<File>(conn).optional()? else { return Ok(()) };
+    let series_no = series::table.find(file.series_id).select(series::series_no).first::<String>(conn)?;
+    let dir = fond_dir_of_series(conn, library, file.series_id)?.join(&file.file_no);
+    write(&dir, Sidecar::File { series_no, file })
+}
+
+fn write_item(conn: &mut SqliteConnection, library: &Path, item_id: i32) -> Result<(), Box<dyn Error>> {
+    let Some(item) = items::table.find(item_id).first::<Item>(conn).optional()? else { return Ok(()) };
+    let (dir, file_no) = file_dir(conn, library, item.file_id)?;
+    write(&dir.join(&item.item_no), Sidecar::Item { file_no, item })
+}
+
+fn fond_dir_of_series(conn: &mut SqliteConnection, library: &Path, series_id: i32) -> Result<PathBuf, Box<dyn Error>> {
+    let fond_id = series::table.find(series_id).select(series::fond_id).first::<i32>(conn)?;
+    let fond_no = fonds::table.find(fond_id).select(fonds::fond_no).first::<String>(conn)?;
+    Ok(library.join(fond_no))
+}
+
+/// Directory and number of a file
+fn file_dir(conn: &mut SqliteConnection, library: &Path, file_id: i32) -> Result<(PathBuf, String), Box<dyn Error>> {
+    let (series_id, file_no) = files::table.find(file_id).select((files::series_id, files::file_no)).first::<(i32, String)>(conn)?;
+    Ok((fond_dir_of_series(conn, library, series_id)?.join(&file_no), file_no))
+}
+
+/// Write a sidecar atomically: a crash leaves either the old or the new sidecar
+fn write(dir: &Path, sidecar: Sidecar) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    let document = SidecarDocument { version: SIDECAR_VERSION, written_at: chrono::Utc::now().naive_utc(), sidecar };
+    let staging = dir.join(format!("{}.tmp", SIDECAR_NAME));
+    fs::write(&staging, serde_json::to_string_pretty(&document)?)?;
+    fs::rename(&staging, dir.join(SIDECAR_NAME))?;
+    Ok(())
+}
+
+/// Remove the sidecar of a deleted record; its directory and payload stay
+fn remove(dir: &Path) -> Result<(), Box<dyn Error>> {
+    match fs::remove_file(dir.join(SIDECAR_NAME)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,123 @@
+use crate::models::translation::{translations, Translatable, Translation};
+use crate::impl_repository;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+
+// 使用宏自动生成 TranslationsRepository 和 GenericRepository 实现
+impl_repository!(
+    TranslationsRepository,                                // 仓储名
+    Translation,                                           // 实体类型
+    translations,                                          // 表模块
+    { entity_type, entity_id, locale, name, created_at, created_by, created_machine }, // 插入列（排除 id）
+    { entity_type, entity_id, locale, name }               // 更新列
+);
+
+#[derive(diesel::QueryableByName)]
+struct EntityId {
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    id: i32,
+}
+
+// 自定义方法实现
+impl TranslationsRepository {
+    fn find_by_entity_type(&mut self, entity_type_param: &str) -> Result<Vec<Translation>, Box<dyn Error>> {
+        use diesel::prelude::*;
+        translations::table
+            .filter(translations::entity_type.eq(entity_type_param))
+            .order((translations::entity_id.asc(), translations::locale.asc()))
+            .load::<Translation>(&mut *self.conn.borrow_mut())
+            .map_err(|e| e.into())
+    }
+
+    /// 实体的全部翻译：语言 -> 名称
+    pub fn find_names(&mut self, entity_type_param: &str, entity_id_param: i32) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+        use diesel::prelude::*;
+        let rows = translations::table
+            .filter(translations::entity_type.eq(entity_type_param))
+            .filter(translations::entity_id.eq(entity_id_param))
+            .load::<Translation>(&mut *self.conn.borrow_mut())?;
+        Ok(rows.into_iter().map(|t| (t.locale, t.name)).collect())
+    }
+
+    /// 某类实体的全部翻译：实体 id -> (语言 -> 名称)
+    pub fn find_all_names(&mut self, entity_type_param: &str) -> Result<HashMap<i32, BTreeMap<String, String>>, Box<dyn Error>> {
+        let mut names: HashMap<i32, BTreeMap<String, String>> = HashMap::new();
+        for t in self.find_by_entity_type(entity_type_param)? {
+            names.entry(t.entity_id).or_default().insert(t.locale, t.name);
+        }
+        Ok(names)
+    }
+
+    /// 某类实体在 `locale` 下的名称：实体 id -> 名称
+    pub fn find_names_in(&mut self, entity_type_param: &str, locale_param: &str) -> Result<HashMap<i32, String>, Box<dyn Error>> {
+        use diesel::prelude::*;
+        let rows = translations::table
+            .filter(translations::entity_type.eq(entity_type_param))
+            .filter(translations::locale.eq(locale_param))
+            .load::<Translation>(&mut *self.conn.borrow_mut())?;
+        Ok(rows.into_iter().map(|t| (t.entity_id, t.name)).collect())
+    }
+
+    /// 设置实体的全部翻译：`names` 中没有的语言被删除，名称为空的语言视为没有翻译
+    pub fn set_names(&mut self, entity_type_param: &str, entity_id_param: i32, names: &BTreeMap<String, String>) -> Result<(), Box<dyn Error>> {
+        use crate::core::{Creatable, GenericRepository};
+        use diesel::prelude::*;
+
+        let existing = translations::table
+            .filter(translations::entity_type.eq(entity_type_param))
+            .filter(translations::entity_id.eq(entity_id_param))
+            .load::<Translation>(&mut *self.conn.borrow_mut())?;
+        for row in &existing {
+            match names.get(&row.locale).filter(|name| !name.trim().is_empty()) {
+                Some(name) if *name != row.name => {
+                    self.update(&Translation { name: name.clone(), ..row.clone() })?;
+                }
+                Some(_) => {}
+                None => {
+                    self.delete(row.id())?;
+                }
+            }
+        }
+        for (locale_value, name_value) in names {
+            if name_value.trim().is_empty() || existing.iter().any(|row| &row.locale == locale_value) {
+                continue;
+            }
+            self.create(Translation {
+                entity_type: entity_type_param.to_string(),
+                entity_id: entity_id_param,
+                locale: locale_value.clone(),
+                name: name_value.clone(),
+                ..Default::default()
+            })?;
+        }
+        Ok(())
+    }
+
+    /// 把实体的名称替换为 `locale` 的翻译，没有翻译的保持原名
+    pub fn localize<E: Translatable>(&mut self, entities: &mut [E], locale_param: &str) -> Result<(), Box<dyn Error>> {
+        let names = self.find_names_in(E::ENTITY_TYPE, locale_param)?;
+        for entity in entities.iter_mut() {
+            if let Some(name) = names.get(&entity.id()) {
+                entity.set_name(name.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// 删除实体已不存在的翻译（实体删除后调用），返回删除的数量
+    pub fn delete_orphans<E: Translatable>(&mut self) -> Result<usize, Box<dyn Error>> {
+        use crate::core::{Creatable, GenericRepository};
+        use diesel::prelude::*;
+
+        let live: HashSet<i32> = diesel::sql_query(format!("SELECT id FROM {}", E::ENTITY_TYPE))
+            .load::<EntityId>(&mut *self.conn.borrow_mut())?
+            .into_iter()
+            .map(|row| row.id)
+            .collect();
+        let orphans: Vec<Translation> = self.find_by_entity_type(E::ENTITY_TYPE)?.into_iter().filter(|t| !live.contains(&t.entity_id)).collect();
+        for orphan in &orphans {
+            self.delete(orphan.id())?;
+        }
+        Ok(orphans.len())
+    }
+}
@@ -0,0 +1,74 @@
+/// Audit Service - Query and export the append-only audit log
+/// Every insert / update / delete made through the repository macros lands here
+use crate::models::audit_log::AuditLog;
+use crate::persistence::{AuditLogFilter, AuditLogRepository};
+use crate::services::csv::csv_escape;
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Audit service for compliance queries and exports
+pub struct AuditService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl AuditService {
+    /// Create a new audit service on the given library connection
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Query audit entries matching the filter, oldest first
+    pub fn query(&self, filter: &AuditLogFilter) -> Result<Vec<AuditLog>, Box<dyn Error>> {
+        AuditLogRepository::new(Rc::clone(&self.conn)).query(filter)
+    }
+
+    /// Full change history of one entity (entity_type is the table name, e.g. "files")
+    pub fn history(&self, entity_type: &str, entity_id: i32) -> Result<Vec<AuditLog>, Box<dyn Error>> {
+        AuditLogRepository::new(Rc::clone(&self.conn)).find_by_entity(entity_type, entity_id)
+    }
+
+    /// Render entries as CSV
+    pub fn to_csv(entries: &[AuditLog]) -> String {
+        let mut csv = String::from("id,created_at,created_by,created_machine,entity_type,entity_id,action,before_json,after_json\n");
+        for entry in entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                entry.id,
+                entry.created_at.format("%Y-%m-%d %H:%M:%S%.3f"),
+                csv_escape(&entry.created_by),
+                csv_escape(&entry.created_machine),
+                csv_escape(&entry.entity_type),
+                entry.entity_id,
+                csv_escape(&entry.action),
+                csv_escape(entry.before_json.as_deref().unwrap_or("")),
+                csv_escape(entry.after_json.as_deref().unwrap_or("")),
+            ));
+        }
+        csv
+    }
+
+    /// Render entries as pretty-printed JSON
+    pub fn to_json(entries: &[AuditLog]) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(entries)?)
+    }
+
+    /// Export entries matching the filter; the format follows the file extension (.csv or .json)
+    pub fn export(&self, filter: &AuditLogFilter, path: &Path) -> Result<usize, Box<dyn Error>> {
+        let entries = self.query(filter)?;
+        let is_csv = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("csv"))
+            .unwrap_or(false);
+        let content = if is_csv {
+            Self::to_csv(&entries)
+        } else {
+            Self::to_json(&entries)?
+        };
+        fs::write(path, content)?;
+        Ok(entries.len())
+    }
+}
@@ -0,0 +1,100 @@
+/// Background Job - Run long library operations on a worker thread
+/// The UI keeps the handle, polls progress and the result from a timer, and can request cancellation
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+/// Progress reported by a running job
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobProgress {
+    pub done: usize,
+    /// Zero while the total is not known yet
+    pub total: usize,
+    /// What the job is working on right now
+    pub current: String,
+}
+
+/// Shared state between a job and its owner: the cancel flag and the latest progress
+///
+/// Work functions check `is_cancelled` between units of work and stop early;
+/// a default control can be passed when running the work synchronously.
+#[derive(Debug, Clone, Default)]
+pub struct JobControl {
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<JobProgress>>,
+}
+
+impl JobControl {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Publish the latest progress
+    pub fn report(&self, done: usize, total: usize, current: &str) {
+        if let Ok(mut progress) = self.progress.lock() {
+            *progress = JobProgress { done, total, current: current.to_string() };
+        }
+    }
+
+    pub fn progress(&self) -> JobProgress {
+        self.progress.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+}
+
+/// Handle of a job running on its own thread
+pub struct BackgroundJob<T> {
+    control: JobControl,
+    receiver: Receiver<Result<T, String>>,
+}
+
+impl<T: Send + 'static> BackgroundJob<T> {
+    /// Start `work` on a named worker thread
+    ///
+    /// The work gets the job's control for progress and cancellation. Errors
+    /// come back as strings since `Box<dyn Error>` cannot cross threads.
+    pub fn spawn(
+        name: &str,
+        work: impl FnOnce(&JobControl) -> Result<T, Box<dyn Error>> + Send + 'static,
+    ) -> Result<Self, Box<dyn Error>> {
+        let control = JobControl::default();
+        let (sender, receiver) = mpsc::channel();
+        let worker_control = control.clone();
+        std::thread::Builder::new().name(name.to_string()).spawn(move || {
+            let result = work(&worker_control).map_err(|e| e.to_string());
+            let _ = sender.send(result);
+        })?;
+        Ok(Self { control, receiver })
+    }
+
+    /// Ask the job to stop at its next checkpoint
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.control.is_cancelled()
+    }
+
+    pub fn progress(&self) -> JobProgress {
+        self.control.progress()
+    }
+
+    /// The result once the job has finished; `None` while it is still running
+    pub fn try_finish(&self) -> Option<Result<T, String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("后台任务异常终止".into())),
+        }
+    }
+
+    /// Block until the job has finished
+    pub fn wait(self) -> Result<T, String> {
+        self.receiver.recv().unwrap_or_else(|_| Err("后台任务异常终止".into()))
+    }
+}
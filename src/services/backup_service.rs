@@ -0,0 +1,494 @@
+/// Backup Service - Back up and restore a library as a single archive file
+/// Archives a consistent database snapshot and optionally the payload directories, with a checksum manifest
+use crate::core::{current_machine, current_user};
+use crate::persistence::backup_database;
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the manifest inside a backup archive
+pub const MANIFEST_NAME: &str = "manifest.json";
+/// Name of the library database inside a library and a backup archive
+pub const DATABASE_NAME: &str = ".fondspod.db";
+/// Temporary folder used while restoring into a library
+const RESTORE_STAGING: &str = ".fondspod_restore";
+
+/// Archive container format, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupFormat {
+    Zip,
+    TarZst,
+}
+
+impl BackupFormat {
+    /// Format of an archive path (`.zip`, `.tar.zst` or `.tzst`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(BackupFormat::Zip)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(BackupFormat::TarZst)
+        } else {
+            None
+        }
+    }
+
+    /// File extension without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BackupFormat::Zip => "zip",
+            BackupFormat::TarZst => "tar.zst",
+        }
+    }
+}
+
+/// One file recorded in a backup manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Path inside the archive, relative to the library root with `/` separators
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Manifest stored as `manifest.json` in every backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub library_name: String,
+    pub created_at: NaiveDateTime,
+    pub created_by: String,
+    pub created_machine: String,
+    pub include_payloads: bool,
+    pub entries: Vec<BackupEntry>,
+}
+
+impl BackupManifest {
+    /// Total size of the archived files
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+}
+
+/// Backup service for one library
+pub struct BackupService {
+    library_path: PathBuf,
+}
+
+impl BackupService {
+    /// Create a new backup service for the library at the given directory
+    pub fn new(library_path: impl AsRef<Path>) -> Self {
+        Self {
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Display name of the library (its folder name)
+    pub fn library_name(&self) -> String {
+        self.library_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "library".to_string())
+    }
+
+    /// Write a backup archive; the format follows the destination extension
+    ///
+    /// The database is copied with SQLite's online backup API first, so the
+    /// archive holds a consistent snapshot even while the library is open.
+    pub fn backup(&self, destination: &Path, include_payloads: bool) -> Result<BackupManifest, Box<dyn Error>> {
+        let format = BackupFormat::from_path(destination).ok_or("备份文件必须是 .zip 或 .tar.zst")?;
+        let database = self.library_path.join(DATABASE_NAME);
+        if !database.exists() {
+            return Err(format!("档案库数据库不存在: {}", database.display()).into());
+        }
+
+        let snapshot = std::env::temp_dir().join(format!(
+            "fondspod-snapshot-{}-{}.db",
+            std::process::id(),
+            chrono::Local::now().format("%Y%m%d%H%M%S%3f")
+        ));
+        backup_database(&database, &snapshot)?;
+
+        let mut files = vec![(DATABASE_NAME.to_string(), snapshot.clone())];
+        if include_payloads {
+            // 备份目录位于库内时不把旧备份（及正在写入的备份）再打包进去
+            let mut exclude = vec![destination.to_path_buf()];
+            if let Some(parent) = destination.parent().filter(|p| *p != self.library_path) {
+                exclude.push(parent.to_path_buf());
+            }
+            Self::collect_payloads(&self.library_path, "", &exclude, &mut files)?;
+        }
+
+        let result = self.write_archive(destination, format, &files, include_payloads);
+        let _ = fs::remove_file(&snapshot);
+        if result.is_err() {
+            let _ = fs::remove_file(destination);
+        }
+        result
+    }
+
+    fn write_archive(
+        &self,
+        destination: &Path,
+        format: BackupFormat,
+        files: &[(String, PathBuf)],
+        include_payloads: bool,
+    ) -> Result<BackupManifest, Box<dyn Error>> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = ArchiveWriter::create(destination, format)?;
+        let mut entries = Vec::new();
+        for (name, path) in files {
+            let (sha256, size) = FixityService::hash_file(path, ChecksumAlgorithm::Sha256)?;
+            writer.add_file(name, path)?;
+            entries.push(BackupEntry { path: name.clone(), size, sha256 });
+        }
+
+        let manifest = BackupManifest {
+            version: 1,
+            library_name: self.library_name(),
+            created_at: chrono::Local::now().naive_local(),
+            created_by: current_user().unwrap_or_default(),
+            created_machine: current_machine().unwrap_or_default(),
+            include_payloads,
+            entries,
+        };
+        writer.add_bytes(MANIFEST_NAME, &serde_json::to_vec_pretty(&manifest)?)?;
+        writer.finish()?;
+        Ok(manifest)
+    }
+
+    /// Payload files below the library, skipping the database files and restore leftovers
+    fn collect_payloads(
+        dir: &Path,
+        prefix: &str,
+        exclude: &[PathBuf],
+        files: &mut Vec<(String, PathBuf)>,
+    ) -> io::Result<()> {
+        let mut entries: Vec<_> = fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if prefix.is_empty() && name.starts_with(".fondspod") {
+                continue;
+            }
+            let path = entry.path();
+            if exclude.contains(&path) {
+                continue;
+            }
+            let relative = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+            if path.is_dir() {
+                Self::collect_payloads(&path, &relative, exclude, files)?;
+            } else {
+                files.push((relative, path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check every file in an archive against its manifest
+    pub fn verify(archive: &Path) -> Result<BackupManifest, Box<dyn Error>> {
+        let mut manifest_bytes = None;
+        let mut hashes: BTreeMap<String, (String, u64)> = BTreeMap::new();
+        read_archive(archive, |name, reader| {
+            if name == MANIFEST_NAME {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                manifest_bytes = Some(data);
+            } else {
+                hashes.insert(name.to_string(), hash_reader(reader)?);
+            }
+            Ok(())
+        })?;
+
+        let manifest: BackupManifest = serde_json::from_slice(&manifest_bytes.ok_or("备份中缺少 manifest.json")?)?;
+        for entry in &manifest.entries {
+            match hashes.remove(&entry.path) {
+                Some((sha256, size)) if sha256 == entry.sha256 && size == entry.size => {}
+                Some(_) => return Err(format!("备份文件已损坏: {}", entry.path).into()),
+                None => return Err(format!("备份中缺少文件: {}", entry.path).into()),
+            }
+        }
+        if let Some(extra) = hashes.keys().next() {
+            return Err(format!("备份中有清单外的文件: {}", extra).into());
+        }
+        if !manifest.entries.iter().any(|e| e.path == DATABASE_NAME) {
+            return Err("备份中缺少档案库数据库".into());
+        }
+        Ok(manifest)
+    }
+
+    /// Reject a restore target that is the open library, however either path is spelled
+    ///
+    /// Both paths are compared in canonical form; a target that does not exist
+    /// yet cannot be the open library and is compared as given.
+    pub fn check_restore_target(target: &Path, open_library: Option<&Path>) -> Result<(), Box<dyn Error>> {
+        let Some(open_library) = open_library else {
+            return Ok(());
+        };
+        let same = match (fs::canonicalize(target), fs::canonicalize(open_library)) {
+            (Ok(target), Ok(open_library)) => target == open_library,
+            _ => target == open_library,
+        };
+        if same {
+            return Err("不能恢复到当前打开的档案库，请先切换到其他档案库".into());
+        }
+        Ok(())
+    }
+
+    /// Restore an archive into a new or existing library folder
+    ///
+    /// The archive is verified before anything is written. Files are unpacked
+    /// into a staging folder first and only moved into place once complete;
+    /// afterwards every restored file is hashed again against the manifest.
+    /// Restoring over an existing library requires `overwrite`, and the
+    /// library must not be open.
+    pub fn restore(archive: &Path, target: &Path, overwrite: bool) -> Result<BackupManifest, Box<dyn Error>> {
+        let manifest = Self::verify(archive)?;
+        if target.join(DATABASE_NAME).exists() && !overwrite {
+            return Err(format!("目标目录已是档案库: {}", target.display()).into());
+        }
+        fs::create_dir_all(target)?;
+
+        let staging = target.join(RESTORE_STAGING);
+        let _ = fs::remove_dir_all(&staging);
+        fs::create_dir_all(&staging)?;
+        let unpacked = read_archive(archive, |name, reader| {
+            if name == MANIFEST_NAME {
+                return Ok(());
+            }
+            let to = staging.join(safe_relative_path(name)?);
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(reader, &mut fs::File::create(&to)?)?;
+            Ok(())
+        });
+        if let Err(e) = unpacked {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+
+        // 旧数据库的 WAL/SHM 文件会覆盖恢复的数据，先删除
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let _ = fs::remove_file(target.join(format!("{}{}", DATABASE_NAME, suffix)));
+        }
+        for entry in &manifest.entries {
+            let relative = safe_relative_path(&entry.path)?;
+            let to = target.join(&relative);
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if to.exists() {
+                fs::remove_file(&to)?;
+            }
+            fs::rename(staging.join(&relative), &to)?;
+        }
+        let _ = fs::remove_dir_all(&staging);
+
+        for entry in &manifest.entries {
+            let (sha256, _) = FixityService::hash_file(&target.join(safe_relative_path(&entry.path)?), ChecksumAlgorithm::Sha256)?;
+            if sha256 != entry.sha256 {
+                return Err(format!("恢复后校验失败: {}", entry.path).into());
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// File name of a scheduled backup, e.g. `Archive-20251218-093000.zip`
+    pub fn backup_file_name(&self, now: NaiveDateTime, format: BackupFormat) -> String {
+        format!("{}-{}.{}", self.library_name(), now.format("%Y%m%d-%H%M%S"), format.extension())
+    }
+
+    /// Scheduled backups of this library in a folder, oldest first
+    pub fn list_backups(&self, backup_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if !backup_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let prefix = format!("{}-", self.library_name());
+        let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                // 前缀后必须紧跟时间戳，避免把 "Archive-2" 的备份算作 "Archive" 的
+                name.strip_prefix(&prefix)
+                    .and_then(|rest| rest.get(..15))
+                    .is_some_and(|stamp| NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S").is_ok())
+                    && BackupFormat::from_path(p).is_some()
+            })
+            .collect();
+        backups.sort();
+        Ok(backups)
+    }
+
+    /// Delete the oldest scheduled backups beyond `keep`; returns the number deleted
+    pub fn prune(&self, backup_dir: &Path, keep: usize) -> Result<usize, Box<dyn Error>> {
+        let backups = self.list_backups(backup_dir)?;
+        let excess = backups.len().saturating_sub(keep);
+        for path in &backups[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(excess)
+    }
+
+    /// Whether a scheduled backup is due (none yet, or the newest is older than the interval)
+    pub fn is_due(&self, backup_dir: &Path, interval: Duration, now: NaiveDateTime) -> Result<bool, Box<dyn Error>> {
+        let Some(newest) = self.list_backups(backup_dir)?.pop() else {
+            return Ok(true);
+        };
+        let name = newest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let stamp = &name[self.library_name().len() + 1..][..15];
+        Ok(match NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S") {
+            Ok(taken_at) => now - taken_at >= interval,
+            Err(_) => true,
+        })
+    }
+
+    /// Take a scheduled backup if one is due, then apply the retention limit
+    pub fn run_scheduled(
+        &self,
+        backup_dir: &Path,
+        format: BackupFormat,
+        include_payloads: bool,
+        interval: Duration,
+        retention: usize,
+        now: NaiveDateTime,
+    ) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        if !self.is_due(backup_dir, interval, now)? {
+            return Ok(None);
+        }
+        let destination = backup_dir.join(self.backup_file_name(now, format));
+        self.backup(&destination, include_payloads)?;
+        self.prune(backup_dir, retention.max(1))?;
+        Ok(Some(destination))
+    }
+}
+
+/// Reject archive entry names that would escape the target folder
+fn safe_relative_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = Path::new(name);
+    if name.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("备份中有不安全的路径: {}", name).into());
+    }
+    Ok(path.to_path_buf())
+}
+
+fn hash_reader(reader: &mut dyn Read) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        size += read as u64;
+        hasher.update(&buffer[..read]);
+    }
+    Ok((hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect(), size))
+}
+
+/// Visit every regular file in an archive
+fn read_archive<F>(archive: &Path, mut visit: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, &mut dyn Read) -> Result<(), Box<dyn Error>>,
+{
+    let format = BackupFormat::from_path(archive).ok_or("备份文件必须是 .zip 或 .tar.zst")?;
+    let file = fs::File::open(archive)?;
+    match format {
+        BackupFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(file)?;
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                let name = entry.name().to_string();
+                visit(&name, &mut entry)?;
+            }
+        }
+        BackupFormat::TarZst => {
+            let mut tar = tar::Archive::new(zstd::Decoder::new(file)?);
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path()?.to_string_lossy().replace('\\', "/");
+                visit(&name, &mut entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Streaming writer for either archive format
+enum ArchiveWriter {
+    Zip(Box<zip::ZipWriter<fs::File>>),
+    TarZst(tar::Builder<zstd::Encoder<'static, fs::File>>),
+}
+
+impl ArchiveWriter {
+    fn create(path: &Path, format: BackupFormat) -> Result<Self, Box<dyn Error>> {
+        let file = fs::File::create(path)?;
+        Ok(match format {
+            BackupFormat::Zip => ArchiveWriter::Zip(Box::new(zip::ZipWriter::new(file))),
+            BackupFormat::TarZst => ArchiveWriter::TarZst(tar::Builder::new(zstd::Encoder::new(file, 0)?)),
+        })
+    }
+
+    fn zip_options() -> zip::write::SimpleFileOptions {
+        zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(true)
+    }
+
+    fn add_file(&mut self, name: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+        match self {
+            ArchiveWriter::Zip(zip) => {
+                zip.start_file(name, Self::zip_options())?;
+                io::copy(&mut fs::File::open(path)?, zip.as_mut())?;
+            }
+            ArchiveWriter::TarZst(tar) => tar.append_path_with_name(path, name)?,
+        }
+        Ok(())
+    }
+
+    fn add_bytes(&mut self, name: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        match self {
+            ArchiveWriter::Zip(zip) => {
+                zip.start_file(name, Self::zip_options())?;
+                zip.write_all(data)?;
+            }
+            ArchiveWriter::TarZst(tar) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+                header.set_cksum();
+                tar.append_data(&mut header, name, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            ArchiveWriter::Zip(zip) => {
+                zip.finish()?;
+            }
+            ArchiveWriter::TarZst(tar) => {
+                tar.into_inner()?.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
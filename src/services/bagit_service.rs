@@ -0,0 +1,603 @@
+/// BagIt Service - Hand fonds, series and files over to other institutions as BagIt packages
+/// Exports payloads with a metadata tree and SHA-256 manifests, and imports validated bags with number remapping
+use crate::core::{current_machine, current_user, GenericRepository};
+use crate::models::app_settings::IngestMode;
+use crate::models::file::File;
+use crate::models::fond::Fond;
+use crate::models::item::Item;
+use crate::models::series::Series;
+use crate::models::fond::fonds;
+use crate::persistence::{sidecars, with_transaction, FilesRepository, FondsRepository, ItemsRepository, SequencesRepository, SeriesRepository};
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use crate::services::ingest_service::IngestService;
+use crate::services::library_path_service::LibraryPathService;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+/// BagIt specification version written into `bagit.txt`
+pub const BAGIT_VERSION: &str = "1.0";
+/// Tag file holding the fond/series/file/item tree
+pub const METADATA_NAME: &str = "metadata/fonds-pod.json";
+const PAYLOAD_MANIFEST: &str = "manifest-sha256.txt";
+const TAG_MANIFEST: &str = "tagmanifest-sha256.txt";
+
+/// Part of the hierarchy to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BagScope {
+    Fond(i32),
+    Series(i32),
+    File(i32),
+}
+
+impl BagScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BagScope::Fond(_) => "fond",
+            BagScope::Series(_) => "series",
+            BagScope::File(_) => "file",
+        }
+    }
+}
+
+/// Item in the bag metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BagItem {
+    pub item_no: String,
+    pub name: String,
+    /// Payload path inside the bag (`data/...`), a file or a folder
+    pub payload: Option<String>,
+    pub source_path: Option<String>,
+}
+
+/// File in the bag metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BagFile {
+    pub file_no: String,
+    pub name: String,
+    pub items: Vec<BagItem>,
+}
+
+/// Series in the bag metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BagSeries {
+    pub series_no: String,
+    pub name: String,
+    pub files: Vec<BagFile>,
+}
+
+/// Fond in the bag metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BagFond {
+    pub fond_no: String,
+    pub fond_classification_code: String,
+    pub name: String,
+    pub series: Vec<BagSeries>,
+}
+
+/// Content of `metadata/fonds-pod.json`
+///
+/// Series and file exports still carry their fond (and series) so the
+/// receiving library can rebuild the full path down to the items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BagMetadata {
+    pub version: u32,
+    /// `fond`, `series` or `file`
+    pub scope: String,
+    pub created_at: NaiveDateTime,
+    pub created_by: String,
+    pub created_machine: String,
+    pub fond: BagFond,
+}
+
+/// Result of importing a bag
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BagImportReport {
+    pub fonds: usize,
+    pub series: usize,
+    pub files: usize,
+    pub items: usize,
+    /// Numbers that collided in the receiving library, as (number in the bag, number assigned)
+    pub remapped: Vec<(String, String)>,
+}
+
+impl BagImportReport {
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "导入 {} 个全宗、{} 个系列、{} 个案卷、{} 个档案项",
+            self.fonds, self.series, self.files, self.items
+        );
+        if !self.remapped.is_empty() {
+            text.push_str(&format!("，{} 个编号冲突已重新编号", self.remapped.len()));
+        }
+        text
+    }
+}
+
+/// BagIt service for one library
+pub struct BagItService {
+    conn: Rc<RefCell<SqliteConnection>>,
+    library_path: PathBuf,
+}
+
+impl BagItService {
+    /// Create a new BagIt service on the given library connection and directory
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>, library_path: impl AsRef<Path>) -> Self {
+        Self {
+            conn,
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Export a fond, series or file as a bag in a new folder under `destination`; returns the bag folder
+    ///
+    /// The bag is named after the exported number. Payloads are laid out as
+    /// `data/<fond_no>/<file_no>/<item_no>/`, like in a library.
+    pub fn export(&self, scope: BagScope, destination: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let (fond, number) = self.metadata_tree(scope)?;
+        let bag = destination.join(&number);
+        if bag.exists() {
+            return Err(format!("目标目录已存在: {}", bag.display()).into());
+        }
+        let result = self.write_bag(&bag, scope, fond, &number);
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&bag);
+        }
+        result.map(|_| bag)
+    }
+
+    /// Metadata tree of the scope with ancestry, and the number the bag is named after
+    fn metadata_tree(&self, scope: BagScope) -> Result<(BagFond, String), Box<dyn Error>> {
+        let mut files_repo = FilesRepository::new(Rc::clone(&self.conn));
+        let mut series_repo = SeriesRepository::new(Rc::clone(&self.conn));
+
+        let (fond_id, series_filter, file_filter) = match scope {
+            BagScope::Fond(id) => (id, None, None),
+            BagScope::Series(id) => {
+                let series = series_repo.find_by_id(id)?.ok_or(format!("系列不存在: {}", id))?;
+                (series.fond_id, Some(id), None)
+            }
+            BagScope::File(id) => {
+                let file = files_repo.find_by_id(id)?.ok_or(format!("案卷不存在: {}", id))?;
+                let series = series_repo
+                    .find_by_id(file.series_id)?
+                    .ok_or(format!("系列不存在: {}", file.series_id))?;
+                (series.fond_id, Some(series.id), Some(id))
+            }
+        };
+        let fond = FondsRepository::new(Rc::clone(&self.conn))
+            .find_by_id(fond_id)?
+            .ok_or(format!("全宗不存在: {}", fond_id))?;
+
+        let all_items = ItemsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let mut bag_series = Vec::new();
+        let mut number = fond.fond_no.clone();
+        for series in series_repo.find_by_predicate(|s| s.fond_id == fond_id && series_filter.is_none_or(|id| s.id == id))? {
+            let mut bag_files = Vec::new();
+            for file in files_repo.find_by_predicate(|f| f.series_id == series.id && file_filter.is_none_or(|id| f.id == id))? {
+                let items = all_items
+                    .iter()
+                    .filter(|i| i.file_id == file.id)
+                    .map(|i| BagItem {
+                        item_no: i.item_no.clone(),
+                        name: i.name.clone(),
+                        payload: i.path.clone().filter(|p| !p.is_empty()),
+                        source_path: i.source_path.clone(),
+                    })
+                    .collect();
+                if file_filter.is_some() {
+                    number = file.file_no.clone();
+                }
+                bag_files.push(BagFile { file_no: file.file_no, name: file.name, items });
+            }
+            if series_filter.is_some() && file_filter.is_none() {
+                number = series.series_no.clone();
+            }
+            bag_series.push(BagSeries { series_no: series.series_no, name: series.name, files: bag_files });
+        }
+
+        Ok((
+            BagFond {
+                fond_no: fond.fond_no,
+                fond_classification_code: fond.fond_classification_code,
+                name: fond.name,
+                series: bag_series,
+            },
+            number,
+        ))
+    }
+
+    fn write_bag(&self, bag: &Path, scope: BagScope, mut fond: BagFond, number: &str) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(bag.join("data"))?;
+
+        // 复制档案项文件，metadata 中的 payload 改写为包内路径
+        let mut manifest = BTreeMap::new();
+        let mut bytes = 0u64;
+        for series in &mut fond.series {
+            for file in &mut series.files {
+                for item in &mut file.items {
+                    let Some(stored) = item.payload.take() else { continue };
+                    let source = LibraryPathService::resolve(&self.library_path, &stored);
+                    if !source.exists() {
+                        return Err(format!("档案项 {} 的路径不存在: {}", item.item_no, source.display()).into());
+                    }
+                    let item_dir = format!("data/{}/{}/{}", fond.fond_no, file.file_no, item.item_no);
+                    for (relative, from) in FixityService::payload_files(&source)? {
+                        let name = format!("{}/{}", item_dir, relative);
+                        let to = bag.join(&name);
+                        if let Some(parent) = to.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::copy(&from, &to)?;
+                        let (hash, size) = FixityService::hash_file(&to, ChecksumAlgorithm::Sha256)?;
+                        bytes += size;
+                        manifest.insert(name, hash);
+                    }
+                    item.payload = Some(match source.file_name().filter(|_| source.is_file()) {
+                        Some(name) => format!("{}/{}", item_dir, name.to_string_lossy()),
+                        None => item_dir,
+                    });
+                }
+            }
+        }
+
+        let metadata = BagMetadata {
+            version: 1,
+            scope: scope.as_str().to_string(),
+            created_at: chrono::Local::now().naive_local(),
+            created_by: current_user().unwrap_or_default(),
+            created_machine: current_machine().unwrap_or_default(),
+            fond,
+        };
+        let metadata_path = bag.join(METADATA_NAME);
+        if let Some(parent) = metadata_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&metadata_path, serde_json::to_vec_pretty(&metadata)?)?;
+
+        fs::write(
+            bag.join("bagit.txt"),
+            format!("BagIt-Version: {}\nTag-File-Character-Encoding: UTF-8\n", BAGIT_VERSION),
+        )?;
+        let bag_info = [
+            ("Bagging-Date", metadata.created_at.format("%Y-%m-%d").to_string()),
+            ("Bag-Software-Agent", format!("fonds-pod {}", env!("CARGO_PKG_VERSION"))),
+            ("Contact-Name", metadata.created_by.clone()),
+            ("External-Identifier", number.to_string()),
+            ("External-Description", metadata.fond.name.clone()),
+            ("Payload-Oxum", format!("{}.{}", bytes, manifest.len())),
+        ];
+        let bag_info: String = bag_info.iter().map(|(label, value)| format!("{}: {}\n", label, value)).collect();
+        fs::write(bag.join("bag-info.txt"), bag_info)?;
+        fs::write(bag.join(PAYLOAD_MANIFEST), manifest_text(&manifest))?;
+
+        let mut tag_manifest = BTreeMap::new();
+        for name in ["bagit.txt", "bag-info.txt", PAYLOAD_MANIFEST, METADATA_NAME] {
+            let (hash, _) = FixityService::hash_file(&bag.join(name), ChecksumAlgorithm::Sha256)?;
+            tag_manifest.insert(name.to_string(), hash);
+        }
+        fs::write(bag.join(TAG_MANIFEST), manifest_text(&tag_manifest))?;
+        Ok(())
+    }
+
+    /// Problems that make a bag invalid; empty when the bag is complete and every checksum matches
+    ///
+    /// Only SHA-256 manifests are read, which is what this application writes.
+    pub fn validate(bag: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut problems = Vec::new();
+        let declaration = fs::read_to_string(bag.join("bagit.txt")).unwrap_or_default();
+        if !declaration.lines().any(|l| l.starts_with("BagIt-Version:")) {
+            problems.push("缺少 bagit.txt 或其中没有 BagIt-Version".to_string());
+            return Ok(problems);
+        }
+        let Ok(manifest) = fs::read_to_string(bag.join(PAYLOAD_MANIFEST)) else {
+            problems.push(format!("缺少 {}", PAYLOAD_MANIFEST));
+            return Ok(problems);
+        };
+
+        let payload = parse_manifest(&manifest, &mut problems);
+        for (name, expected) in &payload {
+            if !name.starts_with("data/") {
+                problems.push(format!("清单中的文件不在 data/ 下: {}", name));
+                continue;
+            }
+            match FixityService::hash_file(&bag.join(name), ChecksumAlgorithm::Sha256) {
+                Ok((actual, _)) if &actual == expected => {}
+                Ok(_) => problems.push(format!("校验和不符: {}", name)),
+                Err(_) => problems.push(format!("清单中的文件缺失: {}", name)),
+            }
+        }
+        let files = FixityService::payload_files(&bag.join("data"))?;
+        let mut bytes = 0u64;
+        for (name, path) in &files {
+            bytes += fs::metadata(path)?.len();
+            let name = format!("data/{}", name);
+            if !payload.contains_key(&name) {
+                problems.push(format!("文件未列入清单: {}", name));
+            }
+        }
+
+        let bag_info = fs::read_to_string(bag.join("bag-info.txt")).unwrap_or_default();
+        if let Some(oxum) = bag_info.lines().find_map(|l| l.strip_prefix("Payload-Oxum:")) {
+            let expected = format!("{}.{}", bytes, files.len());
+            if oxum.trim() != expected {
+                problems.push(format!("Payload-Oxum 不符: {}，实际为 {}", oxum.trim(), expected));
+            }
+        }
+
+        if let Ok(tag_manifest) = fs::read_to_string(bag.join(TAG_MANIFEST)) {
+            for (name, expected) in parse_manifest(&tag_manifest, &mut problems) {
+                match FixityService::hash_file(&bag.join(&name), ChecksumAlgorithm::Sha256) {
+                    Ok((actual, _)) if actual == expected => {}
+                    Ok(_) => problems.push(format!("校验和不符: {}", name)),
+                    Err(_) => problems.push(format!("标签文件缺失: {}", name)),
+                }
+            }
+        }
+        if !bag.join(METADATA_NAME).is_file() {
+            problems.push(format!("缺少 {}", METADATA_NAME));
+        }
+        Ok(problems)
+    }
+
+    /// Read the metadata tree of a bag
+    pub fn read_metadata(bag: &Path) -> Result<BagMetadata, Box<dyn Error>> {
+        Ok(serde_json::from_slice(&fs::read(bag.join(METADATA_NAME))?)?)
+    }
+
+    /// Validate a bag and recreate its hierarchy in this library
+    ///
+    /// A fond or series with the same number and name as an existing one is
+    /// reused, so series and file bags of one fond land in the same place.
+    /// Any other colliding number gets a new one from the library's sequences,
+    /// and the numbers below it follow the new prefix. The import runs as one
+    /// transaction; if it fails, the copied payloads are removed again. The
+    /// bag's SHA-256 manifest is verified first; the library records the
+    /// imported payloads' checksums with `algorithm`.
+    pub fn import(&self, bag: &Path, algorithm: ChecksumAlgorithm) -> Result<BagImportReport, Box<dyn Error>> {
+        let problems = Self::validate(bag)?;
+        if !problems.is_empty() {
+            return Err(format!("BagIt 包校验失败:\n{}", problems.join("\n")).into());
+        }
+        let metadata = Self::read_metadata(bag)?;
+        // 导入前检查所有 payload 路径，避免建了一半的层级
+        for item in metadata.fond.series.iter().flat_map(|s| &s.files).flat_map(|f| &f.items) {
+            if let Some(payload) = &item.payload {
+                if !payload.starts_with("data/") || !is_safe_relative(payload) || !bag.join(payload).exists() {
+                    return Err(format!("档案项 {} 的文件路径无效: {}", item.item_no, payload).into());
+                }
+            }
+        }
+
+        // 新建的目录，导入失败时删除
+        let mut created_dirs = Vec::new();
+        let result = with_transaction(&self.conn, || self.write(bag, &metadata, algorithm, &mut created_dirs));
+        if result.is_err() {
+            for dir in created_dirs.iter().rev() {
+                if let Err(e) = fs::remove_dir_all(dir) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!("Failed to remove {}: {}", dir.display(), e);
+                    }
+                }
+            }
+            // 回滚后按数据库内容重写全宗 sidecar，其中可能列有已回滚的系列
+            let mut conn = self.conn.borrow_mut();
+            let fond_ids: Vec<i32> = fonds::table.select(fonds::id).load(&mut *conn).unwrap_or_default();
+            for fond_id in fond_ids {
+                sidecars::after_write(&mut conn, "fonds", fond_id, None);
+            }
+        }
+        result
+    }
+
+    /// Create the bag's hierarchy and copy its payloads; runs inside the import's transaction
+    ///
+    /// Directories that did not exist before are added to `created_dirs`.
+    fn write(&self, bag: &Path, metadata: &BagMetadata, algorithm: ChecksumAlgorithm, created_dirs: &mut Vec<PathBuf>) -> Result<BagImportReport, Box<dyn Error>> {
+        let mut create_dir = |dir: &Path| -> std::io::Result<()> {
+            if !dir.exists() {
+                created_dirs.push(dir.to_path_buf());
+            }
+            fs::create_dir_all(dir)
+        };
+        let mut fonds_repo = FondsRepository::new(Rc::clone(&self.conn));
+        let mut series_repo = SeriesRepository::new(Rc::clone(&self.conn));
+        let mut files_repo = FilesRepository::new(Rc::clone(&self.conn));
+        let mut items_repo = ItemsRepository::new(Rc::clone(&self.conn));
+        let mut sequences = SequencesRepository::new(Rc::clone(&self.conn));
+        let fixity = FixityService::new(Rc::clone(&self.conn), &self.library_path);
+
+        let mut taken: HashSet<String> = fonds_repo.find_all()?.into_iter().map(|f| f.fond_no).collect();
+        taken.extend(series_repo.find_all()?.into_iter().map(|s| s.series_no));
+        taken.extend(files_repo.find_all()?.into_iter().map(|f| f.file_no));
+        taken.extend(items_repo.find_all()?.into_iter().map(|i| i.item_no));
+
+        let mut report = BagImportReport::default();
+        let source = &metadata.fond;
+        let code = &source.fond_classification_code;
+
+        let (fond_id, fond_no) = match fonds_repo.find_by_fond_no(&source.fond_no)? {
+            Some(existing) if existing.name == source.name => (existing.id, existing.fond_no),
+            existing => {
+                let mut fond_no = source.fond_no.clone();
+                if existing.is_some() {
+                    while taken.contains(&fond_no) {
+                        fond_no = format!("{}{}", code, sequences.get_next_number(code, Some(2))?);
+                    }
+                    report.remapped.push((source.fond_no.clone(), fond_no.clone()));
+                }
+                // 先建目录再写记录，写记录时的 sidecar 会建出目录
+                create_dir(&self.library_path.join(&fond_no))?;
+                let id = fonds_repo.create(Fond {
+                    id: 0,
+                    fond_no: fond_no.clone(),
+                    fond_classification_code: code.clone(),
+                    name: source.name.clone(),
+                    created_by: String::new(),
+                    created_machine: String::new(),
+                    created_at: chrono::Utc::now().naive_utc(),
+                    updated_at: None,
+                    updated_by: None,
+                    updated_machine: None,
+                })?;
+                taken.insert(fond_no.clone());
+                sequences.advance_past(code, &fond_no, 2)?;
+                report.fonds += 1;
+                (id, fond_no)
+            }
+        };
+        create_dir(&self.library_path.join(&fond_no))?;
+
+        for bag_series in &source.series {
+            let wanted = rebase(&bag_series.series_no, &source.fond_no, &fond_no);
+            let existing = series_repo.find_by_series_no(&wanted)?;
+            let (series_id, series_no) = match existing {
+                Some(s) if s.fond_id == fond_id && s.name == bag_series.name => (s.id, s.series_no),
+                _ => {
+                    let mut series_no = wanted.clone();
+                    let mut n = 2;
+                    while taken.contains(&series_no) {
+                        series_no = format!("{}-{}", wanted, n);
+                        n += 1;
+                    }
+                    if series_no != bag_series.series_no {
+                        report.remapped.push((bag_series.series_no.clone(), series_no.clone()));
+                    }
+                    let id = series_repo.create(Series {
+                        id: 0,
+                        fond_id,
+                        series_no: series_no.clone(),
+                        name: bag_series.name.clone(),
+                        created_by: String::new(),
+                        created_machine: String::new(),
+                        created_at: chrono::Utc::now().naive_utc(),
+                        updated_at: None,
+                        updated_by: None,
+                        updated_machine: None,
+                    })?;
+                    taken.insert(series_no.clone());
+                    report.series += 1;
+                    (id, series_no)
+                }
+            };
+
+            for bag_file in &bag_series.files {
+                let mut file_no = rebase(&bag_file.file_no, &bag_series.series_no, &series_no);
+                while taken.contains(&file_no) {
+                    file_no = format!("{}-{}", series_no, sequences.get_next_number(&series_no, Some(2))?);
+                }
+                if file_no != bag_file.file_no {
+                    report.remapped.push((bag_file.file_no.clone(), file_no.clone()));
+                }
+                let file_dir = self.library_path.join(&fond_no).join(&file_no);
+                create_dir(&file_dir)?;
+                let file_id = files_repo.create(File {
+                    id: 0,
+                    series_id,
+                    name: bag_file.name.clone(),
+                    file_no: file_no.clone(),
+                    path: Some(LibraryPathService::to_stored(&self.library_path, &file_dir)),
+                    created_by: String::new(),
+                    created_machine: String::new(),
+                    created_at: chrono::Utc::now().naive_utc(),
+                    updated_at: None,
+                    updated_by: None,
+                    updated_machine: None,
+                })?;
+                taken.insert(file_no.clone());
+                sequences.advance_past(&series_no, &file_no, 2)?;
+                report.files += 1;
+
+                for bag_item in &bag_file.items {
+                    let mut item_no = rebase(&bag_item.item_no, &bag_file.file_no, &file_no);
+                    while taken.contains(&item_no) {
+                        item_no = format!("{}-{}", file_no, sequences.get_next_number(&file_no, Some(3))?);
+                    }
+                    if item_no != bag_item.item_no {
+                        report.remapped.push((bag_item.item_no.clone(), item_no.clone()));
+                    }
+                    let destination = IngestService::destination(&self.library_path, &fond_no, &file_no, &item_no);
+                    create_dir(&destination)?;
+                    let path = match &bag_item.payload {
+                        Some(payload) => {
+                            let outcome = IngestService::ingest(&bag.join(payload), &destination, IngestMode::Copy, |_| {})?;
+                            Some(LibraryPathService::to_stored(&self.library_path, Path::new(&outcome.path)))
+                        }
+                        None => None,
+                    };
+                    let item_id = items_repo.create(Item {
+                        id: 0,
+                        file_id,
+                        item_no: item_no.clone(),
+                        name: bag_item.name.clone(),
+                        path,
+                        // 保留原单位记录的来源路径，没有时记录包内位置
+                        source_path: bag_item.source_path.clone().or_else(|| {
+                            bag_item.payload.as_ref().map(|p| bag.join(p).to_string_lossy().to_string())
+                        }),
+                        created_by: String::new(),
+                        created_machine: String::new(),
+                        created_at: chrono::Utc::now().naive_utc(),
+                        updated_at: None,
+                        updated_by: None,
+                        updated_machine: None,
+                    })?;
+                    taken.insert(item_no.clone());
+                    sequences.advance_past(&file_no, &item_no, 3)?;
+                    if let Err(e) = fixity.record_item(item_id, algorithm) {
+                        log::warn!("Failed to record checksums for imported item {}: {}", item_no, e);
+                    }
+                    report.items += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Replace the parent prefix of a number after the parent was renumbered
+fn rebase(number: &str, old_prefix: &str, new_prefix: &str) -> String {
+    match number.strip_prefix(old_prefix) {
+        Some(rest) if old_prefix != new_prefix => format!("{}{}", new_prefix, rest),
+        _ => number.to_string(),
+    }
+}
+
+fn is_safe_relative(name: &str) -> bool {
+    Path::new(name).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Manifest lines `<sha256>  <path>`, with CR, LF and % percent-encoded as BagIt requires
+fn manifest_text(entries: &BTreeMap<String, String>) -> String {
+    entries
+        .iter()
+        .map(|(name, hash)| {
+            let name = name.replace('%', "%25").replace('\n', "%0A").replace('\r', "%0D");
+            format!("{}  {}\n", hash, name)
+        })
+        .collect()
+}
+
+fn parse_manifest(text: &str, problems: &mut Vec<String>) -> BTreeMap<String, String> {
+    let mut entries = BTreeMap::new();
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let Some((hash, name)) = line.split_once(char::is_whitespace) else {
+            problems.push(format!("无法解析清单行: {}", line));
+            continue;
+        };
+        let name = name.trim_start().replace("%0A", "\n").replace("%0D", "\r").replace("%25", "%");
+        if !is_safe_relative(&name) {
+            problems.push(format!("清单中有不安全的路径: {}", name));
+            continue;
+        }
+        entries.insert(name, hash.to_lowercase());
+    }
+    entries
+}
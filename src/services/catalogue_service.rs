@@ -0,0 +1,480 @@
+/// Catalogue Service - Query the fond hierarchy and export catalogues
+/// The home page lists and the XLSX/CSV catalogue exports run on the same queries
+use crate::core::GenericRepository;
+use crate::models::file::File;
+use crate::models::fond::Fond;
+use crate::models::item::Item;
+use crate::models::series::Series;
+use crate::persistence::schema_item_repository::SchemaItemRepository;
+use crate::persistence::{FilesRepository, FondSchemasRepository, FondsRepository, ItemsRepository, SchemaRepository, SeriesRepository};
+use crate::services::csv::csv_escape;
+use crate::services::xlsx::{Cell, Workbook};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Filter of a view on the hierarchy; every set field narrows the result
+///
+/// The home view lists its series, files and items through these queries and
+/// exports its catalogues with the same query, so an export matches the view.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogueQuery {
+    pub fond_id: Option<i32>,
+    pub series_id: Option<i32>,
+    pub file_id: Option<i32>,
+    /// Text a file's number or name must contain (case-insensitive); items follow their files
+    pub file_filter: Option<String>,
+}
+
+impl CatalogueQuery {
+    /// Everything below one fond
+    pub fn fond(fond_id: i32) -> Self {
+        Self { fond_id: Some(fond_id), ..Default::default() }
+    }
+
+    /// Everything below one series
+    pub fn series(series_id: i32) -> Self {
+        Self { series_id: Some(series_id), ..Default::default() }
+    }
+
+    /// Everything below one file
+    pub fn file(file_id: i32) -> Self {
+        Self { file_id: Some(file_id), ..Default::default() }
+    }
+
+    /// Narrow the files to those whose number or name contains `filter`; blank text clears the filter
+    pub fn with_file_filter(self, filter: &str) -> Self {
+        let filter = filter.trim();
+        Self { file_filter: (!filter.is_empty()).then(|| filter.to_lowercase()), ..self }
+    }
+
+    fn matches_file(&self, file: &File) -> bool {
+        self.file_filter.as_ref().is_none_or(|filter| {
+            file.file_no.to_lowercase().contains(filter) || file.name.to_lowercase().contains(filter)
+        })
+    }
+}
+
+/// Catalogue kind, named after the level it describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CatalogueLevel {
+    /// 全宗目录: one row per series
+    Fond,
+    /// 案卷目录: one row per file
+    Series,
+    /// 卷内目录: one row per item
+    File,
+}
+
+impl CatalogueLevel {
+    /// Key used in settings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatalogueLevel::Fond => "fond",
+            CatalogueLevel::Series => "series",
+            CatalogueLevel::File => "file",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            CatalogueLevel::Fond => "全宗目录",
+            CatalogueLevel::Series => "案卷目录",
+            CatalogueLevel::File => "卷内目录",
+        }
+    }
+
+    /// Columns used when none are configured
+    pub fn default_columns(&self) -> Vec<CatalogueColumn> {
+        use CatalogueColumn::*;
+        match self {
+            CatalogueLevel::Fond => vec![SeriesNo, SeriesName, FileCount, ItemCount],
+            CatalogueLevel::Series => vec![FileNo, FileName, SeriesName, ItemCount, CreatedAt],
+            CatalogueLevel::File => vec![ItemNo, ItemName, FileNo, Path, CreatedAt],
+        }
+    }
+}
+
+/// Catalogue column
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CatalogueColumn {
+    FondNo,
+    FondName,
+    Classification,
+    SeriesNo,
+    SeriesName,
+    FileNo,
+    FileName,
+    ItemNo,
+    ItemName,
+    /// Files in the series
+    FileCount,
+    /// Items in the series or file
+    ItemCount,
+    Path,
+    SourcePath,
+    CreatedAt,
+    CreatedBy,
+    /// Value of a schema dimension of the series (e.g. year, retention period), by schema_no
+    Dimension(String),
+}
+
+impl CatalogueColumn {
+    /// Key used in settings
+    pub fn key(&self) -> String {
+        match self {
+            CatalogueColumn::FondNo => "fond_no".into(),
+            CatalogueColumn::FondName => "fond_name".into(),
+            CatalogueColumn::Classification => "classification".into(),
+            CatalogueColumn::SeriesNo => "series_no".into(),
+            CatalogueColumn::SeriesName => "series_name".into(),
+            CatalogueColumn::FileNo => "file_no".into(),
+            CatalogueColumn::FileName => "file_name".into(),
+            CatalogueColumn::ItemNo => "item_no".into(),
+            CatalogueColumn::ItemName => "item_name".into(),
+            CatalogueColumn::FileCount => "file_count".into(),
+            CatalogueColumn::ItemCount => "item_count".into(),
+            CatalogueColumn::Path => "path".into(),
+            CatalogueColumn::SourcePath => "source_path".into(),
+            CatalogueColumn::CreatedAt => "created_at".into(),
+            CatalogueColumn::CreatedBy => "created_by".into(),
+            CatalogueColumn::Dimension(schema_no) => format!("dimension:{}", schema_no),
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        if let Some(schema_no) = key.strip_prefix("dimension:") {
+            return Some(CatalogueColumn::Dimension(schema_no.to_string()));
+        }
+        Some(match key {
+            "fond_no" => CatalogueColumn::FondNo,
+            "fond_name" => CatalogueColumn::FondName,
+            "classification" => CatalogueColumn::Classification,
+            "series_no" => CatalogueColumn::SeriesNo,
+            "series_name" => CatalogueColumn::SeriesName,
+            "file_no" => CatalogueColumn::FileNo,
+            "file_name" => CatalogueColumn::FileName,
+            "item_no" => CatalogueColumn::ItemNo,
+            "item_name" => CatalogueColumn::ItemName,
+            "file_count" => CatalogueColumn::FileCount,
+            "item_count" => CatalogueColumn::ItemCount,
+            "path" => CatalogueColumn::Path,
+            "source_path" => CatalogueColumn::SourcePath,
+            "created_at" => CatalogueColumn::CreatedAt,
+            "created_by" => CatalogueColumn::CreatedBy,
+            _ => return None,
+        })
+    }
+
+    /// Columns from settings keys, skipping unknown keys
+    pub fn parse_list(keys: &[String]) -> Vec<Self> {
+        keys.iter()
+            .filter_map(|key| {
+                let column = Self::parse(key);
+                if column.is_none() {
+                    log::warn!("Unknown catalogue column: {}", key);
+                }
+                column
+            })
+            .collect()
+    }
+
+    /// Header text; dimension columns use the schema name
+    fn header(&self, schema_names: &HashMap<String, String>) -> String {
+        match self {
+            CatalogueColumn::FondNo => "全宗号".into(),
+            CatalogueColumn::FondName => "全宗名称".into(),
+            CatalogueColumn::Classification => "分类号".into(),
+            CatalogueColumn::SeriesNo => "系列号".into(),
+            CatalogueColumn::SeriesName => "系列名称".into(),
+            CatalogueColumn::FileNo => "案卷号".into(),
+            CatalogueColumn::FileName => "案卷题名".into(),
+            CatalogueColumn::ItemNo => "文件号".into(),
+            CatalogueColumn::ItemName => "文件题名".into(),
+            CatalogueColumn::FileCount => "案卷数".into(),
+            CatalogueColumn::ItemCount => "文件数".into(),
+            CatalogueColumn::Path => "路径".into(),
+            CatalogueColumn::SourcePath => "来源路径".into(),
+            CatalogueColumn::CreatedAt => "创建时间".into(),
+            CatalogueColumn::CreatedBy => "创建人".into(),
+            CatalogueColumn::Dimension(schema_no) => schema_names.get(schema_no).cloned().unwrap_or_else(|| schema_no.clone()),
+        }
+    }
+}
+
+/// One catalogue line with its place in the hierarchy
+struct CatalogueRow {
+    series: Series,
+    file: Option<File>,
+    item: Option<Item>,
+    file_count: usize,
+    item_count: usize,
+}
+
+/// Catalogue rows of one fond
+type FondRows = (Fond, Vec<CatalogueRow>);
+
+/// Catalogue queries and exports for one library
+pub struct CatalogueService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl CatalogueService {
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Fonds matching the query
+    pub fn fonds(&self, query: &CatalogueQuery) -> Result<Vec<Fond>, Box<dyn Error>> {
+        FondsRepository::new(Rc::clone(&self.conn)).find_by_predicate(|f| query.fond_id.is_none_or(|id| f.id == id))
+    }
+
+    /// Series matching the query
+    pub fn series(&self, query: &CatalogueQuery) -> Result<Vec<Series>, Box<dyn Error>> {
+        SeriesRepository::new(Rc::clone(&self.conn)).find_by_predicate(|s| {
+            query.fond_id.is_none_or(|id| s.fond_id == id) && query.series_id.is_none_or(|id| s.id == id)
+        })
+    }
+
+    /// Files matching the query
+    pub fn files(&self, query: &CatalogueQuery) -> Result<Vec<File>, Box<dyn Error>> {
+        let series: Option<HashSet<i32>> = (query.fond_id.is_some() || query.series_id.is_some())
+            .then(|| self.series(query))
+            .transpose()?
+            .map(|list| list.iter().map(|s| s.id).collect());
+        FilesRepository::new(Rc::clone(&self.conn)).find_by_predicate(|f| {
+            series.as_ref().is_none_or(|ids| ids.contains(&f.series_id))
+                && query.file_id.is_none_or(|id| f.id == id)
+                && query.matches_file(f)
+        })
+    }
+
+    /// Items matching the query
+    pub fn items(&self, query: &CatalogueQuery) -> Result<Vec<Item>, Box<dyn Error>> {
+        let files: Option<HashSet<i32>> = (query.fond_id.is_some()
+            || query.series_id.is_some()
+            || query.file_id.is_some()
+            || query.file_filter.is_some())
+            .then(|| self.files(query))
+            .transpose()?
+            .map(|list| list.iter().map(|f| f.id).collect());
+        ItemsRepository::new(Rc::clone(&self.conn)).find_by_predicate(|i| files.as_ref().is_none_or(|ids| ids.contains(&i.file_id)))
+    }
+
+    /// Catalogue of the query, one workbook sheet per fond
+    pub fn workbook(
+        &self,
+        level: CatalogueLevel,
+        query: &CatalogueQuery,
+        columns: &[CatalogueColumn],
+    ) -> Result<Workbook, Box<dyn Error>> {
+        let schema_names = self.schema_names()?;
+        let header: Vec<String> = columns.iter().map(|c| c.header(&schema_names)).collect();
+        let mut workbook = Workbook::new();
+        for (fond, rows) in self.rows(level, query)? {
+            let dimensions = self.dimensions(&fond)?;
+            let cells = rows.iter().map(|row| columns.iter().map(|c| Self::value(c, &fond, row, &dimensions)).collect()).collect();
+            workbook.add_sheet(&format!("{} {}", fond.fond_no, fond.name), header.clone(), cells);
+        }
+        if workbook.sheets().is_empty() {
+            workbook.add_sheet(level.title(), header, Vec::new());
+        }
+        Ok(workbook)
+    }
+
+    /// Catalogue of the query as CSV
+    ///
+    /// Starts with a UTF-8 byte order mark so Excel does not garble Chinese
+    /// text. A catalogue spanning several fonds gets a fond number column.
+    pub fn to_csv(
+        &self,
+        level: CatalogueLevel,
+        query: &CatalogueQuery,
+        columns: &[CatalogueColumn],
+    ) -> Result<String, Box<dyn Error>> {
+        let groups = self.rows(level, query)?;
+        let mut columns = columns.to_vec();
+        if groups.len() > 1 && !columns.contains(&CatalogueColumn::FondNo) {
+            columns.insert(0, CatalogueColumn::FondNo);
+        }
+        let schema_names = self.schema_names()?;
+        let mut csv = String::from("\u{feff}");
+        csv.push_str(&columns.iter().map(|c| csv_escape(&c.header(&schema_names))).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+        for (fond, rows) in &groups {
+            let dimensions = self.dimensions(fond)?;
+            for row in rows {
+                let line: Vec<String> = columns
+                    .iter()
+                    .map(|c| csv_escape(&Self::value(c, fond, row, &dimensions).as_text()))
+                    .collect();
+                csv.push_str(&line.join(","));
+                csv.push('\n');
+            }
+        }
+        Ok(csv)
+    }
+
+    /// Write the catalogue as `.xlsx` or `.csv`, chosen by extension; returns the number of rows
+    pub fn export(
+        &self,
+        level: CatalogueLevel,
+        query: &CatalogueQuery,
+        columns: &[CatalogueColumn],
+        path: &Path,
+    ) -> Result<usize, Box<dyn Error>> {
+        if columns.is_empty() {
+            return Err("没有选择导出列".into());
+        }
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "xlsx" => {
+                let workbook = self.workbook(level, query, columns)?;
+                workbook.save(path)?;
+                Ok(workbook.sheets().iter().map(|s| s.rows.len()).sum())
+            }
+            "csv" => {
+                let csv = self.to_csv(level, query, columns)?;
+                fs::write(path, &csv)?;
+                Ok(csv.lines().count() - 1)
+            }
+            _ => Err("目录只能导出为 .xlsx 或 .csv".into()),
+        }
+    }
+
+    /// Catalogue rows grouped by fond
+    fn rows(&self, level: CatalogueLevel, query: &CatalogueQuery) -> Result<Vec<FondRows>, Box<dyn Error>> {
+        let series = self.series(query)?;
+        let files = self.files(query)?;
+        let items = self.items(query)?;
+        let mut groups = Vec::new();
+        for fond in self.fonds(query)? {
+            let mut rows = Vec::new();
+            for s in series.iter().filter(|s| s.fond_id == fond.id) {
+                let series_files: Vec<&File> = files.iter().filter(|f| f.series_id == s.id).collect();
+                let items_of = |file: &File| items.iter().filter(|i| i.file_id == file.id).count();
+                match level {
+                    CatalogueLevel::Fond => rows.push(CatalogueRow {
+                        series: s.clone(),
+                        file: None,
+                        item: None,
+                        file_count: series_files.len(),
+                        item_count: series_files.iter().map(|f| items_of(f)).sum(),
+                    }),
+                    CatalogueLevel::Series => rows.extend(series_files.iter().map(|f| CatalogueRow {
+                        series: s.clone(),
+                        file: Some((*f).clone()),
+                        item: None,
+                        file_count: 1,
+                        item_count: items_of(f),
+                    })),
+                    CatalogueLevel::File => {
+                        for f in &series_files {
+                            rows.extend(items.iter().filter(|i| i.file_id == f.id).map(|i| CatalogueRow {
+                                series: s.clone(),
+                                file: Some((*f).clone()),
+                                item: Some(i.clone()),
+                                file_count: 1,
+                                item_count: 1,
+                            }));
+                        }
+                    }
+                }
+            }
+            if !rows.is_empty() {
+                groups.push((fond, rows));
+            }
+        }
+        Ok(groups)
+    }
+
+    fn value(column: &CatalogueColumn, fond: &Fond, row: &CatalogueRow, dimensions: &Dimensions) -> Cell {
+        let text = |value: &str| Cell::Text(value.to_string());
+        let file = row.file.as_ref();
+        let item = row.item.as_ref();
+        match column {
+            CatalogueColumn::FondNo => text(&fond.fond_no),
+            CatalogueColumn::FondName => text(&fond.name),
+            CatalogueColumn::Classification => text(&fond.fond_classification_code),
+            CatalogueColumn::SeriesNo => text(&row.series.series_no),
+            CatalogueColumn::SeriesName => text(&row.series.name),
+            CatalogueColumn::FileNo => text(file.map(|f| f.file_no.as_str()).unwrap_or_default()),
+            CatalogueColumn::FileName => text(file.map(|f| f.name.as_str()).unwrap_or_default()),
+            CatalogueColumn::ItemNo => text(item.map(|i| i.item_no.as_str()).unwrap_or_default()),
+            CatalogueColumn::ItemName => text(item.map(|i| i.name.as_str()).unwrap_or_default()),
+            CatalogueColumn::FileCount => Cell::Number(row.file_count as f64),
+            CatalogueColumn::ItemCount => Cell::Number(row.item_count as f64),
+            CatalogueColumn::Path => {
+                text(item.and_then(|i| i.path.as_deref()).or(file.and_then(|f| f.path.as_deref())).unwrap_or_default())
+            }
+            CatalogueColumn::SourcePath => text(item.and_then(|i| i.source_path.as_deref()).unwrap_or_default()),
+            CatalogueColumn::CreatedAt => {
+                let created = item.map(|i| i.created_at).or(file.map(|f| f.created_at)).unwrap_or(row.series.created_at);
+                Cell::Text(created.format("%Y-%m-%d %H:%M:%S").to_string())
+            }
+            CatalogueColumn::CreatedBy => {
+                text(item.map(|i| i.created_by.as_str()).or(file.map(|f| f.created_by.as_str())).unwrap_or(&row.series.created_by))
+            }
+            CatalogueColumn::Dimension(schema_no) => text(&dimensions.value(fond, &row.series, schema_no)),
+        }
+    }
+
+    fn schema_names(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        Ok(SchemaRepository::new(Rc::clone(&self.conn))
+            .find_all()?
+            .into_iter()
+            .map(|s| (s.schema_no, s.name))
+            .collect())
+    }
+
+    /// Schema dimensions of a fond, in the order they make up its series numbers
+    fn dimensions(&self, fond: &Fond) -> Result<Dimensions, Box<dyn Error>> {
+        let mut fond_schemas = FondSchemasRepository::new(Rc::clone(&self.conn)).find_by_predicate(|fs| fs.fond_id == fond.id)?;
+        fond_schemas.sort_by_key(|fs| fs.sort_order);
+        let schemas = SchemaRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let items = SchemaItemRepository::new(Rc::clone(&self.conn)).find_all()?;
+
+        let mut dimensions = Dimensions::default();
+        for fond_schema in fond_schemas {
+            if let Some(schema) = schemas.iter().find(|s| s.id == fond_schema.schema_id) {
+                let names = items
+                    .iter()
+                    .filter(|i| i.schema_id == schema.id)
+                    .map(|i| (i.item_no.clone(), i.item_name.clone()))
+                    .collect();
+                dimensions.schemas.push((schema.schema_no.clone(), names));
+            }
+        }
+        Ok(dimensions)
+    }
+}
+
+/// Schema dimensions of one fond: schema_no and item names by item_no
+#[derive(Default)]
+struct Dimensions {
+    schemas: Vec<(String, HashMap<String, String>)>,
+}
+
+impl Dimensions {
+    /// Value of a dimension for a series, read back from its number (`<fond_no>-<item_no>-<item_no>...`)
+    ///
+    /// Items without a stored name, such as generated years, show their number.
+    fn value(&self, fond: &Fond, series: &Series, schema_no: &str) -> String {
+        let Some(position) = self.schemas.iter().position(|(no, _)| no == schema_no) else {
+            return String::new();
+        };
+        let Some(parts) = series.series_no.strip_prefix(&format!("{}-", fond.fond_no)) else {
+            return String::new();
+        };
+        let parts: Vec<&str> = parts.split('-').collect();
+        if parts.len() != self.schemas.len() {
+            return String::new();
+        }
+        let part = parts[position];
+        self.schemas[position].1.get(part).cloned().unwrap_or_else(|| part.to_string())
+    }
+}
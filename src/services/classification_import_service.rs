@@ -0,0 +1,384 @@
+/// Classification Import Service - Merge an imported classification tree into the library
+/// Nodes are matched by code, so fonds keep their classification and curated
+/// `active` flags survive; the diff is previewed before anything is written
+use crate::core::GenericRepository;
+use crate::models::fond_classification::{ClassificationJson, FondClassification};
+use crate::models::translation::Translatable;
+use crate::persistence::{sidecars, with_transaction, FondClassificationsRepository, FondsRepository, TranslationsRepository};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+use std::rc::Rc;
+
+/// One difference between the library and the imported tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassificationChange {
+    /// New code; `parent` is the parent code, None for a top-level classification
+    Added { code: String, name: String, parent: Option<String> },
+    Renamed { code: String, from: String, to: String },
+    /// Translated names added, changed or removed, by locale
+    Translated { code: String, locales: Vec<String> },
+    Moved { code: String, from: Option<String>, to: Option<String> },
+    /// Marked inactive by the import (local inactive flags are never re-activated)
+    Deactivated { code: String },
+    /// Code missing from the import; `fonds` are the fond numbers still using it
+    Removed { code: String, name: String, fonds: Vec<String>, replaced_by: Option<String> },
+}
+
+impl ClassificationChange {
+    pub fn code(&self) -> &str {
+        match self {
+            Self::Added { code, .. }
+            | Self::Renamed { code, .. }
+            | Self::Translated { code, .. }
+            | Self::Moved { code, .. }
+            | Self::Deactivated { code }
+            | Self::Removed { code, .. } => code,
+        }
+    }
+
+    /// A removal that would leave fonds pointing at a missing classification
+    pub fn is_blocked(&self) -> bool {
+        matches!(self, Self::Removed { fonds, replaced_by: None, .. } if !fonds.is_empty())
+    }
+
+    /// One-line description for the preview
+    pub fn describe(&self) -> String {
+        let parent = |code: &Option<String>| code.clone().unwrap_or_else(|| "顶级".to_string());
+        match self {
+            Self::Added { code, name, parent: p } => format!("新增 {} {}（上级: {}）", code, name, parent(p)),
+            Self::Renamed { code, from, to } => format!("重命名 {}: {} → {}", code, from, to),
+            Self::Translated { code, locales } => format!("更新翻译 {}: {}", code, locales.join(", ")),
+            Self::Moved { code, from, to } => format!("移动 {}: {} → {}", code, parent(from), parent(to)),
+            Self::Deactivated { code } => format!("停用 {}", code),
+            Self::Removed { code, name, fonds, replaced_by } => match (fonds.is_empty(), replaced_by) {
+                (true, _) => format!("删除 {} {}", code, name),
+                (false, Some(target)) => format!("删除 {} {}，{} 个全宗改用 {}", code, name, fonds.len(), target),
+                (false, None) => format!("删除 {} {}：仍被全宗 {} 使用", code, name, fonds.join(", ")),
+            },
+        }
+    }
+}
+
+/// Result of comparing an imported tree with the library
+#[derive(Debug, Clone, Default)]
+pub struct ClassificationDiff {
+    pub changes: Vec<ClassificationChange>,
+    /// Imported nodes identical to the library
+    pub unchanged: usize,
+}
+
+impl ClassificationDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Removals refused because fonds still use the code
+    pub fn blocked(&self) -> Vec<&ClassificationChange> {
+        self.changes.iter().filter(|c| c.is_blocked()).collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let count = |f: fn(&ClassificationChange) -> bool| self.changes.iter().filter(|c| f(c)).count();
+        format!(
+            "新增 {}，重命名 {}，翻译 {}，移动 {}，停用 {}，删除 {}，未变 {}",
+            count(|c| matches!(c, ClassificationChange::Added { .. })),
+            count(|c| matches!(c, ClassificationChange::Renamed { .. })),
+            count(|c| matches!(c, ClassificationChange::Translated { .. })),
+            count(|c| matches!(c, ClassificationChange::Moved { .. })),
+            count(|c| matches!(c, ClassificationChange::Deactivated { .. })),
+            count(|c| matches!(c, ClassificationChange::Removed { .. })),
+            self.unchanged
+        )
+    }
+
+    /// Full change list, one change per line
+    pub fn to_text(&self) -> String {
+        self.changes.iter().map(ClassificationChange::describe).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Imported node with its parent code, in pre-order (parents before children)
+struct FlatNode<'a> {
+    node: &'a ClassificationJson,
+    parent: Option<String>,
+    /// Position among its imported siblings
+    position: i32,
+}
+
+/// Library state the diff is computed against
+struct Current {
+    rows: Vec<FondClassification>,
+    /// First row of each code; later duplicates are treated as removed
+    by_code: HashMap<String, FondClassification>,
+    code_of: HashMap<i32, String>,
+    /// Translated names of each classification id
+    names: HashMap<i32, BTreeMap<String, String>>,
+    fonds_by_code: HashMap<String, Vec<String>>,
+}
+
+/// Classification import service
+pub struct ClassificationImportService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl ClassificationImportService {
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Read a classification tree exported by `export_classifications`
+    pub fn read(path: &Path) -> Result<Vec<ClassificationJson>, Box<dyn Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// The library's classification tree with translated names, in the format `read` accepts
+    pub fn export(&self) -> Result<Vec<ClassificationJson>, Box<dyn Error>> {
+        fn build(
+            parent_id: Option<i32>,
+            children: &HashMap<Option<i32>, Vec<&FondClassification>>,
+            names: &HashMap<i32, BTreeMap<String, String>>,
+        ) -> Vec<ClassificationJson> {
+            children
+                .get(&parent_id)
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| ClassificationJson {
+                            children: build(Some(row.id), children, names),
+                            names: names.get(&row.id).cloned().unwrap_or_default(),
+                            ..ClassificationJson::from_fond_classification(row)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        let rows = FondClassificationsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let names = TranslationsRepository::new(Rc::clone(&self.conn)).find_all_names(FondClassification::ENTITY_TYPE)?;
+        let mut children: HashMap<Option<i32>, Vec<&FondClassification>> = HashMap::new();
+        for row in &rows {
+            children.entry(row.parent_id).or_default().push(row);
+        }
+        Ok(build(None, &children, &names))
+    }
+
+    /// Compare an imported tree with the library without writing anything
+    pub fn preview(&self, tree: &[ClassificationJson]) -> Result<ClassificationDiff, Box<dyn Error>> {
+        let nodes = flatten(tree)?;
+        let remaps = remaps(&nodes)?;
+        let current = self.current()?;
+        Ok(diff(&nodes, &remaps, &current))
+    }
+
+    /// Merge an imported tree into the library in one transaction
+    ///
+    /// Refused when a removed code is still used by fonds and no imported node
+    /// `replaces` it; otherwise those fonds are moved to the replacing code.
+    pub fn apply(&self, tree: &[ClassificationJson]) -> Result<ClassificationDiff, Box<dyn Error>> {
+        let nodes = flatten(tree)?;
+        let remaps = remaps(&nodes)?;
+        let current = self.current()?;
+        let diff = diff(&nodes, &remaps, &current);
+        let blocked: Vec<&str> = diff.blocked().into_iter().map(ClassificationChange::code).collect();
+        if !blocked.is_empty() {
+            return Err(format!(
+                "以下分类仍被全宗使用，不能删除: {}。请在导入文件中用 \"replaces\" 指定替代分类",
+                blocked.join(", ")
+            )
+            .into());
+        }
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+
+        let result = with_transaction(&self.conn, || self.write(&nodes, &remaps, &current));
+        if result.is_err() {
+            // 回滚后按数据库内容重写全宗 sidecar；库级 sidecar 由事务结束时刷新
+            let fonds = FondsRepository::new(Rc::clone(&self.conn)).find_by_predicate(|f| remaps.contains_key(&f.fond_classification_code))?;
+            let mut conn = self.conn.borrow_mut();
+            for fond in fonds {
+                sidecars::after_write(&mut conn, "fonds", fond.id, None);
+            }
+        }
+        result.map(|_| diff)
+    }
+
+    fn current(&self) -> Result<Current, Box<dyn Error>> {
+        let mut rows = FondClassificationsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        rows.sort_by_key(|c| c.id);
+        let mut by_code = HashMap::new();
+        for row in &rows {
+            by_code.entry(row.code.clone()).or_insert_with(|| row.clone());
+        }
+        let code_of = rows.iter().map(|c| (c.id, c.code.clone())).collect();
+        let mut fonds_by_code: HashMap<String, Vec<String>> = HashMap::new();
+        for fond in FondsRepository::new(Rc::clone(&self.conn)).find_all()? {
+            fonds_by_code.entry(fond.fond_classification_code).or_default().push(fond.fond_no);
+        }
+        for fonds in fonds_by_code.values_mut() {
+            fonds.sort();
+        }
+        let names = TranslationsRepository::new(Rc::clone(&self.conn)).find_all_names(FondClassification::ENTITY_TYPE)?;
+        Ok(Current { rows, by_code, code_of, names, fonds_by_code })
+    }
+
+    fn write(&self, nodes: &[FlatNode], remaps: &BTreeMap<String, String>, current: &Current) -> Result<(), Box<dyn Error>> {
+        let mut repo = FondClassificationsRepository::new(Rc::clone(&self.conn));
+        let mut translations = TranslationsRepository::new(Rc::clone(&self.conn));
+        let mut ids: HashMap<&str, i32> = HashMap::new();
+        for flat in nodes {
+            let parent_id = flat.parent.as_deref().map(|code| ids[code]);
+            let id = match current.by_code.get(&flat.node.code) {
+                Some(row) => {
+                    let mut updated = row.clone();
+                    updated.name = flat.node.name.clone();
+                    updated.parent_id = parent_id;
+                    updated.active = row.active && flat.node.active;
+                    if (&updated.name, updated.parent_id, updated.active) != (&row.name, row.parent_id, row.active) {
+                        repo.update(&updated)?;
+                    }
+                    row.id
+                }
+                None => repo.create(flat.node.to_fond_classification(parent_id, flat.position))?,
+            };
+            // 文件中没有 names 的分类保留现有翻译
+            if !flat.node.names.is_empty() {
+                translations.set_names(FondClassification::ENTITY_TYPE, id, &flat.node.names)?;
+            }
+            ids.insert(&flat.node.code, id);
+        }
+
+        let mut fonds_repo = FondsRepository::new(Rc::clone(&self.conn));
+        for mut fond in fonds_repo.find_by_predicate(|f| remaps.contains_key(&f.fond_classification_code))? {
+            fond.fond_classification_code = remaps[&fond.fond_classification_code].clone();
+            fonds_repo.update(&fond)?;
+        }
+
+        // 先删除叶子节点：保留的节点已移到保留的上级下，剩余的子节点只能是待删除的节点
+        let kept: HashSet<i32> = ids.values().copied().collect();
+        let mut removed: Vec<&FondClassification> = current.rows.iter().filter(|c| !kept.contains(&c.id)).collect();
+        while !removed.is_empty() {
+            let parents: HashSet<i32> = removed.iter().filter_map(|c| c.parent_id).collect();
+            let (leaves, rest): (Vec<_>, Vec<_>) = removed.into_iter().partition(|c| !parents.contains(&c.id));
+            if leaves.is_empty() {
+                return Err("分类的上级关系存在循环".into());
+            }
+            for leaf in leaves {
+                repo.delete(leaf.id)?;
+            }
+            removed = rest;
+        }
+        translations.delete_orphans::<FondClassification>()?;
+        Ok(())
+    }
+}
+
+/// Flatten the imported tree, rejecting empty and duplicate codes
+fn flatten(tree: &[ClassificationJson]) -> Result<Vec<FlatNode<'_>>, Box<dyn Error>> {
+    fn walk<'a>(
+        children: &'a [ClassificationJson],
+        parent: Option<&str>,
+        seen: &mut HashSet<&'a str>,
+        out: &mut Vec<FlatNode<'a>>,
+    ) -> Result<(), Box<dyn Error>> {
+        for (position, node) in children.iter().enumerate() {
+            if node.code.trim().is_empty() {
+                return Err(format!("导入文件中有分类缺少代码: {}", node.name).into());
+            }
+            if !seen.insert(&node.code) {
+                return Err(format!("导入文件中分类代码重复: {}", node.code).into());
+            }
+            out.push(FlatNode { node, parent: parent.map(str::to_string), position: position as i32 });
+            walk(&node.children, Some(&node.code), seen, out)?;
+        }
+        Ok(())
+    }
+
+    let mut out = Vec::new();
+    walk(tree, None, &mut HashSet::new(), &mut out)?;
+    Ok(out)
+}
+
+/// Old code -> replacing code, from the `replaces` lists of the imported nodes
+fn remaps(nodes: &[FlatNode]) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let imported: HashSet<&str> = nodes.iter().map(|n| n.node.code.as_str()).collect();
+    let mut remaps = BTreeMap::new();
+    for flat in nodes {
+        for old in &flat.node.replaces {
+            if imported.contains(old.as_str()) {
+                return Err(format!("分类 {} 仍在导入文件中，不能被 {} 取代", old, flat.node.code).into());
+            }
+            if let Some(other) = remaps.insert(old.clone(), flat.node.code.clone()) {
+                return Err(format!("分类 {} 同时被 {} 和 {} 取代", old, other, flat.node.code).into());
+            }
+        }
+    }
+    Ok(remaps)
+}
+
+/// Locales whose translated name differs between the library and the import (empty names count as missing)
+pub(crate) fn changed_locales(current: Option<&BTreeMap<String, String>>, imported: &BTreeMap<String, String>) -> Vec<String> {
+    let empty = BTreeMap::new();
+    let current = current.unwrap_or(&empty);
+    let imported: BTreeMap<&String, &String> = imported.iter().filter(|(_, name)| !name.trim().is_empty()).collect();
+    let locales: std::collections::BTreeSet<&String> = current.keys().chain(imported.keys().copied()).collect();
+    locales
+        .into_iter()
+        .filter(|locale| current.get(*locale) != imported.get(locale).copied())
+        .cloned()
+        .collect()
+}
+
+fn diff(nodes: &[FlatNode], remaps: &BTreeMap<String, String>, current: &Current) -> ClassificationDiff {
+    let mut result = ClassificationDiff::default();
+    for flat in nodes {
+        let node = flat.node;
+        let Some(row) = current.by_code.get(&node.code) else {
+            result.changes.push(ClassificationChange::Added { code: node.code.clone(), name: node.name.clone(), parent: flat.parent.clone() });
+            continue;
+        };
+        let before = result.changes.len();
+        if row.name != node.name {
+            result.changes.push(ClassificationChange::Renamed { code: node.code.clone(), from: row.name.clone(), to: node.name.clone() });
+        }
+        if !node.names.is_empty() {
+            let locales = changed_locales(current.names.get(&row.id), &node.names);
+            if !locales.is_empty() {
+                result.changes.push(ClassificationChange::Translated { code: node.code.clone(), locales });
+            }
+        }
+        let parent = row.parent_id.and_then(|id| current.code_of.get(&id).cloned());
+        if parent != flat.parent {
+            result.changes.push(ClassificationChange::Moved { code: node.code.clone(), from: parent, to: flat.parent.clone() });
+        }
+        if row.active && !node.active {
+            result.changes.push(ClassificationChange::Deactivated { code: node.code.clone() });
+        }
+        if result.changes.len() == before {
+            result.unchanged += 1;
+        }
+    }
+
+    let imported: HashSet<&str> = nodes.iter().map(|n| n.node.code.as_str()).collect();
+    for row in &current.rows {
+        let matched = current.by_code.get(&row.code).is_some_and(|first| first.id == row.id) && imported.contains(row.code.as_str());
+        if matched {
+            continue;
+        }
+        // 重复代码的多余记录被删除，但全宗仍可引用保留下来的同代码分类
+        let fonds = if imported.contains(row.code.as_str()) {
+            Vec::new()
+        } else {
+            current.fonds_by_code.get(&row.code).cloned().unwrap_or_default()
+        };
+        result.changes.push(ClassificationChange::Removed {
+            code: row.code.clone(),
+            name: row.name.clone(),
+            fonds,
+            replaced_by: remaps.get(&row.code).cloned(),
+        });
+    }
+    result
+}
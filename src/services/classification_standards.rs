@@ -0,0 +1,106 @@
+/// Classification standards bundled with the application
+///
+/// The JSON files under `fonds_classifications/` are embedded in the binary so
+/// a new library can start from one of them instead of an empty tree
+use crate::models::fond_classification::ClassificationJson;
+use crate::services::classification_import_service::{ClassificationDiff, ClassificationImportService};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::error::Error;
+use std::rc::Rc;
+
+/// A bundled classification scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassificationStandard {
+    /// Stable identifier, used in settings and dialogs
+    pub id: &'static str,
+    pub name: &'static str,
+    json: &'static str,
+}
+
+const STANDARDS: &[ClassificationStandard] = &[
+    ClassificationStandard {
+        id: "default",
+        name: "默认分类",
+        json: include_str!("../../fonds_classifications/fonds_classifications_default.json"),
+    },
+    ClassificationStandard {
+        id: "GBT15418-94",
+        name: "GB/T 15418-94 档案分类标引规则",
+        json: include_str!("../../fonds_classifications/fonds_classifications_GBT15418-94.json"),
+    },
+];
+
+/// The standard to install into a new library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryStandard {
+    /// `ClassificationStandard::id`
+    pub standard: String,
+    /// Codes to activate (with their parents), everything else inactive;
+    /// None keeps the `active` flags of the standard
+    pub activate: Option<Vec<String>>,
+}
+
+impl ClassificationStandard {
+    /// All bundled standards
+    pub fn all() -> &'static [ClassificationStandard] {
+        STANDARDS
+    }
+
+    /// Find a bundled standard by id
+    pub fn find(id: &str) -> Option<&'static ClassificationStandard> {
+        STANDARDS.iter().find(|s| s.id == id)
+    }
+
+    /// The classification tree of the standard
+    pub fn classifications(&self) -> Result<Vec<ClassificationJson>, Box<dyn Error>> {
+        serde_json::from_str(self.json).map_err(|e| format!("{}: {}", self.id, e).into())
+    }
+
+    /// The classification tree with only `codes` (and their parents) active
+    pub fn classifications_activating(&self, codes: &[String]) -> Result<Vec<ClassificationJson>, Box<dyn Error>> {
+        let mut tree = self.classifications()?;
+        let codes: HashSet<&str> = codes.iter().map(|code| code.trim()).filter(|code| !code.is_empty()).collect();
+        let mut found = HashSet::new();
+        activate_only(&mut tree, &codes, &mut found);
+        let mut missing: Vec<&str> = codes.into_iter().filter(|code| !found.contains(*code)).collect();
+        missing.sort();
+        if !missing.is_empty() {
+            return Err(format!("{} 中没有以下分类代码: {}", self.name, missing.join(", ")).into());
+        }
+        Ok(tree)
+    }
+}
+
+impl LibraryStandard {
+    /// The classification tree to install, checked before the library is created
+    pub fn classifications(&self) -> Result<Vec<ClassificationJson>, Box<dyn Error>> {
+        let standard = ClassificationStandard::find(&self.standard)
+            .ok_or_else(|| format!("未知的分类标准: {}", self.standard))?;
+        match &self.activate {
+            Some(codes) => standard.classifications_activating(codes),
+            None => standard.classifications(),
+        }
+    }
+
+    /// Merge the standard into a library
+    pub fn install(&self, conn: Rc<RefCell<SqliteConnection>>) -> Result<ClassificationDiff, Box<dyn Error>> {
+        ClassificationImportService::new(conn).apply(&self.classifications()?)
+    }
+}
+
+/// Activate the listed codes and the parents of active nodes, deactivate the rest
+fn activate_only(nodes: &mut [ClassificationJson], codes: &HashSet<&str>, found: &mut HashSet<String>) -> bool {
+    let mut any = false;
+    for node in nodes {
+        let listed = codes.contains(node.code.as_str());
+        let child_active = activate_only(&mut node.children, codes, found);
+        node.active = listed || child_active;
+        if listed {
+            found.insert(node.code.clone());
+        }
+        any |= node.active;
+    }
+    any
+}
@@ -0,0 +1,388 @@
+/// Directory Import Service - Turn an existing folder tree into files and items
+/// Scans a directory into an adjustable mapping (folders -> files, their documents -> items) and imports it into a series
+use crate::core::GenericRepository;
+use crate::models::app_settings::IngestMode;
+use crate::models::file::File;
+use crate::models::item::Item;
+use crate::persistence::{establish_connection, FilesRepository, FondsRepository, ItemsRepository, SequencesRepository, SeriesRepository};
+use crate::services::background_job::{BackgroundJob, JobControl};
+use crate::services::backup_service::DATABASE_NAME;
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use crate::services::ingest_service::{IngestOutcome, IngestService};
+use crate::services::library_path_service::LibraryPathService;
+use crate::services::csv::{csv_escape, parse_csv};
+use crate::services::usage_service::UsageService;
+use diesel::{RunQueryDsl, SqliteConnection};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+/// Header of the mapping CSV the user edits between scan and import
+const MAPPING_HEADER: [&str; 5] = ["include", "folder", "file_name", "item", "item_name"];
+
+/// A document that becomes an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedItem {
+    /// Path relative to the scanned root, `/`-separated
+    pub source: String,
+    pub name: String,
+    pub include: bool,
+}
+
+/// A folder that becomes a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedFile {
+    /// Folder relative to the scanned root, `/`-separated; empty for the root itself
+    pub folder: String,
+    pub name: String,
+    pub include: bool,
+    pub items: Vec<MappedItem>,
+}
+
+impl MappedFile {
+    fn included_items(&self) -> impl Iterator<Item = &MappedItem> {
+        self.items.iter().filter(|i| i.include)
+    }
+}
+
+/// Proposed mapping of a directory tree onto files and items
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryMapping {
+    pub root: PathBuf,
+    pub files: Vec<MappedFile>,
+}
+
+impl DirectoryMapping {
+    /// Files that will be created: included and with at least one included item
+    pub fn file_count(&self) -> usize {
+        self.included_files().count()
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.included_files().map(|f| f.included_items().count()).sum()
+    }
+
+    fn included_files(&self) -> impl Iterator<Item = &MappedFile> {
+        self.files.iter().filter(|f| f.include && f.included_items().next().is_some())
+    }
+
+    /// Absolute path of a root-relative source
+    pub fn absolute(&self, relative: &str) -> PathBuf {
+        relative.split('/').filter(|part| !part.is_empty()).fold(self.root.clone(), |path, part| path.join(part))
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} 个文件夹 → {} 个案卷、{} 个文件", self.files.len(), self.file_count(), self.item_count())
+    }
+
+    /// Render the mapping as CSV, one row per item, for adjusting in a spreadsheet
+    ///
+    /// Users can rename files and items, exclude rows with `include` = 0, or
+    /// move an item to another file by changing its `folder`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("\u{feff}{}\r\n", MAPPING_HEADER.join(","));
+        for file in &self.files {
+            for item in &file.items {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\r\n",
+                    u8::from(file.include && item.include),
+                    csv_escape(&file.folder),
+                    csv_escape(&file.name),
+                    csv_escape(&item.source),
+                    csv_escape(&item.name)
+                ));
+            }
+        }
+        csv
+    }
+
+    /// Rebuild a mapping from an edited mapping CSV
+    ///
+    /// Rows are grouped into files by `folder` in first-seen order; the first
+    /// non-empty `file_name` of a group names the file. Every item must still
+    /// exist below the root.
+    pub fn from_csv(root: &Path, text: &str) -> Result<Self, Box<dyn Error>> {
+        let rows = parse_csv(text);
+        let header: Vec<String> = rows.first().map(|h| h.iter().map(|c| c.trim().to_lowercase()).collect()).unwrap_or_default();
+        if header.len() < MAPPING_HEADER.len() || header.iter().zip(MAPPING_HEADER).any(|(h, expected)| h != expected) {
+            return Err(format!("映射表表头应为 {}", MAPPING_HEADER.join(",")).into());
+        }
+
+        let mut mapping = DirectoryMapping { root: root.to_path_buf(), files: Vec::new() };
+        for (index, row) in rows.iter().enumerate().skip(1) {
+            let cell = |i: usize| row.get(i).map(|c| c.trim()).unwrap_or_default();
+            if row.iter().all(|c| c.trim().is_empty()) {
+                continue;
+            }
+            let line = index + 1;
+            let source = cell(3).replace('\\', "/");
+            if source.is_empty() {
+                return Err(format!("第 {} 行缺少文件路径", line).into());
+            }
+            if Path::new(&source).components().any(|c| !matches!(c, Component::Normal(_))) {
+                return Err(format!("第 {} 行的文件路径必须位于扫描目录之内: {}", line, source).into());
+            }
+            if !mapping.absolute(&source).exists() {
+                return Err(format!("第 {} 行的文件不存在: {}", line, source).into());
+            }
+            let include = !matches!(cell(0).to_lowercase().as_str(), "0" | "false" | "no" | "否");
+            let folder = cell(1).replace('\\', "/").trim_matches('/').to_string();
+            let item = MappedItem {
+                name: match cell(4) {
+                    "" => file_stem(&source),
+                    name => name.to_string(),
+                },
+                source,
+                include,
+            };
+            match mapping.files.iter_mut().find(|f| f.folder == folder) {
+                Some(file) => {
+                    if file.name.is_empty() {
+                        file.name = cell(2).to_string();
+                    }
+                    file.items.push(item);
+                }
+                None => mapping.files.push(MappedFile { name: cell(2).to_string(), folder, include: true, items: vec![item] }),
+            }
+        }
+        for file in &mut mapping.files {
+            if file.name.is_empty() {
+                file.name = folder_title(root, &file.folder);
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+/// Result of a directory import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryImportReport {
+    pub files_created: usize,
+    pub items_created: usize,
+    /// The import stopped early on request; everything counted above was imported completely
+    pub cancelled: bool,
+}
+
+impl DirectoryImportReport {
+    pub fn summary(&self) -> String {
+        let status = if self.cancelled { "已取消，" } else { "" };
+        format!("{}已导入 {} 个案卷、{} 个文件", status, self.files_created, self.items_created)
+    }
+}
+
+/// Directory importer for one library
+pub struct DirectoryImportService {
+    conn: Rc<RefCell<SqliteConnection>>,
+    library_path: PathBuf,
+}
+
+impl DirectoryImportService {
+    /// Create a new directory importer on the given library connection and directory
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>, library_path: impl AsRef<Path>) -> Self {
+        Self {
+            conn,
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Walk a directory and propose a mapping
+    ///
+    /// Every folder that directly contains documents becomes a file named by
+    /// its path below the root, and those documents become its items. Nested
+    /// folders are visited in name order; hidden entries and symbolic links
+    /// are skipped.
+    pub fn scan(root: &Path, control: &JobControl) -> Result<DirectoryMapping, Box<dyn Error>> {
+        if !root.is_dir() {
+            return Err(format!("目录不存在: {}", root.display()).into());
+        }
+        let mut mapping = DirectoryMapping { root: root.to_path_buf(), files: Vec::new() };
+        let mut pending = vec![String::new()];
+        let mut scanned = 0;
+        while let Some(folder) = pending.pop() {
+            if control.is_cancelled() {
+                return Err("目录扫描已取消".into());
+            }
+            let mut entries: Vec<_> = fs::read_dir(mapping.absolute(&folder))?
+                .filter_map(Result::ok)
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .collect();
+            entries.sort_by_key(|e| e.file_name());
+
+            let mut items = Vec::new();
+            let mut subfolders = Vec::new();
+            for entry in entries {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let relative = if folder.is_empty() { name.clone() } else { format!("{}/{}", folder, name) };
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    subfolders.push(relative);
+                } else if file_type.is_file() {
+                    items.push(MappedItem { name: file_stem(&name), source: relative, include: true });
+                }
+            }
+            if !items.is_empty() {
+                mapping.files.push(MappedFile { name: folder_title(root, &folder), folder: folder.clone(), include: true, items });
+            }
+            pending.extend(subfolders.into_iter().rev());
+            scanned += 1;
+            control.report(scanned, 0, &folder);
+        }
+        Ok(mapping)
+    }
+
+    /// Create the mapped files and items under a series
+    ///
+    /// File and item numbers come from the sequence counters; documents are
+    /// ingested with the given mode and their checksums recorded. Cancellation
+    /// is honoured between items, so every imported item is complete. A file
+    /// that fails part way is removed again with its items and their payloads;
+    /// files imported before it are kept.
+    pub fn import(
+        &self,
+        mapping: &DirectoryMapping,
+        series_id: i32,
+        ingest_mode: IngestMode,
+        algorithm: ChecksumAlgorithm,
+        control: &JobControl,
+    ) -> Result<DirectoryImportReport, Box<dyn Error>> {
+        let series = SeriesRepository::new(Rc::clone(&self.conn)).find_by_id(series_id)?.ok_or("系列不存在")?;
+        let fond = FondsRepository::new(Rc::clone(&self.conn)).find_by_id(series.fond_id)?.ok_or("全宗不存在")?;
+        let mut files_repo = FilesRepository::new(Rc::clone(&self.conn));
+        let mut items_repo = ItemsRepository::new(Rc::clone(&self.conn));
+        let mut sequences = SequencesRepository::new(Rc::clone(&self.conn));
+        let fixity = FixityService::new(Rc::clone(&self.conn), &self.library_path);
+        let mut taken: HashSet<String> = files_repo.find_all()?.into_iter().map(|f| f.file_no).collect();
+        taken.extend(items_repo.find_all()?.into_iter().map(|i| i.item_no));
+
+        let mut report = DirectoryImportReport::default();
+        let total = mapping.item_count();
+        'files: for mapped in mapping.included_files() {
+            if control.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+            let file_no = sequences.allocate(&series.series_no, 2, |n| taken.contains(n))?;
+            let file_dir = self.library_path.join(&fond.fond_no).join(&file_no);
+            fs::create_dir_all(&file_dir)?;
+            let file_id = files_repo.create(File {
+                id: 0,
+                series_id,
+                name: mapped.name.clone(),
+                file_no: file_no.clone(),
+                path: Some(LibraryPathService::to_stored(&self.library_path, &file_dir)),
+                created_by: String::new(),
+                created_machine: String::new(),
+                created_at: chrono::Utc::now().naive_utc(),
+                updated_at: None,
+                updated_by: None,
+                updated_machine: None,
+            })?;
+            taken.insert(file_no.clone());
+            report.files_created += 1;
+
+            // 本文件已导入的内容；中途失败时连同文件记录一起撤回，之前完成的文件保留
+            let mut ingested: Vec<(IngestOutcome, PathBuf)> = Vec::new();
+            for mapped_item in mapped.included_items() {
+                if control.is_cancelled() {
+                    report.cancelled = true;
+                    break 'files;
+                }
+                control.report(report.items_created, total, &mapped_item.source);
+                let item_no = sequences
+                    .allocate(&file_no, 3, |n| taken.contains(n))
+                    .map_err(|e| self.abandon_file(file_id, &file_dir, &ingested, ingest_mode, e))?;
+                let destination = IngestService::destination(&self.library_path, &fond.fond_no, &file_no, &item_no);
+                let outcome = IngestService::ingest(&mapping.absolute(&mapped_item.source), &destination, ingest_mode, |_| {})
+                    .map_err(|e| self.abandon_file(file_id, &file_dir, &ingested, ingest_mode, e))?;
+                let stored = LibraryPathService::to_stored(&self.library_path, Path::new(&outcome.path));
+                let source_path = outcome.source_path.clone();
+                ingested.push((outcome, destination));
+                let item_id = items_repo
+                    .create(Item {
+                        id: 0,
+                        file_id,
+                        item_no: item_no.clone(),
+                        name: mapped_item.name.clone(),
+                        path: Some(stored),
+                        source_path: Some(source_path),
+                        created_by: String::new(),
+                        created_machine: String::new(),
+                        created_at: chrono::Utc::now().naive_utc(),
+                        updated_at: None,
+                        updated_by: None,
+                        updated_machine: None,
+                    })
+                    .map_err(|e| self.abandon_file(file_id, &file_dir, &ingested, ingest_mode, e))?;
+                taken.insert(item_no.clone());
+                if let Err(e) = fixity.record_item(item_id, algorithm) {
+                    log::warn!("Failed to record checksums for imported item {}: {}", item_no, e);
+                }
+                report.items_created += 1;
+            }
+        }
+        control.report(report.items_created, total, "");
+        Ok(report)
+    }
+
+    /// Remove a file that failed part way - its items, checksums and row, and the payloads
+    /// ingested for it - and hand back the error that stopped it
+    fn abandon_file(
+        &self,
+        file_id: i32,
+        file_dir: &Path,
+        ingested: &[(IngestOutcome, PathBuf)],
+        ingest_mode: IngestMode,
+        error: Box<dyn Error>,
+    ) -> Box<dyn Error> {
+        if let Err(e) = UsageService::new(Rc::clone(&self.conn)).delete_file(file_id) {
+            log::error!("Failed to remove partly imported file {}: {}", file_id, e);
+        }
+        for (outcome, destination) in ingested.iter().rev() {
+            if let Err(e) = IngestService::rollback(outcome, destination, ingest_mode) {
+                log::error!("Failed to roll back ingest into {}: {}", destination.display(), e);
+            }
+        }
+        // 只删除已清空的目录，撤回失败的内容留在原处
+        let _ = fs::remove_dir(file_dir);
+        error
+    }
+
+    /// Scan a directory on a worker thread
+    pub fn spawn_scan(root: PathBuf) -> Result<BackgroundJob<DirectoryMapping>, Box<dyn Error>> {
+        BackgroundJob::spawn("directory-scan", move |control| Self::scan(&root, control))
+    }
+
+    /// Import a mapping on a worker thread with its own connection to the library database
+    pub fn spawn_import(
+        library_path: PathBuf,
+        mapping: DirectoryMapping,
+        series_id: i32,
+        ingest_mode: IngestMode,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<BackgroundJob<DirectoryImportReport>, Box<dyn Error>> {
+        BackgroundJob::spawn("directory-import", move |control| {
+            let conn = establish_connection(&library_path.join(DATABASE_NAME))?;
+            // 界面线程的连接可能同时写入，等待锁释放而不是立即失败
+            diesel::sql_query("PRAGMA busy_timeout = 5000").execute(&mut *conn.borrow_mut())?;
+            Self::new(conn, &library_path).import(&mapping, series_id, ingest_mode, algorithm, control)
+        })
+    }
+}
+
+/// File title of a folder: its path below the root, or the root's own name
+fn folder_title(root: &Path, folder: &str) -> String {
+    if folder.is_empty() {
+        root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    } else {
+        folder.split('/').collect::<Vec<_>>().join(" / ")
+    }
+}
+
+/// Item title of a document: its file name without extension
+fn file_stem(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    Path::new(name).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| name.to_string())
+}
@@ -0,0 +1,328 @@
+/// Fixity Service - Checksums and fixity verification for item payloads
+/// Keeps a per-file checksum manifest and detects changed, missing and new files
+use crate::core::GenericRepository;
+use crate::models::fixity_run::FixityRun;
+use crate::models::item::Item;
+use crate::models::item_checksum::ItemChecksum;
+use crate::persistence::sidecars::SIDECAR_NAME;
+use crate::persistence::{establish_connection, FixityRunsRepository, ItemChecksumsRepository, ItemsRepository};
+use crate::services::background_job::BackgroundJob;
+use crate::services::backup_service::DATABASE_NAME;
+use crate::services::library_path_service::LibraryPathService;
+use crate::services::csv::csv_escape;
+use chrono::{Duration, NaiveDateTime};
+use diesel::{RunQueryDsl, SqliteConnection};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Checksum algorithm used for the manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    /// Algorithms compiled into this build
+    pub const ALL: &'static [ChecksumAlgorithm] = &[
+        ChecksumAlgorithm::Sha256,
+        #[cfg(feature = "blake3")]
+        ChecksumAlgorithm::Blake3,
+    ];
+
+    /// Name stored in the manifest
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            #[cfg(feature = "blake3")]
+            ChecksumAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Parse a stored name; None when the algorithm is unknown or not compiled in
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sha256" => Some(ChecksumAlgorithm::Sha256),
+            #[cfg(feature = "blake3")]
+            "blake3" => Some(ChecksumAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+}
+
+/// A single file flagged by a fixity check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixityIssue {
+    pub item_id: i32,
+    pub item_no: String,
+    pub relative_path: String,
+}
+
+/// Result of a fixity check
+#[derive(Debug, Clone, Default)]
+pub struct FixityReport {
+    /// Files re-hashed and compared against the manifest
+    pub checked: usize,
+    /// Files whose content no longer matches the manifest
+    pub changed: Vec<FixityIssue>,
+    /// Manifest entries whose file is gone
+    pub missing: Vec<FixityIssue>,
+    /// Files on disk that are not in the manifest
+    pub new_files: Vec<FixityIssue>,
+}
+
+impl FixityReport {
+    /// Whether every file matched the manifest
+    pub fn is_clean(&self) -> bool {
+        self.changed.is_empty() && self.missing.is_empty() && self.new_files.is_empty()
+    }
+
+    /// Render the report as CSV (status, item_no, relative_path)
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("status,item_no,relative_path\n");
+        for (status, issues) in [("changed", &self.changed), ("missing", &self.missing), ("new", &self.new_files)] {
+            for issue in issues {
+                csv.push_str(&format!("{},{},{}\n", status, csv_escape(&issue.item_no), csv_escape(&issue.relative_path)));
+            }
+        }
+        csv
+    }
+
+    /// One-line summary for toasts and logs
+    pub fn summary(&self) -> String {
+        format!(
+            "checked {}, changed {}, missing {}, new {}",
+            self.checked,
+            self.changed.len(),
+            self.missing.len(),
+            self.new_files.len()
+        )
+    }
+}
+
+/// Fixity service for item payload checksums
+pub struct FixityService {
+    conn: Rc<RefCell<SqliteConnection>>,
+    library_path: PathBuf,
+}
+
+impl FixityService {
+    /// Create a new fixity service on the given library connection and directory
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>, library_path: impl AsRef<Path>) -> Self {
+        Self {
+            conn,
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Hash a file with the given algorithm, returning the hex digest and the file size
+    pub fn hash_file(path: &Path, algorithm: ChecksumAlgorithm) -> io::Result<(String, u64)> {
+        let mut file = fs::File::open(path)?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut size = 0u64;
+
+        let digest: Vec<u8> = match algorithm {
+            ChecksumAlgorithm::Sha256 => {
+                use sha2::{Digest, Sha256};
+                let mut hasher = Sha256::new();
+                loop {
+                    let read = file.read(&mut buffer)?;
+                    if read == 0 {
+                        break;
+                    }
+                    size += read as u64;
+                    hasher.update(&buffer[..read]);
+                }
+                hasher.finalize().to_vec()
+            }
+            #[cfg(feature = "blake3")]
+            ChecksumAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                loop {
+                    let read = file.read(&mut buffer)?;
+                    if read == 0 {
+                        break;
+                    }
+                    size += read as u64;
+                    hasher.update(&buffer[..read]);
+                }
+                hasher.finalize().as_bytes().to_vec()
+            }
+        };
+
+        Ok((digest.iter().map(|b| format!("{:02x}", b)).collect(), size))
+    }
+
+    /// Files making up an item payload, keyed by path relative to the item path
+    ///
+    /// A file item yields its own file name; a folder item yields every file
+    /// below it, with `/` as separator on all platforms.
+    pub fn payload_files(root: &Path) -> io::Result<BTreeMap<String, PathBuf>> {
+        fn walk(dir: &Path, prefix: &str, files: &mut BTreeMap<String, PathBuf>) -> io::Result<()> {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name == SIDECAR_NAME {
+                    // 元数据 sidecar 不属于载荷
+                    continue;
+                }
+                let relative = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+                let path = entry.path();
+                if path.is_dir() {
+                    walk(&path, &relative, files)?;
+                } else {
+                    files.insert(relative, path);
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = BTreeMap::new();
+        if root.is_dir() {
+            walk(root, "", &mut files)?;
+        } else if root.is_file() {
+            let name = root
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            files.insert(name, root.to_path_buf());
+        }
+        Ok(files)
+    }
+
+    /// Compute and store the manifest for an item, replacing any previous manifest
+    ///
+    /// Also used to accept intentional changes after a fixity check.
+    pub fn record_item(&self, item_id: i32, algorithm: ChecksumAlgorithm) -> Result<usize, Box<dyn Error>> {
+        let item = ItemsRepository::new(Rc::clone(&self.conn))
+            .find_by_id(item_id)?
+            .ok_or("档案项不存在")?;
+        let root = match &item.path {
+            Some(path) if !path.is_empty() => LibraryPathService::resolve(&self.library_path, path),
+            _ => return Ok(0),
+        };
+        if !root.exists() {
+            return Err(format!("路径不存在: {}", root.display()).into());
+        }
+
+        // 先计算全部校验值，避免读取失败时留下半份清单
+        let mut entries = Vec::new();
+        for (relative_path, path) in Self::payload_files(&root)? {
+            let (checksum, size) = Self::hash_file(&path, algorithm)?;
+            entries.push(ItemChecksum {
+                item_id,
+                relative_path,
+                algorithm: algorithm.as_str().to_string(),
+                checksum,
+                file_size: size as i64,
+                ..Default::default()
+            });
+        }
+
+        self.forget_item(item_id)?;
+        let mut repo = ItemChecksumsRepository::new(Rc::clone(&self.conn));
+        let count = entries.len();
+        for entry in entries {
+            repo.create(entry)?;
+        }
+        Ok(count)
+    }
+
+    /// Remove the manifest of an item (e.g. when the item is deleted)
+    pub fn forget_item(&self, item_id: i32) -> Result<(), Box<dyn Error>> {
+        let mut repo = ItemChecksumsRepository::new(Rc::clone(&self.conn));
+        for entry in repo.find_by_item_id(item_id)? {
+            repo.delete(entry.id)?;
+        }
+        Ok(())
+    }
+
+    /// Manifest entries of an item
+    pub fn manifest(&self, item_id: i32) -> Result<Vec<ItemChecksum>, Box<dyn Error>> {
+        ItemChecksumsRepository::new(Rc::clone(&self.conn)).find_by_item_id(item_id)
+    }
+
+    /// Re-hash one item and compare it against its manifest
+    pub fn verify_item(&self, item: &Item, report: &mut FixityReport) -> Result<(), Box<dyn Error>> {
+        let manifest = self.manifest(item.id)?;
+        let on_disk = match &item.path {
+            Some(path) if !path.is_empty() => Self::payload_files(&LibraryPathService::resolve(&self.library_path, path))?,
+            _ => BTreeMap::new(),
+        };
+        let issue = |relative_path: &str| FixityIssue {
+            item_id: item.id,
+            item_no: item.item_no.clone(),
+            relative_path: relative_path.to_string(),
+        };
+
+        for entry in &manifest {
+            let Some(path) = on_disk.get(&entry.relative_path) else {
+                report.missing.push(issue(&entry.relative_path));
+                continue;
+            };
+            let algorithm = ChecksumAlgorithm::parse(&entry.algorithm)
+                .ok_or_else(|| format!("不支持的校验算法: {}", entry.algorithm))?;
+            let (checksum, size) = Self::hash_file(path, algorithm)?;
+            report.checked += 1;
+            if checksum != entry.checksum || size as i64 != entry.file_size {
+                report.changed.push(issue(&entry.relative_path));
+            }
+        }
+
+        for relative_path in on_disk.keys() {
+            if !manifest.iter().any(|e| &e.relative_path == relative_path) {
+                report.new_files.push(issue(relative_path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-hash every item payload and record the run
+    pub fn verify_all(&self) -> Result<FixityReport, Box<dyn Error>> {
+        let mut items = ItemsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        items.sort_by(|a, b| a.item_no.cmp(&b.item_no));
+
+        let mut report = FixityReport::default();
+        for item in &items {
+            self.verify_item(item, &mut report)?;
+        }
+
+        FixityRunsRepository::new(Rc::clone(&self.conn)).create(FixityRun {
+            checked_count: report.checked as i32,
+            changed_count: report.changed.len() as i32,
+            missing_count: report.missing.len() as i32,
+            new_count: report.new_files.len() as i32,
+            ..Default::default()
+        })?;
+        Ok(report)
+    }
+
+    /// Re-hash every item payload on a worker thread with its own connection to the library database
+    pub fn spawn_verify_all(library_path: PathBuf) -> Result<BackgroundJob<FixityReport>, Box<dyn Error>> {
+        BackgroundJob::spawn("fixity-check", move |_| {
+            let conn = establish_connection(&library_path.join(DATABASE_NAME))?;
+            // 界面线程的连接可能同时写入，等待锁释放而不是立即失败
+            diesel::sql_query("PRAGMA busy_timeout = 5000").execute(&mut *conn.borrow_mut())?;
+            Self::new(conn, &library_path).verify_all()
+        })
+    }
+
+    /// Most recent full fixity run
+    pub fn last_run(&self) -> Result<Option<FixityRun>, Box<dyn Error>> {
+        FixityRunsRepository::new(Rc::clone(&self.conn)).find_last()
+    }
+
+    /// Whether a scheduled run is due (never run, or the last run is older than the interval)
+    pub fn is_due(&self, interval: Duration, now: NaiveDateTime) -> Result<bool, Box<dyn Error>> {
+        Ok(match self.last_run()? {
+            Some(run) => now - run.created_at >= interval,
+            None => true,
+        })
+    }
+}
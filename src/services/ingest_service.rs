@@ -0,0 +1,165 @@
+/// Ingest Service - Bring item payloads into managed library storage
+/// Copies or moves picked files under `<library>/<fond_no>/<file_no>/<item_no>/` and verifies them
+use crate::models::app_settings::IngestMode;
+use crate::persistence::sidecars::SIDECAR_NAME;
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Progress of a running ingest, reported after each file
+#[derive(Debug, Clone, Default)]
+pub struct IngestProgress {
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Relative path of the file just stored
+    pub current: String,
+}
+
+impl IngestProgress {
+    /// Completed fraction in 0.0..=1.0, by bytes
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total == 0 {
+            if self.files_total == 0 { 1.0 } else { self.files_done as f32 / self.files_total as f32 }
+        } else {
+            self.bytes_done as f32 / self.bytes_total as f32
+        }
+    }
+}
+
+/// Where an ingested payload ended up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IngestOutcome {
+    /// Path to store in `items.path`
+    pub path: String,
+    /// Original location, stored in `items.source_path` as provenance
+    pub source_path: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Ingest service for item payloads
+pub struct IngestService;
+
+impl IngestService {
+    /// Managed storage directory of an item
+    pub fn destination(library_path: &Path, fond_no: &str, file_no: &str, item_no: &str) -> PathBuf {
+        library_path.join(fond_no).join(file_no).join(item_no)
+    }
+
+    /// Store a payload according to the ingest mode
+    ///
+    /// Every copied file is re-hashed and compared with its source before the
+    /// ingest counts as done; in move mode the original is only deleted after
+    /// the whole payload verified. On failure the partial copy is removed and
+    /// the source is left untouched.
+    pub fn ingest(
+        source: &Path,
+        destination: &Path,
+        mode: IngestMode,
+        mut progress: impl FnMut(&IngestProgress),
+    ) -> Result<IngestOutcome, Box<dyn Error>> {
+        if !source.exists() {
+            return Err(format!("路径不存在: {}", source.display()).into());
+        }
+        let source_path = source.to_string_lossy().to_string();
+        let files = FixityService::payload_files(source)?;
+        let bytes_total = files.values().map(|p| fs::metadata(p).map(|m| m.len()).unwrap_or(0)).sum();
+
+        if mode == IngestMode::Link {
+            return Ok(IngestOutcome { path: source_path.clone(), source_path, files: files.len(), bytes: bytes_total });
+        }
+
+        if destination.exists() && fs::read_dir(destination)?.filter_map(Result::ok).any(|e| e.file_name() != SIDECAR_NAME) {
+            return Err(format!("目标目录已存在且不为空: {}", destination.display()).into());
+        }
+        if destination.starts_with(source) {
+            return Err("不能导入到源目录内部".into());
+        }
+
+        let mut state = IngestProgress { files_total: files.len(), bytes_total, ..Default::default() };
+        let copied = (|| -> Result<(), Box<dyn Error>> {
+            for (relative_path, from) in &files {
+                let to = destination.join(relative_path);
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(from, &to)?;
+                let (expected, size) = FixityService::hash_file(from, ChecksumAlgorithm::Sha256)?;
+                let (actual, _) = FixityService::hash_file(&to, ChecksumAlgorithm::Sha256)?;
+                if expected != actual {
+                    return Err(format!("复制校验失败: {}", relative_path).into());
+                }
+                state.files_done += 1;
+                state.bytes_done += size;
+                state.current = relative_path.clone();
+                progress(&state);
+            }
+            Ok(())
+        })();
+        if let Err(e) = copied {
+            let _ = fs::remove_dir_all(destination);
+            return Err(e);
+        }
+        fs::create_dir_all(destination)?;
+
+        // 单个文件档案项指向复制后的文件，文件夹档案项指向目录
+        let path = if source.is_file() {
+            files.keys().next().map(|name| destination.join(name)).unwrap_or_else(|| destination.to_path_buf())
+        } else {
+            destination.to_path_buf()
+        };
+
+        if mode == IngestMode::Move {
+            let removed = if source.is_file() { fs::remove_file(source) } else { fs::remove_dir_all(source) };
+            if let Err(e) = removed {
+                log::warn!("Ingested {} but failed to remove the original: {}", source_path, e);
+            }
+        }
+
+        Ok(IngestOutcome {
+            path: path.to_string_lossy().to_string(),
+            source_path,
+            files: state.files_done,
+            bytes: state.bytes_done,
+        })
+    }
+
+    /// Undo an ingest whose catalogue row could not be written
+    ///
+    /// Removes the managed copy at `destination`. In move mode the original was
+    /// already deleted, so the payload is first copied back to its source path;
+    /// files still present there are left alone.
+    pub fn rollback(outcome: &IngestOutcome, destination: &Path, mode: IngestMode) -> Result<(), Box<dyn Error>> {
+        if mode == IngestMode::Link {
+            return Ok(());
+        }
+        if mode == IngestMode::Move {
+            let source = Path::new(&outcome.source_path);
+            let stored = Path::new(&outcome.path);
+            if stored != destination {
+                // 单个文件档案项
+                if !source.exists() {
+                    fs::copy(stored, source)?;
+                }
+            } else {
+                for (relative_path, from) in FixityService::payload_files(destination)? {
+                    let to = source.join(&relative_path);
+                    if to.exists() {
+                        continue;
+                    }
+                    if let Some(parent) = to.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(&from, &to)?;
+                }
+            }
+        }
+        match fs::remove_dir_all(destination) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,486 @@
+/// Integrity Service - Cross-check the library database against the filesystem
+/// Finds orphan rows, orphan directories, dangling references and numbering mismatches
+use crate::core::GenericRepository;
+use crate::models::file::File;
+use crate::models::item::Item;
+use crate::persistence::schema_item_repository::SchemaItemRepository;
+use crate::persistence::{
+    BorrowersRepository, FilesRepository, FondSchemasRepository, FondsRepository, ItemChecksumsRepository,
+    ItemsRepository, LoansRepository, SchemaRepository, SeriesRepository, SeriesSchemaItemsRepository,
+};
+use crate::services::library_path_service::LibraryPathService;
+use crate::services::csv::csv_escape;
+use crate::services::usage_service::{Contents, UsageService};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// A single inconsistency between the database and the library directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A file or item row whose path no longer exists on disk
+    MissingPath {
+        entity_type: &'static str,
+        entity_id: i32,
+        number: String,
+        path: String,
+        /// Directory elsewhere in the library carrying the same number, if any
+        candidate: Option<String>,
+    },
+    /// A directory under `<library>/` or `<library>/<fond_no>/` with no matching row
+    OrphanDirectory { path: String },
+    /// A row referencing a parent row that does not exist
+    DanglingReference {
+        entity_type: &'static str,
+        entity_id: i32,
+        column: &'static str,
+        target_id: i32,
+    },
+    /// A number that does not extend its parent's number
+    NumberMismatch {
+        entity_type: &'static str,
+        entity_id: i32,
+        number: String,
+        expected_prefix: String,
+    },
+}
+
+impl IntegrityIssue {
+    /// Short machine-readable kind, used in CSV reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IntegrityIssue::MissingPath { .. } => "missing_path",
+            IntegrityIssue::OrphanDirectory { .. } => "orphan_directory",
+            IntegrityIssue::DanglingReference { .. } => "dangling_reference",
+            IntegrityIssue::NumberMismatch { .. } => "number_mismatch",
+        }
+    }
+
+    /// Repairs the user can choose from, most conservative last
+    ///
+    /// Orphan directories and numbering mismatches need a human decision
+    /// (register the folder, renumber and relabel), so no repair is offered.
+    pub fn repairs(&self) -> Vec<IntegrityRepair> {
+        match self {
+            IntegrityIssue::MissingPath { entity_type, entity_id, candidate, .. } => {
+                let mut repairs = Vec::new();
+                if let Some(path) = candidate {
+                    repairs.push(IntegrityRepair::RelinkPath {
+                        entity_type,
+                        entity_id: *entity_id,
+                        path: path.clone(),
+                    });
+                }
+                repairs.push(IntegrityRepair::ClearPath { entity_type, entity_id: *entity_id });
+                repairs
+            }
+            IntegrityIssue::DanglingReference { entity_type, entity_id, .. } => {
+                vec![IntegrityRepair::DeleteRow { entity_type, entity_id: *entity_id }]
+            }
+            IntegrityIssue::OrphanDirectory { .. } | IntegrityIssue::NumberMismatch { .. } => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityIssue::MissingPath { entity_type, number, path, .. } => write!(f, "{} {} points at missing path {}", entity_type, number, path),
+            IntegrityIssue::OrphanDirectory { path } => write!(f, "directory {} is not registered in the catalogue", path),
+            IntegrityIssue::DanglingReference { entity_type, entity_id, column, target_id } => write!(f, "{} #{} references missing {} {}", entity_type, entity_id, column, target_id),
+            IntegrityIssue::NumberMismatch { entity_type, number, expected_prefix, .. } => write!(f, "{} {} does not start with {}", entity_type, number, expected_prefix),
+        }
+    }
+}
+
+/// A repair action for an integrity issue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityRepair {
+    /// Point the row at a directory that was found elsewhere in the library
+    RelinkPath { entity_type: &'static str, entity_id: i32, path: String },
+    /// Forget the path, keeping the catalogue row
+    ClearPath { entity_type: &'static str, entity_id: i32 },
+    /// Delete a row whose parent no longer exists
+    DeleteRow { entity_type: &'static str, entity_id: i32 },
+}
+
+impl fmt::Display for IntegrityRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityRepair::RelinkPath { entity_type, entity_id, path } => write!(f, "relink {} #{} to {}", entity_type, entity_id, path),
+            IntegrityRepair::ClearPath { entity_type, entity_id } => write!(f, "clear path of {} #{}", entity_type, entity_id),
+            IntegrityRepair::DeleteRow { entity_type, entity_id } => write!(f, "delete {} #{}", entity_type, entity_id),
+        }
+    }
+}
+
+/// What to do with one issue during a guided repair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairChoice {
+    Apply(IntegrityRepair),
+    Skip,
+    /// Leave this and every remaining issue alone
+    Stop,
+}
+
+/// Result of an integrity check
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Whether database and disk agree
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues of one kind (see `IntegrityIssue::kind`)
+    pub fn count(&self, kind: &str) -> usize {
+        self.issues.iter().filter(|i| i.kind() == kind).count()
+    }
+
+    /// Render the report as CSV (kind, description, suggested repair)
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("kind,description,repair\n");
+        for issue in &self.issues {
+            let repair = issue.repairs().first().map(|r| r.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{},{}\n", issue.kind(), csv_escape(&issue.to_string()), csv_escape(&repair)));
+        }
+        csv
+    }
+
+    /// One-line summary for toasts and logs
+    pub fn summary(&self) -> String {
+        format!(
+            "missing paths {}, orphan directories {}, dangling references {}, number mismatches {}",
+            self.count("missing_path"),
+            self.count("orphan_directory"),
+            self.count("dangling_reference"),
+            self.count("number_mismatch")
+        )
+    }
+}
+
+/// Integrity service for one library
+pub struct IntegrityService {
+    conn: Rc<RefCell<SqliteConnection>>,
+    library_path: PathBuf,
+}
+
+impl IntegrityService {
+    /// Create a new integrity service on the given library connection and directory
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>, library_path: impl AsRef<Path>) -> Self {
+        Self {
+            conn,
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Run every check and collect the issues found
+    pub fn check(&self) -> Result<IntegrityReport, Box<dyn Error>> {
+        let mut report = IntegrityReport::default();
+        self.check_references(&mut report)?;
+        self.check_numbers(&mut report)?;
+        self.check_disk(&mut report)?;
+        Ok(report)
+    }
+
+    /// Rows pointing at parents that do not exist
+    fn check_references(&self, report: &mut IntegrityReport) -> Result<(), Box<dyn Error>> {
+        fn ids<E: crate::core::Creatable>(rows: &[E]) -> HashSet<i32> {
+            rows.iter().map(|r| r.id()).collect()
+        }
+        let mut dangling = |entity_type: &'static str, entity_id: i32, column: &'static str, target_id: i32, parents: &HashSet<i32>| {
+            if !parents.contains(&target_id) {
+                report.issues.push(IntegrityIssue::DanglingReference { entity_type, entity_id, column, target_id });
+            }
+        };
+
+        let fonds = ids(&FondsRepository::new(Rc::clone(&self.conn)).find_all()?);
+        let schemas = ids(&SchemaRepository::new(Rc::clone(&self.conn)).find_all()?);
+        let schema_items = SchemaItemRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let series = SeriesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let files = FilesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let items = ItemsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let borrowers = ids(&BorrowersRepository::new(Rc::clone(&self.conn)).find_all()?);
+        let (schema_item_ids, series_ids, file_ids, item_ids) = (ids(&schema_items), ids(&series), ids(&files), ids(&items));
+
+        for s in &schema_items {
+            dangling("schema_items", s.id, "schema_id", s.schema_id, &schemas);
+            if let Some(parent_id) = s.parent_id {
+                dangling("schema_items", s.id, "parent_id", parent_id, &schema_item_ids);
+            }
+        }
+        for fs in FondSchemasRepository::new(Rc::clone(&self.conn)).find_all()? {
+            dangling("fond_schemas", fs.id, "fond_id", fs.fond_id, &fonds);
+            dangling("fond_schemas", fs.id, "schema_id", fs.schema_id, &schemas);
+            if let Some(schema_item_id) = fs.schema_item_id {
+                dangling("fond_schemas", fs.id, "schema_item_id", schema_item_id, &schema_item_ids);
+            }
+        }
+        for s in &series {
+            dangling("series", s.id, "fond_id", s.fond_id, &fonds);
+        }
+        for link in SeriesSchemaItemsRepository::new(Rc::clone(&self.conn)).find_all()? {
+            dangling("series_schema_items", link.id, "series_id", link.series_id, &series_ids);
+            dangling("series_schema_items", link.id, "schema_item_id", link.schema_item_id, &schema_item_ids);
+        }
+        for f in &files {
+            dangling("files", f.id, "series_id", f.series_id, &series_ids);
+        }
+        for i in &items {
+            dangling("items", i.id, "file_id", i.file_id, &file_ids);
+        }
+        for l in LoansRepository::new(Rc::clone(&self.conn)).find_all()? {
+            dangling("loans", l.id, "borrower_id", l.borrower_id, &borrowers);
+            if let Some(file_id) = l.file_id {
+                dangling("loans", l.id, "file_id", file_id, &file_ids);
+            }
+            if let Some(item_id) = l.item_id {
+                dangling("loans", l.id, "item_id", item_id, &item_ids);
+            }
+        }
+        for c in ItemChecksumsRepository::new(Rc::clone(&self.conn)).find_all()? {
+            dangling("item_checksums", c.id, "item_id", c.item_id, &item_ids);
+        }
+        Ok(())
+    }
+
+    /// Numbers must extend their parent's number (GA01 → GA01-2025 → GA01-2025-01 → GA01-2025-01-001)
+    fn check_numbers(&self, report: &mut IntegrityReport) -> Result<(), Box<dyn Error>> {
+        let fonds: BTreeMap<i32, String> = FondsRepository::new(Rc::clone(&self.conn))
+            .find_all()?
+            .into_iter()
+            .map(|f| (f.id, f.fond_no))
+            .collect();
+        let series = SeriesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let files = FilesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let items = ItemsRepository::new(Rc::clone(&self.conn)).find_all()?;
+
+        let mut mismatch = |entity_type: &'static str, entity_id: i32, number: &str, parent_no: Option<&String>| {
+            // 父级缺失时已作为悬空引用报告
+            let Some(parent_no) = parent_no else { return };
+            let expected_prefix = format!("{}-", parent_no);
+            if !number.starts_with(&expected_prefix) {
+                report.issues.push(IntegrityIssue::NumberMismatch {
+                    entity_type,
+                    entity_id,
+                    number: number.to_string(),
+                    expected_prefix,
+                });
+            }
+        };
+
+        let series_nos: BTreeMap<i32, String> = series.iter().map(|s| (s.id, s.series_no.clone())).collect();
+        let file_nos: BTreeMap<i32, String> = files.iter().map(|f| (f.id, f.file_no.clone())).collect();
+        for s in &series {
+            mismatch("series", s.id, &s.series_no, fonds.get(&s.fond_id));
+        }
+        for f in &files {
+            mismatch("files", f.id, &f.file_no, series_nos.get(&f.series_id));
+        }
+        for i in &items {
+            mismatch("items", i.id, &i.item_no, file_nos.get(&i.file_id));
+        }
+        Ok(())
+    }
+
+    /// Paths that vanished from disk and directories the catalogue does not know about
+    fn check_disk(&self, report: &mut IntegrityReport) -> Result<(), Box<dyn Error>> {
+        let fond_nos: HashSet<String> = FondsRepository::new(Rc::clone(&self.conn))
+            .find_all()?
+            .into_iter()
+            .map(|f| f.fond_no)
+            .collect();
+        let files = FilesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let items = ItemsRepository::new(Rc::clone(&self.conn)).find_all()?;
+
+        // 库目录下的全宗目录及其子目录
+        let mut fond_dirs = Vec::new();
+        let mut sub_dirs: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for dir in Self::sub_directories(&self.library_path)? {
+            let name = Self::dir_name(&dir);
+            if !fond_nos.contains(&name) {
+                report.issues.push(IntegrityIssue::OrphanDirectory { path: dir.to_string_lossy().to_string() });
+                continue;
+            }
+            for sub in Self::sub_directories(&dir)? {
+                sub_dirs.insert(sub.to_string_lossy().to_string(), sub);
+            }
+            fond_dirs.push(dir);
+        }
+
+        let mut referenced: HashSet<PathBuf> = HashSet::new();
+        let mut missing = |entity_type: &'static str, entity_id: i32, number: &str, path: &Option<String>| {
+            let Some(path) = path.as_ref().filter(|p| !p.is_empty()) else { return };
+            let p = LibraryPathService::resolve(&self.library_path, path);
+            if p.exists() {
+                if let Ok(canonical) = p.canonicalize() {
+                    referenced.insert(canonical);
+                }
+                return;
+            }
+            // 目录被移动时，尝试在其他全宗目录下按编号找到它
+            let candidate = fond_dirs
+                .iter()
+                .map(|d| d.join(number))
+                .find(|d| d.is_dir())
+                .map(|d| d.to_string_lossy().to_string());
+            report.issues.push(IntegrityIssue::MissingPath {
+                entity_type,
+                entity_id,
+                number: number.to_string(),
+                path: path.clone(),
+                candidate,
+            });
+        };
+        for File { id, file_no, path, .. } in &files {
+            missing("files", *id, file_no, path);
+        }
+        for Item { id, item_no, path, .. } in &items {
+            missing("items", *id, item_no, path);
+        }
+
+        let candidates: HashSet<String> = report
+            .issues
+            .iter()
+            .filter_map(|i| match i {
+                IntegrityIssue::MissingPath { candidate, .. } => candidate.clone(),
+                _ => None,
+            })
+            .collect();
+        for (display, dir) in sub_dirs {
+            let known = dir.canonicalize().map(|c| referenced.contains(&c)).unwrap_or(false);
+            // 作为重新链接候选的目录会在修复后被引用，不再单独报告
+            if !known && !candidates.contains(&display) {
+                report.issues.push(IntegrityIssue::OrphanDirectory { path: display });
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply one repair chosen by the user
+    pub fn apply(&self, repair: &IntegrityRepair) -> Result<(), Box<dyn Error>> {
+        match repair {
+            IntegrityRepair::RelinkPath { entity_type, entity_id, path } => {
+                let stored = LibraryPathService::to_stored(&self.library_path, Path::new(path));
+                self.set_path(entity_type, *entity_id, Some(stored))
+            }
+            IntegrityRepair::ClearPath { entity_type, entity_id } => self.set_path(entity_type, *entity_id, None),
+            IntegrityRepair::DeleteRow { entity_type, entity_id } => {
+                let id = *entity_id;
+                // 行下还有子行时连同子行一起删除；仍被使用或有借阅记录时拒绝
+                let usage = UsageService::new(Rc::clone(&self.conn));
+                match *entity_type {
+                    "schema_items" => usage.delete_schema_item(id).map(|_| ()),
+                    "fond_schemas" => FondSchemasRepository::new(Rc::clone(&self.conn)).delete(id),
+                    "series_schema_items" => SeriesSchemaItemsRepository::new(Rc::clone(&self.conn)).delete(id),
+                    "series" => usage.delete_series(id).map(|_| ()),
+                    "files" => usage.delete_file(id).map(|_| ()),
+                    "items" => usage.delete_item(id),
+                    "loans" => LoansRepository::new(Rc::clone(&self.conn)).delete(id),
+                    "item_checksums" => ItemChecksumsRepository::new(Rc::clone(&self.conn)).delete(id),
+                    other => Err(format!("不支持删除的实体类型: {}", other).into()),
+                }
+            }
+        }
+    }
+
+    /// Rows deleted together with a `DeleteRow` repair, if any
+    pub fn deleted_with(&self, repair: &IntegrityRepair) -> Result<Contents, Box<dyn Error>> {
+        let usage = UsageService::new(Rc::clone(&self.conn));
+        match repair {
+            IntegrityRepair::DeleteRow { entity_type: "series", entity_id } => usage.series_contents(*entity_id),
+            IntegrityRepair::DeleteRow { entity_type: "files", entity_id } => usage.file_contents(*entity_id),
+            _ => Ok(Contents::default()),
+        }
+    }
+
+    /// Walk the repairable issues one by one, letting `choose` pick a repair for each;
+    /// returns the number applied
+    ///
+    /// Deletions are offered first, with the child rows they take along. A
+    /// deletion can leave other rows dangling or make later issues stale, so
+    /// the check is repeated after each one; skipped issues are not asked again.
+    pub fn repair_each(
+        &self,
+        mut choose: impl FnMut(&IntegrityIssue, &[IntegrityRepair], &Contents) -> RepairChoice,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut applied = 0;
+        let mut skipped: Vec<IntegrityIssue> = Vec::new();
+        'check: loop {
+            let mut issues: Vec<IntegrityIssue> = self
+                .check()?
+                .issues
+                .into_iter()
+                .filter(|i| !i.repairs().is_empty() && !skipped.contains(i))
+                .collect();
+            issues.sort_by_key(|i| !matches!(i, IntegrityIssue::DanglingReference { .. }));
+            if issues.is_empty() {
+                return Ok(applied);
+            }
+            for issue in issues {
+                let repairs = issue.repairs();
+                let deleted = match repairs.iter().find(|r| matches!(r, IntegrityRepair::DeleteRow { .. })) {
+                    Some(repair) => self.deleted_with(repair)?,
+                    None => Contents::default(),
+                };
+                match choose(&issue, &repairs, &deleted) {
+                    RepairChoice::Apply(repair) => {
+                        self.apply(&repair)?;
+                        applied += 1;
+                        if matches!(repair, IntegrityRepair::DeleteRow { .. }) {
+                            continue 'check;
+                        }
+                        // 修复后仍存在的问题（例如重新关联的目录又被移走）不再询问
+                        skipped.push(issue);
+                    }
+                    RepairChoice::Skip => skipped.push(issue),
+                    RepairChoice::Stop => return Ok(applied),
+                }
+            }
+        }
+    }
+
+    fn set_path(&self, entity_type: &str, id: i32, path: Option<String>) -> Result<(), Box<dyn Error>> {
+        match entity_type {
+            "files" => {
+                let mut repo = FilesRepository::new(Rc::clone(&self.conn));
+                let mut file = repo.find_by_id(id)?.ok_or("案卷不存在")?;
+                file.path = path;
+                repo.update(&file)
+            }
+            "items" => {
+                let mut repo = ItemsRepository::new(Rc::clone(&self.conn));
+                let mut item = repo.find_by_id(id)?.ok_or("档案项不存在")?;
+                item.path = path;
+                repo.update(&item)
+            }
+            other => Err(format!("实体类型没有路径: {}", other).into()),
+        }
+    }
+
+    /// Visible sub-directories of a directory, sorted; the database and other dot-files are skipped
+    fn sub_directories(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut dirs: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir() && !Self::dir_name(p).starts_with('.'))
+            .collect();
+        dirs.sort();
+        Ok(dirs)
+    }
+
+    fn dir_name(path: &Path) -> String {
+        path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,295 @@
+/// Journal Service - Verify the hash-chained audit log against the catalogue
+/// Detects edits made to the library database behind the application's back
+use crate::core::{current_machine, current_user, Creatable, GenericRepository};
+use crate::models::audit_log::{AuditAction, AuditLog, NewAuditLog};
+use crate::persistence::audit_log_repository::AuditLogRepository;
+use crate::persistence::schema_item_repository::SchemaItemRepository;
+use crate::persistence::{
+    BorrowersRepository, FilesRepository, FixityRunsRepository, FondClassificationsRepository,
+    FondSchemasRepository, FondsRepository, ItemChecksumsRepository, ItemsRepository, LoansRepository,
+    SchemaRepository, SeriesRepository, SeriesSchemaItemsRepository, TranslationsRepository,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Rows keyed by (entity_type, entity_id)
+type RowMap = BTreeMap<(String, i32), Value>;
+
+/// Last journaled state keyed by (entity_type, entity_id); None for deleted rows
+type JournalState = BTreeMap<(String, i32), Option<Value>>;
+
+/// Anchor for the chain: the last entry id and hash at the time of export
+///
+/// Kept outside the database (e.g. printed or handed to the auditors), so that
+/// rewriting the whole chain after the checkpoint can still be detected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalCheckpoint {
+    /// Id of the last journal entry, 0 for an empty journal
+    pub entry_id: i32,
+    /// Hash of the last journal entry, empty for an empty journal
+    pub hash: String,
+    /// When the checkpoint was taken
+    pub created_at: NaiveDateTime,
+}
+
+/// First place where the journal and the catalogue disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalDivergence {
+    /// The entry's prev_hash does not match the previous entry (entries removed or inserted)
+    BrokenLink { entry_id: i32 },
+    /// The entry's content does not match its own hash (entry edited)
+    HashMismatch { entry_id: i32 },
+    /// The checkpoint entry is missing or has a different hash (chain truncated or rewritten)
+    CheckpointMismatch { entry_id: i32 },
+    /// The row differs from the last state recorded in the journal
+    RowModified { entity_type: String, entity_id: i32 },
+    /// The journal says the row exists, but it is gone from the table
+    RowMissing { entity_type: String, entity_id: i32 },
+    /// The row exists in the table but was never journaled
+    RowUntracked { entity_type: String, entity_id: i32 },
+}
+
+impl fmt::Display for JournalDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalDivergence::BrokenLink { entry_id } => write!(f, "journal entry {} does not link to the previous entry", entry_id),
+            JournalDivergence::HashMismatch { entry_id } => write!(f, "journal entry {} was modified", entry_id),
+            JournalDivergence::CheckpointMismatch { entry_id } => write!(f, "journal does not contain checkpoint entry {}", entry_id),
+            JournalDivergence::RowModified { entity_type, entity_id } => write!(f, "{} #{} was modified outside the application", entity_type, entity_id),
+            JournalDivergence::RowMissing { entity_type, entity_id } => write!(f, "{} #{} was deleted outside the application", entity_type, entity_id),
+            JournalDivergence::RowUntracked { entity_type, entity_id } => write!(f, "{} #{} was inserted outside the application", entity_type, entity_id),
+        }
+    }
+}
+
+/// Result of a journal verification
+#[derive(Debug, Clone, Default)]
+pub struct JournalVerification {
+    /// Number of journal entries walked
+    pub entries_checked: usize,
+    /// Number of current rows compared against the journal
+    pub rows_checked: usize,
+    /// First divergence found, None when the catalogue matches the journal
+    pub divergence: Option<JournalDivergence>,
+}
+
+impl JournalVerification {
+    /// Whether the chain is intact and matches the current tables
+    pub fn is_intact(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Journal service for tamper-evidence checks
+pub struct JournalService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl JournalService {
+    /// Create a new journal service on the given library connection
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Current end of the chain
+    pub fn checkpoint(&self) -> Result<JournalCheckpoint, Box<dyn Error>> {
+        let last = AuditLogRepository::new(Rc::clone(&self.conn)).find_last()?;
+        Ok(JournalCheckpoint {
+            entry_id: last.as_ref().map(|e| e.id).unwrap_or(0),
+            hash: last.map(|e| e.hash).unwrap_or_default(),
+            created_at: chrono::Local::now().naive_local(),
+        })
+    }
+
+    /// Write the current checkpoint to a JSON file
+    pub fn export_checkpoint(&self, path: &Path) -> Result<JournalCheckpoint, Box<dyn Error>> {
+        let checkpoint = self.checkpoint()?;
+        fs::write(path, serde_json::to_string_pretty(&checkpoint)?)?;
+        Ok(checkpoint)
+    }
+
+    /// Read a checkpoint file written by `export_checkpoint`
+    pub fn load_checkpoint(path: &Path) -> Result<JournalCheckpoint, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Walk the chain, check it against an optional checkpoint, then compare the
+    /// last journaled state of every record with the current table contents
+    pub fn verify(&self, checkpoint: Option<&JournalCheckpoint>) -> Result<JournalVerification, Box<dyn Error>> {
+        let entries = AuditLogRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let mut report = JournalVerification {
+            entries_checked: entries.len(),
+            ..Default::default()
+        };
+
+        if let Some(divergence) = Self::verify_chain(&entries, checkpoint) {
+            report.divergence = Some(divergence);
+            return Ok(report);
+        }
+
+        let expected = Self::replay(&entries)?;
+        let current = self.current_rows()?;
+        report.rows_checked = current.len();
+        report.divergence = Self::compare(&expected, &current);
+        Ok(report)
+    }
+
+    /// Adopt records that existed before the journal was introduced
+    ///
+    /// Writes a snapshot entry for every row that has no journal entry yet.
+    /// Run once after upgrading an existing library, never to silence a
+    /// verification failure.
+    pub fn seal_untracked(&self) -> Result<usize, Box<dyn Error>> {
+        let entries = AuditLogRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let expected = Self::replay(&entries)?;
+        let current = self.current_rows()?;
+        let untracked: Vec<(&(String, i32), &Value)> = current
+            .iter()
+            .filter(|(key, _)| !expected.contains_key(*key))
+            .collect();
+
+        let user = current_user().unwrap_or_default();
+        let machine = current_machine().unwrap_or_default();
+        let mut conn = self.conn.borrow_mut();
+        conn.transaction::<_, Box<dyn Error>, _>(|conn| {
+            for ((entity_type, entity_id), row) in &untracked {
+                let entry = NewAuditLog::from_change(
+                    entity_type,
+                    *entity_id,
+                    AuditAction::Snapshot,
+                    None,
+                    Some(*row),
+                    &user,
+                    &machine,
+                )?;
+                AuditLogRepository::record(conn, entry)?;
+            }
+            Ok(())
+        })?;
+        Ok(untracked.len())
+    }
+
+    /// Check prev_hash links, per-entry hashes and the checkpoint anchor
+    fn verify_chain(entries: &[AuditLog], checkpoint: Option<&JournalCheckpoint>) -> Option<JournalDivergence> {
+        let mut prev_hash = String::new();
+        for entry in entries {
+            // 启用哈希链之前写入的日志没有哈希，只允许出现在链首
+            if entry.hash.is_empty() && prev_hash.is_empty() {
+                continue;
+            }
+            if entry.prev_hash != prev_hash {
+                return Some(JournalDivergence::BrokenLink { entry_id: entry.id });
+            }
+            if entry.compute_hash() != entry.hash {
+                return Some(JournalDivergence::HashMismatch { entry_id: entry.id });
+            }
+            prev_hash = entry.hash.clone();
+        }
+
+        if let Some(checkpoint) = checkpoint {
+            if checkpoint.entry_id > 0 {
+                let anchored = entries
+                    .iter()
+                    .any(|e| e.id == checkpoint.entry_id && e.hash == checkpoint.hash);
+                if !anchored {
+                    return Some(JournalDivergence::CheckpointMismatch { entry_id: checkpoint.entry_id });
+                }
+            }
+        }
+        None
+    }
+
+    /// Last journaled state of every record; None for deleted records
+    fn replay(entries: &[AuditLog]) -> Result<JournalState, Box<dyn Error>> {
+        let mut state = BTreeMap::new();
+        for entry in entries {
+            let after = match &entry.after_json {
+                Some(json) => Some(serde_json::from_str::<Value>(json)?),
+                None => None,
+            };
+            state.insert((entry.entity_type.clone(), entry.entity_id), after);
+        }
+        Ok(state)
+    }
+
+    fn compare(expected: &JournalState, current: &RowMap) -> Option<JournalDivergence> {
+        for ((entity_type, entity_id), state) in expected {
+            let key = (entity_type.clone(), *entity_id);
+            match (state, current.get(&key)) {
+                (Some(expected_row), Some(row)) if !Self::rows_match(expected_row, row) => {
+                    return Some(JournalDivergence::RowModified { entity_type: key.0, entity_id: key.1 });
+                }
+                (Some(_), None) => {
+                    return Some(JournalDivergence::RowMissing { entity_type: key.0, entity_id: key.1 });
+                }
+                (None, Some(_)) => {
+                    return Some(JournalDivergence::RowUntracked { entity_type: key.0, entity_id: key.1 });
+                }
+                _ => {}
+            }
+        }
+        current
+            .keys()
+            .find(|key| !expected.contains_key(*key))
+            .map(|(entity_type, entity_id)| JournalDivergence::RowUntracked {
+                entity_type: entity_type.clone(),
+                entity_id: *entity_id,
+            })
+    }
+
+    /// Columns added after an entry was journaled are absent from its JSON;
+    /// they must still hold their default (null) until the next journaled write
+    fn rows_match(expected: &Value, current: &Value) -> bool {
+        match (expected, current) {
+            (Value::Object(expected), Value::Object(current)) => {
+                expected.keys().all(|key| current.contains_key(key))
+                    && current
+                        .iter()
+                        .all(|(key, value)| expected.get(key).unwrap_or(&Value::Null) == value)
+            }
+            _ => expected == current,
+        }
+    }
+
+    /// Current contents of every journaled table, serialized the same way as the journal
+    fn current_rows(&self) -> Result<RowMap, Box<dyn Error>> {
+        fn collect<E, R>(rows: &mut RowMap, entity_type: &str, mut repo: R) -> Result<(), Box<dyn Error>>
+        where
+            E: Creatable + Serialize,
+            R: GenericRepository<E>,
+        {
+            for entity in repo.find_all()? {
+                rows.insert((entity_type.to_string(), entity.id()), serde_json::to_value(&entity)?);
+            }
+            Ok(())
+        }
+
+        let conn = &self.conn;
+        let mut rows = RowMap::new();
+        collect(&mut rows, "fond_classifications", FondClassificationsRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "schemas", SchemaRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "schema_items", SchemaItemRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "fonds", FondsRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "fond_schemas", FondSchemasRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "series", SeriesRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "series_schema_items", SeriesSchemaItemsRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "files", FilesRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "items", ItemsRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "borrowers", BorrowersRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "loans", LoansRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "item_checksums", ItemChecksumsRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "fixity_runs", FixityRunsRepository::new(Rc::clone(conn)))?;
+        collect(&mut rows, "translations", TranslationsRepository::new(Rc::clone(conn)))?;
+        Ok(rows)
+    }
+}
@@ -0,0 +1,264 @@
+/// Library Path Service - Store payload paths relative to the library root
+/// Keeps libraries movable between folders and machines, and relinks paths that point outside
+use crate::core::GenericRepository;
+use crate::models::item_checksum::ItemChecksum;
+use crate::persistence::{FilesRepository, ItemChecksumsRepository, ItemsRepository};
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+/// A file or item path that is not inside the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalPath {
+    pub entity_type: &'static str,
+    pub entity_id: i32,
+    pub number: String,
+    pub path: String,
+    /// Whether the path currently exists on this machine
+    pub exists: bool,
+}
+
+/// Result of relinking broken external paths
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelinkReport {
+    pub relinked: usize,
+    /// Paths with several equally good candidates, left unchanged
+    pub ambiguous: Vec<(ExternalPath, Vec<PathBuf>)>,
+    /// Paths without a trustworthy candidate, left unchanged
+    pub not_found: Vec<ExternalPath>,
+}
+
+impl RelinkReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "已重新链接 {} 个路径，{} 个有多个候选位置，{} 个未找到",
+            self.relinked,
+            self.ambiguous.len(),
+            self.not_found.len()
+        )
+    }
+}
+
+/// Path service for one library
+pub struct LibraryPathService {
+    conn: Rc<RefCell<SqliteConnection>>,
+    library_path: PathBuf,
+}
+
+impl LibraryPathService {
+    /// Create a new path service on the given library connection and directory
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>, library_path: impl AsRef<Path>) -> Self {
+        Self {
+            conn,
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Form of a path to store in the database
+    ///
+    /// Paths inside the library become relative with `/` separators on all
+    /// platforms; anything else is kept absolute.
+    pub fn to_stored(library_path: &Path, path: &Path) -> String {
+        let relative = path.strip_prefix(library_path).ok().map(Path::to_path_buf).or_else(|| {
+            // 符号链接或 `..` 导致字面前缀不同，退回到规范化路径比较
+            let library = library_path.canonicalize().ok()?;
+            let path = path.canonicalize().ok()?;
+            path.strip_prefix(library).ok().map(Path::to_path_buf)
+        });
+        match relative {
+            Some(relative) if path.is_absolute() => relative
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => path.to_string_lossy().to_string(),
+        }
+    }
+
+    /// Absolute location of a stored path on this machine
+    pub fn resolve(library_path: &Path, stored: &str) -> PathBuf {
+        let path = Path::new(stored);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            library_path.join(path)
+        }
+    }
+
+    /// Whether a stored path is relative to the library
+    pub fn is_relative(stored: &str) -> bool {
+        !stored.is_empty() && !Path::new(stored).is_absolute()
+    }
+
+    /// Rewrite absolute paths that lie inside the library as relative paths; returns the number rewritten
+    ///
+    /// Safe to run on every open: once migrated, no row matches any more.
+    pub fn migrate_absolute_paths(&self) -> Result<usize, Box<dyn Error>> {
+        let mut rewritten = 0;
+
+        let mut files_repo = FilesRepository::new(Rc::clone(&self.conn));
+        for mut file in files_repo.find_all()? {
+            if let Some(stored) = self.restore(file.path.as_deref()) {
+                file.path = Some(stored);
+                files_repo.update(&file)?;
+                rewritten += 1;
+            }
+        }
+
+        let mut items_repo = ItemsRepository::new(Rc::clone(&self.conn));
+        for mut item in items_repo.find_all()? {
+            if let Some(stored) = self.restore(item.path.as_deref()) {
+                item.path = Some(stored);
+                items_repo.update(&item)?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// New stored form of an absolute path inside the library, None when nothing changes
+    fn restore(&self, path: Option<&str>) -> Option<String> {
+        let path = path.filter(|p| !p.is_empty() && Path::new(p).is_absolute())?;
+        let stored = Self::to_stored(&self.library_path, Path::new(path));
+        (stored != path).then_some(stored)
+    }
+
+    /// Paths of files and items that point outside the library
+    pub fn external_paths(&self) -> Result<Vec<ExternalPath>, Box<dyn Error>> {
+        let external = |entity_type: &'static str, entity_id: i32, number: &str, path: &Option<String>| {
+            let path = path.as_ref().filter(|p| !p.is_empty() && !Self::is_relative(p))?;
+            Some(ExternalPath {
+                entity_type,
+                entity_id,
+                number: number.to_string(),
+                path: path.clone(),
+                exists: Path::new(path).exists(),
+            })
+        };
+
+        let mut paths: Vec<ExternalPath> = FilesRepository::new(Rc::clone(&self.conn))
+            .find_all()?
+            .iter()
+            .filter_map(|f| external("files", f.id, &f.file_no, &f.path))
+            .collect();
+        paths.extend(
+            ItemsRepository::new(Rc::clone(&self.conn))
+                .find_all()?
+                .iter()
+                .filter_map(|i| external("items", i.id, &i.item_no, &i.path)),
+        );
+        Ok(paths)
+    }
+
+    /// Relink broken external paths by looking for them anywhere under a search folder
+    ///
+    /// Candidates are the entries sharing the longest run of trailing path
+    /// components with the old path, so picking the new mount point of a moved
+    /// drive or share fixes every path below it in one go. A single candidate
+    /// matching at least two components is taken; otherwise an item's candidates
+    /// are checked against its checksum manifest and taken when exactly one
+    /// matches. Several candidates are reported as ambiguous, never guessed.
+    pub fn relink(&self, search_root: &Path) -> Result<RelinkReport, Box<dyn Error>> {
+        let mut report = RelinkReport::default();
+        for external in self.external_paths()?.into_iter().filter(|e| !e.exists) {
+            let (matched, candidates) = Self::find_under(search_root, Path::new(&external.path));
+            let manifest = match external.entity_type {
+                "items" => ItemChecksumsRepository::new(Rc::clone(&self.conn)).find_by_item_id(external.entity_id)?,
+                _ => Vec::new(),
+            };
+            let verified: Vec<PathBuf> = if manifest.is_empty() {
+                Vec::new()
+            } else {
+                candidates.iter().filter(|c| matches_manifest(c, &manifest)).cloned().collect()
+            };
+            let found = if matched >= 2 && candidates.len() == 1 {
+                candidates[0].clone()
+            } else if verified.len() == 1 {
+                verified[0].clone()
+            } else if candidates.len() > 1 {
+                log::warn!("Several candidates to relink {} {}: {:?}", external.entity_type, external.number, candidates);
+                let candidates = if verified.is_empty() { candidates } else { verified };
+                report.ambiguous.push((external, candidates));
+                continue;
+            } else {
+                // 只有名称相同且无法用校验值确认的位置不采用
+                log::warn!("Could not relink {} {}: {}", external.entity_type, external.number, external.path);
+                report.not_found.push(external);
+                continue;
+            };
+            let stored = Self::to_stored(&self.library_path, &found);
+            match external.entity_type {
+                "files" => {
+                    let mut repo = FilesRepository::new(Rc::clone(&self.conn));
+                    if let Some(mut file) = repo.find_by_id(external.entity_id)? {
+                        file.path = Some(stored);
+                        repo.update(&file)?;
+                    }
+                }
+                _ => {
+                    let mut repo = ItemsRepository::new(Rc::clone(&self.conn));
+                    if let Some(mut item) = repo.find_by_id(external.entity_id)? {
+                        item.path = Some(stored);
+                        repo.update(&item)?;
+                    }
+                }
+            }
+            report.relinked += 1;
+        }
+        Ok(report)
+    }
+
+    /// Entries under `search_root` sharing the longest run of trailing components with `old`,
+    /// with the length of that run; symlinked folders are not followed
+    fn find_under(search_root: &Path, old: &Path) -> (usize, Vec<PathBuf>) {
+        fn walk(dir: &Path, trail: &mut Vec<String>, parts: &[String], best: &mut (usize, Vec<PathBuf>)) {
+            let Ok(entries) = fs::read_dir(dir) else { return };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                trail.push(name);
+                let matched = trail.iter().rev().zip(parts.iter().rev()).take_while(|(a, b)| a == b).count();
+                if matched > 0 && matched >= best.0 {
+                    if matched > best.0 {
+                        *best = (matched, Vec::new());
+                    }
+                    best.1.push(entry.path());
+                }
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    walk(&entry.path(), trail, parts, best);
+                }
+                trail.pop();
+            }
+        }
+
+        let parts: Vec<String> = old
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        let mut best = (0, Vec::new());
+        walk(search_root, &mut Vec::new(), &parts, &mut best);
+        best.1.sort();
+        best
+    }
+}
+
+/// Whether a candidate holds exactly the payload recorded in an item's checksum manifest
+fn matches_manifest(candidate: &Path, manifest: &[ItemChecksum]) -> bool {
+    let Ok(files) = FixityService::payload_files(candidate) else { return false };
+    files.len() == manifest.len()
+        && manifest.iter().all(|entry| {
+            let (Some(path), Some(algorithm)) = (files.get(&entry.relative_path), ChecksumAlgorithm::parse(&entry.algorithm)) else {
+                return false;
+            };
+            FixityService::hash_file(path, algorithm).is_ok_and(|(checksum, size)| checksum == entry.checksum && size as i64 == entry.file_size)
+        })
+}
@@ -0,0 +1,193 @@
+/// Library Template Service - Save a library's configuration and start new libraries from it
+/// A template holds the classifications, schemas with their items, fond number
+/// conventions and custom settings, never fonds, series, files or items
+use crate::core::GenericRepository;
+use crate::models::app_settings::{AppSettings, IngestMode};
+use crate::models::fond_classification::ClassificationJson;
+use crate::models::schema::SchemaJson;
+use crate::persistence::{with_transaction, FondClassificationsRepository, SequencesRepository};
+use crate::services::classification_import_service::ClassificationImportService;
+use crate::services::schema_import_service::SchemaImportService;
+use chrono::NaiveDateTime;
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Number of digits of the fond numbers generated under a classification code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumberingConvention {
+    pub prefix: String,
+    pub digits: i32,
+}
+
+/// Application settings carried by a template; applied when a library is created from it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateSettings {
+    pub ingest_mode: IngestMode,
+    pub fixity_interval_days: u32,
+    #[serde(default)]
+    pub checksum_algorithm: Option<String>,
+    #[serde(default)]
+    pub catalogue_columns: BTreeMap<String, Vec<String>>,
+}
+
+impl TemplateSettings {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            ingest_mode: settings.ingest_mode,
+            fixity_interval_days: settings.fixity_interval_days,
+            checksum_algorithm: Some(settings.checksum_algorithm.clone()),
+            catalogue_columns: settings.catalogue_columns.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut AppSettings) {
+        settings.ingest_mode = self.ingest_mode;
+        settings.fixity_interval_days = self.fixity_interval_days;
+        // 旧模板没有记录算法，保留当前设置
+        if let Some(algorithm) = &self.checksum_algorithm {
+            settings.checksum_algorithm = algorithm.clone();
+        }
+        settings.catalogue_columns = self.catalogue_columns.clone();
+    }
+}
+
+/// Configuration captured from a library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryTemplate {
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    /// Name of the library the template was saved from
+    pub source_library: String,
+    pub classifications: Vec<ClassificationJson>,
+    pub schemas: Vec<SchemaJson>,
+    #[serde(default)]
+    pub numbering: Vec<NumberingConvention>,
+    pub settings: TemplateSettings,
+}
+
+impl LibraryTemplate {
+    /// Capture the configuration tables of a library
+    pub fn capture(
+        conn: Rc<RefCell<SqliteConnection>>,
+        name: &str,
+        source_library: &str,
+        settings: &AppSettings,
+    ) -> Result<Self, Box<dyn Error>> {
+        let classifications = ClassificationImportService::new(Rc::clone(&conn)).export()?;
+        let schemas = SchemaImportService::new(Rc::clone(&conn)).export()?;
+        // 全宗号序列以分类代码为前缀；案卷、文件的序列属于具体记录，不进入模板
+        let mut sequences = SequencesRepository::new(Rc::clone(&conn));
+        let mut numbering = Vec::new();
+        for classification in FondClassificationsRepository::new(Rc::clone(&conn)).find_all()? {
+            if let Some(sequence) = sequences.find_by_prefix(&classification.code)? {
+                numbering.push(NumberingConvention { prefix: sequence.prefix, digits: sequence.digits });
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+            source_library: source_library.to_string(),
+            classifications,
+            schemas,
+            numbering,
+            settings: TemplateSettings::from_settings(settings),
+        })
+    }
+
+    /// Install the configuration tables into a new, empty library
+    ///
+    /// Runs as one transaction, so a failure leaves the library without any of the template's rows.
+    pub fn install(&self, conn: Rc<RefCell<SqliteConnection>>) -> Result<(), Box<dyn Error>> {
+        let (classifications, schemas) = with_transaction(&conn, || {
+            let classifications = ClassificationImportService::new(Rc::clone(&conn)).apply(&self.classifications)?;
+            let schemas = SchemaImportService::new(Rc::clone(&conn)).apply(&self.schemas)?;
+            let mut sequences = SequencesRepository::new(Rc::clone(&conn));
+            for convention in &self.numbering {
+                if sequences.find_by_prefix(&convention.prefix)?.is_none() {
+                    sequences.insert(&convention.prefix, 1, convention.digits)?;
+                }
+            }
+            Ok((classifications, schemas))
+        })?;
+        log::info!(
+            "Installed template {}: classifications {}; schemas {}; {} numbering conventions",
+            self.name,
+            classifications.summary(),
+            schemas.summary(),
+            self.numbering.len()
+        );
+        Ok(())
+    }
+}
+
+/// Reads and writes templates as `<name>.json` in one directory
+pub struct LibraryTemplateService {
+    dir: PathBuf,
+}
+
+impl LibraryTemplateService {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("模板名称不能为空".into());
+        }
+        if name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) || name.starts_with('.') {
+            return Err(format!("模板名称不能包含路径字符: {}", name).into());
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Template names, sorted
+    pub fn list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.path(name).is_ok_and(|path| path.exists())
+    }
+
+    pub fn load(&self, name: &str) -> Result<LibraryTemplate, Box<dyn Error>> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(format!("模板不存在: {}", name).into());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Write a template, replacing one with the same name
+    pub fn save(&self, template: &LibraryTemplate) -> Result<PathBuf, Box<dyn Error>> {
+        let path = self.path(&template.name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, serde_json::to_string_pretty(template)?)?;
+        Ok(path)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        fs::remove_file(self.path(name)?)?;
+        Ok(())
+    }
+}
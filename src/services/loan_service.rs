@@ -0,0 +1,177 @@
+/// Loan Service - Business logic for borrowing physical and digital records
+/// Handles borrowers, lending/returning files and items, approval and overdue tracking
+use crate::core::GenericRepository;
+use crate::models::borrower::Borrower;
+use crate::models::loan::{ApprovalStatus, Loan};
+use crate::persistence::{BorrowersRepository, ItemsRepository, LoansRepository};
+use chrono::NaiveDateTime;
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+/// Loan service for the reading room
+pub struct LoanService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl LoanService {
+    /// Create a new loan service on the given library connection
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    fn loans_repo(&self) -> LoansRepository {
+        LoansRepository::new(Rc::clone(&self.conn))
+    }
+
+    /// Register a borrower
+    pub fn add_borrower(&self, name: &str, department: &str, contact: &str) -> Result<i32, Box<dyn Error>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("借阅人姓名不能为空".into());
+        }
+        BorrowersRepository::new(Rc::clone(&self.conn)).create(Borrower {
+            name: name.to_string(),
+            department: department.trim().to_string(),
+            contact: contact.trim().to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// All registered borrowers, sorted by name
+    pub fn list_borrowers(&self) -> Result<Vec<Borrower>, Box<dyn Error>> {
+        let mut borrowers = BorrowersRepository::new(Rc::clone(&self.conn)).find_all()?;
+        borrowers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(borrowers)
+    }
+
+    /// Lend a whole file (案卷)
+    ///
+    /// Refused while the file, or any item inside it, is still out.
+    pub fn lend_file(
+        &self,
+        borrower_id: i32,
+        file_id: i32,
+        purpose: &str,
+        loan_date: NaiveDateTime,
+        due_date: NaiveDateTime,
+    ) -> Result<i32, Box<dyn Error>> {
+        let mut loans_repo = self.loans_repo();
+        if !loans_repo.find_open_by_file_id(file_id)?.is_empty() {
+            return Err("该案卷已借出，尚未归还".into());
+        }
+        let items = ItemsRepository::new(Rc::clone(&self.conn)).find_by_predicate(|i| i.file_id == file_id)?;
+        for item in &items {
+            if !loans_repo.find_open_by_item_id(item.id)?.is_empty() {
+                return Err(format!("案卷中的文件 {} 已借出，尚未归还", item.item_no).into());
+            }
+        }
+        self.create_loan(borrower_id, Some(file_id), None, purpose, loan_date, due_date)
+    }
+
+    /// Lend a single item (文件)
+    ///
+    /// Refused while the item, or the file that contains it, is still out.
+    pub fn lend_item(
+        &self,
+        borrower_id: i32,
+        item_id: i32,
+        purpose: &str,
+        loan_date: NaiveDateTime,
+        due_date: NaiveDateTime,
+    ) -> Result<i32, Box<dyn Error>> {
+        let item = ItemsRepository::new(Rc::clone(&self.conn))
+            .find_by_id(item_id)?
+            .ok_or("文件不存在")?;
+        let mut loans_repo = self.loans_repo();
+        if !loans_repo.find_open_by_item_id(item_id)?.is_empty() {
+            return Err("该文件已借出，尚未归还".into());
+        }
+        if !loans_repo.find_open_by_file_id(item.file_id)?.is_empty() {
+            return Err("该文件所属案卷已借出，尚未归还".into());
+        }
+        self.create_loan(borrower_id, None, Some(item_id), purpose, loan_date, due_date)
+    }
+
+    fn create_loan(
+        &self,
+        borrower_id: i32,
+        file_id: Option<i32>,
+        item_id: Option<i32>,
+        purpose: &str,
+        loan_date: NaiveDateTime,
+        due_date: NaiveDateTime,
+    ) -> Result<i32, Box<dyn Error>> {
+        if due_date < loan_date {
+            return Err("应还日期不能早于借出日期".into());
+        }
+        if BorrowersRepository::new(Rc::clone(&self.conn)).find_by_id(borrower_id)?.is_none() {
+            return Err("借阅人不存在".into());
+        }
+        self.loans_repo().create(Loan {
+            borrower_id,
+            file_id,
+            item_id,
+            purpose: purpose.trim().to_string(),
+            loan_date,
+            due_date,
+            approval_status: ApprovalStatus::Pending.as_str().to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn load_loan(&self, loan_id: i32) -> Result<Loan, Box<dyn Error>> {
+        Ok(self.loans_repo().find_by_id(loan_id)?.ok_or("借阅记录不存在")?)
+    }
+
+    /// Approve a pending loan
+    pub fn approve(&self, loan_id: i32, approver: &str) -> Result<(), Box<dyn Error>> {
+        self.set_approval(loan_id, ApprovalStatus::Approved, approver)
+    }
+
+    /// Reject a pending loan; the record is released for other borrowers
+    pub fn reject(&self, loan_id: i32, approver: &str) -> Result<(), Box<dyn Error>> {
+        self.set_approval(loan_id, ApprovalStatus::Rejected, approver)
+    }
+
+    fn set_approval(&self, loan_id: i32, status: ApprovalStatus, approver: &str) -> Result<(), Box<dyn Error>> {
+        let mut loan = self.load_loan(loan_id)?;
+        if loan.status() != ApprovalStatus::Pending {
+            return Err("该借阅申请已审批".into());
+        }
+        loan.approval_status = status.as_str().to_string();
+        loan.approved_by = approver.to_string();
+        self.loans_repo().update(&loan)
+    }
+
+    /// Record the return of a loan
+    pub fn return_loan(&self, loan_id: i32, return_date: NaiveDateTime) -> Result<(), Box<dyn Error>> {
+        let mut loan = self.load_loan(loan_id)?;
+        if loan.return_date.is_some() {
+            return Err("该借阅已归还".into());
+        }
+        if loan.status() == ApprovalStatus::Rejected {
+            return Err("已驳回的借阅申请无需归还".into());
+        }
+        loan.return_date = Some(return_date);
+        self.loans_repo().update(&loan)
+    }
+
+    /// Loans past their due date and not yet returned, oldest due first
+    pub fn overdue(&self, now: NaiveDateTime) -> Result<Vec<Loan>, Box<dyn Error>> {
+        let mut loans = self.loans_repo().find_by_predicate(|l| l.is_overdue(now))?;
+        loans.sort_by_key(|l| l.due_date);
+        Ok(loans)
+    }
+
+    /// Loans that are currently out (pending or approved, not returned)
+    pub fn open_loans(&self) -> Result<Vec<Loan>, Box<dyn Error>> {
+        self.loans_repo().find_by_predicate(|l| l.is_open())
+    }
+
+    /// Full loan history of a borrower, newest first
+    pub fn borrower_history(&self, borrower_id: i32) -> Result<Vec<Loan>, Box<dyn Error>> {
+        self.loans_repo().find_by_borrower_id(borrower_id)
+    }
+}
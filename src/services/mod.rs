@@ -1,5 +1,47 @@
 pub mod runtime_translations;
 pub mod settings_service;
+pub mod scan_service;
+pub mod loan_service;
+pub mod audit_service;
+pub mod journal_service;
+pub mod fixity_service;
+pub mod integrity_service;
+pub mod ingest_service;
+pub mod library_path_service;
+pub mod backup_service;
+pub mod bagit_service;
+pub mod xlsx;
+pub mod csv;
+pub mod catalogue_service;
+pub mod register_import_service;
+pub mod background_job;
+pub mod directory_import_service;
+pub mod recovery_service;
+pub mod classification_import_service;
+pub mod classification_standards;
+pub mod schema_import_service;
+pub mod library_template_service;
+pub mod usage_service;
 
-pub use runtime_translations::{init_gettext, set_language, gettext_tr};
-pub use settings_service::SettingsService;
\ No newline at end of file
+pub use runtime_translations::{init_gettext, set_language, gettext_tr, current_language};
+pub use settings_service::SettingsService;
+pub use scan_service::{ScanService, ScanTarget, StocktakeReport};
+pub use loan_service::LoanService;
+pub use audit_service::AuditService;
+pub use journal_service::{JournalService, JournalCheckpoint, JournalDivergence, JournalVerification};
+pub use fixity_service::{FixityService, FixityReport, FixityIssue, ChecksumAlgorithm};
+pub use integrity_service::{IntegrityService, IntegrityReport, IntegrityIssue, IntegrityRepair, RepairChoice};
+pub use ingest_service::{IngestService, IngestProgress, IngestOutcome};
+pub use library_path_service::{LibraryPathService, ExternalPath, RelinkReport};
+pub use backup_service::{BackupService, BackupManifest, BackupEntry, BackupFormat};
+pub use bagit_service::{BagItService, BagScope, BagMetadata, BagImportReport};
+pub use catalogue_service::{CatalogueService, CatalogueQuery, CatalogueLevel, CatalogueColumn};
+pub use register_import_service::{RegisterImportService, RegisterTable, RegisterMapping, RegisterField, RegisterPreview, RegisterRowPreview, RegisterImportReport, NumberMode};
+pub use background_job::{BackgroundJob, JobControl, JobProgress};
+pub use directory_import_service::{DirectoryImportService, DirectoryMapping, MappedFile, MappedItem, DirectoryImportReport};
+pub use recovery_service::{RecoveryService, RecoveredLibrary, RecoveredFond, RecoveryReport};
+pub use classification_import_service::{ClassificationImportService, ClassificationDiff, ClassificationChange};
+pub use classification_standards::{ClassificationStandard, LibraryStandard};
+pub use schema_import_service::{SchemaImportService, SchemaDiff, SchemaChange};
+pub use usage_service::{UsageService, Usage, Contents};
+pub use library_template_service::{LibraryTemplateService, LibraryTemplate, TemplateSettings, NumberingConvention};
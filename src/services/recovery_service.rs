@@ -0,0 +1,368 @@
+/// Recovery Service - Rebuild a library database from its sidecar files
+/// Reads the `.fondspod.json` sidecars of the library folders and recreates the catalogue in a fresh `.fondspod.db`
+use crate::core::GenericRepository;
+use crate::models::file::File;
+use crate::models::fond::Fond;
+use crate::models::fond_classification::FondClassification;
+use crate::models::fond_schema::FondSchema;
+use crate::models::item::Item;
+use crate::models::schema_item::SchemaItem;
+use crate::models::series::Series;
+use crate::models::translation::Translation;
+use crate::persistence::schema_item_repository::{SchemaItemRepository, PATH_SEPARATOR};
+use crate::persistence::sidecars::{FondSchemaSidecar, SchemaSidecar, Sidecar, SidecarDocument, SIDECAR_NAME};
+use crate::persistence::{
+    establish_connection, FilesRepository, FondClassificationsRepository, FondSchemasRepository, FondsRepository,
+    ItemsRepository, SchemaRepository, SequencesRepository, SeriesRepository, TranslationsRepository,
+};
+use crate::services::backup_service::DATABASE_NAME;
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// A fond as found on disk, with the files and items below it
+#[derive(Debug, Clone)]
+pub struct RecoveredFond {
+    pub fond: Fond,
+    pub schemas: Vec<FondSchemaSidecar>,
+    pub series: Vec<Series>,
+    /// Each file with the number of its series
+    pub files: Vec<(String, File)>,
+    /// Each item with the number of its file
+    pub items: Vec<(String, Item)>,
+}
+
+/// Everything the sidecars of a library describe
+#[derive(Debug, Clone, Default)]
+pub struct RecoveredLibrary {
+    pub classifications: Vec<FondClassification>,
+    pub schemas: Vec<SchemaSidecar>,
+    /// Translated names of the classifications and schema items
+    pub translations: Vec<Translation>,
+    pub fonds: Vec<RecoveredFond>,
+    /// Sidecars that could not be read or do not fit the layout
+    pub problems: Vec<String>,
+}
+
+impl RecoveredLibrary {
+    pub fn summary(&self) -> String {
+        let files: usize = self.fonds.iter().map(|f| f.files.len()).sum();
+        let items: usize = self.fonds.iter().map(|f| f.items.len()).sum();
+        format!(
+            "{} 个全宗、{} 个系列、{} 个案卷、{} 个文件，{} 个问题",
+            self.fonds.len(),
+            self.fonds.iter().map(|f| f.series.len()).sum::<usize>(),
+            files,
+            items,
+            self.problems.len()
+        )
+    }
+}
+
+/// Result of a rebuild
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryReport {
+    pub fonds: usize,
+    pub series: usize,
+    pub files: usize,
+    pub items: usize,
+    /// Problems found while reading sidecars and records skipped while rebuilding
+    pub problems: Vec<String>,
+    /// Where the previous database was moved, if there was one
+    pub previous_database: Option<PathBuf>,
+}
+
+impl RecoveryReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "已重建 {} 个全宗、{} 个系列、{} 个案卷、{} 个文件，{} 个问题",
+            self.fonds, self.series, self.files, self.items, self.problems.len()
+        )
+    }
+}
+
+/// Recovery for one library folder
+pub struct RecoveryService {
+    library_path: PathBuf,
+}
+
+impl RecoveryService {
+    pub fn new(library_path: impl AsRef<Path>) -> Self {
+        Self { library_path: library_path.as_ref().to_path_buf() }
+    }
+
+    /// Read every sidecar of the library: `<fond_no>/<file_no>/<item_no>` below the library folder
+    pub fn scan(&self) -> Result<RecoveredLibrary, Box<dyn Error>> {
+        if !self.library_path.is_dir() {
+            return Err(format!("档案库目录不存在: {}", self.library_path.display()).into());
+        }
+        let mut library = RecoveredLibrary::default();
+        match self.read(&self.library_path, &mut library.problems) {
+            Some(Sidecar::Library { classifications, schemas, translations }) => {
+                library.classifications = classifications;
+                library.schemas = schemas;
+                library.translations = translations;
+            }
+            Some(_) => library.problems.push(format!("{}: 不是档案库 sidecar", self.library_path.display())),
+            None => {}
+        }
+
+        for fond_dir in sub_directories(&self.library_path)? {
+            let Some(sidecar) = self.read(&fond_dir, &mut library.problems) else { continue };
+            let Sidecar::Fond { fond, schemas, series } = sidecar else {
+                library.problems.push(format!("{}: 不是全宗 sidecar", fond_dir.display()));
+                continue;
+            };
+            let mut recovered = RecoveredFond { fond, schemas, series, files: Vec::new(), items: Vec::new() };
+            for file_dir in sub_directories(&fond_dir)? {
+                match self.read(&file_dir, &mut library.problems) {
+                    Some(Sidecar::File { series_no, file }) => recovered.files.push((series_no, file)),
+                    Some(_) => library.problems.push(format!("{}: 不是案卷 sidecar", file_dir.display())),
+                    None => continue,
+                }
+                for item_dir in sub_directories(&file_dir)? {
+                    match self.read(&item_dir, &mut library.problems) {
+                        Some(Sidecar::Item { file_no, item }) => recovered.items.push((file_no, item)),
+                        Some(_) => library.problems.push(format!("{}: 不是文件 sidecar", item_dir.display())),
+                        None => {}
+                    }
+                }
+            }
+            library.fonds.push(recovered);
+        }
+        Ok(library)
+    }
+
+    /// Rebuild `.fondspod.db` from the sidecars
+    ///
+    /// The catalogue is written to a staging database first; only when that
+    /// succeeded is the existing database (if any) moved aside as
+    /// `.fondspod.db.broken-<timestamp>` and replaced. Records get new ids and
+    /// their original creation stamps; the number counters are advanced past
+    /// every restored number and item checksums are recorded afresh from the
+    /// payloads on disk. Loans, borrowers and the audit history are not part
+    /// of the sidecars and start empty.
+    pub fn rebuild(&self) -> Result<RecoveryReport, Box<dyn Error>> {
+        let recovered = self.scan()?;
+        if recovered.fonds.is_empty() && recovered.schemas.is_empty() && recovered.classifications.is_empty() {
+            return Err("档案库中没有找到 sidecar，无法重建".into());
+        }
+
+        let staging = self.library_path.join(format!("{}.rebuild", DATABASE_NAME));
+        let _ = fs::remove_file(&staging);
+        let mut report = RecoveryReport { problems: recovered.problems.clone(), ..Default::default() };
+        let restored = self.restore(&staging, &recovered, &mut report);
+        if let Err(e) = restored {
+            let _ = fs::remove_file(&staging);
+            return Err(e);
+        }
+
+        let database = self.library_path.join(DATABASE_NAME);
+        if database.exists() {
+            let aside = self.library_path.join(format!("{}.broken-{}", DATABASE_NAME, chrono::Local::now().format("%Y%m%d-%H%M%S")));
+            fs::rename(&database, &aside)?;
+            report.previous_database = Some(aside);
+        }
+        fs::rename(&staging, &database)?;
+        log::info!("Rebuilt {:?} from sidecars: {}", database, report.summary());
+        Ok(report)
+    }
+
+    fn restore(&self, database: &Path, recovered: &RecoveredLibrary, report: &mut RecoveryReport) -> Result<(), Box<dyn Error>> {
+        let conn = establish_connection(database)?;
+
+        // 分类：父级先于子级写入，按旧 id 映射到新 id
+        let mut classifications_repo = FondClassificationsRepository::new(Rc::clone(&conn));
+        let existing_codes: HashMap<String, i32> = classifications_repo.find_all()?.into_iter().map(|c| (c.code, c.id)).collect();
+        let mut classification_ids: HashMap<i32, i32> = HashMap::new();
+        let mut pending: Vec<&FondClassification> = recovered.classifications.iter().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            let mut waiting = Vec::new();
+            for classification in pending {
+                let parent_id = match classification.parent_id {
+                    Some(old) => match classification_ids.get(&old) {
+                        Some(new) => Some(*new),
+                        None => {
+                            waiting.push(classification);
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                let id = match existing_codes.get(&classification.code).filter(|_| parent_id.is_none()) {
+                    Some(id) => *id,
+                    None => classifications_repo.insert(&FondClassification { parent_id, ..classification.clone() })?,
+                };
+                classification_ids.insert(classification.id, id);
+            }
+            if waiting.len() == before {
+                for classification in &waiting {
+                    report.problems.push(format!("分类 {} 的上级分类不存在，已跳过", classification.code));
+                }
+                break;
+            }
+            pending = waiting;
+        }
+
+        // 方案按编号匹配（内置的 Year 方案已存在）
+        let mut schemas_repo = SchemaRepository::new(Rc::clone(&conn));
+        let mut items_repo = SchemaItemRepository::new(Rc::clone(&conn));
+        let mut schema_ids: HashMap<String, i32> = schemas_repo.find_all()?.into_iter().map(|s| (s.schema_no, s.id)).collect();
+        let mut schema_item_ids: HashMap<(String, String), i32> = HashMap::new();
+        let mut schema_item_new_ids: HashMap<i32, i32> = HashMap::new();
+        // 条目按编号路径匹配，不同上级下可以有相同编号的条目
+        let mut schema_item_paths: HashMap<i32, String> = HashMap::new();
+        for sidecar in &recovered.schemas {
+            let schema_id = match schema_ids.get(&sidecar.schema.schema_no) {
+                Some(id) => *id,
+                None => schemas_repo.insert(&sidecar.schema)?,
+            };
+            schema_ids.insert(sidecar.schema.schema_no.clone(), schema_id);
+            let existing: HashMap<String, i32> = items_repo.find_path_numbered(schema_id)?.into_iter().collect();
+            // 上级条目先于下级条目写入
+            let mut pending: Vec<&SchemaItem> = sidecar.items.iter().collect();
+            while !pending.is_empty() {
+                let before = pending.len();
+                let mut waiting = Vec::new();
+                for item in pending {
+                    let (parent_id, path) = match item.parent_id {
+                        Some(old) => match (schema_item_new_ids.get(&old), schema_item_paths.get(&old)) {
+                            (Some(new), Some(parent_path)) => (Some(*new), format!("{}{}{}", parent_path, PATH_SEPARATOR, item.item_no)),
+                            _ => {
+                                waiting.push(item);
+                                continue;
+                            }
+                        },
+                        None => (None, item.item_no.clone()),
+                    };
+                    let id = match existing.get(&path) {
+                        Some(id) => *id,
+                        None => items_repo.insert(&SchemaItem { schema_id, parent_id, ..item.clone() })?,
+                    };
+                    schema_item_ids.insert((sidecar.schema.schema_no.clone(), path.clone()), id);
+                    schema_item_new_ids.insert(item.id, id);
+                    schema_item_paths.insert(item.id, path);
+                }
+                if waiting.len() == before {
+                    for item in &waiting {
+                        report.problems.push(format!("方案 {} 的条目 {} 的上级条目不存在，已跳过", sidecar.schema.schema_no, item.item_no));
+                    }
+                    break;
+                }
+                pending = waiting;
+            }
+        }
+
+        // 翻译按旧 id 映射到新的分类和方案条目
+        let mut translations_repo = TranslationsRepository::new(Rc::clone(&conn));
+        for translation in &recovered.translations {
+            let entity_id = match translation.entity_type.as_str() {
+                "fond_classifications" => classification_ids.get(&translation.entity_id),
+                "schema_items" => schema_item_new_ids.get(&translation.entity_id),
+                _ => None,
+            };
+            match entity_id {
+                Some(entity_id) => {
+                    translations_repo.insert(&Translation { entity_id: *entity_id, ..translation.clone() })?;
+                }
+                None => report.problems.push(format!(
+                    "{} {} 的 {} 翻译没有对应的记录，已跳过",
+                    translation.entity_type, translation.entity_id, translation.locale
+                )),
+            }
+        }
+
+        let mut fonds_repo = FondsRepository::new(Rc::clone(&conn));
+        let mut fond_schemas_repo = FondSchemasRepository::new(Rc::clone(&conn));
+        let mut series_repo = SeriesRepository::new(Rc::clone(&conn));
+        let mut files_repo = FilesRepository::new(Rc::clone(&conn));
+        let mut item_records = ItemsRepository::new(Rc::clone(&conn));
+        let mut sequences = SequencesRepository::new(Rc::clone(&conn));
+        let fixity = FixityService::new(Rc::clone(&conn), &self.library_path);
+        for fond in &recovered.fonds {
+            let fond_id = fonds_repo.insert(&fond.fond)?;
+            sequences.advance_past(&fond.fond.fond_classification_code, &fond.fond.fond_no, 2)?;
+            report.fonds += 1;
+
+            for dimension in &fond.schemas {
+                let Some(schema_id) = schema_ids.get(&dimension.schema_no) else {
+                    report.problems.push(format!("全宗 {} 的方案 {} 不存在，已跳过", fond.fond.fond_no, dimension.schema_no));
+                    continue;
+                };
+                let schema_item_id = dimension
+                    .item_no
+                    .as_ref()
+                    .and_then(|item_no| schema_item_ids.get(&(dimension.schema_no.clone(), item_no.clone())).copied());
+                fond_schemas_repo.insert(&FondSchema {
+                    fond_id,
+                    schema_id: *schema_id,
+                    schema_item_id,
+                    item_level: dimension.item_level,
+                    sort_order: dimension.sort_order,
+                    ..Default::default()
+                })?;
+            }
+
+            let mut series_ids: HashMap<&str, i32> = HashMap::new();
+            for series in &fond.series {
+                series_ids.insert(&series.series_no, series_repo.insert(&Series { fond_id, ..series.clone() })?);
+                report.series += 1;
+            }
+
+            let mut file_ids: HashMap<&str, i32> = HashMap::new();
+            for (series_no, file) in &fond.files {
+                let Some(series_id) = series_ids.get(series_no.as_str()) else {
+                    report.problems.push(format!("案卷 {} 的系列 {} 不存在，已跳过", file.file_no, series_no));
+                    continue;
+                };
+                file_ids.insert(&file.file_no, files_repo.insert(&File { series_id: *series_id, ..file.clone() })?);
+                sequences.advance_past(series_no, &file.file_no, 2)?;
+                report.files += 1;
+            }
+
+            for (file_no, item) in &fond.items {
+                let Some(file_id) = file_ids.get(file_no.as_str()) else {
+                    report.problems.push(format!("文件 {} 的案卷 {} 不存在，已跳过", item.item_no, file_no));
+                    continue;
+                };
+                let item_id = item_records.insert(&Item { file_id: *file_id, ..item.clone() })?;
+                sequences.advance_past(file_no, &item.item_no, 3)?;
+                if let Err(e) = fixity.record_item(item_id, ChecksumAlgorithm::Sha256) {
+                    report.problems.push(format!("文件 {} 的校验值未能记录: {}", item.item_no, e));
+                }
+                report.items += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sidecar of a directory; unreadable sidecars are recorded as problems
+    fn read(&self, dir: &Path, problems: &mut Vec<String>) -> Option<Sidecar> {
+        let path = dir.join(SIDECAR_NAME);
+        if !path.is_file() {
+            return None;
+        }
+        match SidecarDocument::read(&path) {
+            Ok(document) => Some(document.sidecar),
+            Err(e) => {
+                problems.push(format!("{}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+}
+
+/// Visible sub-directories of a directory, sorted
+fn sub_directories(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && !p.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')))
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
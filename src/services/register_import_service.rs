@@ -0,0 +1,628 @@
+/// Register Import Service - Bulk import files and items from legacy CSV/XLSX registers
+/// Maps spreadsheet columns onto hierarchy fields, validates every row into a preview, then creates the records
+use crate::core::GenericRepository;
+use crate::models::app_settings::IngestMode;
+use crate::models::file::File;
+use crate::models::fond::Fond;
+use crate::models::item::Item;
+use crate::models::series::Series;
+use crate::models::fond::fonds;
+use crate::persistence::{sidecars, with_transaction, FilesRepository, FondsRepository, ItemsRepository, SequencesRepository, SeriesRepository};
+use crate::services::fixity_service::{ChecksumAlgorithm, FixityService};
+use crate::services::ingest_service::{IngestOutcome, IngestService};
+use crate::services::library_path_service::LibraryPathService;
+use crate::services::csv::{csv_escape, parse_csv};
+use crate::services::xlsx;
+use diesel::prelude::*;
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Field a register column can be mapped onto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RegisterField {
+    FondNo,
+    SeriesNo,
+    FileNo,
+    FileName,
+    ItemNo,
+    ItemName,
+    /// Payload of the item, ingested like a picked file
+    Path,
+}
+
+impl RegisterField {
+    pub const ALL: [RegisterField; 7] = [
+        RegisterField::FondNo,
+        RegisterField::SeriesNo,
+        RegisterField::FileNo,
+        RegisterField::FileName,
+        RegisterField::ItemNo,
+        RegisterField::ItemName,
+        RegisterField::Path,
+    ];
+
+    /// Header names recognised when detecting the mapping; the catalogue export headers are included
+    fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            RegisterField::FondNo => &["fond_no", "全宗号"],
+            RegisterField::SeriesNo => &["series_no", "系列号"],
+            RegisterField::FileNo => &["file_no", "案卷号"],
+            RegisterField::FileName => &["file_name", "案卷题名", "案卷名称"],
+            RegisterField::ItemNo => &["item_no", "文件号"],
+            RegisterField::ItemName => &["item_name", "文件题名", "文件名称"],
+            RegisterField::Path => &["path", "路径"],
+        }
+    }
+}
+
+/// Which register column feeds which field
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterMapping {
+    columns: BTreeMap<RegisterField, usize>,
+}
+
+impl RegisterMapping {
+    /// Mapping guessed from the header row
+    pub fn detect(header: &[String]) -> Self {
+        let mut mapping = Self::default();
+        for field in RegisterField::ALL {
+            let found = header.iter().position(|h| {
+                let h = h.trim().trim_start_matches('\u{feff}');
+                field.aliases().iter().any(|alias| h.eq_ignore_ascii_case(alias))
+            });
+            mapping.set(field, found);
+        }
+        mapping
+    }
+
+    /// Map a field onto a column, or unmap it
+    pub fn set(&mut self, field: RegisterField, column: Option<usize>) {
+        match column {
+            Some(column) => self.columns.insert(field, column),
+            None => self.columns.remove(&field),
+        };
+    }
+
+    pub fn column(&self, field: RegisterField) -> Option<usize> {
+        self.columns.get(&field).copied()
+    }
+
+    /// Trimmed non-empty value of a field in a row
+    fn value<'a>(&self, row: &'a [String], field: RegisterField) -> Option<&'a str> {
+        self.column(field)
+            .and_then(|c| row.get(c))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Numbers for new files and items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberMode {
+    /// Allocate from the library's sequences; supplied numbers only group rows into files
+    #[default]
+    Allocate,
+    /// Keep supplied numbers and move the sequences past them; rows without a number are allocated
+    Keep,
+}
+
+/// Register read from a spreadsheet: header row and data rows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterTable {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl RegisterTable {
+    /// Read a `.csv` or `.xlsx` register; the first row is the header
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let mut rows = match extension.as_str() {
+            "csv" => parse_csv(&fs::read_to_string(path)?),
+            "xlsx" => xlsx::read_first_sheet(path)?,
+            _ => return Err("登记表只能是 .csv 或 .xlsx".into()),
+        };
+        if rows.is_empty() {
+            return Err("登记表为空".into());
+        }
+        let header = rows.remove(0);
+        Ok(Self { header, rows })
+    }
+}
+
+/// Validation result of one register row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterRowPreview {
+    /// Line in the spreadsheet, counting the header as line 1
+    pub line: usize,
+    pub series_no: String,
+    /// File number to use; empty when it will be allocated
+    pub file_no: String,
+    /// Item number to use; empty when it will be allocated or the row has no item
+    pub item_no: String,
+    pub errors: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// Item to create
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedItem {
+    item_no: Option<String>,
+    name: String,
+    path: Option<String>,
+}
+
+/// File to create, or an existing file that receives new items
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedFile {
+    existing_id: Option<i32>,
+    series_no: String,
+    file_no: Option<String>,
+    name: String,
+    items: Vec<PlannedItem>,
+}
+
+/// Preview of a register import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterPreview {
+    pub rows: Vec<RegisterRowPreview>,
+    /// Series numbers that do not exist yet and will be generated from the fond's schemas
+    pub missing_series: BTreeSet<String>,
+    plan: Vec<PlannedFile>,
+}
+
+impl RegisterPreview {
+    /// Whether every row can be imported
+    pub fn is_valid(&self) -> bool {
+        self.rows.iter().all(|r| r.errors.is_empty())
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.rows.iter().map(|r| r.errors.len()).sum()
+    }
+
+    pub fn new_files(&self) -> usize {
+        self.plan.iter().filter(|f| f.existing_id.is_none()).count()
+    }
+
+    pub fn new_items(&self) -> usize {
+        self.plan.iter().map(|f| f.items.len()).sum()
+    }
+
+    /// Render the preview as CSV (line, series_no, file_no, item_no, errors, notes)
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("line,series_no,file_no,item_no,errors,notes\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                row.line,
+                csv_escape(&row.series_no),
+                csv_escape(&row.file_no),
+                csv_escape(&row.item_no),
+                csv_escape(&row.errors.join("; ")),
+                csv_escape(&row.notes.join("; "))
+            ));
+        }
+        csv
+    }
+
+    /// One-line summary for dialogs and logs
+    pub fn summary(&self) -> String {
+        format!(
+            "{} 行，新建 {} 个案卷、{} 个文件，{} 处错误",
+            self.rows.len(),
+            self.new_files(),
+            self.new_items(),
+            self.error_count()
+        )
+    }
+}
+
+/// Result of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterImportReport {
+    pub series_generated: usize,
+    pub files_created: usize,
+    pub items_created: usize,
+}
+
+/// Register importer for one library
+/// Payloads and directories a running import has put into the library
+#[derive(Default)]
+struct Staged {
+    ingested: Vec<(IngestOutcome, PathBuf)>,
+    dirs: Vec<PathBuf>,
+}
+
+pub struct RegisterImportService {
+    conn: Rc<RefCell<SqliteConnection>>,
+    library_path: PathBuf,
+}
+
+impl RegisterImportService {
+    /// Create a new register importer on the given library connection and directory
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>, library_path: impl AsRef<Path>) -> Self {
+        Self {
+            conn,
+            library_path: library_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Validate every row without writing anything
+    ///
+    /// Rows in a series that does not exist yet are accepted when the series
+    /// number starts with a known fond number; the import generates it.
+    pub fn preview(&self, table: &RegisterTable, mapping: &RegisterMapping, mode: NumberMode) -> Result<RegisterPreview, Box<dyn Error>> {
+        self.validate(table, mapping, mode, true)
+    }
+
+    /// Validate and import a register
+    ///
+    /// `generate_series` is called once per fond with missing series and must
+    /// create the fond's series from its schemas; rows whose series still does
+    /// not exist afterwards fail the import. Nothing is created unless every
+    /// row validates, and the import runs as one transaction: on failure no
+    /// rows remain and copied payloads are removed again.
+    pub fn import(
+        &self,
+        table: &RegisterTable,
+        mapping: &RegisterMapping,
+        mode: NumberMode,
+        ingest_mode: IngestMode,
+        algorithm: ChecksumAlgorithm,
+        mut generate_series: impl FnMut(i32) -> Result<(), Box<dyn Error>>,
+    ) -> Result<RegisterImportReport, Box<dyn Error>> {
+        let preview = self.validate(table, mapping, mode, true)?;
+        if !preview.is_valid() {
+            return Err(format!("登记表有 {} 处错误，未导入", preview.error_count()).into());
+        }
+
+        let mut staged = Staged::default();
+        let result = with_transaction(&self.conn, || {
+            self.write(table, mapping, mode, ingest_mode, algorithm, &preview, &mut generate_series, &mut staged)
+        });
+        if result.is_err() {
+            self.discard(&staged, ingest_mode);
+        }
+        result
+    }
+
+    /// Create the previewed series, files and items; runs inside the import's transaction
+    #[allow(clippy::too_many_arguments)]
+    fn write(
+        &self,
+        table: &RegisterTable,
+        mapping: &RegisterMapping,
+        mode: NumberMode,
+        ingest_mode: IngestMode,
+        algorithm: ChecksumAlgorithm,
+        preview: &RegisterPreview,
+        generate_series: &mut impl FnMut(i32) -> Result<(), Box<dyn Error>>,
+        staged: &mut Staged,
+    ) -> Result<RegisterImportReport, Box<dyn Error>> {
+        let mut report = RegisterImportReport::default();
+        if !preview.missing_series.is_empty() {
+            let fonds = FondsRepository::new(Rc::clone(&self.conn)).find_all()?;
+            let fond_ids: BTreeSet<i32> = preview
+                .missing_series
+                .iter()
+                .filter_map(|series_no| Self::fond_of_series_no(&fonds, series_no).map(|f| f.id))
+                .collect();
+            let before = SeriesRepository::new(Rc::clone(&self.conn)).find_all()?.len();
+            for fond_id in fond_ids {
+                generate_series(fond_id)?;
+            }
+            report.series_generated = SeriesRepository::new(Rc::clone(&self.conn)).find_all()?.len() - before;
+        }
+
+        let preview = self.validate(table, mapping, mode, false)?;
+        if !preview.is_valid() {
+            let first = preview.rows.iter().find(|r| !r.errors.is_empty()).map(|r| format!("第 {} 行: {}", r.line, r.errors.join("; ")));
+            return Err(format!("方案生成后仍有系列不存在，未导入。{}", first.unwrap_or_default()).into());
+        }
+
+        let fonds = FondsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let series_list = SeriesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let mut files_repo = FilesRepository::new(Rc::clone(&self.conn));
+        let mut items_repo = ItemsRepository::new(Rc::clone(&self.conn));
+        let mut sequences = SequencesRepository::new(Rc::clone(&self.conn));
+        let fixity = FixityService::new(Rc::clone(&self.conn), &self.library_path);
+        let mut taken: HashSet<String> = files_repo.find_all()?.into_iter().map(|f| f.file_no).collect();
+        taken.extend(items_repo.find_all()?.into_iter().map(|i| i.item_no));
+
+        for planned in &preview.plan {
+            let series = series_list.iter().find(|s| s.series_no == planned.series_no).ok_or("系列不存在")?;
+            let fond_no = fonds.iter().find(|f| f.id == series.fond_id).map(|f| f.fond_no.clone()).ok_or("全宗不存在")?;
+
+            let (file_id, file_no) = match planned.existing_id {
+                Some(id) => (id, planned.file_no.clone().unwrap_or_default()),
+                None => {
+                    let file_no = match &planned.file_no {
+                        Some(file_no) => {
+                            sequences.advance_past(&series.series_no, file_no, 2)?;
+                            file_no.clone()
+                        }
+                        None => sequences.allocate(&series.series_no, 2, |n| taken.contains(n))?,
+                    };
+                    let file_dir = self.library_path.join(&fond_no).join(&file_no);
+                    if !file_dir.exists() {
+                        staged.dirs.push(file_dir.clone());
+                    }
+                    fs::create_dir_all(&file_dir)?;
+                    let id = files_repo.create(File {
+                        id: 0,
+                        series_id: series.id,
+                        name: planned.name.clone(),
+                        file_no: file_no.clone(),
+                        path: Some(LibraryPathService::to_stored(&self.library_path, &file_dir)),
+                        created_by: String::new(),
+                        created_machine: String::new(),
+                        created_at: chrono::Utc::now().naive_utc(),
+                        updated_at: None,
+                        updated_by: None,
+                        updated_machine: None,
+                    })?;
+                    taken.insert(file_no.clone());
+                    report.files_created += 1;
+                    (id, file_no)
+                }
+            };
+
+            for planned_item in &planned.items {
+                let item_no = match &planned_item.item_no {
+                    Some(item_no) => {
+                        sequences.advance_past(&file_no, item_no, 3)?;
+                        item_no.clone()
+                    }
+                    None => sequences.allocate(&file_no, 3, |n| taken.contains(n))?,
+                };
+                let destination = IngestService::destination(&self.library_path, &fond_no, &file_no, &item_no);
+                if !destination.exists() {
+                    staged.dirs.push(destination.clone());
+                }
+                let (path, source_path) = match &planned_item.path {
+                    Some(source) => {
+                        let outcome = IngestService::ingest(Path::new(source), &destination, ingest_mode, |_| {})?;
+                        staged.ingested.push((outcome.clone(), destination));
+                        (Some(LibraryPathService::to_stored(&self.library_path, Path::new(&outcome.path))), Some(outcome.source_path))
+                    }
+                    None => (None, None),
+                };
+                let item_id = items_repo.create(Item {
+                    id: 0,
+                    file_id,
+                    item_no: item_no.clone(),
+                    name: planned_item.name.clone(),
+                    path,
+                    source_path,
+                    created_by: String::new(),
+                    created_machine: String::new(),
+                    created_at: chrono::Utc::now().naive_utc(),
+                    updated_at: None,
+                    updated_by: None,
+                    updated_machine: None,
+                })?;
+                taken.insert(item_no.clone());
+                if let Err(e) = fixity.record_item(item_id, algorithm) {
+                    log::warn!("Failed to record checksums for imported item {}: {}", item_no, e);
+                }
+                report.items_created += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Undo what a failed import left in the library directory
+    ///
+    /// The rows are already rolled back; moved payloads go back to their
+    /// sources, new directories are removed and the fond sidecars, which may
+    /// list series generated by the import, are rewritten.
+    fn discard(&self, staged: &Staged, ingest_mode: IngestMode) {
+        for (outcome, destination) in staged.ingested.iter().rev() {
+            if let Err(e) = IngestService::rollback(outcome, destination, ingest_mode) {
+                log::error!("Failed to roll back ingest into {}: {}", destination.display(), e);
+            }
+        }
+        for dir in staged.dirs.iter().rev() {
+            if let Err(e) = fs::remove_dir_all(dir) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove {}: {}", dir.display(), e);
+                }
+            }
+        }
+        let mut conn = self.conn.borrow_mut();
+        let fond_ids: Vec<i32> = fonds::table.select(fonds::id).load(&mut *conn).unwrap_or_default();
+        for fond_id in fond_ids {
+            sidecars::after_write(&mut conn, "fonds", fond_id, None);
+        }
+    }
+
+    /// Fond a series number belongs to, by the longest fond number prefix
+    fn fond_of_series_no<'a>(fonds: &'a [Fond], series_no: &str) -> Option<&'a Fond> {
+        fonds
+            .iter()
+            .filter(|f| series_no.starts_with(&format!("{}-", f.fond_no)))
+            .max_by_key(|f| f.fond_no.len())
+    }
+
+    fn validate(
+        &self,
+        table: &RegisterTable,
+        mapping: &RegisterMapping,
+        mode: NumberMode,
+        allow_missing_series: bool,
+    ) -> Result<RegisterPreview, Box<dyn Error>> {
+        if mapping.column(RegisterField::FileNo).is_none() && mapping.column(RegisterField::FileName).is_none() {
+            return Err("至少需要映射案卷号或案卷题名列".into());
+        }
+        let fonds = FondsRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let series_list = SeriesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let files = FilesRepository::new(Rc::clone(&self.conn)).find_all()?;
+        let existing_items: HashSet<String> = ItemsRepository::new(Rc::clone(&self.conn)).find_all()?.into_iter().map(|i| i.item_no).collect();
+        let series_by_no: HashMap<&str, &Series> = series_list.iter().map(|s| (s.series_no.as_str(), s)).collect();
+        let files_by_no: HashMap<&str, &File> = files.iter().map(|f| (f.file_no.as_str(), f)).collect();
+
+        let mut preview = RegisterPreview::default();
+        let mut plan_index: HashMap<String, usize> = HashMap::new();
+        let mut kept_items: HashSet<String> = HashSet::new();
+
+        for (index, row) in table.rows.iter().enumerate() {
+            if row.iter().all(|v| v.trim().is_empty()) {
+                continue;
+            }
+            let value = |field| mapping.value(row, field);
+            let mut result = RegisterRowPreview { line: index + 2, ..Default::default() };
+            let file_no = value(RegisterField::FileNo);
+            let existing_file = file_no.and_then(|no| files_by_no.get(no).copied());
+
+            // 确定系列：系列号列优先，其次已有案卷，最后按案卷号前缀匹配
+            let series_no = match (value(RegisterField::SeriesNo), existing_file) {
+                (Some(series_no), _) => Some(series_no.to_string()),
+                (None, Some(file)) => series_list.iter().find(|s| s.id == file.series_id).map(|s| s.series_no.clone()),
+                (None, None) => file_no.and_then(|no| {
+                    series_list
+                        .iter()
+                        .filter(|s| no.starts_with(&format!("{}-", s.series_no)))
+                        .max_by_key(|s| s.series_no.len())
+                        .map(|s| s.series_no.clone())
+                }),
+            };
+            let Some(series_no) = series_no else {
+                result.errors.push("无法确定系列：缺少系列号，案卷号也不属于任何已有系列".into());
+                preview.rows.push(result);
+                continue;
+            };
+            result.series_no = series_no.clone();
+
+            let fond = match series_by_no.get(series_no.as_str()) {
+                Some(series) => fonds.iter().find(|f| f.id == series.fond_id),
+                None => {
+                    let fond = Self::fond_of_series_no(&fonds, &series_no);
+                    match fond {
+                        Some(_) if allow_missing_series => {
+                            preview.missing_series.insert(series_no.clone());
+                            result.notes.push(format!("系列 {} 不存在，将按全宗方案生成", series_no));
+                        }
+                        _ => result.errors.push(format!("系列 {} 不存在", series_no)),
+                    }
+                    fond
+                }
+            };
+            if let (Some(fond_no), Some(fond)) = (value(RegisterField::FondNo), fond) {
+                if fond_no != fond.fond_no {
+                    result.errors.push(format!("全宗号 {} 与系列所属全宗 {} 不符", fond_no, fond.fond_no));
+                }
+            }
+
+            // 确定案卷：已有案卷号直接使用，否则按案卷号或题名合并为新案卷
+            let key = match (existing_file, file_no) {
+                (Some(file), _) => {
+                    if result.series_no != series_list.iter().find(|s| s.id == file.series_id).map(|s| s.series_no.as_str()).unwrap_or_default() {
+                        result.errors.push(format!("案卷 {} 不属于系列 {}", file.file_no, result.series_no));
+                    }
+                    result.file_no = file.file_no.clone();
+                    format!("file:{}", file.file_no)
+                }
+                (None, Some(no)) => {
+                    if mode == NumberMode::Keep {
+                        if no.strip_prefix(&format!("{}-", series_no)).is_none_or(|rest| rest.parse::<u32>().is_err()) {
+                            result.errors.push(format!("案卷号 {} 应为 {}-序号", no, series_no));
+                        }
+                        result.file_no = no.to_string();
+                    }
+                    format!("file:{}", no)
+                }
+                (None, None) => match value(RegisterField::FileName) {
+                    Some(name) => format!("name:{}:{}", series_no, name),
+                    None => {
+                        result.errors.push("缺少案卷号或案卷题名".into());
+                        preview.rows.push(result);
+                        continue;
+                    }
+                },
+            };
+            let file_index = match plan_index.get(&key) {
+                Some(&i) => {
+                    if preview.plan[i].series_no != series_no {
+                        result.errors.push(format!("同一案卷出现在不同系列: {}", preview.plan[i].series_no));
+                    }
+                    i
+                }
+                None => {
+                    let name = match (existing_file, value(RegisterField::FileName)) {
+                        (Some(file), _) => file.name.clone(),
+                        (None, Some(name)) => name.to_string(),
+                        (None, None) => {
+                            result.errors.push("新案卷缺少案卷题名".into());
+                            String::new()
+                        }
+                    };
+                    if existing_file.is_none() && mode == NumberMode::Allocate && file_no.is_some() {
+                        result.notes.push("案卷号将重新分配".into());
+                    }
+                    preview.plan.push(PlannedFile {
+                        existing_id: existing_file.map(|f| f.id),
+                        series_no: series_no.clone(),
+                        file_no: existing_file
+                            .map(|f| f.file_no.clone())
+                            .or_else(|| file_no.filter(|_| mode == NumberMode::Keep).map(str::to_string)),
+                        name,
+                        items: Vec::new(),
+                    });
+                    plan_index.insert(key, preview.plan.len() - 1);
+                    preview.plan.len() - 1
+                }
+            };
+
+            // 文件：有文件题名、文件号或路径的行新建文件
+            let item_no = value(RegisterField::ItemNo);
+            let path = value(RegisterField::Path);
+            if value(RegisterField::ItemName).is_some() || item_no.is_some() || path.is_some() {
+                let Some(name) = value(RegisterField::ItemName) else {
+                    result.errors.push("缺少文件题名".into());
+                    preview.rows.push(result);
+                    continue;
+                };
+                let kept_no = match item_no {
+                    Some(no) if mode == NumberMode::Keep => {
+                        if existing_items.contains(no) || !kept_items.insert(no.to_string()) {
+                            result.errors.push(format!("文件号 {} 已存在", no));
+                        }
+                        match &preview.plan[file_index].file_no {
+                            Some(file_no) => {
+                                if no.strip_prefix(&format!("{}-", file_no)).is_none_or(|rest| rest.parse::<u32>().is_err()) {
+                                    result.errors.push(format!("文件号 {} 应为 {}-序号", no, file_no));
+                                }
+                            }
+                            None => result.errors.push("保留文件号时需要同时提供案卷号".into()),
+                        }
+                        result.item_no = no.to_string();
+                        Some(no.to_string())
+                    }
+                    Some(_) => {
+                        result.notes.push("文件号将重新分配".into());
+                        None
+                    }
+                    None => None,
+                };
+                if let Some(path) = path {
+                    if !Path::new(path).exists() {
+                        result.errors.push(format!("路径不存在: {}", path));
+                    }
+                }
+                preview.plan[file_index].items.push(PlannedItem {
+                    item_no: kept_no,
+                    name: name.to_string(),
+                    path: path.map(str::to_string),
+                });
+            }
+            preview.rows.push(result);
+        }
+        Ok(preview)
+    }
+}
@@ -101,3 +101,10 @@ pub fn gettext_tr(msgid: &str) -> String {
     msgid.to_string()
 }
 
+/// Get the language whose translations are loaded (also used for translated entity names)
+pub fn current_language() -> String {
+    CURRENT_LANGUAGE
+        .get()
+        .and_then(|language| language.lock().ok().map(|l| l.clone()))
+        .unwrap_or_else(|| "zh_CN".to_string())
+}
@@ -0,0 +1,198 @@
+/// Scan Service - Resolve scanned numbers (barcode / QR / keyboard wedge input)
+/// Handles record lookup by fond_no / series_no / file_no / item_no and shelf stocktaking
+use crate::core::GenericRepository;
+use crate::models::file::File;
+use crate::models::fond::Fond;
+use crate::models::item::Item;
+use crate::models::series::Series;
+use crate::persistence::{FilesRepository, FondsRepository, ItemsRepository, SeriesRepository};
+use crate::services::csv::csv_escape;
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::error::Error;
+use std::rc::Rc;
+
+/// The entity a scanned code resolved to
+#[derive(Debug, Clone)]
+pub enum ScanTarget {
+    Fond(Fond),
+    Series(Series),
+    File(File),
+    Item(Item),
+}
+
+impl ScanTarget {
+    /// Entity type name used for logging and reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScanTarget::Fond(_) => "fond",
+            ScanTarget::Series(_) => "series",
+            ScanTarget::File(_) => "file",
+            ScanTarget::Item(_) => "item",
+        }
+    }
+}
+
+/// Result of a stocktake: files expected on the shelf versus files scanned
+#[derive(Debug, Clone, Default)]
+pub struct StocktakeReport {
+    /// Files expected in the stocktake scope
+    pub expected: Vec<File>,
+    /// Expected files that were scanned
+    pub found: Vec<File>,
+    /// Expected files that were not scanned
+    pub missing: Vec<File>,
+    /// Scanned codes that resolved to files outside the scope
+    pub misplaced: Vec<String>,
+    /// Scanned codes that could not be resolved at all
+    pub unknown: Vec<String>,
+}
+
+impl StocktakeReport {
+    /// Render the report as CSV (status, file_no, name)
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("status,file_no,name\n");
+        for file in &self.found {
+            csv.push_str(&format!("found,{},{}\n", csv_escape(&file.file_no), csv_escape(&file.name)));
+        }
+        for file in &self.missing {
+            csv.push_str(&format!("missing,{},{}\n", csv_escape(&file.file_no), csv_escape(&file.name)));
+        }
+        for code in &self.misplaced {
+            csv.push_str(&format!("misplaced,{},\n", csv_escape(code)));
+        }
+        for code in &self.unknown {
+            csv.push_str(&format!("unknown,{},\n", csv_escape(code)));
+        }
+        csv
+    }
+
+    /// One-line summary for toasts and logs
+    pub fn summary(&self) -> String {
+        format!(
+            "expected {}, found {}, missing {}, misplaced {}, unknown {}",
+            self.expected.len(),
+            self.found.len(),
+            self.missing.len(),
+            self.misplaced.len(),
+            self.unknown.len()
+        )
+    }
+}
+
+/// Scan service for resolving printed numbers to records
+pub struct ScanService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl ScanService {
+    /// Create a new scan service on the given library connection
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Normalize scanner input: keyboard-wedge scanners may append CR/LF or pad with spaces
+    pub fn normalize(code: &str) -> String {
+        code.trim().trim_matches(|c: char| c.is_control()).trim().to_string()
+    }
+
+    /// Resolve a scanned code to the matching entity
+    ///
+    /// Numbers are hierarchical (item_no extends file_no, which extends series_no),
+    /// so the most specific level is tried first.
+    pub fn resolve(&self, code: &str) -> Result<Option<ScanTarget>, Box<dyn Error>> {
+        let code = Self::normalize(code);
+        if code.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(item) = ItemsRepository::new(Rc::clone(&self.conn)).find_by_item_no(&code)? {
+            return Ok(Some(ScanTarget::Item(item)));
+        }
+        if let Some(file) = FilesRepository::new(Rc::clone(&self.conn)).find_by_file_no(&code)? {
+            return Ok(Some(ScanTarget::File(file)));
+        }
+        if let Some(series) = SeriesRepository::new(Rc::clone(&self.conn)).find_by_series_no(&code)? {
+            return Ok(Some(ScanTarget::Series(series)));
+        }
+        if let Some(fond) = FondsRepository::new(Rc::clone(&self.conn)).find_by_fond_no(&code)? {
+            return Ok(Some(ScanTarget::Fond(fond)));
+        }
+
+        // 扫码枪可能改变大小写，退回到不区分大小写的匹配
+        let upper = code.to_uppercase();
+        if upper != code {
+            return self.resolve(&upper);
+        }
+
+        Ok(None)
+    }
+
+    /// Files expected on the shelf for a fond (optionally restricted to one series)
+    pub fn expected_files(&self, fond_id: i32, series_id: Option<i32>) -> Result<Vec<File>, Box<dyn Error>> {
+        let series_ids: HashSet<i32> = SeriesRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|s| s.fond_id == fond_id && (series_id.is_none() || series_id == Some(s.id)))?
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+
+        let mut files = FilesRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|f| series_ids.contains(&f.series_id))?;
+        files.sort_by(|a, b| a.file_no.cmp(&b.file_no));
+        Ok(files)
+    }
+
+    /// Compare a batch of scanned codes against the files expected in scope
+    ///
+    /// Item codes count towards their parent file, so scanning any label inside a
+    /// folder marks the folder as present.
+    pub fn stocktake(
+        &self,
+        fond_id: i32,
+        series_id: Option<i32>,
+        scanned_codes: &[String],
+    ) -> Result<StocktakeReport, Box<dyn Error>> {
+        let expected = self.expected_files(fond_id, series_id)?;
+        let expected_ids: HashSet<i32> = expected.iter().map(|f| f.id).collect();
+
+        let mut scanned_file_ids: HashSet<i32> = HashSet::new();
+        let mut misplaced = Vec::new();
+        let mut unknown = Vec::new();
+
+        for code in scanned_codes {
+            let code = Self::normalize(code);
+            if code.is_empty() {
+                continue;
+            }
+            let file_id = match self.resolve(&code)? {
+                Some(ScanTarget::File(file)) => file.id,
+                Some(ScanTarget::Item(item)) => item.file_id,
+                // 全宗号/案卷号标签不对应具体文件，忽略
+                Some(_) => continue,
+                None => {
+                    unknown.push(code);
+                    continue;
+                }
+            };
+            if expected_ids.contains(&file_id) {
+                scanned_file_ids.insert(file_id);
+            } else {
+                misplaced.push(code);
+            }
+        }
+
+        let (found, missing): (Vec<File>, Vec<File>) = expected
+            .iter()
+            .cloned()
+            .partition(|f| scanned_file_ids.contains(&f.id));
+
+        Ok(StocktakeReport {
+            expected,
+            found,
+            missing,
+            misplaced,
+            unknown,
+        })
+    }
+}
@@ -0,0 +1,499 @@
+/// Schema Import Service - Export schemas with their items and merge them into a library
+/// Schemas are matched by `schema_no` and items by their path number (the item numbers
+/// from the top level down, as in series numbers), so series keep their dimensions;
+/// nothing is deleted, local inactive flags are never re-activated and the built-in
+/// `Year` schema is left alone
+use crate::core::GenericRepository;
+use crate::models::schema::{Schema, SchemaJson};
+use crate::models::schema_item::{SchemaItem, SchemaItemJson};
+use crate::models::translation::Translatable;
+use crate::persistence::schema_item_repository::{SchemaItemRepository, PATH_SEPARATOR};
+use crate::persistence::{with_transaction, SchemaRepository, TranslationsRepository};
+use crate::services::classification_import_service::changed_locales;
+use crate::services::csv::{csv_escape, parse_csv};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// The built-in schema whose items are generated from years
+pub const YEAR_SCHEMA_NO: &str = "Year";
+
+/// CSV header; `item_name.<locale>` columns after these carry translated item names
+const CSV_COLUMNS: [&str; 7] = ["schema_no", "schema_name", "sort_order", "item_no", "item_name", "active", "parent_no"];
+
+/// One difference between the library and the imported schemas; items are identified by their path number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    AddedSchema { schema_no: String, name: String },
+    RenamedSchema { schema_no: String, from: String, to: String },
+    Reordered { schema_no: String, from: i32, to: i32 },
+    AddedItem { schema_no: String, item_no: String, name: String },
+    RenamedItem { schema_no: String, item_no: String, from: String, to: String },
+    /// Translated item names added, changed or removed, by locale
+    TranslatedItem { schema_no: String, item_no: String, locales: Vec<String> },
+    /// Marked inactive by the import
+    DeactivatedItem { schema_no: String, item_no: String },
+}
+
+impl SchemaChange {
+    /// One line for the preview dialog
+    pub fn describe(&self) -> String {
+        match self {
+            Self::AddedSchema { schema_no, name } => format!("新增方案 {} {}", schema_no, name),
+            Self::RenamedSchema { schema_no, from, to } => format!("重命名方案 {}: {} → {}", schema_no, from, to),
+            Self::Reordered { schema_no, from, to } => format!("调整方案 {} 的排序: {} → {}", schema_no, from, to),
+            Self::AddedItem { schema_no, item_no, name } => format!("新增条目 {}/{} {}", schema_no, item_no, name),
+            Self::RenamedItem { schema_no, item_no, from, to } => format!("重命名条目 {}/{}: {} → {}", schema_no, item_no, from, to),
+            Self::TranslatedItem { schema_no, item_no, locales } => format!("更新翻译 {}/{}: {}", schema_no, item_no, locales.join(", ")),
+            Self::DeactivatedItem { schema_no, item_no } => format!("停用条目 {}/{}", schema_no, item_no),
+        }
+    }
+}
+
+/// Result of comparing imported schemas with the library
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+    /// Imported schemas and items identical to the library
+    pub unchanged: usize,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let count = |f: fn(&SchemaChange) -> bool| self.changes.iter().filter(|c| f(c)).count();
+        format!(
+            "新增方案 {}，修改方案 {}，新增条目 {}，修改条目 {}，未变 {}",
+            count(|c| matches!(c, SchemaChange::AddedSchema { .. })),
+            count(|c| matches!(c, SchemaChange::RenamedSchema { .. } | SchemaChange::Reordered { .. })),
+            count(|c| matches!(c, SchemaChange::AddedItem { .. })),
+            count(|c| matches!(
+                c,
+                SchemaChange::RenamedItem { .. } | SchemaChange::TranslatedItem { .. } | SchemaChange::DeactivatedItem { .. }
+            )),
+            self.unchanged
+        )
+    }
+
+    /// Full change list, one change per line
+    pub fn to_text(&self) -> String {
+        self.changes.iter().map(SchemaChange::describe).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Library state the diff is computed against
+struct Current {
+    schemas: HashMap<String, Schema>,
+    /// Items by schema id and path number
+    items: HashMap<(i32, String), SchemaItem>,
+    /// Translated names of each schema item id
+    names: HashMap<i32, BTreeMap<String, String>>,
+}
+
+/// Schema import/export service
+pub struct SchemaImportService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl SchemaImportService {
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Read a `.json` or `.csv` file written by `write`
+    pub fn read(path: &Path) -> Result<Vec<SchemaJson>, Box<dyn Error>> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        match extension.as_str() {
+            "json" => Ok(serde_json::from_str(&fs::read_to_string(path)?)?),
+            "csv" => Self::from_csv(&fs::read_to_string(path)?),
+            _ => Err("方案只能从 .json 或 .csv 文件导入".into()),
+        }
+    }
+
+    /// Write schemas as `.json` or `.csv`, chosen by extension
+    pub fn write(path: &Path, schemas: &[SchemaJson]) -> Result<(), Box<dyn Error>> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        match extension.as_str() {
+            "json" => fs::write(path, serde_json::to_string_pretty(schemas)?)?,
+            "csv" => fs::write(path, Self::to_csv(schemas))?,
+            _ => return Err("方案只能导出为 .json 或 .csv".into()),
+        }
+        Ok(())
+    }
+
+    /// All schemas except `Year`, with their items in tree order (parents first) and translated item names;
+    /// `parent_no` is the path number of the parent
+    pub fn export(&self) -> Result<Vec<SchemaJson>, Box<dyn Error>> {
+        let mut schemas = SchemaRepository::new(Rc::clone(&self.conn)).find_all()?;
+        schemas.sort_by_key(|s| (s.sort_order, s.id));
+        let mut items_repo = SchemaItemRepository::new(Rc::clone(&self.conn));
+        let names = TranslationsRepository::new(Rc::clone(&self.conn)).find_all_names(SchemaItem::ENTITY_TYPE)?;
+        let mut exported = Vec::new();
+        for schema in schemas.iter().filter(|s| s.schema_no != YEAR_SCHEMA_NO) {
+            let items = items_repo.find_by_schema_id(schema.id)?;
+            let paths: HashMap<i32, String> = items_repo.find_path_numbered(schema.id)?.into_iter().map(|(path, id)| (id, path)).collect();
+            exported.push(SchemaJson {
+                items: items
+                    .iter()
+                    .map(|item| SchemaItemJson {
+                        parent_no: item.parent_id.and_then(|id| paths.get(&id)).cloned(),
+                        names: names.get(&item.id).cloned().unwrap_or_default(),
+                        ..SchemaItemJson::from_schema_item(item)
+                    })
+                    .collect(),
+                ..SchemaJson::from_schema(schema)
+            });
+        }
+        Ok(exported)
+    }
+
+    /// One row per item (a schema without items gets one row with empty item columns)
+    pub fn to_csv(schemas: &[SchemaJson]) -> String {
+        let locales: BTreeSet<&String> = schemas.iter().flat_map(|s| &s.items).flat_map(|i| i.names.keys()).collect();
+        let mut header: Vec<String> = CSV_COLUMNS.iter().map(|c| c.to_string()).collect();
+        header.extend(locales.iter().map(|locale| format!("item_name.{}", locale)));
+        let mut csv = String::from("\u{feff}");
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+        for schema in schemas {
+            let mut line = |item: Option<&SchemaItemJson>| {
+                let mut values = vec![
+                    csv_escape(&schema.schema_no),
+                    csv_escape(&schema.name),
+                    schema.sort_order.to_string(),
+                    csv_escape(item.map_or("", |i| &i.item_no)),
+                    csv_escape(item.map_or("", |i| &i.item_name)),
+                    item.map_or(String::new(), |i| i.active.to_string()),
+                    csv_escape(item.and_then(|i| i.parent_no.as_deref()).unwrap_or("")),
+                ];
+                values.extend(locales.iter().map(|l| csv_escape(item.and_then(|i| i.names.get(*l)).map_or("", |n| n))));
+                csv.push_str(&values.join(","));
+                csv.push('\n');
+            };
+            if schema.items.is_empty() {
+                line(None);
+            }
+            for item in &schema.items {
+                line(Some(item));
+            }
+        }
+        csv
+    }
+
+    /// Parse the CSV layout of `to_csv`; rows of one schema must agree on its name and sort order
+    pub fn from_csv(text: &str) -> Result<Vec<SchemaJson>, Box<dyn Error>> {
+        let mut rows = parse_csv(text).into_iter();
+        let header = rows.next().ok_or("CSV 文件为空")?;
+        let header: Vec<String> = header.iter().map(|h| h.trim().to_string()).collect();
+        let column = |name: &str| header.iter().position(|h| h == name).ok_or_else(|| format!("CSV 缺少列: {}", name));
+        let [schema_no, schema_name, sort_order, item_no, item_name, active, parent_no] = CSV_COLUMNS.map(column);
+        let (schema_no, schema_name, item_no, item_name) = (schema_no?, schema_name?, item_no?, item_name?);
+        let (sort_order, active, parent_no) = (sort_order.ok(), active.ok(), parent_no.ok());
+        let locales: Vec<(usize, &str)> = header
+            .iter()
+            .enumerate()
+            .filter_map(|(i, h)| h.strip_prefix("item_name.").map(|locale| (i, locale)))
+            .collect();
+
+        let mut schemas: Vec<SchemaJson> = Vec::new();
+        for (line, row) in rows.enumerate() {
+            let line = line + 2;
+            let cell = |i: usize| row.get(i).map(|v| v.trim()).unwrap_or("");
+            if row.iter().all(|v| v.trim().is_empty()) {
+                continue;
+            }
+            let order = match sort_order.map(cell).filter(|v| !v.is_empty()) {
+                Some(v) => v.parse::<i32>().map_err(|_| format!("第 {} 行: 排序不是整数: {}", line, v))?,
+                None => 0,
+            };
+            let index = match schemas.iter().position(|s| s.schema_no == cell(schema_no)) {
+                Some(index) => {
+                    let schema = &schemas[index];
+                    if schema.name != cell(schema_name) || schema.sort_order != order {
+                        return Err(format!("第 {} 行: 方案 {} 的名称或排序与前面的行不一致", line, schema.schema_no).into());
+                    }
+                    index
+                }
+                None => {
+                    schemas.push(SchemaJson {
+                        schema_no: cell(schema_no).to_string(),
+                        name: cell(schema_name).to_string(),
+                        sort_order: order,
+                        items: Vec::new(),
+                    });
+                    schemas.len() - 1
+                }
+            };
+            if !cell(item_no).is_empty() || !cell(item_name).is_empty() {
+                let names = locales
+                    .iter()
+                    .filter(|(i, _)| !cell(*i).is_empty())
+                    .map(|(i, locale)| (locale.to_string(), cell(*i).to_string()))
+                    .collect();
+                let active = match active.map(cell).filter(|v| !v.is_empty()) {
+                    Some(v) => v.parse::<bool>().map_err(|_| format!("第 {} 行: 启用状态应为 true 或 false: {}", line, v))?,
+                    None => true,
+                };
+                schemas[index].items.push(SchemaItemJson {
+                    item_no: cell(item_no).to_string(),
+                    item_name: cell(item_name).to_string(),
+                    parent_no: parent_no.map(cell).filter(|v| !v.is_empty()).map(str::to_string),
+                    active,
+                    names,
+                });
+            }
+        }
+        Ok(schemas)
+    }
+
+    /// Compare imported schemas with the library without writing anything
+    pub fn preview(&self, schemas: &[SchemaJson]) -> Result<SchemaDiff, Box<dyn Error>> {
+        validate(schemas)?;
+        diff(schemas, &self.current()?)
+    }
+
+    /// Merge imported schemas into the library in one transaction
+    pub fn apply(&self, schemas: &[SchemaJson]) -> Result<SchemaDiff, Box<dyn Error>> {
+        validate(schemas)?;
+        let current = self.current()?;
+        let diff = diff(schemas, &current)?;
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+        with_transaction(&self.conn, || self.write_merge(schemas, &current))?;
+        Ok(diff)
+    }
+
+    fn current(&self) -> Result<Current, Box<dyn Error>> {
+        let mut schemas = SchemaRepository::new(Rc::clone(&self.conn)).find_all()?;
+        schemas.sort_by_key(|s| std::cmp::Reverse(s.id));
+        let mut items_repo = SchemaItemRepository::new(Rc::clone(&self.conn));
+        let rows: HashMap<i32, SchemaItem> = items_repo.find_all()?.into_iter().map(|i| (i.id, i)).collect();
+        // 重复的代码和编号路径以最早的记录为准
+        let mut items = HashMap::new();
+        for schema in &schemas {
+            let mut numbered = items_repo.find_path_numbered(schema.id)?;
+            numbered.sort_by_key(|(_, id)| std::cmp::Reverse(*id));
+            for (path, id) in numbered {
+                if let Some(row) = rows.get(&id) {
+                    items.insert((schema.id, path), row.clone());
+                }
+            }
+        }
+        let schemas = schemas.into_iter().map(|s| (s.schema_no.clone(), s)).collect();
+        let names = TranslationsRepository::new(Rc::clone(&self.conn)).find_all_names(SchemaItem::ENTITY_TYPE)?;
+        Ok(Current { schemas, items, names })
+    }
+
+    fn write_merge(&self, schemas: &[SchemaJson], current: &Current) -> Result<(), Box<dyn Error>> {
+        let mut schemas_repo = SchemaRepository::new(Rc::clone(&self.conn));
+        let mut items_repo = SchemaItemRepository::new(Rc::clone(&self.conn));
+        let mut translations = TranslationsRepository::new(Rc::clone(&self.conn));
+        for imported in schemas.iter().filter(|s| s.schema_no != YEAR_SCHEMA_NO) {
+            let schema_id = match current.schemas.get(&imported.schema_no) {
+                Some(row) => {
+                    if (&row.name, row.sort_order) != (&imported.name, imported.sort_order) {
+                        schemas_repo.update(&Schema { name: imported.name.clone(), sort_order: imported.sort_order, ..row.clone() })?;
+                    }
+                    row.id
+                }
+                None => schemas_repo.create(Schema {
+                    schema_no: imported.schema_no.clone(),
+                    name: imported.name.clone(),
+                    sort_order: imported.sort_order,
+                    ..Default::default()
+                })?,
+            };
+            // 新条目按文件中的顺序排在同级条目末尾；上级条目先于下级写入，新条目直接挂到上级条目下
+            let paths = resolve_paths(imported, Some(schema_id), current)?;
+            let mut in_file_order = paths.clone();
+            in_file_order.sort_by_key(|(index, _)| *index);
+            let mut next_sort_order = items_repo.next_sort_order(schema_id)?;
+            let mut sort_orders: HashMap<usize, i32> = HashMap::new();
+            for (index, path) in in_file_order {
+                if !current.items.contains_key(&(schema_id, path)) {
+                    sort_orders.insert(index, next_sort_order);
+                    next_sort_order += 1;
+                }
+            }
+            let mut item_ids: HashMap<String, i32> = HashMap::new();
+            for (index, path) in paths {
+                let item = &imported.items[index];
+                let item_id = match current.items.get(&(schema_id, path.clone())) {
+                    Some(row) => {
+                        let active = row.active && item.active;
+                        if (&row.item_name, row.active) != (&item.item_name, active) {
+                            items_repo.update(&SchemaItem { item_name: item.item_name.clone(), active, ..row.clone() })?;
+                        }
+                        row.id
+                    }
+                    None => {
+                        let parent_id = match &item.parent_no {
+                            Some(parent_no) => Some(match item_ids.get(parent_no) {
+                                Some(parent_id) => *parent_id,
+                                None => current.items.get(&(schema_id, parent_no.clone())).map(|i| i.id).ok_or_else(|| {
+                                    format!("方案 {} 中条目的上级条目 {} 不存在", imported.schema_no, parent_no)
+                                })?,
+                            }),
+                            None => None,
+                        };
+                        items_repo.create(SchemaItem {
+                            schema_id,
+                            item_no: item.item_no.clone(),
+                            item_name: item.item_name.clone(),
+                            parent_id,
+                            active: item.active,
+                            sort_order: sort_orders.get(&index).copied().unwrap_or_default(),
+                            ..Default::default()
+                        })?
+                    }
+                };
+                item_ids.insert(path, item_id);
+                // 文件中没有 names 的条目保留现有翻译
+                if !item.names.is_empty() {
+                    translations.set_names(SchemaItem::ENTITY_TYPE, item_id, &item.names)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reject empty or duplicate numbers and names, and items for the built-in `Year` schema
+fn validate(schemas: &[SchemaJson]) -> Result<(), Box<dyn Error>> {
+    let mut seen = HashSet::new();
+    for schema in schemas {
+        if schema.schema_no.trim().is_empty() || schema.name.trim().is_empty() {
+            return Err(format!("导入文件中有方案缺少编号或名称: {} {}", schema.schema_no, schema.name).into());
+        }
+        if !seen.insert(&schema.schema_no) {
+            return Err(format!("导入文件中方案编号重复: {}", schema.schema_no).into());
+        }
+        if schema.schema_no == YEAR_SCHEMA_NO && !schema.items.is_empty() {
+            return Err("内置的 Year 方案按年份自动生成条目，不能导入条目".into());
+        }
+        for item in &schema.items {
+            if item.item_no.trim().is_empty() || item.item_name.trim().is_empty() {
+                return Err(format!("方案 {} 中有条目缺少编号或名称: {} {}", schema.schema_no, item.item_no, item.item_name).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Path numbers of the imported items of a schema as (index in the file, path number),
+/// parents before their children
+///
+/// `parent_no` is the path number of an item in the file or in the library. Two items
+/// with the same path, a missing parent or a cycle of parents is an error.
+fn resolve_paths(schema: &SchemaJson, schema_id: Option<i32>, current: &Current) -> Result<Vec<(usize, String)>, Box<dyn Error>> {
+    let in_library = |path: &str| schema_id.is_some_and(|id| current.items.contains_key(&(id, path.to_string())));
+    let mut resolved: Vec<(usize, String)> = Vec::new();
+    let mut paths: HashSet<String> = HashSet::new();
+    let mut pending: Vec<usize> = (0..schema.items.len()).collect();
+    while !pending.is_empty() {
+        let before = pending.len();
+        let mut waiting = Vec::new();
+        for index in pending {
+            let item = &schema.items[index];
+            let path = match item.parent_no.as_deref() {
+                None => item.item_no.clone(),
+                Some(parent) if paths.contains(parent) || in_library(parent) => format!("{}{}{}", parent, PATH_SEPARATOR, item.item_no),
+                Some(_) => {
+                    waiting.push(index);
+                    continue;
+                }
+            };
+            if !paths.insert(path.clone()) {
+                return Err(format!("方案 {} 中条目编号重复: {}", schema.schema_no, path).into());
+            }
+            resolved.push((index, path));
+        }
+        if waiting.len() == before {
+            let item = &schema.items[waiting[0]];
+            return Err(format!(
+                "方案 {} 中条目 {} 的上级条目 {} 不存在或上级关系存在循环",
+                schema.schema_no,
+                item.item_no,
+                item.parent_no.as_deref().unwrap_or_default()
+            )
+            .into());
+        }
+        pending = waiting;
+    }
+    Ok(resolved)
+}
+
+fn diff(schemas: &[SchemaJson], current: &Current) -> Result<SchemaDiff, Box<dyn Error>> {
+    let mut result = SchemaDiff::default();
+    for schema in schemas.iter().filter(|s| s.schema_no != YEAR_SCHEMA_NO) {
+        let row = current.schemas.get(&schema.schema_no);
+        let mut paths = resolve_paths(schema, row.map(|r| r.id), current)?;
+        paths.sort_by_key(|(index, _)| *index);
+        let Some(row) = row else {
+            result.changes.push(SchemaChange::AddedSchema { schema_no: schema.schema_no.clone(), name: schema.name.clone() });
+            for (index, path) in paths {
+                result.changes.push(SchemaChange::AddedItem {
+                    schema_no: schema.schema_no.clone(),
+                    item_no: path,
+                    name: schema.items[index].item_name.clone(),
+                });
+            }
+            continue;
+        };
+        let before = result.changes.len();
+        if row.name != schema.name {
+            result.changes.push(SchemaChange::RenamedSchema { schema_no: schema.schema_no.clone(), from: row.name.clone(), to: schema.name.clone() });
+        }
+        if row.sort_order != schema.sort_order {
+            result.changes.push(SchemaChange::Reordered { schema_no: schema.schema_no.clone(), from: row.sort_order, to: schema.sort_order });
+        }
+        if result.changes.len() == before {
+            result.unchanged += 1;
+        }
+        for (index, path) in paths {
+            let item = &schema.items[index];
+            let Some(existing) = current.items.get(&(row.id, path.clone())) else {
+                result.changes.push(SchemaChange::AddedItem {
+                    schema_no: schema.schema_no.clone(),
+                    item_no: path.clone(),
+                    name: item.item_name.clone(),
+                });
+                continue;
+            };
+            let before = result.changes.len();
+            if existing.item_name != item.item_name {
+                result.changes.push(SchemaChange::RenamedItem {
+                    schema_no: schema.schema_no.clone(),
+                    item_no: path.clone(),
+                    from: existing.item_name.clone(),
+                    to: item.item_name.clone(),
+                });
+            }
+            if existing.active && !item.active {
+                result.changes.push(SchemaChange::DeactivatedItem { schema_no: schema.schema_no.clone(), item_no: path.clone() });
+            }
+            if !item.names.is_empty() {
+                let locales = changed_locales(current.names.get(&existing.id), &item.names);
+                if !locales.is_empty() {
+                    result.changes.push(SchemaChange::TranslatedItem {
+                        schema_no: schema.schema_no.clone(),
+                        item_no: path.clone(),
+                        locales,
+                    });
+                }
+            }
+            if result.changes.len() == before {
+                result.unchanged += 1;
+            }
+        }
+    }
+    Ok(result)
+}
@@ -1,9 +1,16 @@
 /// Settings Service - Business logic for settings management
 /// Handles archive libraries, language preferences, and configuration
-use crate::models::app_settings::ArchiveLibrary;
+use crate::models::app_settings::{ArchiveLibrary, BackupSettings, IngestMode};
 use crate::persistence::config_repository::FileConfigRepository;
+use crate::services::classification_import_service::ClassificationImportService;
+use crate::services::classification_standards::{ClassificationStandard, LibraryStandard};
+use crate::services::fixity_service::ChecksumAlgorithm;
+use crate::services::library_template_service::{LibraryTemplate, LibraryTemplateService};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 
 /// Settings service for managing application preferences
@@ -39,11 +46,54 @@ impl SettingsService {
         Ok(settings.archive_libraries)
     }
 
+    /// Classification standards a new library can start from
+    pub fn list_classification_standards(&self) -> &'static [ClassificationStandard] {
+        ClassificationStandard::all()
+    }
+
     /// Add a new archive library
+    ///
+    /// When the library database is created here and `standard` is given, the
+    /// standard's classifications are installed into it
     pub fn add_archive_library(
         &self,
         name: String,
         path: String,
+        standard: Option<&LibraryStandard>,
+    ) -> Result<(), Box<dyn Error>> {
+        // Resolve the classification standard before touching the disk
+        let classifications = standard.map(LibraryStandard::classifications).transpose()?;
+        let log_path = path.clone();
+        self.create_archive_library(name, path, |conn| {
+            if let Some(classifications) = classifications {
+                let diff = ClassificationImportService::new(conn).apply(&classifications)?;
+                log::info!("Installed classification standard into {}: {}", log_path, diff.summary());
+            }
+            Ok(())
+        })
+    }
+
+    /// Add a new archive library configured from a saved template
+    ///
+    /// The template's settings replace the current ingest mode, fixity interval, checksum algorithm and catalogue columns
+    pub fn add_archive_library_from_template(&self, name: String, path: String, template_name: &str) -> Result<(), Box<dyn Error>> {
+        let template = self.template_service().load(template_name)?;
+        if PathBuf::from(&path).join(".fondspod.db").exists() {
+            return Err("Library already exists at this path; templates only apply to new libraries".into());
+        }
+        self.create_archive_library(name, path, |conn| template.install(conn))?;
+        let mut settings = self.config_repo.load()?;
+        template.settings.apply_to(&mut settings);
+        self.config_repo.save(&settings)?;
+        Ok(())
+    }
+
+    /// Validate, create the directory and database (running `install` on a new database) and register the library
+    fn create_archive_library(
+        &self,
+        name: String,
+        path: String,
+        install: impl FnOnce(Rc<RefCell<SqliteConnection>>) -> Result<(), Box<dyn Error>>,
     ) -> Result<(), Box<dyn Error>> {
         // Validate inputs
         if name.trim().is_empty() {
@@ -67,12 +117,18 @@ impl SettingsService {
         }
 
         // Ensure directory exists
+        let created_dir = !path_buf.exists();
         fs::create_dir_all(&path)?;
-        
+
         // Initialize database if needed
         let db_path = std::path::PathBuf::from(&path).join(".fondspod.db");
         if !db_path.exists() {
-            crate::persistence::establish_connection(&db_path)?;
+            let installed = crate::persistence::establish_connection(&db_path).and_then(install);
+            if let Err(e) = installed {
+                // 初始化失败时删除半成品，避免下次被当作已有档案库打开
+                Self::discard_library(&path_buf, &db_path, created_dir);
+                return Err(e);
+            }
         }
         
         // Save to configuration
@@ -83,6 +139,43 @@ impl SettingsService {
         Ok(())
     }
 
+    /// Remove what a failed library creation left on disk: the whole directory
+    /// when it was created for the library, otherwise only the database files
+    fn discard_library(path: &Path, db_path: &Path, created_dir: bool) {
+        if created_dir {
+            if let Err(e) = fs::remove_dir_all(path) {
+                log::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+            return;
+        }
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let file = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+            if let Err(e) = fs::remove_file(&file) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove {}: {}", file.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Saved library templates
+    pub fn template_service(&self) -> LibraryTemplateService {
+        LibraryTemplateService::new(self.config_repo.templates_dir())
+    }
+
+    /// Save the configuration of the library at `index` as a template, replacing one with the same name
+    pub fn save_library_as_template(&self, index: usize, template_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let libraries = self.list_archive_libraries()?;
+        let library = libraries.get(index).ok_or("Archive index out of bounds")?;
+        let db_path = PathBuf::from(&library.path).join(".fondspod.db");
+        if !db_path.exists() {
+            return Err(format!("Library database not found: {}", db_path.display()).into());
+        }
+        let conn = crate::persistence::establish_connection(&db_path)?;
+        let template = LibraryTemplate::capture(conn, template_name, &library.name, &self.config_repo.load()?)?;
+        self.template_service().save(&template)
+    }
+
     /// Remove an archive library
     pub fn remove_archive_library(&self, index: usize) -> Result<(), Box<dyn Error>> {
         let libraries = self.list_archive_libraries()?;
@@ -149,7 +242,7 @@ impl SettingsService {
         for new_lib in libraries {
             if !current_libraries.iter().any(|l| l.path == new_lib.path) {
                 // Use the existing add_archive_library method for validation and addition
-                let _ = self.add_archive_library(new_lib.name, new_lib.path);
+                let _ = self.add_archive_library(new_lib.name, new_lib.path, None);
             }
         }
 
@@ -167,6 +260,71 @@ impl SettingsService {
         self.config_repo.save(&settings)?;
         Ok(())
     }
+
+    /// Get the scheduled fixity check interval in days (0 = disabled)
+    pub fn get_fixity_interval_days(&self) -> Result<u32, Box<dyn Error>> {
+        let settings = self.config_repo.load()?;
+        Ok(settings.fixity_interval_days)
+    }
+
+    /// Get how new item payloads are stored
+    pub fn get_ingest_mode(&self) -> Result<IngestMode, Box<dyn Error>> {
+        let settings = self.config_repo.load()?;
+        Ok(settings.ingest_mode)
+    }
+
+    /// Get the checksum algorithm recorded for new item payloads; unknown names fall back to SHA-256
+    pub fn get_checksum_algorithm(&self) -> Result<ChecksumAlgorithm, Box<dyn Error>> {
+        let settings = self.config_repo.load()?;
+        Ok(ChecksumAlgorithm::parse(&settings.checksum_algorithm).unwrap_or(ChecksumAlgorithm::Sha256))
+    }
+
+    /// Get the scheduled backup settings
+    pub fn get_backup_settings(&self) -> Result<BackupSettings, Box<dyn Error>> {
+        let settings = self.config_repo.load()?;
+        Ok(settings.backup)
+    }
+
+    /// Folder scheduled backups are written to
+    pub fn get_backup_directory(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let backup = self.get_backup_settings()?;
+        Ok(match backup.directory {
+            Some(directory) if !directory.trim().is_empty() => PathBuf::from(directory),
+            _ => dirs::data_local_dir()
+                .map(|dir| dir.join("FondsPod").join("backups"))
+                .unwrap_or_else(|| PathBuf::from("backups")),
+        })
+    }
+
+    /// Set how new item payloads are stored
+    pub fn set_ingest_mode(&self, mode: IngestMode) -> Result<(), Box<dyn Error>> {
+        let mut settings = self.config_repo.load()?;
+        settings.ingest_mode = mode;
+        self.config_repo.save(&settings)?;
+        Ok(())
+    }
+
+    /// Set the checksum algorithm recorded for new item payloads
+    pub fn set_checksum_algorithm(&self, algorithm: ChecksumAlgorithm) -> Result<(), Box<dyn Error>> {
+        let mut settings = self.config_repo.load()?;
+        settings.checksum_algorithm = algorithm.as_str().to_string();
+        self.config_repo.save(&settings)?;
+        Ok(())
+    }
+
+    /// Get the configured catalogue export columns of a level, None when the defaults apply
+    pub fn get_catalogue_columns(&self, level: &str) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+        let settings = self.config_repo.load()?;
+        Ok(settings.catalogue_columns.get(level).cloned())
+    }
+
+    /// Set the catalogue export columns of a level
+    pub fn set_catalogue_columns(&self, level: &str, columns: Vec<String>) -> Result<(), Box<dyn Error>> {
+        let mut settings = self.config_repo.load()?;
+        settings.catalogue_columns.insert(level.to_string(), columns);
+        self.config_repo.save(&settings)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -176,14 +334,14 @@ mod tests {
     #[test]
     fn test_validate_archive_name() {
         let service = SettingsService::new();
-        let result = service.add_archive_library("".to_string(), "/tmp".to_string());
+        let result = service.add_archive_library("".to_string(), "/tmp".to_string(), None);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_validate_archive_path() {
         let service = SettingsService::new();
-        let result = service.add_archive_library("Test".to_string(), "".to_string());
+        let result = service.add_archive_library("Test".to_string(), "".to_string(), None);
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,468 @@
+/// Usage Service - Find where schemas, schema items and classifications are still used
+/// Deleting a referenced record would break the numbering of existing series or
+/// the generation of new ones, so deletes go through these checks first.
+/// Catalogue deletes (fonds, series, files, items) also go through here: their
+/// rows below are deleted with them, and records with loans are kept.
+use crate::core::{GenericRepository, HierarchicalRepository};
+use crate::models::fond::Fond;
+use crate::models::fond_classification::FondClassification;
+use crate::models::fond_schema::FondSchema;
+use crate::models::schema_item::SchemaItem;
+use crate::persistence::schema_item_repository::SchemaItemRepository;
+use crate::persistence::{
+    sidecars, with_transaction, FilesRepository, FondClassificationsRepository, FondSchemasRepository, FondsRepository,
+    ItemChecksumsRepository, ItemsRepository, LoansRepository, SchemaRepository, SeriesRepository, SeriesSchemaItemsRepository,
+    TranslationsRepository,
+};
+use diesel::SqliteConnection;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::rc::Rc;
+
+/// Fonds and series that reference a record
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Fond numbers, sorted
+    pub fonds: Vec<String>,
+    /// Series numbers, sorted
+    pub series: Vec<String>,
+}
+
+impl Usage {
+    pub fn is_empty(&self) -> bool {
+        self.fonds.is_empty() && self.series.is_empty()
+    }
+
+    /// One line for dialogs, listing at most a few numbers of each kind
+    pub fn describe(&self) -> String {
+        const SHOWN: usize = 5;
+        fn list(kind: &str, numbers: &[String]) -> Option<String> {
+            if numbers.is_empty() {
+                return None;
+            }
+            let shown = numbers.iter().take(SHOWN).cloned().collect::<Vec<_>>().join(", ");
+            Some(if numbers.len() > SHOWN {
+                format!("{} {} 等 {} 个", kind, shown, numbers.len())
+            } else {
+                format!("{} {}", kind, shown)
+            })
+        }
+        [list("全宗", &self.fonds), list("案卷", &self.series)].into_iter().flatten().collect::<Vec<_>>().join("；")
+    }
+
+    fn from_sets(fonds: BTreeSet<String>, series: BTreeSet<String>) -> Self {
+        Self { fonds: fonds.into_iter().collect(), series: series.into_iter().collect() }
+    }
+}
+
+/// Catalogue rows deleted together with a fond, series, file or item
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contents {
+    pub series: Vec<i32>,
+    pub files: Vec<i32>,
+    pub items: Vec<i32>,
+    /// Loans of the files and items, returned ones included
+    pub loans: usize,
+}
+
+impl Contents {
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty() && self.files.is_empty() && self.items.is_empty()
+    }
+
+    /// One line for dialogs, e.g. "2 个系列、5 个案卷、40 个文件"
+    pub fn describe(&self) -> String {
+        [(self.series.len(), "系列"), (self.files.len(), "案卷"), (self.items.len(), "文件")]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, kind)| format!("{} 个{}", count, kind))
+            .collect::<Vec<_>>()
+            .join("、")
+    }
+}
+
+/// Usage checks and checked deletes
+pub struct UsageService {
+    conn: Rc<RefCell<SqliteConnection>>,
+}
+
+impl UsageService {
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self { conn }
+    }
+
+    fn fonds_by_id(&self) -> Result<HashMap<i32, Fond>, Box<dyn Error>> {
+        Ok(FondsRepository::new(Rc::clone(&self.conn)).find_all()?.into_iter().map(|f| (f.id, f)).collect())
+    }
+
+    /// Dimensions of every fond in series-number order
+    fn dimensions_by_fond(&self) -> Result<HashMap<i32, Vec<FondSchema>>, Box<dyn Error>> {
+        let mut dimensions: HashMap<i32, Vec<FondSchema>> = HashMap::new();
+        for fond_schema in FondSchemasRepository::new(Rc::clone(&self.conn)).find_all()? {
+            dimensions.entry(fond_schema.fond_id).or_default().push(fond_schema);
+        }
+        for fond_schemas in dimensions.values_mut() {
+            fond_schemas.sort_by_key(|fs| (fs.sort_order, fs.id));
+        }
+        Ok(dimensions)
+    }
+
+    /// Fonds that use the schema as a dimension, and all their series
+    pub fn schema_usage(&self, schema_id: i32) -> Result<Usage, Box<dyn Error>> {
+        let fonds = self.fonds_by_id()?;
+        let fond_ids: BTreeSet<i32> = FondSchemasRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|fs| fs.schema_id == schema_id)?
+            .into_iter()
+            .map(|fs| fs.fond_id)
+            .collect();
+        let series = SeriesRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|s| fond_ids.contains(&s.fond_id))?
+            .into_iter()
+            .map(|s| s.series_no)
+            .collect();
+        let fond_nos = fond_ids.iter().filter_map(|id| fonds.get(id)).map(|f| f.fond_no.clone()).collect();
+        Ok(Usage::from_sets(fond_nos, series))
+    }
+
+    /// Fonds that pin the item or one of its descendants as their dimension value, and series
+    /// generated from the item or one of its descendants
+    ///
+    /// Series record the schema items they were generated from. Series without that record
+    /// (generated before it existed, imported or rebuilt) are matched against the values
+    /// their fond's dimensions can take; a series number that matches no combination is
+    /// counted as using the item when its fond has the item's schema as a dimension.
+    pub fn schema_item_usage(&self, item_id: i32) -> Result<Usage, Box<dyn Error>> {
+        let mut items_repo = SchemaItemRepository::new(Rc::clone(&self.conn));
+        let item = items_repo.find_by_id(item_id)?.ok_or("条目不存在")?;
+        let subtree: HashSet<i32> =
+            items_repo.find_descendants(item_id)?.into_iter().map(|i| i.id).chain(std::iter::once(item_id)).collect();
+        let fonds = self.fonds_by_id()?;
+        let dimensions = self.dimensions_by_fond()?;
+        let mut links: HashMap<i32, Vec<i32>> = HashMap::new();
+        for link in SeriesSchemaItemsRepository::new(Rc::clone(&self.conn)).find_all()? {
+            links.entry(link.series_id).or_default().push(link.schema_item_id);
+        }
+        let year_ids: HashSet<i32> = SchemaRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|s| s.schema_no == "Year")?
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        let mut values: HashMap<i32, Vec<(String, i32)>> = HashMap::new();
+        for fs in dimensions.values().flatten().filter(|fs| !year_ids.contains(&fs.schema_id)) {
+            if let Entry::Vacant(e) = values.entry(fs.schema_id) {
+                e.insert(items_repo.find_path_numbered(fs.schema_id)?);
+            }
+        }
+
+        let mut fond_nos = BTreeSet::new();
+        let mut series_nos = BTreeSet::new();
+        for series in SeriesRepository::new(Rc::clone(&self.conn)).find_all()? {
+            let Some(fond) = fonds.get(&series.fond_id) else { continue };
+            let used = match links.get(&series.id) {
+                Some(item_ids) => item_ids.iter().any(|id| subtree.contains(id)),
+                None => {
+                    let fond_schemas = dimensions.get(&series.fond_id).map(Vec::as_slice).unwrap_or_default();
+                    let dimension_values: Vec<Option<&[(String, i32)]>> = fond_schemas
+                        .iter()
+                        .map(|fs| {
+                            if year_ids.contains(&fs.schema_id) {
+                                None
+                            } else {
+                                Some(values.get(&fs.schema_id).map(Vec::as_slice).unwrap_or_default())
+                            }
+                        })
+                        .collect();
+                    let rest = series.series_no.strip_prefix(&fond.fond_no).and_then(|rest| rest.strip_prefix('-'));
+                    let mut parses = Vec::new();
+                    if let Some(rest) = rest {
+                        parse_series_values(rest, &dimension_values, &mut Vec::new(), &mut parses);
+                    }
+                    if parses.is_empty() {
+                        fond_schemas.iter().any(|fs| fs.schema_id == item.schema_id)
+                    } else {
+                        parses.iter().flatten().flatten().any(|id| subtree.contains(id))
+                    }
+                }
+            };
+            if used {
+                fond_nos.insert(fond.fond_no.clone());
+                series_nos.insert(series.series_no);
+            }
+        }
+        for fond_schemas in dimensions.values() {
+            for fond_schema in fond_schemas.iter().filter(|fs| fs.schema_item_id.is_some_and(|id| subtree.contains(&id))) {
+                if let Some(fond) = fonds.get(&fond_schema.fond_id) {
+                    fond_nos.insert(fond.fond_no.clone());
+                }
+            }
+        }
+        Ok(Usage::from_sets(fond_nos, series_nos))
+    }
+
+    /// Fonds filed under the classification or any of its descendants
+    pub fn classification_usage(&self, classification_id: i32) -> Result<Usage, Box<dyn Error>> {
+        let codes = self.subtree_codes(classification_id)?;
+        let fond_nos = FondsRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|f| codes.contains(&f.fond_classification_code))?
+            .into_iter()
+            .map(|f| f.fond_no)
+            .collect();
+        Ok(Usage::from_sets(fond_nos, BTreeSet::new()))
+    }
+
+    fn subtree_codes(&self, classification_id: i32) -> Result<BTreeSet<String>, Box<dyn Error>> {
+        let mut repo = FondClassificationsRepository::new(Rc::clone(&self.conn));
+        let root = repo.find_by_id(classification_id)?.ok_or("分类不存在")?;
+        let mut codes: BTreeSet<String> = repo.find_descendants(classification_id)?.into_iter().map(|c| c.code).collect();
+        codes.insert(root.code);
+        Ok(codes)
+    }
+
+    /// Delete an unused schema together with its items and their translations,
+    /// returning the number of items deleted
+    pub fn delete_schema(&self, schema_id: i32) -> Result<usize, Box<dyn Error>> {
+        let usage = self.schema_usage(schema_id)?;
+        if !usage.is_empty() {
+            return Err(format!("方案仍在使用，不能删除: {}", usage.describe()).into());
+        }
+        with_transaction(&self.conn, || {
+            let mut items_repo = SchemaItemRepository::new(Rc::clone(&self.conn));
+            let items = items_repo.find_by_schema_id(schema_id)?;
+            // 下级条目先于上级条目删除
+            for item in items.iter().rev() {
+                items_repo.delete(item.id)?;
+            }
+            SchemaRepository::new(Rc::clone(&self.conn)).delete(schema_id)?;
+            TranslationsRepository::new(Rc::clone(&self.conn)).delete_orphans::<SchemaItem>()?;
+            Ok(items.len())
+        })
+    }
+
+    /// Delete a schema item and its descendants that no fond or series uses,
+    /// returning the number of items deleted
+    pub fn delete_schema_item(&self, item_id: i32) -> Result<usize, Box<dyn Error>> {
+        let usage = self.schema_item_usage(item_id)?;
+        if !usage.is_empty() {
+            return Err(format!("条目仍在使用，不能删除（可改为停用）: {}", usage.describe()).into());
+        }
+        with_transaction(&self.conn, || {
+            let count = SchemaItemRepository::new(Rc::clone(&self.conn)).delete_subtree(item_id)?;
+            TranslationsRepository::new(Rc::clone(&self.conn)).delete_orphans::<SchemaItem>()?;
+            Ok(count)
+        })
+    }
+
+    /// Delete a classification and its descendants that no fond is filed under,
+    /// returning the number of classifications deleted
+    pub fn delete_classification(&self, classification_id: i32) -> Result<usize, Box<dyn Error>> {
+        let usage = self.classification_usage(classification_id)?;
+        if !usage.is_empty() {
+            return Err(format!("分类仍在使用，不能删除: {}", usage.describe()).into());
+        }
+        with_transaction(&self.conn, || {
+            let count = FondClassificationsRepository::new(Rc::clone(&self.conn)).delete_subtree(classification_id)?;
+            TranslationsRepository::new(Rc::clone(&self.conn)).delete_orphans::<FondClassification>()?;
+            Ok(count)
+        })
+    }
+
+    /// File the fonds of a classification subtree under `target_id`, then delete the subtree;
+    /// returns the number of fonds moved
+    pub fn remap_and_delete_classification(&self, classification_id: i32, target_id: i32) -> Result<usize, Box<dyn Error>> {
+        let codes = self.subtree_codes(classification_id)?;
+        let target = FondClassificationsRepository::new(Rc::clone(&self.conn)).find_by_id(target_id)?.ok_or("目标分类不存在")?;
+        if codes.contains(&target.code) {
+            return Err("不能改为被删除的分类或其下级分类".into());
+        }
+        let mut fonds_repo = FondsRepository::new(Rc::clone(&self.conn));
+        let fonds = fonds_repo.find_by_predicate(|f| codes.contains(&f.fond_classification_code))?;
+        let result = with_transaction(&self.conn, || {
+            for fond in &fonds {
+                fonds_repo.update(&Fond { fond_classification_code: target.code.clone(), ..fond.clone() })?;
+            }
+            FondClassificationsRepository::new(Rc::clone(&self.conn)).delete_subtree(classification_id)?;
+            TranslationsRepository::new(Rc::clone(&self.conn)).delete_orphans::<FondClassification>()?;
+            Ok(fonds.len())
+        });
+        match &result {
+            Ok(count) => log::info!("Moved {} fonds to classification {} and deleted classification {}", count, target.code, classification_id),
+            Err(_) => {
+                for fond in &fonds {
+                    sidecars::after_write(&mut self.conn.borrow_mut(), "fonds", fond.id, None);
+                }
+            }
+        }
+        result
+    }
+
+    /// Series, files and items of a fond
+    pub fn fond_contents(&self, fond_id: i32) -> Result<Contents, Box<dyn Error>> {
+        let series = SeriesRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|s| s.fond_id == fond_id)?
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        self.contents(series, Vec::new(), Vec::new())
+    }
+
+    /// Files and items of a series
+    pub fn series_contents(&self, series_id: i32) -> Result<Contents, Box<dyn Error>> {
+        self.contents(vec![series_id], Vec::new(), Vec::new())
+            .map(|contents| Contents { series: Vec::new(), ..contents })
+    }
+
+    /// Items of a file
+    pub fn file_contents(&self, file_id: i32) -> Result<Contents, Box<dyn Error>> {
+        self.contents(Vec::new(), vec![file_id], Vec::new())
+            .map(|contents| Contents { files: Vec::new(), ..contents })
+    }
+
+    /// Rows below the given series and files, together with the loans of every file and item
+    fn contents(&self, series: Vec<i32>, files: Vec<i32>, items: Vec<i32>) -> Result<Contents, Box<dyn Error>> {
+        let series_ids: HashSet<i32> = series.iter().copied().collect();
+        let mut file_ids: HashSet<i32> = files.iter().copied().collect();
+        let mut files = files;
+        for file in FilesRepository::new(Rc::clone(&self.conn)).find_by_predicate(|f| series_ids.contains(&f.series_id))? {
+            if file_ids.insert(file.id) {
+                files.push(file.id);
+            }
+        }
+        let mut item_ids: HashSet<i32> = items.iter().copied().collect();
+        let mut items = items;
+        for item in ItemsRepository::new(Rc::clone(&self.conn)).find_by_predicate(|i| file_ids.contains(&i.file_id))? {
+            if item_ids.insert(item.id) {
+                items.push(item.id);
+            }
+        }
+        let loans = LoansRepository::new(Rc::clone(&self.conn))
+            .find_by_predicate(|l| l.file_id.is_some_and(|id| file_ids.contains(&id)) || l.item_id.is_some_and(|id| item_ids.contains(&id)))?
+            .len();
+        Ok(Contents { series, files, items, loans })
+    }
+
+    /// Delete a fond with its schema dimensions, series, files and items;
+    /// returns the rows deleted below the fond
+    pub fn delete_fond(&self, fond_id: i32) -> Result<Contents, Box<dyn Error>> {
+        let contents = self.fond_contents(fond_id)?;
+        self.delete_catalogue(&contents, "全宗", || {
+            let mut fond_schemas_repo = FondSchemasRepository::new(Rc::clone(&self.conn));
+            for fond_schema in fond_schemas_repo.find_by_predicate(|fs| fs.fond_id == fond_id)? {
+                fond_schemas_repo.delete(fond_schema.id)?;
+            }
+            FondsRepository::new(Rc::clone(&self.conn)).delete(fond_id)
+        })?;
+        Ok(contents)
+    }
+
+    /// Delete a series with its files and items; returns the rows deleted below the series
+    pub fn delete_series(&self, series_id: i32) -> Result<Contents, Box<dyn Error>> {
+        let contents = self.series_contents(series_id)?;
+        self.delete_catalogue(&contents, "系列", || SeriesRepository::new(Rc::clone(&self.conn)).delete(series_id))?;
+        Ok(contents)
+    }
+
+    /// Delete a file with its items; returns the rows deleted below the file
+    pub fn delete_file(&self, file_id: i32) -> Result<Contents, Box<dyn Error>> {
+        let contents = self.file_contents(file_id)?;
+        self.delete_catalogue(&contents, "案卷", || FilesRepository::new(Rc::clone(&self.conn)).delete(file_id))?;
+        Ok(contents)
+    }
+
+    /// Delete an item with its checksum manifest
+    pub fn delete_item(&self, item_id: i32) -> Result<(), Box<dyn Error>> {
+        let contents = self.contents(Vec::new(), Vec::new(), vec![item_id])?;
+        self.delete_catalogue(&contents, "文件", || Ok(()))
+    }
+
+    /// Delete `contents` bottom-up and then the record itself in one transaction
+    ///
+    /// Loans are the reading room's history, so records with loans are never deleted.
+    /// Payload directories stay on disk; only the catalogue rows and their sidecars go.
+    fn delete_catalogue(&self, contents: &Contents, kind: &str, delete_record: impl FnOnce() -> Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+        if contents.loans > 0 {
+            return Err(format!("{}有 {} 条借阅记录，不能删除", kind, contents.loans).into());
+        }
+        let result = with_transaction(&self.conn, || {
+            let mut checksums = ItemChecksumsRepository::new(Rc::clone(&self.conn));
+            let mut items_repo = ItemsRepository::new(Rc::clone(&self.conn));
+            for &item_id in &contents.items {
+                for entry in checksums.find_by_item_id(item_id)? {
+                    checksums.delete(entry.id)?;
+                }
+                items_repo.delete(item_id)?;
+            }
+            let mut files_repo = FilesRepository::new(Rc::clone(&self.conn));
+            for &file_id in &contents.files {
+                files_repo.delete(file_id)?;
+            }
+            let mut links = SeriesSchemaItemsRepository::new(Rc::clone(&self.conn));
+            let mut series_repo = SeriesRepository::new(Rc::clone(&self.conn));
+            for &series_id in &contents.series {
+                for link in links.find_by_series_id(series_id)? {
+                    links.delete(link.id)?;
+                }
+                series_repo.delete(series_id)?;
+            }
+            delete_record()
+        });
+        if result.is_err() {
+            // 回滚后恢复被删除行的 sidecar
+            let mut conn = self.conn.borrow_mut();
+            for &series_id in &contents.series {
+                sidecars::after_write(&mut conn, "series", series_id, None);
+            }
+            for &file_id in &contents.files {
+                sidecars::after_write(&mut conn, "files", file_id, None);
+            }
+            for &item_id in &contents.items {
+                sidecars::after_write(&mut conn, "items", item_id, None);
+            }
+        }
+        result
+    }
+}
+
+/// Every way the values part of a series number (`<value>-<value>…`) splits into one value
+/// per dimension; each split lists the schema item ids chosen, None for Year dimensions
+///
+/// `dimensions` holds, per dimension, the path numbers it can take with their item ids,
+/// or None for a Year dimension, which takes any run of digits. Values may themselves
+/// contain `-`, so every candidate is tried rather than splitting on `-`.
+fn parse_series_values(rest: &str, dimensions: &[Option<&[(String, i32)]>], chosen: &mut Vec<Option<i32>>, parses: &mut Vec<Vec<Option<i32>>>) {
+    let Some((dimension, remaining)) = dimensions.split_first() else {
+        if rest.is_empty() {
+            parses.push(chosen.clone());
+        }
+        return;
+    };
+    // 当前值之后要么结束，要么以 '-' 接下一个维度的值
+    let after = |value_len: usize| -> Option<&str> {
+        let tail = &rest[value_len..];
+        match (remaining.is_empty(), tail.strip_prefix('-')) {
+            (true, _) if tail.is_empty() => Some(tail),
+            (false, Some(next)) => Some(next),
+            _ => None,
+        }
+    };
+    match dimension {
+        None => {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            if let Some(next) = (digits > 0).then(|| after(digits)).flatten() {
+                chosen.push(None);
+                parse_series_values(next, remaining, chosen, parses);
+                chosen.pop();
+            }
+        }
+        Some(values) => {
+            for (path_no, id) in values.iter() {
+                if let Some(next) = rest.starts_with(path_no.as_str()).then(|| after(path_no.len())).flatten() {
+                    chosen.push(Some(*id));
+                    parse_series_values(next, remaining, chosen, parses);
+                    chosen.pop();
+                }
+            }
+        }
+    }
+}
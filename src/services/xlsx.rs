@@ -0,0 +1,456 @@
+/// XLSX - Minimal Office Open XML workbook writer and reader
+/// Enough SpreadsheetML for plain tabular data: text and number cells, a bold header row and an autofilter
+use std::error::Error;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+/// Longest sheet name Excel accepts
+const MAX_SHEET_NAME: usize = 31;
+
+/// One cell value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+impl Cell {
+    /// Text shown for the cell, as written to CSV
+    pub fn as_text(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Number(n) => n.to_string(),
+        }
+    }
+}
+
+/// Sheet with a header row and data rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub name: String,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+/// Workbook built in memory and written in one go
+#[derive(Debug, Clone, Default)]
+pub struct Workbook {
+    sheets: Vec<Sheet>,
+}
+
+impl Workbook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sheet; the name is cleaned up and made unique as Excel requires
+    pub fn add_sheet(&mut self, name: &str, header: Vec<String>, rows: Vec<Vec<Cell>>) {
+        let cleaned: String = name
+            .chars()
+            .map(|c| if matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\') { '_' } else { c })
+            .take(MAX_SHEET_NAME)
+            .collect();
+        let cleaned = if cleaned.trim().is_empty() { format!("Sheet{}", self.sheets.len() + 1) } else { cleaned };
+        let mut unique = cleaned.clone();
+        let mut n = 2;
+        while self.sheets.iter().any(|s| s.name.eq_ignore_ascii_case(&unique)) {
+            let suffix = format!(" ({})", n);
+            let base: String = cleaned.chars().take(MAX_SHEET_NAME - suffix.chars().count()).collect();
+            unique = format!("{}{}", base, suffix);
+            n += 1;
+        }
+        self.sheets.push(Sheet { name: unique, header, rows });
+    }
+
+    pub fn sheets(&self) -> &[Sheet] {
+        &self.sheets
+    }
+
+    /// Write the workbook as an `.xlsx` file
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if self.sheets.is_empty() {
+            return Err("工作簿中没有工作表".into());
+        }
+        let mut zip = zip::ZipWriter::new(fs::File::create(path)?);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        let mut content_types = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+            r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+            r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+            r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+            r#"<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
+        ));
+        let mut workbook = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+            r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+        ));
+        let mut workbook_rels = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        ));
+        let mut defined_names = String::new();
+        for (index, sheet) in self.sheets.iter().enumerate() {
+            let n = index + 1;
+            content_types.push_str(&format!(
+                r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                n
+            ));
+            workbook.push_str(&format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, escape(&sheet.name), n, n));
+            workbook_rels.push_str(&format!(
+                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+                n, n
+            ));
+            if !sheet.header.is_empty() {
+                defined_names.push_str(&format!(
+                    r#"<definedName name="_xlnm._FilterDatabase" localSheetId="{}" hidden="1">'{}'!$A$1:${}${}</definedName>"#,
+                    index,
+                    escape(&sheet.name.replace('\'', "''")),
+                    column_name(sheet.header.len() - 1),
+                    sheet.rows.len() + 1
+                ));
+            }
+        }
+        content_types.push_str("</Types>");
+        workbook.push_str("</sheets>");
+        if !defined_names.is_empty() {
+            workbook.push_str(&format!("<definedNames>{}</definedNames>", defined_names));
+        }
+        workbook.push_str("</workbook>");
+        workbook_rels.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#,
+            self.sheets.len() + 1
+        ));
+
+        zip.start_file("[Content_Types].xml", options)?;
+        zip.write_all(content_types.as_bytes())?;
+        zip.start_file("_rels/.rels", options)?;
+        zip.write_all(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+                r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+                r#"</Relationships>"#,
+            )
+            .as_bytes(),
+        )?;
+        zip.start_file("xl/workbook.xml", options)?;
+        zip.write_all(workbook.as_bytes())?;
+        zip.start_file("xl/_rels/workbook.xml.rels", options)?;
+        zip.write_all(workbook_rels.as_bytes())?;
+        zip.start_file("xl/styles.xml", options)?;
+        zip.write_all(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+                r#"<fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts>"#,
+                r#"<fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills>"#,
+                r#"<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>"#,
+                r#"<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>"#,
+                r#"<cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>"#,
+                r#"<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs>"#,
+                r#"</styleSheet>"#,
+            )
+            .as_bytes(),
+        )?;
+        for (index, sheet) in self.sheets.iter().enumerate() {
+            zip.start_file(format!("xl/worksheets/sheet{}.xml", index + 1), options)?;
+            zip.write_all(sheet_xml(sheet).as_bytes())?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+fn sheet_xml(sheet: &Sheet) -> String {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+    ));
+    // 冻结表头行
+    xml.push_str(r#"<sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews>"#);
+    xml.push_str("<sheetData>");
+    let header: Vec<Cell> = sheet.header.iter().map(|h| Cell::Text(h.clone())).collect();
+    for (row_index, row) in std::iter::once(&header).chain(sheet.rows.iter()).enumerate() {
+        let r = row_index + 1;
+        xml.push_str(&format!(r#"<row r="{}">"#, r));
+        let style = if row_index == 0 { r#" s="1""# } else { "" };
+        for (col_index, cell) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(col_index), r);
+            match cell {
+                Cell::Text(text) if text.is_empty() => {}
+                Cell::Text(text) => xml.push_str(&format!(
+                    r#"<c r="{}"{} t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                    reference, style, escape(text)
+                )),
+                Cell::Number(n) => xml.push_str(&format!(r#"<c r="{}"{}><v>{}</v></c>"#, reference, style, n)),
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData>");
+    if !sheet.header.is_empty() {
+        xml.push_str(&format!(
+            r#"<autoFilter ref="A1:{}{}"/>"#,
+            column_name(sheet.header.len() - 1),
+            sheet.rows.len() + 1
+        ));
+    }
+    xml.push_str("</worksheet>");
+    xml
+}
+
+/// Cell texts of the first sheet of an `.xlsx` file, row by row
+///
+/// Shared, inline and formula strings are read as text and numbers as
+/// written; gaps left by empty cells are filled with empty strings.
+pub fn read_first_sheet(path: &Path) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+    let mut zip = zip::ZipArchive::new(fs::File::open(path)?)?;
+    let mut read = |name: &str| -> Result<Option<String>, Box<dyn Error>> {
+        match zip.by_name(name) {
+            Ok(mut entry) => {
+                let mut text = String::new();
+                entry.read_to_string(&mut text)?;
+                Ok(Some(text))
+            }
+            Err(zip::result::ZipError::FileNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    };
+
+    // 第一个工作表不一定是 sheet1.xml，按 workbook 的关系文件查找
+    let workbook = read("xl/workbook.xml")?.ok_or("不是有效的 xlsx 文件")?;
+    let relationship = xml_events(&workbook)
+        .into_iter()
+        .find_map(|e| match e {
+            XmlEvent::Start(name, attrs) if name == "sheet" => attribute(&attrs, "r:id").map(str::to_string),
+            _ => None,
+        })
+        .ok_or("工作簿中没有工作表")?;
+    let relationships = read("xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    let target = xml_events(&relationships)
+        .into_iter()
+        .find_map(|e| match e {
+            XmlEvent::Start(name, attrs) if name == "Relationship" && attribute(&attrs, "Id") == Some(relationship.as_str()) => {
+                attribute(&attrs, "Target").map(str::to_string)
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| "worksheets/sheet1.xml".to_string());
+    let sheet_path = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("xl/{}", target),
+    };
+
+    let mut shared = Vec::new();
+    if let Some(xml) = read("xl/sharedStrings.xml")? {
+        let mut current: Option<String> = None;
+        let mut in_text = false;
+        for event in xml_events(&xml) {
+            match event {
+                XmlEvent::Start(name, _) if name == "si" => current = Some(String::new()),
+                XmlEvent::Start(name, _) if name == "t" => in_text = true,
+                XmlEvent::End(name) if name == "t" => in_text = false,
+                XmlEvent::Text(text) if in_text => {
+                    if let Some(current) = current.as_mut() {
+                        current.push_str(&text);
+                    }
+                }
+                XmlEvent::End(name) if name == "si" => shared.push(current.take().unwrap_or_default()),
+                _ => {}
+            }
+        }
+    }
+
+    let sheet = read(&sheet_path)?.ok_or_else(|| format!("找不到工作表: {}", sheet_path))?;
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut cell: Option<(usize, Option<String>)> = None;
+    let mut value = String::new();
+    let mut in_value = false;
+    for event in xml_events(&sheet) {
+        match event {
+            XmlEvent::Start(name, _) if name == "row" => rows.push(Vec::new()),
+            XmlEvent::Start(name, attrs) if name == "c" => {
+                let row = rows.last().map(Vec::len).unwrap_or(0);
+                let column = attribute(&attrs, "r").and_then(column_index).unwrap_or(row);
+                cell = Some((column, attribute(&attrs, "t").map(str::to_string)));
+                value.clear();
+            }
+            XmlEvent::Start(name, _) if name == "v" || name == "t" => in_value = true,
+            XmlEvent::End(name) if name == "v" || name == "t" => in_value = false,
+            XmlEvent::Text(text) if in_value => value.push_str(&text),
+            XmlEvent::End(name) if name == "c" => {
+                let Some((column, kind)) = cell.take() else { continue };
+                let text = match kind.as_deref() {
+                    Some("s") => value.trim().parse::<usize>().ok().and_then(|i| shared.get(i).cloned()).unwrap_or_default(),
+                    Some("b") => if value.trim() == "1" { "TRUE".into() } else { "FALSE".into() },
+                    _ => value.clone(),
+                };
+                if let Some(row) = rows.last_mut() {
+                    if row.len() <= column {
+                        row.resize(column + 1, String::new());
+                    }
+                    row[column] = text;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
+
+/// Zero-based column index of a cell reference such as `AB12`
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference.bytes().take_while(u8::is_ascii_alphabetic).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let n = letters.iter().fold(0usize, |n, c| n * 26 + (c.to_ascii_uppercase() - b'A') as usize + 1);
+    Some(n - 1)
+}
+
+/// Parsing event of the small XML reader below
+#[derive(Debug, Clone, PartialEq)]
+enum XmlEvent {
+    /// Element start with its attributes; self-closing elements also produce an End
+    Start(String, Vec<(String, String)>),
+    End(String),
+    Text(String),
+}
+
+/// Flat list of XML events, enough for the well-formed parts Excel and this module write
+fn xml_events(xml: &str) -> Vec<XmlEvent> {
+    let mut events = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        if start > 0 {
+            events.push(XmlEvent::Text(unescape(&rest[..start])));
+        }
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").unwrap_or(after.len());
+            events.push(XmlEvent::Text(after[..end].to_string()));
+            rest = after.get(end + 3..).unwrap_or("");
+            continue;
+        }
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            events.push(XmlEvent::End(local_name(name.trim()).to_string()));
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = local_name(&tag[..name_end]).to_string();
+        events.push(XmlEvent::Start(name.clone(), attributes(&tag[name_end..])));
+        if self_closing {
+            events.push(XmlEvent::End(name));
+        }
+    }
+    events
+}
+
+/// Element name without a namespace prefix (`x:row` → `row`)
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn attributes(text: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = text;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().to_string();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else { break };
+        let Some(close) = after[1..].find(quote) else { break };
+        attrs.push((name, unescape(&after[1..close + 1])));
+        rest = &after[close + 2..];
+    }
+    attrs
+}
+
+/// Attribute value by full name (`r:id`) or, failing that, by local name
+fn attribute<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(n, _)| n == name)
+        .or_else(|| attrs.iter().find(|(n, _)| local_name(n) == name))
+        .map(|(_, v)| v.as_str())
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        result.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Spreadsheet column letters for a zero-based index (0 → A, 26 → AA)
+pub fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        name.push(b'A' + rem as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Escape text for XML, dropping control characters XML 1.0 cannot carry
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
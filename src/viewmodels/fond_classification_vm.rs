@@ -1,6 +1,8 @@
-use crate::core::{CrudViewModel, ToCrudListItem, ActiveableRepository, GenericRepository};
+use crate::core::{GenericRepository, TreeCrudViewModel, TreeMove, TreeScope};
 use crate::models::fond_classification::{FondClassification, ClassificationJson};
-use crate::persistence::FondClassificationsRepository;
+use crate::models::translation::Translatable;
+use crate::persistence::{FondClassificationsRepository, TranslationsRepository};
+use crate::services::{ClassificationImportService, ClassificationDiff, Usage, UsageService};
 use crate::AppWindow;
 use crate::CrudListItem;
 use crate::CrudViewModelBase;
@@ -10,11 +12,12 @@ use std::rc::Rc;
 
 /// Fond Classification管理ViewModel
 ///
-/// 此ViewModel通过复用CrudViewModelBase trait和宏，提供了极简的实现。
+/// 左侧列表是顶级分类，右侧是选中顶级分类的整棵子树，两者都由 `TreeCrudViewModel` 提供。
 /// 只需实现 `create_default()` 方法来定义新项的默认值。
 pub struct FondClassificationViewModel {
-    inner: CrudViewModel<FondClassification, FondClassificationsRepository>,
-    child_items: Rc<slint::VecModel<CrudListItem>>,
+    inner: TreeCrudViewModel<FondClassification, FondClassificationsRepository>,
+    /// 右侧树（第 0 行是选中的顶级分类本身）
+    tree: TreeCrudViewModel<FondClassification, FondClassificationsRepository>,
     selected_top_classification_id: Option<i32>,
     repo: Rc<RefCell<FondClassificationsRepository>>,
 }
@@ -22,11 +25,11 @@ pub struct FondClassificationViewModel {
 impl FondClassificationViewModel {
     /// 创建新的 FondClassificationViewModel 实例
     pub fn new(repo: Rc<RefCell<FondClassificationsRepository>>) -> Self {
-        let inner = CrudViewModel::new(Rc::clone(&repo));
-        let child_items = Rc::new(slint::VecModel::default());
+        let inner = TreeCrudViewModel::new(Rc::clone(&repo), TreeScope::Level(None));
+        let tree = TreeCrudViewModel::new(Rc::clone(&repo), TreeScope::All);
         Self {
             inner,
-            child_items,
+            tree,
             selected_top_classification_id: None,
             repo,
         }
@@ -34,11 +37,24 @@ impl FondClassificationViewModel {
 
     /// 更新数据库连接并重新加载数据
     pub fn update_connection(&self, new_conn: Rc<RefCell<diesel::SqliteConnection>>) {
-        self.repo.borrow_mut().update_connection(new_conn.clone());
-        self.inner.get_repo().borrow_mut().update_connection(new_conn);
+        self.repo.borrow_mut().update_connection(new_conn);
+        self.refresh_display_names();
         self.load();
     }
 
+    /// 按当前界面语言读取分类的翻译名称，下次加载列表时生效
+    pub fn refresh_display_names(&self) {
+        let locale = crate::services::current_language();
+        let names = TranslationsRepository::new(self.repo.borrow().connection())
+            .find_names_in(FondClassification::ENTITY_TYPE, &locale)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to load {} classification names: {}", locale, e);
+                Default::default()
+            });
+        self.inner.set_display_names(names.clone());
+        self.tree.set_display_names(names);
+    }
+
     /// 创建默认的FondClassification实例 - 由 `impl_crud_vm_base!` 宏使用
     fn create_default() -> FondClassification {
         use std::sync::atomic::{AtomicU32, Ordering};
@@ -63,117 +79,164 @@ impl FondClassificationViewModel {
 
     /// 初始化子分类：如果有顶级分类，自动激活第一个并加载其子分类
     pub fn initialize_child_classifications(&mut self) {
+        self.refresh_display_names();
+        self.inner.load();
         if let Some(first_item) = self.inner.items.row_data(0) {
             log::info!("Initializing child classifications for first item: id={}, name={}", first_item.id, first_item.title);
             self.load_child_classifications(Some(first_item.id));
         } else {
             log::info!("No top-level classifications found, clearing child items");
-            self.child_items.set_vec(Vec::new());
+            self.load_child_classifications(None);
         }
     }
 
-    /// 加载子分类数据
+    /// 加载选中顶级分类的整棵子树（任意深度）
     pub fn load_child_classifications(&mut self, parent_id: Option<i32>) {
         self.selected_top_classification_id = parent_id;
-        let child_classifications = {
-            let mut repo = self.repo.borrow_mut();
-            repo.find_by_parent_id(parent_id).unwrap_or_default()
-        };
-        let crud_items: Vec<CrudListItem> = child_classifications
-            .iter()
-            .map(|item| item.to_crud_list_item())
-            .collect();
-        (*self.child_items).set_vec(crud_items.clone());
-        log::info!("Loaded {} child classifications for parent_id {:?}", crud_items.len(), parent_id);
+        match parent_id {
+            Some(top_id) => self.tree.set_scope(TreeScope::Subtree(top_id)),
+            None => self.tree.clear(),
+        }
+        log::info!("Loaded {} classifications in the tree of {:?}", self.tree.items.row_count(), parent_id);
     }
 
     /// 获取子分类项
     pub fn get_child_items(&self) -> slint::ModelRc<crate::CrudListItem> {
-        slint::ModelRc::new(Rc::clone(&self.child_items))
-    }
-
-    /// 删除子分类
-    pub fn delete_child(&self, index: usize) -> Result<(), String> {
-        if let Some(item) = (*self.child_items).row_data(index) {
-            let mut repo = self.repo.borrow_mut();
-            match repo.delete(item.id) {
-                Ok(_) => {
-                    (*self.child_items).remove(index);
-                    log::info!("Deleted child classification at index {}", index);
-                    Ok(())
-                }
-                Err(e) => {
-                    log::error!("Failed to delete child classification at index {}: {}", index, e);
-                    Err(format!("删除子分类失败: {}", e))
-                }
+        self.tree.get_items()
+    }
+
+    /// 树中第 `index` 行的分类 id
+    pub fn tree_id_at(&self, index: usize) -> Option<i32> {
+        self.tree.id_at(index)
+    }
+
+    /// 分类在树中的行号
+    pub fn tree_index_of(&self, id: i32) -> Option<usize> {
+        self.tree.index_of(id)
+    }
+
+    /// 顶级分类在左侧列表中的行号
+    pub fn top_index_of(&self, id: i32) -> Option<usize> {
+        self.inner.index_of(id)
+    }
+
+    /// 在树中第 `index` 行的分类下添加子分类（没有有效行时加到顶级分类下）
+    pub fn add_tree_child(&self, index: usize, code: &str, name: &str) -> Result<i32, String> {
+        if self.selected_top_classification_id.is_none() {
+            return Err("请先选择顶级分类".to_string());
+        }
+        let exists = !self.repo.borrow_mut().find_by_predicate(|c| c.code == code).map_err(|e| e.to_string())?.is_empty();
+        if exists {
+            return Err(format!("分类代码 {} 已存在", code));
+        }
+        self.tree
+            .add_child(index, FondClassification {
+                code: code.to_string(),
+                name: name.to_string(),
+                active: true,
+                ..Default::default()
+            })
+            .map_err(|e| format!("添加子分类失败: {}", e))
+    }
+
+    /// 移动树中第 `index` 行的分类（连同其下级分类）
+    ///
+    /// 返回移动后所在的顶级分类 id 和被移动分类的 id，用于重新选中。
+    pub fn move_tree_node(&self, index: usize, movement: TreeMove) -> Result<(i32, i32), String> {
+        let moved = self.tree.move_node(index, movement)?;
+        log::info!("Moved classification {} ({:?}) under top classification {}", moved.1, movement, moved.0);
+        Ok(moved)
+    }
+
+    /// 树中第 `index` 行分类的下级分类数量
+    pub fn descendant_count(&self, index: usize) -> usize {
+        self.tree.descendant_count(index)
+    }
+
+    /// 左侧列表中第 `index` 行顶级分类的下级分类数量
+    pub fn top_descendant_count(&self, index: usize) -> usize {
+        self.inner.descendant_count(index)
+    }
+
+    fn usage_service(&self) -> UsageService {
+        UsageService::new(self.repo.borrow().connection())
+    }
+
+    /// 左侧列表（`top` 为 true）或右侧树中第 `index` 行的分类 id
+    fn id_at(&self, index: usize, top: bool) -> Result<i32, String> {
+        let id = if top { self.inner.id_at(index) } else { self.tree.id_at(index) };
+        id.ok_or_else(|| "项目未找到".to_string())
+    }
+
+    /// 归入第 `index` 行分类及其下级分类的全宗
+    pub fn usage_at(&self, index: usize, top: bool) -> Result<Usage, String> {
+        self.usage_service().classification_usage(self.id_at(index, top)?).map_err(|e| e.to_string())
+    }
+
+    /// 删除仍被使用的分类时，全宗可以改为归入的分类（被删除分类的上级分类）
+    pub fn remap_target_at(&self, index: usize, top: bool) -> Option<FondClassification> {
+        let id = self.id_at(index, top).ok()?;
+        let parent_id = self.repo.borrow_mut().find_by_id(id).ok()??.parent_id?;
+        self.repo.borrow_mut().find_by_id(parent_id).ok()?
+    }
+
+    /// 删除第 `index` 行的分类及其下级分类，返回删除的数量；仍有全宗归入时拒绝删除
+    pub fn delete_at(&self, index: usize, top: bool) -> Result<usize, String> {
+        let id = self.id_at(index, top)?;
+        match self.usage_service().delete_classification(id) {
+            Ok(count) => {
+                log::info!("Deleted {} classifications at row {} (top={})", count, index, top);
+                Ok(count)
             }
+            Err(e) => {
+                log::error!("Failed to delete classification at row {} (top={}): {}", index, top, e);
+                Err(e.to_string())
+            }
+        }
+    }
+
+    /// 把归入第 `index` 行分类子树的全宗改为归入上级分类，再删除子树，返回改动的全宗数量
+    pub fn remap_and_delete_at(&self, index: usize, top: bool) -> Result<usize, String> {
+        let id = self.id_at(index, top)?;
+        let target = self.remap_target_at(index, top).ok_or("顶级分类没有可以改为归入的上级分类")?;
+        self.usage_service().remap_and_delete_classification(id, target.id).map_err(|e| e.to_string())
+    }
+
+    /// 删除树中第 `index` 行的分类及其下级分类，返回删除的数量
+    pub fn delete_child(&self, index: usize) -> Result<usize, String> {
+        self.delete_at(index, false)
+    }
+
+    /// 删除左侧列表中第 `index` 行的顶级分类及其下级分类
+    pub fn delete_top_classification(&self, index: usize) -> Result<usize, String> {
+        self.delete_at(index, true)
+    }
+
+    /// 启用分类（连同上级分类）或停用分类（连同下级分类）
+    pub fn set_classification_active(&self, id: i32, active: bool) {
+        if active {
+            self.inner.activate(id);
         } else {
-            Err("子分类未找到".to_string())
+            self.inner.deactivate(id);
         }
+        log::info!("Set classification {} active={}", id, active);
     }
 
     /// 激活子分类
     pub fn activate_child(&mut self, id: i32) {
-        {
-            let mut repo = self.repo.borrow_mut();
-            if repo.activate(id).is_ok() {
-                log::info!("Activated child classification with id {}", id);
-            } else {
-                log::error!("Failed to activate child classification with id {}", id);
-                return;
-            }
-        }
-        // 重新加载子分类
+        self.set_classification_active(id, true);
         self.load_child_classifications(self.selected_top_classification_id);
     }
 
     /// 停用子分类
     pub fn deactivate_child(&mut self, id: i32) {
-        {
-            let mut repo = self.repo.borrow_mut();
-            if repo.deactivate(id).is_ok() {
-                log::info!("Deactivated child classification with id {}", id);
-            } else {
-                log::error!("Failed to deactivate child classification with id {}", id);
-                return;
-            }
-        }
-        // 重新加载子分类
+        self.set_classification_active(id, false);
         self.load_child_classifications(self.selected_top_classification_id);
     }
 
-    /// 导出分类到JSON文件
+    /// 导出分类到JSON文件（包含各语言的名称）
     pub fn export_classifications(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        use std::collections::HashMap;
-
-        let mut repo = self.repo.borrow_mut();
-
-        // 获取所有分类
-        let all_classifications = repo.find_all().unwrap_or_default();
-
-        // 构建父子关系映射
-        let mut parent_map: HashMap<Option<i32>, Vec<&FondClassification>> = HashMap::new();
-        for classification in &all_classifications {
-            parent_map.entry(classification.parent_id).or_insert_with(Vec::new).push(classification);
-        }
-
-        // 递归构建JSON结构
-        fn build_json_tree(
-            parent_id: Option<i32>,
-            parent_map: &HashMap<Option<i32>, Vec<&FondClassification>>
-        ) -> Vec<ClassificationJson> {
-            let mut result = Vec::new();
-            if let Some(classifications) = parent_map.get(&parent_id) {
-                for classification in classifications {
-                    let mut json_classification = ClassificationJson::from_fond_classification(classification);
-                    json_classification.children = build_json_tree(Some(classification.id), parent_map);
-                    result.push(json_classification);
-                }
-            }
-            result
-        }
-
-        let json_classifications = build_json_tree(None, &parent_map);
+        let json_classifications = self.import_service().export()?;
 
         // 序列化为JSON
         let json_string = serde_json::to_string_pretty(&json_classifications)?;
@@ -185,45 +248,26 @@ impl FondClassificationViewModel {
         Ok(())
     }
 
-    /// 从JSON文件导入分类
-    pub fn import_classifications(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        // 读取JSON文件
-        let json_string = std::fs::read_to_string(file_path)?;
-        let json_classifications: Vec<ClassificationJson> = serde_json::from_str(&json_string)?;
-
-        let mut repo = self.repo.borrow_mut();
-
-        // 在导入前清理所有现有数据
-        log::info!("Clearing existing classification data before import");
-        repo.delete_all()?;
-
-        // 递归导入分类
-        fn import_recursive(
-            classifications: &[ClassificationJson],
-            parent_id: Option<i32>,
-            sort_order_start: i32,
-            repo: &mut FondClassificationsRepository
-        ) -> Result<(), Box<dyn std::error::Error>> {
-            let mut current_sort_order = sort_order_start;
-
-            for json_classification in classifications {
-                // 创建分类
-                let fond_classification = json_classification.to_fond_classification(parent_id, current_sort_order);
-                let created_id = repo.create(fond_classification)?;
-                current_sort_order += 1;
-
-                // 递归导入子分类
-                import_recursive(&json_classification.children, Some(created_id), 0, repo)?;
-            }
-
-            Ok(())
-        }
+    /// 读取JSON文件并与现有分类比较，不写入数据库
+    pub fn preview_import(&self, file_path: &str) -> Result<(Vec<ClassificationJson>, ClassificationDiff), Box<dyn std::error::Error>> {
+        let classifications = ClassificationImportService::read(std::path::Path::new(file_path))?;
+        let diff = self.import_service().preview(&classifications)?;
+        Ok((classifications, diff))
+    }
 
-        // 开始导入
-        import_recursive(&json_classifications, None, 0, &mut *repo)?;
+    /// 按代码合并导入分类（在一个事务中完成）
+    ///
+    /// 保留现有分类的 id 和停用状态；仍被全宗使用的分类只有在导入文件中被
+    /// `replaces` 取代时才会删除。
+    pub fn import_classifications(&self, classifications: &[ClassificationJson]) -> Result<ClassificationDiff, Box<dyn std::error::Error>> {
+        let diff = self.import_service().apply(classifications)?;
+        self.refresh_display_names();
+        log::info!("Merged imported classifications: {}", diff.summary());
+        Ok(diff)
+    }
 
-        log::info!("Successfully imported classifications from {}", file_path);
-        Ok(())
+    fn import_service(&self) -> ClassificationImportService {
+        ClassificationImportService::new(self.repo.borrow().connection())
     }
 
     /// 测试导入功能（用于开发调试）
@@ -247,6 +291,27 @@ impl FondClassificationViewModel {
         Ok(())
     }
 
+    /// 重新加载顶级分类和分类树，并选中 `top_id` 的树中的 `selected_id`
+    ///
+    /// `top_id` 不再是顶级分类时（例如被删除）改为选中第一个顶级分类。
+    fn refresh_tree(vm: &Rc<RefCell<Self>>, ui: &AppWindow, top_id: Option<i32>, selected_id: Option<i32>) {
+        vm.borrow().load();
+        ui.set_classification_crud_items(vm.borrow().get_items());
+
+        let top_id = {
+            let vm_ref = vm.borrow();
+            let first_id = vm_ref.get_by_index(0).map(|item| item.id);
+            top_id.filter(|id| vm_ref.top_index_of(*id).is_some()).or(first_id)
+        };
+        if let Some(index) = top_id.and_then(|id| vm.borrow().top_index_of(id)) {
+            ui.set_selected_classification(index as i32);
+        }
+        vm.borrow_mut().load_child_classifications(top_id);
+        ui.set_child_crud_items(vm.borrow().get_child_items());
+        let child_index = selected_id.and_then(|id| vm.borrow().tree_index_of(id)).unwrap_or(0);
+        ui.set_selected_child(child_index as i32);
+    }
+
     /// 为UI设置CRUD回调 - 标准实现在这里
     pub fn setup_callbacks(vm: Rc<RefCell<Self>>, ui_handle: &AppWindow) {
         use crate::core::CrudViewModelBase;
@@ -289,11 +354,9 @@ impl FondClassificationViewModel {
                         ..Default::default()
                     };
 
-                    // 添加到数据库
+                    // 添加到数据库，排在最后一个顶级分类之后
                     vm_clone.borrow().inner.add(&mut new_classification);
                     log::info!("Successfully added new top classification");
-                    // 重新加载数据以确保排序正确
-                    vm_clone.borrow().load();
                     let items = vm_clone.borrow().get_items();
                     ui.set_classification_crud_items(items);
                     ui.set_show_add_top_classification_dialog(false);
@@ -326,6 +389,7 @@ impl FondClassificationViewModel {
                     vm_clone.borrow_mut().load_child_classifications(Some(id));
                     let child_items = vm_clone.borrow().get_child_items();
                     ui.set_child_crud_items(child_items);
+                    ui.set_selected_child(0);
                 }
             }
         });
@@ -339,7 +403,7 @@ impl FondClassificationViewModel {
             }
         });
 
-        // Confirm add child classification callback
+        // Confirm add child classification callback - 加到树中选中的分类下
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
         ui_handle.on_confirm_add_child_classification(move |fields| {
@@ -356,55 +420,21 @@ impl FondClassificationViewModel {
                     }
                 }
 
-                if !code.is_empty() && !name.is_empty() {
-                    let selected_parent_id = {
-                        let vm_ref = vm_clone.borrow();
-                        vm_ref.selected_top_classification_id
-                    };
-
-                    if selected_parent_id.is_none() {
-                        log::warn!("No top classification selected, cannot add child classification");
-                        return;
-                    }
-
-                    // 创建新的子分类
-                    let new_classification = FondClassification {
-                        id: 0,
-                        code,
-                        name,
-                        parent_id: selected_parent_id,
-                        active: true,
-                        sort_order: 0,
-                        ..Default::default()
-                    };
-
-                    // 添加到数据库
-                    let create_result = {
-                        let repo_ref = vm_clone.borrow().repo.clone();
-                        let mut repo = repo_ref.borrow_mut();
-                        repo.create(new_classification)
-                    };
-
-                    if let Ok(_) = create_result {
-                        log::info!("Successfully added new child classification");
-                    } else {
-                        log::error!("Failed to add new child classification");
-                        return;
-                    }
+                if code.is_empty() || name.is_empty() {
+                    log::warn!("Code or name is empty, not adding child classification");
+                    return;
+                }
 
-                    // 重新加载子分类数据
-                    {
-                        let parent_id = {
-                            let vm_ref = vm_clone.borrow();
-                            vm_ref.selected_top_classification_id
-                        };
-                        vm_clone.borrow_mut().load_child_classifications(parent_id);
+                let index = ui.get_selected_child().max(0) as usize;
+                let create_result = vm_clone.borrow().add_tree_child(index, &code, &name);
+                match create_result {
+                    Ok(id) => {
+                        log::info!("Successfully added new child classification {}", id);
+                        let top_id = vm_clone.borrow().selected_top_classification_id;
+                        Self::refresh_tree(&vm_clone, &ui, top_id, Some(id));
+                        ui.set_show_add_child_classification_dialog(false);
                     }
-                    let child_items = vm_clone.borrow().get_child_items();
-                    ui.set_child_crud_items(child_items);
-                    ui.set_show_add_child_classification_dialog(false);
-                } else {
-                    log::warn!("Code or name is empty, not adding child classification");
+                    Err(e) => show_toast(&ui_weak, e),
                 }
             }
         });
@@ -418,57 +448,67 @@ impl FondClassificationViewModel {
             }
         });
 
-        // Delete child classification callback
+        // Move child classification callback - 上移、下移、升级、降级（连同下级分类）
+        let vm_clone = vm.clone();
+        let ui_weak = ui_handle.as_weak();
+        ui_handle.on_move_child_classification(move |idx, action| {
+            log::info!("FondClassificationViewModel::setup_callbacks: move child classification {} action {}", idx, action);
+            let Some(movement) = TreeMove::from_action(action) else { return };
+            if let Some(ui) = ui_weak.upgrade() {
+                let move_result = vm_clone.borrow().move_tree_node(idx as usize, movement);
+                match move_result {
+                    Ok((top_id, id)) => Self::refresh_tree(&vm_clone, &ui, Some(top_id), Some(id)),
+                    Err(e) => show_toast(&ui_weak, e),
+                }
+            }
+        });
+
+        // Delete child classification callback - 连同下级分类一起删除
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
         ui_handle.on_delete_child_classification(move |idx| {
             log::info!("FondClassificationViewModel::setup_callbacks: delete child classification triggered for index {}", idx);
             if let Some(ui) = ui_weak.upgrade() {
-                match vm_clone.borrow().delete_child(idx as usize) {
+                let delete_result = match confirm_classification_delete(&vm_clone.borrow(), idx as usize, false) {
+                    DeleteChoice::Cancel => return,
+                    DeleteChoice::Delete => vm_clone.borrow().delete_child(idx as usize),
+                    DeleteChoice::RemapToParent => vm_clone.borrow().remap_and_delete_at(idx as usize, false),
+                };
+                match delete_result {
                     Ok(_) => {
-                        let child_items = vm_clone.borrow().get_child_items();
-                        ui.set_child_crud_items(child_items);
-                    }
-                    Err(e) => {
-                        ui.set_toast_message(e.into());
-                        ui.set_toast_visible(true);
-                        
-                        let ui_weak_clone = ui_weak.clone();
-                        Timer::single_shot(std::time::Duration::from_secs(3), move || {
-                            if let Some(ui) = ui_weak_clone.upgrade() {
-                                ui.set_toast_visible(false);
-                            }
-                        });
+                        let top_id = vm_clone.borrow().selected_top_classification_id;
+                        Self::refresh_tree(&vm_clone, &ui, top_id, None);
                     }
+                    Err(e) => show_toast(&ui_weak, e),
                 }
             }
         });
 
-        // Activate child classification callback
+        // Activate child classification callback - 同时启用上级分类
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
         ui_handle.on_activate_child_classification(move |id| {
             log::info!("FondClassificationViewModel::setup_callbacks: activate child classification triggered for id {}", id);
             if let Some(ui) = ui_weak.upgrade() {
-                vm_clone.borrow_mut().activate_child(id);
-                let child_items = vm_clone.borrow().get_child_items();
-                ui.set_child_crud_items(child_items);
+                vm_clone.borrow().set_classification_active(id, true);
+                let top_id = vm_clone.borrow().selected_top_classification_id;
+                Self::refresh_tree(&vm_clone, &ui, top_id, Some(id));
             }
         });
 
-        // Deactivate child classification callback
+        // Deactivate child classification callback - 同时停用下级分类
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
         ui_handle.on_deactivate_child_classification(move |id| {
             log::info!("FondClassificationViewModel::setup_callbacks: deactivate child classification triggered for id {}", id);
             if let Some(ui) = ui_weak.upgrade() {
-                vm_clone.borrow_mut().deactivate_child(id);
-                let child_items = vm_clone.borrow().get_child_items();
-                ui.set_child_crud_items(child_items);
+                vm_clone.borrow().set_classification_active(id, false);
+                let top_id = vm_clone.borrow().selected_top_classification_id;
+                Self::refresh_tree(&vm_clone, &ui, top_id, Some(id));
             }
         });
 
-        // Delete callback
+        // Delete callback - 顶级分类连同下级分类一起删除
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
         ui_handle.on_delete_fond_classification(move |idx| {
@@ -477,26 +517,14 @@ impl FondClassificationViewModel {
                 idx
             );
             if let Some(ui) = ui_weak.upgrade() {
-                match vm_clone.borrow().delete(idx) {
-                    Ok(_) => {
-                        let items = vm_clone.borrow().get_items();
-                        log::info!(
-                            "FondClassificationViewModel::setup_callbacks: Setting {} items to UI",
-                            items.row_count()
-                        );
-                        ui.set_classification_crud_items(items);
-                    }
-                    Err(e) => {
-                        ui.set_toast_message(e.into());
-                        ui.set_toast_visible(true);
-                        
-                        let ui_weak_clone = ui_weak.clone();
-                        Timer::single_shot(std::time::Duration::from_secs(3), move || {
-                            if let Some(ui) = ui_weak_clone.upgrade() {
-                                ui.set_toast_visible(false);
-                            }
-                        });
-                    }
+                let delete_result = match confirm_classification_delete(&vm_clone.borrow(), idx as usize, true) {
+                    DeleteChoice::Cancel => return,
+                    DeleteChoice::Delete => vm_clone.borrow().delete_top_classification(idx as usize),
+                    DeleteChoice::RemapToParent => vm_clone.borrow().remap_and_delete_at(idx as usize, true),
+                };
+                match delete_result {
+                    Ok(_) => Self::refresh_tree(&vm_clone, &ui, None, None),
+                    Err(e) => show_toast(&ui_weak, e),
                 }
             }
         });
@@ -510,17 +538,13 @@ impl FondClassificationViewModel {
                 id
             );
             if let Some(ui) = ui_weak.upgrade() {
-                vm_clone.borrow().activate(id);
-                let items = vm_clone.borrow().get_items();
-                log::info!(
-                    "FondClassificationViewModel::setup_callbacks: Setting {} items to UI",
-                    items.row_count()
-                );
-                ui.set_classification_crud_items(items);
+                vm_clone.borrow().set_classification_active(id, true);
+                let top_id = vm_clone.borrow().selected_top_classification_id;
+                Self::refresh_tree(&vm_clone, &ui, top_id, None);
             }
         });
 
-        // Deactivate callback
+        // Deactivate callback - 同时停用下级分类
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
         ui_handle.on_deactivate_top_classification(move |id| {
@@ -529,13 +553,9 @@ impl FondClassificationViewModel {
                 id
             );
             if let Some(ui) = ui_weak.upgrade() {
-                vm_clone.borrow().deactivate(id);
-                let items = vm_clone.borrow().get_items();
-                log::info!(
-                    "FondClassificationViewModel::setup_callbacks: Setting {} items to UI",
-                    items.row_count()
-                );
-                ui.set_classification_crud_items(items);
+                vm_clone.borrow().set_classification_active(id, false);
+                let top_id = vm_clone.borrow().selected_top_classification_id;
+                Self::refresh_tree(&vm_clone, &ui, top_id, None);
             }
         });
 
@@ -589,15 +609,57 @@ impl FondClassificationViewModel {
         ui_handle.on_import_classifications(move || {
             log::info!("FondClassificationViewModel::setup_callbacks: import classifications triggered");
             // 使用文件对话框选择文件
-            use rfd::FileDialog;
+            use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
             if let Some(path) = FileDialog::new()
                 .add_filter("JSON files", &["json"])
                 .pick_file() {
-                // 先执行导入操作
-                let import_result = vm_clone.borrow().import_classifications(&path.to_string_lossy());
+                // 先预览差异，确认后再合并
+                let preview = vm_clone.borrow().preview_import(&path.to_string_lossy());
+                let import_result = match preview {
+                    Ok((_, diff)) if diff.is_empty() => {
+                        MessageDialog::new()
+                            .set_level(MessageLevel::Info)
+                            .set_title("导入分类")
+                            .set_description("导入文件与现有分类一致，无需导入")
+                            .set_buttons(MessageButtons::Ok)
+                            .show();
+                        return;
+                    }
+                    Ok((_, diff)) if !diff.blocked().is_empty() => {
+                        let blocked: Vec<String> = diff.blocked().iter().map(|c| c.describe()).collect();
+                        MessageDialog::new()
+                            .set_level(MessageLevel::Warning)
+                            .set_title("无法导入分类")
+                            .set_description(format!(
+                                "{}\n\n请在导入文件中为替代分类添加 \"replaces\": [\"旧代码\"]，相关全宗将改用替代分类。",
+                                blocked.join("\n")
+                            ))
+                            .set_buttons(MessageButtons::Ok)
+                            .show();
+                        return;
+                    }
+                    Ok((classifications, diff)) => {
+                        const SHOWN: usize = 30;
+                        let mut lines: Vec<String> = diff.changes.iter().take(SHOWN).map(|c| c.describe()).collect();
+                        if diff.changes.len() > SHOWN {
+                            lines.push(format!("……另有 {} 项", diff.changes.len() - SHOWN));
+                        }
+                        let confirmed = MessageDialog::new()
+                            .set_level(MessageLevel::Info)
+                            .set_title("确认导入分类")
+                            .set_description(format!("{}\n\n{}\n\n是否应用以上变更？", diff.summary(), lines.join("\n")))
+                            .set_buttons(MessageButtons::YesNo)
+                            .show();
+                        if confirmed != MessageDialogResult::Yes {
+                            return;
+                        }
+                        vm_clone.borrow().import_classifications(&classifications)
+                    }
+                    Err(e) => Err(e),
+                };
 
                 match import_result {
-                    Ok(_) => {
+                    Ok(diff) => {
                         log::info!("Successfully imported classifications from {}", path.display());
                         // 重新加载数据
                         if let Some(ui) = ui_weak.upgrade() {
@@ -606,7 +668,7 @@ impl FondClassificationViewModel {
                                 vm_clone.borrow_mut().selected_top_classification_id = None;
                             }
                             {
-                                vm_clone.borrow().child_items.set_vec(Vec::new());
+                                vm_clone.borrow().tree.clear();
                             }
 
                             // 重新加载顶级分类数据
@@ -619,7 +681,7 @@ impl FondClassificationViewModel {
                             let child_items = vm_clone.borrow().get_child_items();
                             ui.set_child_crud_items(child_items);
 
-                            ui.set_toast_message("分类导入成功".into());
+                            ui.set_toast_message(format!("分类导入成功: {}", diff.summary()).into());
                             ui.set_toast_visible(true);
                             
                             let ui_weak_clone = ui_weak.clone();
@@ -650,6 +712,80 @@ impl FondClassificationViewModel {
     }
 }
 
+/// 显示 3 秒后自动隐藏的提示
+fn show_toast(ui_weak: &slint::Weak<AppWindow>, message: String) {
+    if let Some(ui) = ui_weak.upgrade() {
+        ui.set_toast_message(message.into());
+        ui.set_toast_visible(true);
+
+        let ui_weak_clone = ui_weak.clone();
+        Timer::single_shot(std::time::Duration::from_secs(3), move || {
+            if let Some(ui) = ui_weak_clone.upgrade() {
+                ui.set_toast_visible(false);
+            }
+        });
+    }
+}
+
+/// 删除带有下级分类的分类前确认
+/// 删除分类时用户的选择
+enum DeleteChoice {
+    Cancel,
+    Delete,
+    /// 全宗改为归入上级分类后删除
+    RemapToParent,
+}
+
+/// 确认删除分类：有下级分类时提示一并删除；仍有全宗归入时列出这些全宗，
+/// 有上级分类时询问是否改为归入上级分类，否则拒绝删除
+fn confirm_classification_delete(vm: &FondClassificationViewModel, index: usize, top: bool) -> DeleteChoice {
+    use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+    let usage = match vm.usage_at(index, top) {
+        Ok(usage) => usage,
+        Err(e) => {
+            log::error!("Failed to check classification usage: {}", e);
+            return DeleteChoice::Cancel;
+        }
+    };
+    let descendants = if top { vm.top_descendant_count(index) } else { vm.descendant_count(index) };
+    let subtree = if descendants > 0 { format!("该分类有 {} 个下级分类，将一并删除。", descendants) } else { String::new() };
+    if usage.is_empty() {
+        if descendants == 0 {
+            return DeleteChoice::Delete;
+        }
+        let confirmed = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("删除分类")
+            .set_description(format!("{}是否继续？", subtree))
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        return if confirmed == MessageDialogResult::Yes { DeleteChoice::Delete } else { DeleteChoice::Cancel };
+    }
+    let Some(parent) = vm.remap_target_at(index, top) else {
+        MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("删除分类")
+            .set_description(format!("以下全宗仍归入该分类或其下级分类，不能删除：\n{}", usage.describe()))
+            .set_buttons(MessageButtons::Ok)
+            .show();
+        return DeleteChoice::Cancel;
+    };
+    let confirmed = MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("删除分类")
+        .set_description(format!(
+            "以下全宗仍归入该分类或其下级分类：\n{}\n\n{}是否把这些全宗改为归入上级分类 {} {} 后删除？",
+            usage.describe(),
+            subtree,
+            parent.code,
+            parent.name
+        ))
+        .set_buttons(MessageButtons::YesNo)
+        .show();
+    if confirmed == MessageDialogResult::Yes { DeleteChoice::RemapToParent } else { DeleteChoice::Cancel }
+}
+
 // 使用宏自动生成 ActiveableCrudViewModel trait 实现
 crate::impl_activeable_crud_vm_base!(
     FondClassificationViewModel,
@@ -2,6 +2,7 @@ use crate::core::CrudViewModel;
 use crate::core::CrudViewModelBase;
 use crate::models::Fond;
 use crate::persistence::FondsRepository;
+use crate::services::{Contents, UsageService};
 use crate::AppWindow;
 use crate::CrudListItem;
 use slint::{ComponentHandle, Model};
@@ -60,6 +61,16 @@ impl FondViewModel {
         self.library_path = path;
     }
 
+    fn usage_service(&self) -> UsageService {
+        UsageService::new(self.inner.get_repo().borrow().connection())
+    }
+
+    /// 全宗下将被一并删除的系列、案卷和文件
+    pub fn contents_at(&self, index: i32) -> Result<Contents, String> {
+        let item = self.inner.items.row_data(index.max(0) as usize).ok_or("项目未找到")?;
+        self.usage_service().fond_contents(item.id).map_err(|e| e.to_string())
+    }
+
     /// 为UI设置CRUD回调 - 标准实现在这里
     pub fn setup_callbacks(vm: Rc<RefCell<Self>>, ui_handle: &AppWindow) {
         use crate::core::CrudViewModelBase;
@@ -89,7 +100,28 @@ impl FondViewModel {
                 idx
             );
             if let Some(ui) = ui_weak.upgrade() {
-                let _ = vm_clone.borrow().delete(idx);
+                // 全宗下的系列、案卷和文件一并删除，先确认
+                match vm_clone.borrow().contents_at(idx) {
+                    Ok(contents) if !contents.is_empty() => {
+                        let confirmed = rfd::MessageDialog::new()
+                            .set_level(rfd::MessageLevel::Warning)
+                            .set_title("删除全宗")
+                            .set_description(format!("该全宗有 {}，将一并删除。是否继续？", contents.describe()))
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show();
+                        if confirmed != rfd::MessageDialogResult::Yes {
+                            return;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        ui.invoke_show_toast(format!("删除全宗失败: {}", e).into());
+                        return;
+                    }
+                }
+                if let Err(e) = vm_clone.borrow().delete(idx) {
+                    ui.invoke_show_toast(format!("删除全宗失败: {}", e).into());
+                }
                 let items = vm_clone.borrow().get_items();
                 log::info!(
                     "FondViewModel::setup_callbacks: Setting {} items to UI",
@@ -146,8 +178,10 @@ impl CrudViewModelBase for FondViewModel {
     fn delete(&self, index: i32) -> Result<(), String> {
         log::info!("{}: Deleting item at index {}", Self::vm_name(), index);
         if index >= 0 {
-            match self.inner.delete(index as usize) {
+            let item = self.inner.items.row_data(index as usize).ok_or("项目未找到")?;
+            match self.usage_service().delete_fond(item.id).map_err(|e| e.to_string()) {
                 Ok(_) => {
+                    self.inner.items.remove(index as usize);
                     log::info!(
                         "{}: Deleted item, remaining count: {}",
                         Self::vm_name(),
@@ -164,24 +198,4 @@ impl CrudViewModelBase for FondViewModel {
             Err("无效索引".to_string())
         }
     }
-
-    fn activate(&self, id: i32) {
-        log::info!("{}: Activating item with id {}", Self::vm_name(), id);
-        self.inner.activate(id);
-        log::info!(
-            "{}: Activated item, current count: {}",
-            Self::vm_name(),
-            self.inner.items.row_count()
-        );
-    }
-
-    fn deactivate(&self, id: i32) {
-        log::info!("{}: Deactivating item with id {}", Self::vm_name(), id);
-        self.inner.deactivate(id);
-        log::info!(
-            "{}: Deactivated item, current count: {}",
-            Self::vm_name(),
-            self.inner.items.row_count()
-        );
-    }
 }
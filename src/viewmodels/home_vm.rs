@@ -1,29 +1,51 @@
 /// Home View Model - MVVM architecture
 /// Manages the state and business logic for the home page (fonds management)
-use crate::services::SettingsService;
+use crate::services::{SettingsService, ScanService, ScanTarget, StocktakeReport, FixityService, FixityReport, ChecksumAlgorithm, IntegrityService, IntegrityReport, IntegrityIssue, IntegrityRepair, RepairChoice, IngestService, IngestOutcome, LibraryPathService, RelinkReport, BackupService, BackupFormat, BackupManifest, BagItService, BagScope, BagImportReport, CatalogueService, CatalogueQuery, CatalogueLevel, CatalogueColumn, RegisterImportService, RegisterTable, RegisterMapping, RegisterPreview, RegisterImportReport, NumberMode, BackgroundJob, DirectoryImportService, DirectoryMapping, DirectoryImportReport, RecoveryService, RecoveryReport, UsageService, Contents, gettext_tr};
 use crate::{AppWindow, CrudListItem, DialogField, DialogFieldType};
 use crate::persistence::{
-    FondsRepository, SeriesRepository, FilesRepository, ItemsRepository,
+    FondsRepository, SeriesRepository, SeriesSchemaItemsRepository, FilesRepository, ItemsRepository,
     FondSchemasRepository, FondClassificationsRepository, SchemaRepository, 
-    schema_item_repository::SchemaItemRepository, SequencesRepository,
+    schema_item_repository::SchemaItemRepository, SequencesRepository, TranslationsRepository,
     establish_connection,
 };
+use crate::models::app_settings::IngestMode;
 use crate::models::fond::Fond;
 use crate::models::series::Series;
+use crate::models::series_schema_item::SeriesSchemaItem;
 use crate::models::file::File;
 use crate::models::item::Item;
 use crate::models::fond_schema::FondSchema;
-use crate::core::GenericRepository;
+use crate::models::fond_classification::FondClassification;
+use crate::models::translation::Translatable;
+use crate::core::{GenericRepository, HierarchicalRepository, Updatable};
 use slint::{ComponentHandle, ModelRc, VecModel, SharedString, Model};
 use crate::slint_generatedAppWindow;
 use std::cell::RefCell;
 use std::error::Error;
 use std::rc::Rc;
 use std::path::PathBuf;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use diesel::SqliteConnection;
 use open;
 
+/// Directory import running in the background, polled from a UI timer
+enum DirectoryJob {
+    Scan { job: BackgroundJob<DirectoryMapping>, series_id: i32 },
+    Import(BackgroundJob<DirectoryImportReport>),
+}
+
+/// Item whose payload is being ingested; the row is written once the ingest finished
+struct PendingItem {
+    file_id: i32,
+    item_no: String,
+    name: String,
+    /// Managed destination and mode, for rolling the ingest back
+    ingest: Option<(PathBuf, IngestMode)>,
+}
+
+type IngestJob = BackgroundJob<IngestOutcome>;
+type AddItemJob = (PendingItem, IngestJob);
+
 /// Home ViewModel - handles state and business logic for fonds management
 pub struct HomeViewModel {
     pub library_names: Vec<String>,
@@ -44,6 +66,8 @@ pub struct HomeViewModel {
     // Files data
     pub files_list: Vec<File>,
     pub selected_file: i32,
+    /// Text the listed files' number or name must contain; also narrows catalogue exports
+    pub file_filter: String,
     
     // Items data
     pub items_list: Vec<Item>,
@@ -60,6 +84,10 @@ pub struct HomeViewModel {
     pub rename_file_index: i32,
     pub show_rename_item_dialog: bool,
     pub rename_item_index: i32,
+
+    // Stocktake (batch scanning) state
+    pub stocktake_active: bool,
+    pub stocktake_scans: Vec<String>,
     
     settings_service: Rc<SettingsService>,
     db_connection: Option<Rc<RefCell<SqliteConnection>>>,
@@ -82,6 +110,7 @@ impl Default for HomeViewModel {
             selected_series_no: String::new(),
             files_list: Vec::new(),
             selected_file: 0,
+            file_filter: String::new(),
             items_list: Vec::new(),
             selected_item: 0,
             show_add_file_dialog: false,
@@ -94,6 +123,8 @@ impl Default for HomeViewModel {
             rename_file_index: -1,
             show_rename_item_dialog: false,
             rename_item_index: -1,
+            stocktake_active: false,
+            stocktake_scans: Vec::new(),
             settings_service: Rc::new(SettingsService::new()),
             db_connection: None,
             current_db_path: None,
@@ -118,6 +149,7 @@ impl HomeViewModel {
             selected_series_no: String::new(),
             files_list: Vec::new(),
             selected_file: 0,
+            file_filter: String::new(),
             items_list: Vec::new(),
             selected_item: 0,
             show_add_file_dialog: false,
@@ -130,6 +162,8 @@ impl HomeViewModel {
             rename_file_index: -1,
             show_rename_item_dialog: false,
             rename_item_index: -1,
+            stocktake_active: false,
+            stocktake_scans: Vec::new(),
             settings_service,
             db_connection: None,
             current_db_path: None,
@@ -152,7 +186,8 @@ impl HomeViewModel {
     pub fn browse_file_or_folder(&self) -> Option<String> {
         // Get the current file's path as default directory
         let default_dir = if self.selected_file >= 0 && (self.selected_file as usize) < self.files_list.len() {
-            self.files_list[self.selected_file as usize].path.clone()
+            self.files_list[self.selected_file as usize].path.as_deref()
+                .map(|p| self.resolve_path(p).to_string_lossy().to_string())
                 .unwrap_or_else(|| self.last_opened_library.clone())
         } else {
             self.last_opened_library.clone()
@@ -174,7 +209,14 @@ impl HomeViewModel {
         let conn = establish_connection(&db_path)?;
         self.db_connection = Some(Rc::clone(&conn));
         self.current_db_path = Some(db_path);
-        self.sequences_repo = Some(SequencesRepository::new(conn));
+        self.sequences_repo = Some(SequencesRepository::new(Rc::clone(&conn)));
+
+        // 库内的绝对路径改写为相对路径，使档案库可整体移动或在其他机器上挂载
+        match LibraryPathService::new(conn, library_path).migrate_absolute_paths() {
+            Ok(0) => {}
+            Ok(count) => log::info!("Rewrote {} absolute paths as library-relative", count),
+            Err(e) => log::warn!("Failed to rewrite absolute paths: {}", e),
+        }
         Ok(())
     }
 
@@ -218,6 +260,51 @@ impl HomeViewModel {
         self.db_connection.as_ref().map(|conn| SchemaItemRepository::new(Rc::clone(conn)))
     }
 
+    /// Get a scan service for resolving scanned numbers
+    fn get_scan_service(&self) -> Option<ScanService> {
+        self.db_connection.as_ref().map(|conn| ScanService::new(Rc::clone(conn)))
+    }
+
+    /// Get a fixity service for item payload checksums
+    fn get_fixity_service(&self) -> Option<FixityService> {
+        self.db_connection.as_ref().map(|conn| FixityService::new(Rc::clone(conn), &self.last_opened_library))
+    }
+
+    /// Get a usage service for checked catalogue deletes
+    fn get_usage_service(&self) -> Option<UsageService> {
+        self.db_connection.as_ref().map(|conn| UsageService::new(Rc::clone(conn)))
+    }
+
+    /// Get an integrity checker for the current library
+    fn get_integrity_service(&self) -> Option<IntegrityService> {
+        self.db_connection.as_ref().map(|conn| IntegrityService::new(Rc::clone(conn), &self.last_opened_library))
+    }
+
+    /// Get a path service for the current library
+    fn get_library_path_service(&self) -> Option<LibraryPathService> {
+        self.db_connection.as_ref().map(|conn| LibraryPathService::new(Rc::clone(conn), &self.last_opened_library))
+    }
+
+    /// Get the catalogue queries behind the home page lists and catalogue exports
+    fn get_catalogue_service(&self) -> Option<CatalogueService> {
+        self.db_connection.as_ref().map(|conn| CatalogueService::new(Rc::clone(conn)))
+    }
+
+    /// Get a BagIt exporter and importer for the current library
+    fn get_bagit_service(&self) -> Option<BagItService> {
+        self.db_connection.as_ref().map(|conn| BagItService::new(Rc::clone(conn), &self.last_opened_library))
+    }
+
+    /// Get a register importer for the current library
+    fn get_register_import_service(&self) -> Option<RegisterImportService> {
+        self.db_connection.as_ref().map(|conn| RegisterImportService::new(Rc::clone(conn), &self.last_opened_library))
+    }
+
+    /// Absolute location of a stored file or item path
+    pub fn resolve_path(&self, stored: &str) -> PathBuf {
+        LibraryPathService::resolve(std::path::Path::new(&self.last_opened_library), stored)
+    }
+
     /// Get fond_no for a given series by looking up the fond
     fn get_fond_no_for_series(&self, fond_id: i32) -> Result<String, Box<dyn Error>> {
         for fond in &self.fonds_list {
@@ -293,8 +380,8 @@ impl HomeViewModel {
 
     /// Load fonds for the current archive
     pub fn load_fonds(&mut self) -> Result<(), Box<dyn Error>> {
-        if let Some(mut repo) = self.get_fonds_repo() {
-            self.fonds_list = repo.find_all()?;
+        if let Some(catalogue) = self.get_catalogue_service() {
+            self.fonds_list = catalogue.fonds(&CatalogueQuery::default())?;
             log::info!("HomeViewModel: Loaded {} fonds", self.fonds_list.len());
             
             // Reset fonds selection and dependent data
@@ -318,12 +405,9 @@ impl HomeViewModel {
 
     /// Load series for a specific fond
     pub fn load_series(&mut self, fond_id: i32) -> Result<(), Box<dyn Error>> {
-        if let Some(mut repo) = self.get_series_repo() {
+        if let Some(catalogue) = self.get_catalogue_service() {
             // Find series for this fond by fond_id
-            let all_series = repo.find_all()?;
-            self.series_list = all_series.into_iter()
-                .filter(|s| s.fond_id == fond_id)
-                .collect();
+            self.series_list = catalogue.series(&CatalogueQuery::fond(fond_id))?;
             log::info!("HomeViewModel: Loaded {} series for fond_id {}", self.series_list.len(), fond_id);
             
             // If no series found, try to generate them
@@ -331,10 +415,7 @@ impl HomeViewModel {
                 log::info!("No series found for fond_id {}, attempting to generate series", fond_id);
                 self.generate_series(fond_id)?;
                 // Reload series after generation
-                let all_series = repo.find_all()?;
-                self.series_list = all_series.into_iter()
-                    .filter(|s| s.fond_id == fond_id)
-                    .collect();
+                self.series_list = catalogue.series(&CatalogueQuery::fond(fond_id))?;
                 log::info!("After generation: Loaded {} series for fond_id {}", self.series_list.len(), fond_id);
             }
             
@@ -357,11 +438,8 @@ impl HomeViewModel {
 
     /// Load files for a specific series
     pub fn load_files(&mut self, series_id: i32) -> Result<(), Box<dyn Error>> {
-        if let Some(mut repo) = self.get_files_repo() {
-            let all_files = repo.find_all()?;
-            self.files_list = all_files.into_iter()
-                .filter(|f| f.series_id == series_id)
-                .collect();
+        if let Some(catalogue) = self.get_catalogue_service() {
+            self.files_list = catalogue.files(&CatalogueQuery::series(series_id).with_file_filter(&self.file_filter))?;
             log::info!("HomeViewModel: Loaded {} files for series_id {}", self.files_list.len(), series_id);
             
             // Reset selection and load items for first file
@@ -377,13 +455,23 @@ impl HomeViewModel {
         Ok(())
     }
 
+    /// Filter the files list of the selected series by number or name
+    pub fn set_file_filter(&mut self, filter: &str) -> Result<(), Box<dyn Error>> {
+        self.file_filter = filter.to_string();
+        if let Some(series_id) = self.selected_series_id() {
+            self.load_files(series_id)?;
+        }
+        Ok(())
+    }
+
+    fn selected_series_id(&self) -> Option<i32> {
+        usize::try_from(self.selected_series_index).ok().and_then(|i| self.series_list.get(i)).map(|s| s.id)
+    }
+
     /// Load items for a specific file
     pub fn load_items(&mut self, file_id: i32) -> Result<(), Box<dyn Error>> {
-        if let Some(mut repo) = self.get_items_repo() {
-            let all_items = repo.find_all()?;
-            self.items_list = all_items.into_iter()
-                .filter(|i| i.file_id == file_id)
-                .collect();
+        if let Some(catalogue) = self.get_catalogue_service() {
+            self.items_list = catalogue.items(&CatalogueQuery::file(file_id))?;
             log::info!("HomeViewModel: Loaded {} items for file_id {}", self.items_list.len(), file_id);
             self.selected_item = 0;
         }
@@ -436,11 +524,10 @@ impl HomeViewModel {
         if let Some(mut schema_repo) = self.get_schema_repo() {
             if let Some(mut items_repo) = self.get_schema_items_repo() {
                 let all_schemas = schema_repo.find_all()?;
-                let all_items = items_repo.find_all()?;
                 log::info!("Available schemas: {}", all_schemas.iter().map(|s| s.schema_no.as_str()).collect::<Vec<_>>().join(", "));
                 
                 for fond_schema in &fond_schemas {
-                    log::debug!("Processing fond_schema: schema_item_id={:?}", fond_schema.schema_item_id);
+                    log::debug!("Processing fond_schema: schema_item_id={:?}, item_level={:?}", fond_schema.schema_item_id, fond_schema.item_level);
                     
                     if let Some(schema) = all_schemas.iter().find(|s| s.id == fond_schema.schema_id) {
                         if schema.schema_no == "Year" {
@@ -453,26 +540,26 @@ impl HomeViewModel {
                                     schema_id: schema.id,
                                     item_no: year.to_string(),
                                     item_name: year.to_string(),
+                                    parent_id: None,
+                                    active: true,
+                                    sort_order: year,
                                     created_by: "system".into(),
                                     created_machine: "system".into(),
                                     created_at: chrono::Utc::now().naive_utc(),
+                                    updated_at: None,
+                                    updated_by: None,
+                                    updated_machine: None,
                                 });
                             }
                             dimension_items.push(year_items);
-                        } else if let Some(item_id) = fond_schema.schema_item_id {
-                            // Normal schema with specific item
-                            if let Some(item) = all_items.iter().find(|i| i.id == item_id) {
-                                dimension_items.push(vec![item.clone()]);
-                            } else {
-                                log::warn!("Schema item not found for id {}", item_id);
-                            }
                         } else {
-                            // Normal schema: collect all existing items for this schema
-                            let schema_items: Vec<_> = all_items.iter()
-                                .filter(|i| i.schema_id == schema.id)
-                                .cloned()
-                                .collect();
-                            dimension_items.push(schema_items);
+                            // Normal schema: active leaves (or items at the chosen level) in tree order, numbered by
+                            // their path; a pinned item limits them to its subtree. Series of inactive items are kept
+                            let items = items_repo.find_expanded(schema.id, fond_schema.schema_item_id, fond_schema.item_level)?;
+                            if items.is_empty() {
+                                log::info!("Schema {} has no active items for fond {} - no new series for it", schema.schema_no, fond_no);
+                            }
+                            dimension_items.push(items);
                         }
                     }
                 }
@@ -541,8 +628,18 @@ impl HomeViewModel {
                     created_by: String::new(),
                     created_machine: String::new(),
                     created_at: chrono::Utc::now().naive_utc(),
+                    updated_at: None,
+                    updated_by: None,
+                    updated_machine: None,
                 };
-                series_repo.create(series)?;
+                let series_id = series_repo.create(series)?;
+                // 记录生成系列所用的方案条目（Year 维度没有条目），删除条目前据此检查使用情况
+                if let Some(conn) = self.db_connection.as_ref() {
+                    let mut links = SeriesSchemaItemsRepository::new(Rc::clone(conn));
+                    for item in combo.iter().filter(|item| item.id != 0) {
+                        links.create(SeriesSchemaItem { series_id, schema_item_id: item.id, ..Default::default() })?;
+                    }
+                }
                 created_count += 1;
             }
             
@@ -555,7 +652,10 @@ impl HomeViewModel {
     }
 
     /// Add a new fond with the given data
-    pub fn add_fond(&mut self, name: &str, classification_code: &str, selected_schema_nos: Vec<String>) -> Result<(), Box<dyn Error>> {
+    ///
+    /// `selected_schemas` are the dimensions in order: schema number and the level a nested
+    /// schema expands to (None for its leaf items).
+    pub fn add_fond(&mut self, name: &str, classification_code: &str, selected_schemas: Vec<(String, Option<i32>)>) -> Result<(), Box<dyn Error>> {
         // Generate fond_no using sequence
         let fond_no = self.generate_next_fond_no(classification_code)?;
         
@@ -569,6 +669,9 @@ impl HomeViewModel {
                 created_by: String::new(),
                 created_machine: String::new(),
                 created_at: chrono::Utc::now().naive_utc(),
+                updated_at: None,
+                updated_by: None,
+                updated_machine: None,
             };
             let id = repo.create(fond)?;
             log::info!("Created fond: {} - {}", fond_no, name);
@@ -583,7 +686,7 @@ impl HomeViewModel {
                 if let Some(mut items_repo) = self.get_schema_items_repo() {
                     let all_schemas = schema_repo.find_all()?;
                     let all_items = items_repo.find_all()?;
-                    for (order, schema_no) in selected_schema_nos.iter().enumerate() {
+                    for (order, (schema_no, item_level)) in selected_schemas.iter().enumerate() {
                         if let Some(schema) = all_schemas.iter().find(|s| s.schema_no == *schema_no) {
                             // For all schemas, set schema_item_id to None to allow cartesian product of all items
                             let fond_schema = FondSchema {
@@ -591,15 +694,19 @@ impl HomeViewModel {
                                 fond_id,
                                 schema_id: schema.id,
                                 schema_item_id: None,
+                                item_level: *item_level,
                                 sort_order: order as i32,
                                 created_by: String::new(),
                                 created_machine: String::new(),
                                 created_at: chrono::Utc::now().naive_utc(),
+                                updated_at: None,
+                                updated_by: None,
+                                updated_machine: None,
                             };
                             fs_repo.create(fond_schema)?;
                         }
                     }
-                    log::info!("Created {} fond_schemas for fond {}", selected_schema_nos.len(), fond_no);
+                    log::info!("Created {} fond_schemas for fond {}", selected_schemas.len(), fond_no);
                 }
             }
         }
@@ -650,7 +757,7 @@ impl HomeViewModel {
                         log::info!("Created file directory: {:?}", file_dir);
                     }
                     
-                    Some(file_dir.to_string_lossy().to_string())
+                    Some(LibraryPathService::to_stored(std::path::Path::new(&self.last_opened_library), &file_dir))
                 } else {
                     return Err("No fond selected".into());
                 }
@@ -667,6 +774,9 @@ impl HomeViewModel {
                 created_by: String::new(),
                 created_machine: String::new(),
                 created_at: chrono::Utc::now().naive_utc(),
+                updated_at: None,
+                updated_by: None,
+                updated_machine: None,
             };
             repo.create(file)?;
             log::info!("Created file: {} - {} (path: {:?})", file_no, name, file_path);
@@ -701,35 +811,179 @@ impl HomeViewModel {
             Err("No database connection".into())
         }
     }
-    pub fn add_item(&mut self, name: &str, path: Option<String>) -> Result<(), Box<dyn Error>> {
+    /// Reserve an item number and start ingesting its payload in the background
+    ///
+    /// The item row is only written by `finish_add_item` once the payload is in
+    /// place; without a path no job is started.
+    fn start_add_item(&mut self, name: &str, path: Option<String>) -> Result<(PendingItem, Option<IngestJob>), Box<dyn Error>> {
         if self.files_list.is_empty() || self.selected_file < 0 {
             return Err("No file selected".into());
         }
-        
+
         let file_id = self.files_list[self.selected_file as usize].id;
         let file_no = self.files_list[self.selected_file as usize].file_no.clone();
         // Generate item_no
         let item_no = self.generate_next_item_no(&file_no)?;
-        
-        if let Some(mut repo) = self.get_items_repo() {
-            let item = Item {
+        let mut pending = PendingItem { file_id, item_no: item_no.clone(), name: name.to_string(), ingest: None };
+
+        // 将选中的文件复制或移动到库目录下，原路径作为来源记录保留
+        let Some(source) = path.filter(|p| !p.trim().is_empty()) else { return Ok((pending, None)) };
+        let fond_no = self.fonds_list
+            .get(self.selected_fonds_index as usize)
+            .map(|f| f.fond_no.clone())
+            .ok_or("No fond selected")?;
+        let mode = self.settings_service.get_ingest_mode()?;
+        let destination = IngestService::destination(
+            std::path::Path::new(&self.last_opened_library), &fond_no, &file_no, &item_no);
+        pending.ingest = Some((destination.clone(), mode));
+        let job = BackgroundJob::spawn(&format!("ingest-{}", item_no), move |control| {
+            IngestService::ingest(std::path::Path::new(&source), &destination, mode, |p| {
+                control.report(p.files_done, p.files_total, &p.current);
+            })
+        })?;
+        Ok((pending, Some(job)))
+    }
+
+    /// Write the item row for a finished ingest and record its checksums
+    ///
+    /// If the row cannot be written the ingest is rolled back, so no payload is
+    /// left in the library without a catalogue entry.
+    fn finish_add_item(&mut self, pending: PendingItem, outcome: Option<IngestOutcome>) -> Result<(), Box<dyn Error>> {
+        let PendingItem { file_id, item_no, name, ingest } = pending;
+        let (path, source_path) = match &outcome {
+            Some(outcome) => {
+                log::info!("Ingested {}: {} files, {} bytes -> {}", item_no, outcome.files, outcome.bytes, outcome.path);
+                let stored = LibraryPathService::to_stored(std::path::Path::new(&self.last_opened_library), std::path::Path::new(&outcome.path));
+                (Some(stored), Some(outcome.source_path.clone()))
+            }
+            None => (None, None),
+        };
+
+        let created = match self.get_items_repo() {
+            Some(mut repo) => repo.create(Item {
                 id: 0,
                 file_id,
                 item_no: item_no.clone(),
-                name: name.to_string(),
+                name: name.clone(),
                 path,
+                source_path,
                 created_by: String::new(),
                 created_machine: String::new(),
                 created_at: chrono::Utc::now().naive_utc(),
-            };
-            repo.create(item)?;
-            log::info!("Created item: {} - {}", item_no, name);
+                updated_at: None,
+                updated_by: None,
+                updated_machine: None,
+            }),
+            None => Err("No database connection".into()),
+        };
+        let item_id = match created {
+            Ok(item_id) => item_id,
+            Err(e) => {
+                if let (Some(outcome), Some((destination, mode))) = (&outcome, &ingest) {
+                    if let Err(rollback) = IngestService::rollback(outcome, destination, *mode) {
+                        log::error!("Failed to roll back ingest of {}: {}", item_no, rollback);
+                        return Err(format!("{}；回滚导入失败: {}", e, rollback).into());
+                    }
+                }
+                return Err(e);
+            }
+        };
+        log::info!("Created item: {} - {}", item_no, name);
+
+        // 记录文件校验清单；计算失败不影响档案项本身，下次完整性校验会报告为新增文件
+        if let Some(fixity) = self.get_fixity_service() {
+            let algorithm = self.settings_service.get_checksum_algorithm().unwrap_or(ChecksumAlgorithm::Sha256);
+            match fixity.record_item(item_id, algorithm) {
+                Ok(count) => log::info!("Recorded {} checksums for item {}", count, item_no),
+                Err(e) => log::warn!("Failed to record checksums for item {}: {}", item_no, e),
+            }
         }
 
         self.load_items(file_id)?;
         Ok(())
     }
 
+    /// Add an item from the UI, ingesting its payload in the background
+    ///
+    /// Progress is shown as toasts while the job runs; only one ingest runs at a time.
+    fn spawn_add_item(vm: &Rc<RefCell<Self>>, ui_weak: &slint::Weak<AppWindow>, running: &Rc<RefCell<Option<AddItemJob>>>, timer: &Rc<slint::Timer>, name: &str, path: Option<String>) -> bool {
+        let Some(ui) = ui_weak.upgrade() else { return false };
+        if running.borrow().is_some() {
+            ui.invoke_show_toast("正在导入文件，请稍候".into());
+            return false;
+        }
+        let Ok(mut home) = vm.try_borrow_mut() else { return false };
+        let (pending, job) = match home.start_add_item(name, path) {
+            Ok(started) => started,
+            Err(e) => {
+                log::error!("Failed to add item: {}", e);
+                ui.invoke_show_toast(format!("添加文件失败: {}", e).into());
+                return false;
+            }
+        };
+        let Some(job) = job else {
+            match home.finish_add_item(pending, None) {
+                Ok(()) => {
+                    home.init_ui(&ui);
+                    ui.invoke_show_toast("文件添加成功".into());
+                }
+                Err(e) => {
+                    log::error!("Failed to add item: {}", e);
+                    ui.invoke_show_toast(format!("添加文件失败: {}", e).into());
+                }
+            }
+            return true;
+        };
+        drop(home);
+        ui.invoke_show_toast(format!("正在导入 {}", pending.name).into());
+        *running.borrow_mut() = Some((pending, job));
+
+        let vm = Rc::clone(vm);
+        let ui_weak = ui_weak.clone();
+        let running = Rc::clone(running);
+        let timer_weak = Rc::downgrade(timer);
+        let mut ticks = 0u32;
+        timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(200), move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            ticks += 1;
+            let Some((pending, job)) = running.borrow_mut().take() else {
+                if let Some(timer) = timer_weak.upgrade() {
+                    timer.stop();
+                }
+                return;
+            };
+            // 界面正忙时下次再取结果
+            let Ok(mut home) = vm.try_borrow_mut() else {
+                *running.borrow_mut() = Some((pending, job));
+                return;
+            };
+            let result = match job.try_finish() {
+                None => {
+                    if ticks.is_multiple_of(10) {
+                        let progress = job.progress();
+                        ui.invoke_show_toast(format!("正在导入 {} {}/{}: {}", pending.name, progress.done, progress.total, progress.current).into());
+                    }
+                    *running.borrow_mut() = Some((pending, job));
+                    return;
+                }
+                Some(Err(e)) => Err(e.into()),
+                Some(Ok(outcome)) => {
+                    let finished = home.finish_add_item(pending, Some(outcome));
+                    home.init_ui(&ui);
+                    finished
+                }
+            };
+            match result {
+                Ok(()) => ui.invoke_show_toast("文件添加成功".into()),
+                Err(e) => {
+                    log::error!("Failed to add item: {}", e);
+                    ui.invoke_show_toast(format!("添加文件失败: {}", e).into());
+                }
+            }
+        });
+        true
+    }
+
     /// Generate next item number
     fn generate_next_item_no(&mut self, file_no: &str) -> Result<String, Box<dyn Error>> {
         if let Some(mut repo) = self.sequences_repo.as_mut() {
@@ -740,6 +994,12 @@ impl HomeViewModel {
         }
     }
     
+    /// Items the selected file still contains, deleted together with it
+    pub fn selected_file_contents(&self) -> Result<Contents, Box<dyn Error>> {
+        let file = self.files_list.get(self.selected_file.max(0) as usize).ok_or("No file selected")?;
+        self.get_usage_service().ok_or("No database connection")?.file_contents(file.id)
+    }
+
     /// Delete the selected file
     pub fn delete_file(&mut self) -> Result<(), Box<dyn Error>> {
         if self.files_list.is_empty() || self.selected_file < 0 {
@@ -749,9 +1009,9 @@ impl HomeViewModel {
         let file_id = self.files_list[self.selected_file as usize].id;
         let series_id = self.files_list[self.selected_file as usize].series_id;
         
-        if let Some(mut repo) = self.get_files_repo() {
-            repo.delete(file_id)?;
-            log::info!("Deleted file with id {}", file_id);
+        if let Some(usage) = self.get_usage_service() {
+            let contents = usage.delete_file(file_id)?;
+            log::info!("Deleted file with id {} and {} items", file_id, contents.items.len());
         }
 
         self.load_files(series_id)?;
@@ -788,8 +1048,8 @@ impl HomeViewModel {
         let item_id = self.items_list[self.selected_item as usize].id;
         let file_id = self.items_list[self.selected_item as usize].file_id;
         
-        if let Some(mut repo) = self.get_items_repo() {
-            repo.delete(item_id)?;
+        if let Some(usage) = self.get_usage_service() {
+            usage.delete_item(item_id)?;
             log::info!("Deleted item with id {}", item_id);
         }
 
@@ -818,13 +1078,440 @@ impl HomeViewModel {
         Ok(())
     }
 
+    /// Resolve a scanned fond_no / series_no / file_no / item_no and select it in the tree
+    pub fn select_by_code(&mut self, code: &str) -> Result<ScanTarget, Box<dyn Error>> {
+        let service = self.get_scan_service().ok_or("No database connection")?;
+        let target = service.resolve(code)?
+            .ok_or_else(|| format!("No record found for '{}'", ScanService::normalize(code)))?;
+
+        // Walk up the hierarchy to collect the ids to select at each level
+        let mut item_id = None;
+        let mut file_id = None;
+        let mut series_id = None;
+        let fond_id = match &target {
+            ScanTarget::Fond(fond) => fond.id,
+            ScanTarget::Series(series) => {
+                series_id = Some(series.id);
+                series.fond_id
+            }
+            ScanTarget::File(file) => {
+                file_id = Some(file.id);
+                series_id = Some(file.series_id);
+                self.get_series_repo().ok_or("No database connection")?
+                    .find_by_id(file.series_id)?
+                    .ok_or_else(|| format!("Series with id {} not found", file.series_id))?
+                    .fond_id
+            }
+            ScanTarget::Item(item) => {
+                item_id = Some(item.id);
+                file_id = Some(item.file_id);
+                let file = self.get_files_repo().ok_or("No database connection")?
+                    .find_by_id(item.file_id)?
+                    .ok_or_else(|| format!("File with id {} not found", item.file_id))?;
+                series_id = Some(file.series_id);
+                self.get_series_repo().ok_or("No database connection")?
+                    .find_by_id(file.series_id)?
+                    .ok_or_else(|| format!("Series with id {} not found", file.series_id))?
+                    .fond_id
+            }
+        };
+
+        if !self.fonds_list.iter().any(|f| f.id == fond_id) {
+            self.load_fonds()?;
+        }
+        let fond_index = self.fonds_list.iter().position(|f| f.id == fond_id)
+            .ok_or_else(|| format!("Fond with id {} not found", fond_id))?;
+        self.selected_fonds_index = fond_index as i32;
+        self.load_series(fond_id)?;
+
+        if let Some(series_id) = series_id {
+            let series_index = self.series_list.iter().position(|s| s.id == series_id)
+                .ok_or_else(|| format!("Series with id {} not found", series_id))?;
+            self.selected_series_index = series_index as i32;
+            self.selected_series_no = format!("S{:05}", series_id);
+            // 扫描到的案卷可能被筛选隐藏，先清除筛选
+            if file_id.is_some() {
+                self.file_filter.clear();
+            }
+            self.load_files(series_id)?;
+
+            if let Some(file_id) = file_id {
+                let file_index = self.files_list.iter().position(|f| f.id == file_id)
+                    .ok_or_else(|| format!("File with id {} not found", file_id))?;
+                self.selected_file = file_index as i32;
+                self.selected_file_index = file_index as i32;
+                self.load_items(file_id)?;
+
+                if let Some(item_id) = item_id {
+                    let item_index = self.items_list.iter().position(|i| i.id == item_id)
+                        .ok_or_else(|| format!("Item with id {} not found", item_id))?;
+                    self.selected_item = item_index as i32;
+                    self.selected_item_index = item_index as i32;
+                }
+            }
+        }
+
+        log::info!("HomeViewModel: Selected {} for scanned code '{}'", target.kind(), ScanService::normalize(code));
+        Ok(target)
+    }
+
+    /// Start a stocktake session for the selected fond
+    pub fn start_stocktake(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.selected_fonds_index < 0 || self.selected_fonds_index as usize >= self.fonds_list.len() {
+            return Err("No fond selected".into());
+        }
+        self.stocktake_active = true;
+        self.stocktake_scans.clear();
+        log::info!("HomeViewModel: Stocktake started for fond {}", self.fonds_list[self.selected_fonds_index as usize].fond_no);
+        Ok(())
+    }
+
+    /// Record a scanned code in the running stocktake session
+    pub fn record_stocktake_scan(&mut self, code: &str) {
+        let code = ScanService::normalize(code);
+        if !code.is_empty() {
+            self.stocktake_scans.push(code);
+        }
+    }
+
+    /// Finish the stocktake session and build the expected-versus-missing report
+    pub fn finish_stocktake(&mut self) -> Result<StocktakeReport, Box<dyn Error>> {
+        let fond_id = self.fonds_list.get(self.selected_fonds_index as usize)
+            .map(|f| f.id)
+            .ok_or("No fond selected")?;
+        let service = self.get_scan_service().ok_or("No database connection")?;
+        let report = service.stocktake(fond_id, None, &self.stocktake_scans)?;
+
+        self.stocktake_active = false;
+        self.stocktake_scans.clear();
+        log::info!("HomeViewModel: Stocktake finished: {}", report.summary());
+        Ok(report)
+    }
+
+    /// Re-hash every item payload in the current library
+    pub fn run_fixity_check(&mut self) -> Result<FixityReport, Box<dyn Error>> {
+        let fixity = self.get_fixity_service().ok_or("No database connection")?;
+        let report = fixity.verify_all()?;
+        log::info!("Fixity check finished: {}", report.summary());
+        Ok(report)
+    }
+
+    /// Start the fixity check in the background if the configured interval has elapsed since the last run
+    pub fn start_scheduled_fixity_check(&self) -> Result<Option<BackgroundJob<FixityReport>>, Box<dyn Error>> {
+        let interval_days = self.settings_service.get_fixity_interval_days()?;
+        if interval_days == 0 {
+            return Ok(None);
+        }
+        let fixity = self.get_fixity_service().ok_or("No database connection")?;
+        let now = chrono::Local::now().naive_local();
+        if !fixity.is_due(chrono::Duration::days(interval_days as i64), now)? {
+            return Ok(None);
+        }
+        FixityService::spawn_verify_all(PathBuf::from(&self.last_opened_library)).map(Some)
+    }
+
+    /// Cross-check the library database against the library directory
+    pub fn check_integrity(&mut self) -> Result<IntegrityReport, Box<dyn Error>> {
+        let integrity = self.get_integrity_service().ok_or("No database connection")?;
+        let report = integrity.check()?;
+        log::info!("Integrity check finished: {}", report.summary());
+        Ok(report)
+    }
+
+    /// Let the user decide on each repairable issue, then reload the fonds tree
+    pub fn repair_integrity(&mut self) -> Result<usize, Box<dyn Error>> {
+        let integrity = self.get_integrity_service().ok_or("No database connection")?;
+        let applied = integrity.repair_each(Self::choose_repair)?;
+        log::info!("Applied {} integrity repairs", applied);
+        self.load_fonds()?;
+        Ok(applied)
+    }
+
+    /// Ask which repair to apply to one issue; closing the dialog stops the repair
+    fn choose_repair(issue: &IntegrityIssue, repairs: &[IntegrityRepair], deleted: &Contents) -> RepairChoice {
+        use rfd::{MessageButtons, MessageDialog, MessageDialogResult};
+        let mut options: Vec<(String, RepairChoice)> = repairs
+            .iter()
+            .map(|repair| {
+                let label = match repair {
+                    IntegrityRepair::RelinkPath { .. } => "重新关联",
+                    IntegrityRepair::ClearPath { .. } => "清除路径",
+                    IntegrityRepair::DeleteRow { .. } => "删除",
+                };
+                (label.to_string(), RepairChoice::Apply(repair.clone()))
+            })
+            .collect();
+        options.push(("跳过".to_string(), RepairChoice::Skip));
+        if options.len() < 3 {
+            options.push(("停止".to_string(), RepairChoice::Stop));
+        }
+
+        let mut message = format!("{}\n", issue);
+        for repair in repairs {
+            message.push_str(&format!("\n• {}", repair));
+        }
+        if !deleted.is_empty() {
+            message.push_str(&format!("\n\n删除时将同时删除其下的 {}。", deleted.describe()));
+        }
+        message.push_str("\n\n关闭此对话框将停止修复。");
+        let result = MessageDialog::new()
+            .set_title("修复一致性问题")
+            .set_description(message)
+            .set_buttons(MessageButtons::YesNoCancelCustom(options[0].0.clone(), options[1].0.clone(), options[2].0.clone()))
+            .show();
+        let chosen = match result {
+            MessageDialogResult::Custom(label) => options.iter().position(|(l, _)| *l == label),
+            MessageDialogResult::Yes => Some(0),
+            MessageDialogResult::No => Some(1),
+            _ => None,
+        };
+        chosen.map(|i| options.swap_remove(i).1).unwrap_or(RepairChoice::Stop)
+    }
+
+    /// Relink file and item paths outside the library that no longer exist, searching under a folder
+    pub fn relink_external_paths(&mut self, search_root: &std::path::Path) -> Result<RelinkReport, Box<dyn Error>> {
+        let paths = self.get_library_path_service().ok_or("No database connection")?;
+        let report = paths.relink(search_root)?;
+        log::info!("Relinked external paths under {:?}: {}", search_root, report.summary());
+        self.load_fonds()?;
+        Ok(report)
+    }
+
+    /// Back up the current library to an archive file (.zip or .tar.zst)
+    pub fn backup_library(&mut self, destination: &std::path::Path, include_payloads: bool) -> Result<BackupManifest, Box<dyn Error>> {
+        if self.db_connection.is_none() {
+            return Err("No database connection".into());
+        }
+        let manifest = BackupService::new(&self.last_opened_library).backup(destination, include_payloads)?;
+        log::info!("Backed up {} files ({} bytes) to {:?}", manifest.entries.len(), manifest.total_size(), destination);
+        Ok(manifest)
+    }
+
+    /// Start a scheduled backup of the current library in the background if one is due
+    pub fn start_scheduled_backup(&self) -> Result<Option<BackgroundJob<Option<PathBuf>>>, Box<dyn Error>> {
+        let settings = self.settings_service.get_backup_settings()?;
+        if settings.interval_days == 0 || self.db_connection.is_none() {
+            return Ok(None);
+        }
+        let backup_dir = self.settings_service.get_backup_directory()?;
+        let interval = chrono::Duration::days(settings.interval_days as i64);
+        let now = chrono::Local::now().naive_local();
+        let service = BackupService::new(&self.last_opened_library);
+        if !service.is_due(&backup_dir, interval, now)? {
+            return Ok(None);
+        }
+        BackgroundJob::spawn("scheduled-backup", move |_| {
+            service.run_scheduled(&backup_dir, BackupFormat::Zip, settings.include_payloads, interval, settings.retention, now)
+        })
+        .map(Some)
+    }
+
+    /// Restore a backup archive into a library folder and register it as a library
+    ///
+    /// The open library cannot be restored over; switch to another library first.
+    pub fn restore_library(&mut self, archive: &std::path::Path, target: &std::path::Path, overwrite: bool) -> Result<BackupManifest, Box<dyn Error>> {
+        let open_library = self.db_connection.as_ref().map(|_| std::path::Path::new(&self.last_opened_library));
+        BackupService::check_restore_target(target, open_library)?;
+        let manifest = BackupService::restore(archive, target, overwrite)?;
+        log::info!("Restored {} files from {:?} into {:?}", manifest.entries.len(), archive, target);
+
+        let target_str = target.to_string_lossy().to_string();
+        let libraries = self.settings_service.list_archive_libraries()?;
+        if !libraries.iter().any(|lib| lib.path == target_str) {
+            let mut name = manifest.library_name.clone();
+            let mut n = 2;
+            while libraries.iter().any(|lib| lib.name == name) {
+                name = format!("{} ({})", manifest.library_name, n);
+                n += 1;
+            }
+            self.settings_service.add_archive_library(name, target_str, None)?;
+            self.load_libraries()?;
+        }
+        Ok(manifest)
+    }
+
+    /// Export a fond, series or file as a BagIt package under the destination folder
+    pub fn export_bag(&self, scope: BagScope, destination: &std::path::Path) -> Result<PathBuf, Box<dyn Error>> {
+        let service = self.get_bagit_service().ok_or("No database connection")?;
+        let bag = service.export(scope, destination)?;
+        log::info!("Exported {:?} as BagIt package {:?}", scope, bag);
+        Ok(bag)
+    }
+
+    /// Validate a BagIt package and import its hierarchy into the current library
+    pub fn import_bag(&mut self, bag: &std::path::Path) -> Result<BagImportReport, Box<dyn Error>> {
+        let service = self.get_bagit_service().ok_or("No database connection")?;
+        let report = service.import(bag, self.settings_service.get_checksum_algorithm()?)?;
+        for (from, to) in &report.remapped {
+            log::info!("Imported {} as {}", from, to);
+        }
+        log::info!("Imported BagIt package {:?}: {}", bag, report.summary());
+        self.load_fonds()?;
+        Ok(report)
+    }
+
+    /// Query of the current view for a catalogue level: the selected fond's series,
+    /// the selected series' files or the selected file's items, with the files filter applied
+    pub fn catalogue_query(&self, level: CatalogueLevel) -> CatalogueQuery {
+        let selected = |index: i32, ids: Vec<i32>| usize::try_from(index).ok().and_then(|i| ids.get(i).copied());
+        let query = match level {
+            CatalogueLevel::Fond => CatalogueQuery {
+                fond_id: selected(self.selected_fonds_index, self.fonds_list.iter().map(|f| f.id).collect()),
+                ..Default::default()
+            },
+            CatalogueLevel::Series => CatalogueQuery {
+                series_id: self.selected_series_id(),
+                ..Default::default()
+            },
+            CatalogueLevel::File => CatalogueQuery {
+                file_id: selected(self.selected_file, self.files_list.iter().map(|f| f.id).collect()),
+                ..Default::default()
+            },
+        };
+        query.with_file_filter(&self.file_filter)
+    }
+
+    /// Export the catalogue of the current view as .xlsx or .csv with the configured columns; returns the number of rows
+    pub fn export_catalogue(&self, level: CatalogueLevel, path: &std::path::Path) -> Result<usize, Box<dyn Error>> {
+        let catalogue = self.get_catalogue_service().ok_or("No database connection")?;
+        let columns = match self.settings_service.get_catalogue_columns(level.as_str())? {
+            Some(keys) => CatalogueColumn::parse_list(&keys),
+            None => level.default_columns(),
+        };
+        let rows = catalogue.export(level, &self.catalogue_query(level), &columns, path)?;
+        log::info!("Exported {} ({} rows) to {:?}", level.title(), rows, path);
+        Ok(rows)
+    }
+
+    /// Read a CSV/XLSX register, map its columns by header and validate every row
+    pub fn preview_register(&self, path: &std::path::Path, mode: NumberMode) -> Result<RegisterPreview, Box<dyn Error>> {
+        let service = self.get_register_import_service().ok_or("No database connection")?;
+        let table = RegisterTable::read(path)?;
+        let preview = service.preview(&table, &RegisterMapping::detect(&table.header), mode)?;
+        log::info!("Previewed register {:?}: {}", path, preview.summary());
+        Ok(preview)
+    }
+
+    /// Import a register, generating missing series from the fond schemas
+    pub fn import_register(&mut self, path: &std::path::Path, mode: NumberMode) -> Result<RegisterImportReport, Box<dyn Error>> {
+        let service = self.get_register_import_service().ok_or("No database connection")?;
+        let table = RegisterTable::read(path)?;
+        let mapping = RegisterMapping::detect(&table.header);
+        let ingest_mode = self.settings_service.get_ingest_mode()?;
+        let algorithm = self.settings_service.get_checksum_algorithm()?;
+        // generate_series 依赖已加载的全宗列表
+        self.load_fonds()?;
+        let report = service.import(&table, &mapping, mode, ingest_mode, algorithm, |fond_id| self.generate_series(fond_id))?;
+        log::info!(
+            "Imported register {:?}: {} series generated, {} files, {} items",
+            path, report.series_generated, report.files_created, report.items_created
+        );
+        self.load_fonds()?;
+        Ok(report)
+    }
+
+    /// Start importing a scanned directory mapping into a series on a worker thread
+    fn start_directory_import(&self, mapping: DirectoryMapping, series_id: i32) -> Result<BackgroundJob<DirectoryImportReport>, Box<dyn Error>> {
+        if self.db_connection.is_none() {
+            return Err("No database connection".into());
+        }
+        let ingest_mode = self.settings_service.get_ingest_mode()?;
+        let algorithm = self.settings_service.get_checksum_algorithm()?;
+        log::info!("Importing directory {:?} into series {}: {}", mapping.root, series_id, mapping.summary());
+        DirectoryImportService::spawn_import(PathBuf::from(&self.last_opened_library), mapping, series_id, ingest_mode, algorithm)
+    }
+
+    /// Let the user accept a scanned mapping as is, or adjust it as CSV first; `None` when cancelled
+    fn review_directory_mapping(mapping: DirectoryMapping) -> Result<Option<DirectoryMapping>, Box<dyn Error>> {
+        use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+        let choice = MessageDialog::new()
+            .set_title("导入目录")
+            .set_description(format!("扫描完成：{}。\n按此映射导入？选择“否”可导出映射表，调整后再导入。", mapping.summary()))
+            .set_buttons(MessageButtons::YesNoCancel)
+            .show();
+        match choice {
+            MessageDialogResult::Yes => return Ok(Some(mapping)),
+            MessageDialogResult::No => {}
+            _ => return Ok(None),
+        }
+
+        // 在表格软件中调整映射表：改名、将 include 置 0 排除、修改 folder 归入其他案卷
+        let Some(csv) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .set_file_name("目录映射表.csv")
+            .save_file() else { return Ok(None) };
+        std::fs::write(&csv, mapping.to_csv())?;
+        let edited = MessageDialog::new()
+            .set_title("调整映射表")
+            .set_description(format!("映射表已保存到 {}。编辑并保存后点击“确定”选择调整后的映射表。", csv.display()))
+            .set_buttons(MessageButtons::OkCancel)
+            .show();
+        if !matches!(edited, MessageDialogResult::Ok) {
+            return Ok(None);
+        }
+        let Some(csv) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .set_directory(csv.parent().unwrap_or(std::path::Path::new(".")))
+            .pick_file() else { return Ok(None) };
+        let adjusted = DirectoryMapping::from_csv(&mapping.root, &std::fs::read_to_string(&csv)?)?;
+        let confirm = MessageDialog::new()
+            .set_title("导入目录")
+            .set_description(format!("调整后：{}。确认导入？", adjusted.summary()))
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        Ok(matches!(confirm, MessageDialogResult::Yes).then_some(adjusted))
+    }
+
+    /// Write the sidecar of every record, for libraries created before sidecars existed
+    pub fn write_all_sidecars(&self) -> Result<usize, Box<dyn Error>> {
+        let conn = self.db_connection.as_ref().ok_or("No database connection")?;
+        let written = crate::persistence::sidecars::write_all(&mut conn.borrow_mut())?;
+        log::info!("Wrote {} sidecars for {}", written, self.last_opened_library);
+        Ok(written)
+    }
+
+    /// Rebuild the current library's database from its sidecars and reopen it
+    pub fn rebuild_library_database(&mut self) -> Result<RecoveryReport, Box<dyn Error>> {
+        if self.last_opened_library.is_empty() {
+            return Err("没有打开的档案库".into());
+        }
+        // 先释放当前连接，重建后重新打开
+        self.db_connection = None;
+        self.sequences_repo = None;
+        let library = self.last_opened_library.clone();
+        let result = RecoveryService::new(&library).rebuild();
+        self.update_connection(&library)?;
+        let report = result?;
+        for problem in &report.problems {
+            log::warn!("Rebuild: {}", problem);
+        }
+        if let Some(previous) = &report.previous_database {
+            log::info!("Previous database moved to {:?}", previous);
+        }
+        self.load_fonds()?;
+        Ok(report)
+    }
+
+    /// Classification names in the current UI language, for classifications that have a translation
+    fn classification_names(&self) -> HashMap<i32, String> {
+        let Some(conn) = &self.db_connection else { return HashMap::new() };
+        let locale = crate::services::current_language();
+        TranslationsRepository::new(Rc::clone(conn))
+            .find_names_in(FondClassification::ENTITY_TYPE, &locale)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to load {} classification names: {}", locale, e);
+                HashMap::new()
+            })
+    }
+
     /// Get classification options for the add fonds dialog
     pub fn get_classification_options(&self) -> Vec<SharedString> {
         if let Some(mut repo) = self.get_classifications_repo() {
             if let Ok(classifications) = repo.find_by_parent_id(None) {
+                let names = self.classification_names();
                 return classifications.into_iter()
                     .filter(|c| c.active)
-                    .map(|c| SharedString::from(format!("{} - {}", c.code, c.name)))
+                    .map(|c| SharedString::from(format!("{} - {}", c.code, names.get(&c.id).unwrap_or(&c.name))))
                     .collect();
             }
         }
@@ -835,9 +1522,10 @@ impl HomeViewModel {
     pub fn get_primary_classifications(&self) -> Vec<SharedString> {
         if let Some(mut repo) = self.get_classifications_repo() {
             if let Ok(classifications) = repo.find_by_parent_id(None) {
+                let names = self.classification_names();
                 return classifications.into_iter()
                     .filter(|c| c.active)
-                    .map(|c| SharedString::from(format!("{} - {}", c.code, c.name)))
+                    .map(|c| SharedString::from(format!("{} - {}", c.code, names.get(&c.id).unwrap_or(&c.name))))
                     .collect();
             }
         }
@@ -857,57 +1545,65 @@ impl HomeViewModel {
         Vec::new()
     }
 
+    /// Active classifications below each active primary classification, at any depth
+    ///
+    /// Pre-order with their depth below the primary; a deactivated node hides its subtree.
+    fn get_secondary_trees(&self) -> Vec<Vec<(usize, FondClassification)>> {
+        let Some(mut repo) = self.get_classifications_repo() else { return Vec::new() };
+        let Ok(primary_classifications) = repo.find_by_parent_id(None) else { return Vec::new() };
+        primary_classifications
+            .into_iter()
+            .filter(|c| c.active)
+            .map(|primary| {
+                let mut hidden_below: Option<usize> = None;
+                repo.find_tree(Some(primary.id))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(depth, c)| {
+                        if hidden_below.is_some_and(|hidden| *depth > hidden) {
+                            return false;
+                        }
+                        hidden_below = (!c.active).then_some(*depth);
+                        c.active
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
     /// Get secondary classifications for the add fonds dialog
     pub fn get_secondary_classifications(&self) -> Vec<Vec<SharedString>> {
-        if let Some(mut repo) = self.get_classifications_repo() {
-            if let Ok(primary_classifications) = repo.find_by_parent_id(None) {
-                let mut secondary_lists = Vec::new();
-                for primary in primary_classifications.into_iter().filter(|c| c.active) {
-                    if let Ok(secondary_classifications) = repo.find_by_parent_id(Some(primary.id)) {
-                        let secondary_list = secondary_classifications.into_iter()
-                            .filter(|c| c.active)
-                            .map(|c| SharedString::from(format!("{} - {}", c.code, c.name)))
-                            .collect();
-                        secondary_lists.push(secondary_list);
-                    } else {
-                        secondary_lists.push(Vec::new());
-                    }
-                }
-                return secondary_lists;
-            }
-        }
-        Vec::new()
+        let names = self.classification_names();
+        self.get_secondary_trees()
+            .into_iter()
+            .map(|tree| {
+                tree.into_iter()
+                    .map(|(depth, c)| {
+                        let name = names.get(&c.id).unwrap_or(&c.name);
+                        SharedString::from(format!("{}{} - {}", "　".repeat(depth), c.code, name))
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
     /// Get secondary codes for the add fonds dialog
     pub fn get_secondary_codes(&self) -> Vec<Vec<SharedString>> {
-        if let Some(mut repo) = self.get_classifications_repo() {
-            if let Ok(primary_classifications) = repo.find_by_parent_id(None) {
-                let mut secondary_lists = Vec::new();
-                for primary in primary_classifications.into_iter().filter(|c| c.active) {
-                    if let Ok(secondary_classifications) = repo.find_by_parent_id(Some(primary.id)) {
-                        let secondary_list = secondary_classifications.into_iter()
-                            .filter(|c| c.active)
-                            .map(|c| SharedString::from(c.code))
-                            .collect();
-                        secondary_lists.push(secondary_list);
-                    } else {
-                        secondary_lists.push(Vec::new());
-                    }
-                }
-                return secondary_lists;
-            }
-        }
-        Vec::new()
+        self.get_secondary_trees()
+            .into_iter()
+            .map(|tree| tree.into_iter().map(|(_, c)| SharedString::from(c.code)).collect())
+            .collect()
     }
 
     /// Get schema options for the add fonds dialog
     pub fn get_schema_options(&self) -> Vec<slint_generatedAppWindow::FondsSchemaOption> {
-        if let Some(mut repo) = self.get_schema_repo() {
+        if let (Some(mut repo), Some(mut items_repo)) = (self.get_schema_repo(), self.get_schema_items_repo()) {
             if let Ok(schemas) = repo.find_all() {
                 return schemas.into_iter()
                     .map(|s| slint_generatedAppWindow::FondsSchemaOption {
                         id: s.id,
+                        levels: ModelRc::new(VecModel::from(Self::schema_level_labels(&mut items_repo, s.id))),
+                        level: 0,
                         schema_no: s.schema_no.into(),
                         name: s.name.into(),
                     })
@@ -917,6 +1613,19 @@ impl HomeViewModel {
         Vec::new()
     }
 
+    /// Levels a nested schema can expand to: leaf items first, then each level above the deepest one;
+    /// empty for a flat schema
+    fn schema_level_labels(items_repo: &mut SchemaItemRepository, schema_id: i32) -> Vec<SharedString> {
+        let depth = items_repo.find_tree_by_schema_id(schema_id).unwrap_or_default().iter().map(|(depth, _)| *depth).max().unwrap_or(0);
+        if depth == 0 {
+            return Vec::new();
+        }
+        std::iter::once(gettext_tr("label_leaf_items"))
+            .chain((1..=depth).map(|level| gettext_tr("label_item_level").replace("{n}", &level.to_string())))
+            .map(SharedString::from)
+            .collect()
+    }
+
     /// Initialize UI with current data
     pub fn init_ui(&self, ui_handle: &AppWindow) {
         // Set library names
@@ -946,6 +1655,7 @@ impl HomeViewModel {
                 title: s.name.clone().into(),
                 subtitle: s.series_no.clone().into(),
                 active: true,
+                detail: s.change_summary().into(),
             })
             .collect();
         let series_model = ModelRc::new(VecModel::from(series_items));
@@ -960,11 +1670,13 @@ impl HomeViewModel {
                 title: f.name.clone().into(),
                 subtitle: f.file_no.clone().into(),
                 active: true,
+                detail: f.change_summary().into(),
             })
             .collect();
         let files_model = ModelRc::new(VecModel::from(files_items));
         ui_handle.set_files_list_items(files_model);
         ui_handle.set_selected_file(self.selected_file);
+        ui_handle.set_file_filter(self.file_filter.clone().into());
 
         // Set items list items
         let items_items: Vec<CrudListItem> = self.items_list.iter()
@@ -973,12 +1685,16 @@ impl HomeViewModel {
                 title: i.name.clone().into(),
                 subtitle: i.item_no.clone().into(),
                 active: true,
+                detail: i.change_summary().into(),
             })
             .collect();
         let items_model = ModelRc::new(VecModel::from(items_items));
         ui_handle.set_items_list_items(items_model);
         ui_handle.set_selected_item(self.selected_item);
 
+        // Set stocktake state
+        ui_handle.set_stocktake_active(self.stocktake_active);
+
         // Set dialog states
         ui_handle.set_show_add_file_dialog(self.show_add_file_dialog);
         
@@ -1060,7 +1776,11 @@ impl HomeViewModel {
     pub fn setup_ui_callbacks(&self, ui_handle: &AppWindow, vm: Rc<RefCell<Self>>) {
         let ui_weak = ui_handle.as_weak();
         
-        // Archive selected callback
+        // Archive selected callback; the scheduled backup and fixity check run in the background and report when done
+        let backup_job: Rc<RefCell<Option<BackgroundJob<Option<PathBuf>>>>> = Rc::new(RefCell::new(None));
+        let backup_timer = Rc::new(slint::Timer::default());
+        let fixity_job: Rc<RefCell<Option<BackgroundJob<FixityReport>>>> = Rc::new(RefCell::new(None));
+        let fixity_timer = Rc::new(slint::Timer::default());
         ui_handle.on_archive_selected({
             let vm = Rc::clone(&vm);
             let ui_weak = ui_weak.clone();
@@ -1073,6 +1793,91 @@ impl HomeViewModel {
                         if let Some(ui) = ui_weak.upgrade() {
                             vm.init_ui(&ui);
                         }
+                        // 定期备份，上一次备份仍在进行时不再启动
+                        if backup_job.borrow().is_none() {
+                            match vm.start_scheduled_backup() {
+                                Ok(Some(job)) => {
+                                    *backup_job.borrow_mut() = Some(job);
+                                    let ui_weak = ui_weak.clone();
+                                    let backup_job = Rc::clone(&backup_job);
+                                    let timer_weak = Rc::downgrade(&backup_timer);
+                                    backup_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(200), move || {
+                                        let finished = match backup_job.borrow().as_ref() {
+                                            Some(job) => job.try_finish(),
+                                            None => return,
+                                        };
+                                        let Some(result) = finished else { return };
+                                        *backup_job.borrow_mut() = None;
+                                        if let Some(timer) = timer_weak.upgrade() {
+                                            timer.stop();
+                                        }
+                                        let message = match result {
+                                            Ok(Some(path)) => {
+                                                log::info!("Scheduled backup written to {:?}", path);
+                                                format!("定期备份已完成: {}", path.display())
+                                            }
+                                            Ok(None) => return,
+                                            Err(e) => {
+                                                log::error!("Scheduled backup failed: {}", e);
+                                                format!("定期备份失败: {}", e)
+                                            }
+                                        };
+                                        if let Some(ui) = ui_weak.upgrade() {
+                                            ui.invoke_show_toast(message.into());
+                                        }
+                                    });
+                                }
+                                Ok(None) => {}
+                                Err(e) => {
+                                    log::error!("Scheduled backup failed: {}", e);
+                                    if let Some(ui) = ui_weak.upgrade() {
+                                        ui.invoke_show_toast(format!("定期备份失败: {}", e).into());
+                                    }
+                                }
+                            }
+                        }
+                        // 定期完整性校验，上一次校验仍在进行时不再启动
+                        if fixity_job.borrow().is_some() {
+                            return;
+                        }
+                        match vm.start_scheduled_fixity_check() {
+                            Ok(Some(job)) => *fixity_job.borrow_mut() = Some(job),
+                            Ok(None) => return,
+                            Err(e) => {
+                                log::error!("Scheduled fixity check failed: {}", e);
+                                return;
+                            }
+                        }
+                        let ui_weak = ui_weak.clone();
+                        let fixity_job = Rc::clone(&fixity_job);
+                        let timer_weak = Rc::downgrade(&fixity_timer);
+                        fixity_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(200), move || {
+                            let finished = match fixity_job.borrow().as_ref() {
+                                Some(job) => job.try_finish(),
+                                None => return,
+                            };
+                            let Some(result) = finished else { return };
+                            *fixity_job.borrow_mut() = None;
+                            if let Some(timer) = timer_weak.upgrade() {
+                                timer.stop();
+                            }
+                            match result {
+                                Ok(report) => {
+                                    log::info!("Scheduled fixity check finished: {}", report.summary());
+                                    if !report.is_clean() {
+                                        if let Some(ui) = ui_weak.upgrade() {
+                                            ui.invoke_show_toast(format!(
+                                                "定期完整性校验发现问题：变化 {}，缺失 {}，新增 {}",
+                                                report.changed.len(),
+                                                report.missing.len(),
+                                                report.new_files.len()
+                                            ).into());
+                                        }
+                                    }
+                                }
+                                Err(e) => log::error!("Scheduled fixity check failed: {}", e),
+                            }
+                        });
                     }
                 }
             }
@@ -1151,12 +1956,7 @@ impl HomeViewModel {
                 if let Ok(vm_ref) = vm.try_borrow() {
                     if let Some(ui) = ui_weak.upgrade() {
                         // Prepare dialog data via ui properties
-                        let schemas = vm_ref.get_schema_options();
-                        let available: Vec<_> = schemas.into_iter().map(|s| slint_generatedAppWindow::FondsSchemaOption {
-                            id: s.id,
-                            schema_no: s.schema_no,
-                            name: s.name,
-                        }).collect();
+                        let available = vm_ref.get_schema_options();
                         
                         let primary_classifications = vm_ref.get_primary_classifications();
                         let primary_codes = vm_ref.get_primary_codes();
@@ -1235,9 +2035,11 @@ impl HomeViewModel {
                         "".to_string()
                     };
                     
-                    // Convert selected_schemas to Vec<String> (extract schema_no)
-                    let schema_nos: Vec<String> = selected_schemas.iter().map(|s| s.schema_no.to_string()).collect();
-                    if let Err(e) = vm.add_fond(&fonds_name, &actual_code, schema_nos) {
+                    // Convert selected_schemas to (schema_no, item_level); level index 0 is leaf items
+                    let schemas: Vec<(String, Option<i32>)> = selected_schemas.iter()
+                        .map(|s| (s.schema_no.to_string(), (s.level > 0).then_some(s.level - 1)))
+                        .collect();
+                    if let Err(e) = vm.add_fond(&fonds_name, &actual_code, schemas) {
                         if let Some(ui) = ui_weak.upgrade() {
                             ui.invoke_show_toast(format!("添加全宗失败: {}", e).into());
                         }
@@ -1293,6 +2095,20 @@ impl HomeViewModel {
             }
         });
 
+        // Set chosen schema level callback
+        ui_handle.on_set_chosen_schema_level({
+            let ui_weak = ui_weak.clone();
+            move |index, level| {
+                if let Some(ui) = ui_weak.upgrade() {
+                    let chosen = ui.get_selected_schemas();
+                    if let Some(mut item) = chosen.row_data(index as usize) {
+                        item.level = level;
+                        chosen.set_row_data(index as usize, item);
+                    }
+                }
+            }
+        });
+
         // Move schema back callback
         ui_handle.on_move_schema_back({
             let ui_weak = ui_weak.clone();
@@ -1380,6 +2196,21 @@ impl HomeViewModel {
             let ui_weak = ui_weak.clone();
             move || {
                 if let Ok(mut vm) = vm.try_borrow_mut() {
+                    // 案卷下的文件一并删除，先确认
+                    if let Ok(contents) = vm.selected_file_contents() {
+                        if !contents.is_empty() {
+                            use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+                            let confirmed = MessageDialog::new()
+                                .set_level(MessageLevel::Warning)
+                                .set_title("删除案卷")
+                                .set_description(format!("该案卷有 {}，将一并删除。是否继续？", contents.describe()))
+                                .set_buttons(MessageButtons::YesNo)
+                                .show();
+                            if !matches!(confirmed, MessageDialogResult::Yes) {
+                                return;
+                            }
+                        }
+                    }
                     if let Err(e) = vm.delete_file() {
                         log::error!("Failed to delete file: {}", e);
                         if let Some(ui) = ui_weak.upgrade() {
@@ -1412,10 +2243,16 @@ impl HomeViewModel {
             }
         });
 
+        // Item payloads are ingested in the background; the add callbacks share one job and its poll timer
+        let adding: Rc<RefCell<Option<AddItemJob>>> = Rc::new(RefCell::new(None));
+        let add_timer = Rc::new(slint::Timer::default());
+
         // Add item callback
         ui_handle.on_add_item({
             let vm = Rc::clone(&vm);
             let ui_weak = ui_weak.clone();
+            let adding = Rc::clone(&adding);
+            let add_timer = Rc::clone(&add_timer);
             move || {
                 // Show folder picker dialog
                 use rfd::FileDialog;
@@ -1429,17 +2266,7 @@ impl HomeViewModel {
                         .to_string();
                     let path_str = folder_path.to_string_lossy().to_string();
                     
-                    if let Ok(mut vm) = vm.try_borrow_mut() {
-                        if let Err(e) = vm.add_item(&folder_name, Some(path_str)) {
-                            log::error!("Failed to add item: {}", e);
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.invoke_show_toast(format!("添加文件失败: {}", e).into());
-                            }
-                        } else if let Some(ui) = ui_weak.upgrade() {
-                            vm.init_ui(&ui);
-                            ui.invoke_show_toast("文件添加成功".into());
-                        }
-                    }
+                    HomeViewModel::spawn_add_item(&vm, &ui_weak, &adding, &add_timer, &folder_name, Some(path_str));
                 }
             }
         });
@@ -1448,6 +2275,8 @@ impl HomeViewModel {
         ui_handle.on_add_folder_item({
             let vm = Rc::clone(&vm);
             let ui_weak = ui_weak.clone();
+            let adding = Rc::clone(&adding);
+            let add_timer = Rc::clone(&add_timer);
             move || {
                 // Show folder picker dialog
                 use rfd::FileDialog;
@@ -1461,17 +2290,7 @@ impl HomeViewModel {
                         .to_string();
                     let path_str = folder_path.to_string_lossy().to_string();
                     
-                    if let Ok(mut vm) = vm.try_borrow_mut() {
-                        if let Err(e) = vm.add_item(&folder_name, Some(path_str)) {
-                            log::error!("Failed to add folder item: {}", e);
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.invoke_show_toast(format!("添加文件夹失败: {}", e).into());
-                            }
-                        } else if let Some(ui) = ui_weak.upgrade() {
-                            vm.init_ui(&ui);
-                            ui.invoke_show_toast("文件夹添加成功".into());
-                        }
-                    }
+                    HomeViewModel::spawn_add_item(&vm, &ui_weak, &adding, &add_timer, &folder_name, Some(path_str));
                 }
             }
         });
@@ -1626,6 +2445,8 @@ impl HomeViewModel {
         ui_handle.on_confirm_add_item({
             let vm = Rc::clone(&vm);
             let ui_weak = ui_weak.clone();
+            let adding = Rc::clone(&adding);
+            let add_timer = Rc::clone(&add_timer);
             move |fields| {
                 let item_name = if fields.row_count() >= 1 {
                     fields.row_data(0).unwrap().value.to_string()
@@ -1649,18 +2470,11 @@ impl HomeViewModel {
                     return;
                 }
                 
+                // Add item; the dialog stays open while another ingest is still running
+                if !HomeViewModel::spawn_add_item(&vm, &ui_weak, &adding, &add_timer, &item_name, Some(item_path)) {
+                    return;
+                }
                 if let Ok(mut vm) = vm.try_borrow_mut() {
-                    // Add item
-                    if let Err(e) = vm.add_item(&item_name, Some(item_path)) {
-                        log::error!("Failed to add item: {}", e);
-                        if let Some(ui) = ui_weak.upgrade() {
-                            ui.invoke_show_toast(format!("添加文件失败: {}", e).into());
-                        }
-                    } else if let Some(ui) = ui_weak.upgrade() {
-                        vm.init_ui(&ui);
-                        ui.invoke_show_toast("文件添加成功".into());
-                    }
-                    
                     vm.show_add_item_dialog = false;
                     vm.new_item_name.clear();
                     vm.new_item_path.clear();
@@ -1840,6 +2654,706 @@ impl HomeViewModel {
             }
         });
         
+        // Scan code callback - keyboard-wedge scanners type the code and press Enter
+        ui_handle.on_scan_code({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move |code| {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    if vm.stocktake_active {
+                        vm.record_stocktake_scan(&code);
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.invoke_show_toast(format!("已扫描 {} 项", vm.stocktake_scans.len()).into());
+                        }
+                        return;
+                    }
+                    match vm.select_by_code(&code) {
+                        Ok(_) => {
+                            if let Some(ui) = ui_weak.upgrade() {
+                                vm.init_ui(&ui);
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to resolve scanned code '{}': {}", code, e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("未找到编号: {}", code.trim()).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Files filter callback - narrows the files list and the catalogue exports
+        ui_handle.on_file_filter_changed({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move |filter| {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    if let Err(e) = vm.set_file_filter(&filter) {
+                        log::error!("Failed to filter files: {}", e);
+                        return;
+                    }
+                    if let Some(ui) = ui_weak.upgrade() {
+                        vm.init_ui(&ui);
+                    }
+                }
+            }
+        });
+
+        // Toggle stocktake callback - start a session, or finish it and save the report
+        ui_handle.on_toggle_stocktake({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    if !vm.stocktake_active {
+                        if let Err(e) = vm.start_stocktake() {
+                            log::error!("Failed to start stocktake: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("开始盘点失败: {}", e).into());
+                            }
+                        } else if let Some(ui) = ui_weak.upgrade() {
+                            vm.init_ui(&ui);
+                            ui.invoke_show_toast("盘点开始，请扫描架上案卷".into());
+                        }
+                        return;
+                    }
+
+                    match vm.finish_stocktake() {
+                        Ok(report) => {
+                            use rfd::FileDialog;
+                            if let Some(path) = FileDialog::new()
+                                .add_filter("CSV files", &["csv"])
+                                .set_file_name("stocktake_report.csv")
+                                .save_file() {
+                                if let Err(e) = std::fs::write(&path, report.to_csv()) {
+                                    log::error!("Failed to write stocktake report {:?}: {}", path, e);
+                                }
+                            }
+                            if let Some(ui) = ui_weak.upgrade() {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(format!(
+                                    "盘点完成：应有 {}，已找到 {}，缺失 {}",
+                                    report.expected.len(),
+                                    report.found.len(),
+                                    report.missing.len()
+                                ).into());
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to finish stocktake: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("盘点失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Fixity check callback (on demand)
+        ui_handle.on_run_fixity_check({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    match vm.run_fixity_check() {
+                        Ok(report) => {
+                            if !report.is_clean() {
+                                use rfd::FileDialog;
+                                if let Some(path) = FileDialog::new()
+                                    .add_filter("CSV files", &["csv"])
+                                    .set_file_name("fixity_report.csv")
+                                    .save_file() {
+                                    if let Err(e) = std::fs::write(&path, report.to_csv()) {
+                                        log::error!("Failed to write fixity report {:?}: {}", path, e);
+                                    }
+                                }
+                            }
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!(
+                                    "完整性校验完成：已校验 {}，变化 {}，缺失 {}，新增 {}",
+                                    report.checked,
+                                    report.changed.len(),
+                                    report.missing.len(),
+                                    report.new_files.len()
+                                ).into());
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Fixity check failed: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("完整性校验失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Integrity check callback: report, then offer the suggested repairs
+        ui_handle.on_check_integrity({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    let report = match vm.check_integrity() {
+                        Ok(report) => report,
+                        Err(e) => {
+                            log::error!("Integrity check failed: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("一致性检查失败: {}", e).into());
+                            }
+                            return;
+                        }
+                    };
+                    if report.is_clean() {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.invoke_show_toast("一致性检查完成：数据库与磁盘一致".into());
+                        }
+                        return;
+                    }
+
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    if let Some(path) = FileDialog::new()
+                        .add_filter("CSV files", &["csv"])
+                        .set_file_name("integrity_report.csv")
+                        .save_file() {
+                        if let Err(e) = std::fs::write(&path, report.to_csv()) {
+                            log::error!("Failed to write integrity report {:?}: {}", path, e);
+                        }
+                    }
+
+                    let repairable = report.issues.iter().filter(|i| !i.repairs().is_empty()).count();
+                    let mut message = format!("发现 {} 个问题，其中 {} 个可自动修复。", report.issues.len(), repairable);
+                    for issue in report.issues.iter().take(10) {
+                        message.push_str(&format!("\n• {}", issue));
+                    }
+                    if repairable > 0 {
+                        message.push_str("\n\n是否逐项选择修复方式？需要删除记录的问题排在前面。");
+                    }
+                    let confirmed = MessageDialog::new()
+                        .set_title("一致性检查")
+                        .set_description(message)
+                        .set_buttons(if repairable > 0 { MessageButtons::YesNo } else { MessageButtons::Ok })
+                        .show();
+                    if repairable == 0 || !matches!(confirmed, MessageDialogResult::Yes) {
+                        return;
+                    }
+
+                    match vm.repair_integrity() {
+                        Ok(applied) => {
+                            if let Some(ui) = ui_weak.upgrade() {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(format!("已应用 {} 项修复", applied).into());
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Integrity repair failed: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("修复失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Relink callback: fix paths pointing outside the library after a drive or share moved
+        ui_handle.on_relink_paths({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    let broken = vm.get_library_path_service()
+                        .map(|p| p.external_paths().unwrap_or_default())
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|e| !e.exists)
+                        .count();
+                    if broken == 0 {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.invoke_show_toast("没有需要重新链接的库外路径".into());
+                        }
+                        return;
+                    }
+
+                    use rfd::FileDialog;
+                    let Some(search_root) = FileDialog::new()
+                        .set_title(format!("{} 个库外路径失效，请选择新的位置", broken))
+                        .pick_folder() else { return };
+                    match vm.relink_external_paths(&search_root) {
+                        Ok(report) => {
+                            if let Some(ui) = ui_weak.upgrade() {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(report.summary().into());
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to relink paths: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("重新链接失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Backup callback: archive the current library into a single file
+        ui_handle.on_backup_library({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    let now = chrono::Local::now().naive_local();
+                    let file_name = BackupService::new(&vm.last_opened_library).backup_file_name(now, BackupFormat::Zip);
+                    let Some(destination) = FileDialog::new()
+                        .add_filter("ZIP", &["zip"])
+                        .add_filter("tar.zst", &["zst", "tzst"])
+                        .set_file_name(&file_name)
+                        .save_file() else { return };
+                    let include_payloads = matches!(
+                        MessageDialog::new()
+                            .set_title("备份档案库")
+                            .set_description("是否同时备份档案文件？选择“否”仅备份数据库。")
+                            .set_buttons(MessageButtons::YesNo)
+                            .show(),
+                        MessageDialogResult::Yes
+                    );
+                    let result = vm.backup_library(&destination, include_payloads);
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match result {
+                            Ok(manifest) => ui.invoke_show_toast(format!("备份完成：{} 个文件", manifest.entries.len()).into()),
+                            Err(e) => {
+                                log::error!("Backup failed: {}", e);
+                                ui.invoke_show_toast(format!("备份失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Restore callback: verify an archive and unpack it into a new or existing library folder
+        ui_handle.on_restore_library({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    let Some(archive) = FileDialog::new()
+                        .add_filter("Backup", &["zip", "zst", "tzst"])
+                        .pick_file() else { return };
+                    let Some(target) = FileDialog::new()
+                        .set_title("选择恢复到的档案库目录")
+                        .pick_folder() else { return };
+                    let overwrite = target.join(".fondspod.db").exists();
+                    if overwrite {
+                        let confirmed = MessageDialog::new()
+                            .set_title("恢复档案库")
+                            .set_description(format!("{} 已是档案库，恢复将覆盖其中的数据库和同名文件。是否继续？", target.display()))
+                            .set_buttons(MessageButtons::YesNo)
+                            .show();
+                        if !matches!(confirmed, MessageDialogResult::Yes) {
+                            return;
+                        }
+                    }
+                    let result = vm.restore_library(&archive, &target, overwrite);
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match result {
+                            Ok(manifest) => {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(format!("恢复完成并已校验：{} 个文件", manifest.entries.len()).into());
+                            }
+                            Err(e) => {
+                                log::error!("Restore failed: {}", e);
+                                ui.invoke_show_toast(format!("恢复失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Catalogue export callback: 全宗目录, 案卷目录 or 卷内目录 of the current view to XLSX or CSV
+        ui_handle.on_export_catalogue({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(vm) = vm.try_borrow() {
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    let fond_level = MessageDialog::new()
+                        .set_title("导出目录")
+                        .set_description("导出全宗目录？选择“否”可导出当前系列的案卷目录或当前案卷的卷内目录。")
+                        .set_buttons(MessageButtons::YesNoCancel)
+                        .show();
+                    let level = match fond_level {
+                        MessageDialogResult::Yes => CatalogueLevel::Fond,
+                        MessageDialogResult::No => {
+                            let series_level = MessageDialog::new()
+                                .set_title("导出目录")
+                                .set_description("导出案卷目录？选择“否”导出卷内目录。")
+                                .set_buttons(MessageButtons::YesNo)
+                                .show();
+                            if matches!(series_level, MessageDialogResult::Yes) { CatalogueLevel::Series } else { CatalogueLevel::File }
+                        }
+                        _ => return,
+                    };
+                    let Some(path) = FileDialog::new()
+                        .add_filter("Excel", &["xlsx"])
+                        .add_filter("CSV files", &["csv"])
+                        .set_file_name(format!("{}.xlsx", level.title()))
+                        .save_file() else { return };
+                    let result = vm.export_catalogue(level, &path);
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match result {
+                            Ok(rows) => ui.invoke_show_toast(format!("已导出{}：{} 行", level.title(), rows).into()),
+                            Err(e) => {
+                                log::error!("Catalogue export failed: {}", e);
+                                ui.invoke_show_toast(format!("导出失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // BagIt export callback: hand the selected fond, series or file over as a BagIt package
+        ui_handle.on_export_bag({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(vm) = vm.try_borrow() {
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    let Some(fond) = usize::try_from(vm.selected_fonds_index).ok().and_then(|i| vm.fonds_list.get(i)) else {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.invoke_show_toast("请先选择全宗".into());
+                        }
+                        return;
+                    };
+                    let series = usize::try_from(vm.selected_series_index).ok().and_then(|i| vm.series_list.get(i));
+                    let file = usize::try_from(vm.selected_file).ok().and_then(|i| vm.files_list.get(i));
+
+                    // 先问是否导出整个全宗，否则再在系列和案卷之间选择
+                    let whole_fond = MessageDialog::new()
+                        .set_title("导出 BagIt 包")
+                        .set_description(format!("导出整个全宗 {}？选择“否”可只导出当前系列或案卷。", fond.fond_no))
+                        .set_buttons(MessageButtons::YesNoCancel)
+                        .show();
+                    let scope = match whole_fond {
+                        MessageDialogResult::Yes => BagScope::Fond(fond.id),
+                        MessageDialogResult::No => match (series, file) {
+                            (Some(series), Some(file)) => {
+                                let whole_series = MessageDialog::new()
+                                    .set_title("导出 BagIt 包")
+                                    .set_description(format!("导出系列 {}？选择“否”只导出案卷 {}。", series.series_no, file.file_no))
+                                    .set_buttons(MessageButtons::YesNo)
+                                    .show();
+                                if matches!(whole_series, MessageDialogResult::Yes) { BagScope::Series(series.id) } else { BagScope::File(file.id) }
+                            }
+                            (Some(series), None) => BagScope::Series(series.id),
+                            _ => return,
+                        },
+                        _ => return,
+                    };
+                    let Some(destination) = FileDialog::new()
+                        .set_title("选择 BagIt 包的保存位置")
+                        .pick_folder() else { return };
+                    let result = vm.export_bag(scope, &destination);
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match result {
+                            Ok(bag) => ui.invoke_show_toast(format!("已导出 BagIt 包: {}", bag.display()).into()),
+                            Err(e) => {
+                                log::error!("BagIt export failed: {}", e);
+                                ui.invoke_show_toast(format!("导出失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // BagIt import callback: validate a package and recreate its hierarchy in the current library
+        ui_handle.on_import_bag({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    use rfd::FileDialog;
+                    let Some(bag) = FileDialog::new()
+                        .set_title("选择 BagIt 包目录")
+                        .pick_folder() else { return };
+                    let result = vm.import_bag(&bag);
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match result {
+                            Ok(report) => {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(report.summary().into());
+                            }
+                            Err(e) => {
+                                log::error!("BagIt import failed: {}", e);
+                                ui.invoke_show_toast(format!("导入失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Register import callback: preview a CSV/XLSX register and create its files and items
+        ui_handle.on_import_register({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    let Some(path) = FileDialog::new()
+                        .set_title("选择登记表")
+                        .add_filter("登记表", &["xlsx", "csv"])
+                        .pick_file() else { return };
+                    let keep = MessageDialog::new()
+                        .set_title("导入登记表")
+                        .set_description("保留登记表中的案卷号和文件号？选择“否”由系统重新分配编号。")
+                        .set_buttons(MessageButtons::YesNoCancel)
+                        .show();
+                    let mode = match keep {
+                        MessageDialogResult::Yes => NumberMode::Keep,
+                        MessageDialogResult::No => NumberMode::Allocate,
+                        _ => return,
+                    };
+                    let preview = match vm.preview_register(&path, mode) {
+                        Ok(preview) => preview,
+                        Err(e) => {
+                            log::error!("Register preview failed: {}", e);
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("读取登记表失败: {}", e).into());
+                            }
+                            return;
+                        }
+                    };
+
+                    // 有错误时只允许保存预览结果，逐行修正后再导入
+                    if !preview.is_valid() {
+                        let save = MessageDialog::new()
+                            .set_title("登记表校验失败")
+                            .set_description(format!("{}。是否保存逐行校验结果？", preview.summary()))
+                            .set_buttons(MessageButtons::YesNo)
+                            .show();
+                        if matches!(save, MessageDialogResult::Yes) {
+                            if let Some(report) = FileDialog::new()
+                                .add_filter("CSV files", &["csv"])
+                                .set_file_name("登记表校验结果.csv")
+                                .save_file()
+                            {
+                                if let Err(e) = std::fs::write(&report, preview.to_csv()) {
+                                    log::error!("Failed to write register preview: {}", e);
+                                }
+                            }
+                        }
+                        return;
+                    }
+
+                    let mut description = preview.summary();
+                    if !preview.missing_series.is_empty() {
+                        let missing: Vec<&str> = preview.missing_series.iter().map(String::as_str).collect();
+                        description.push_str(&format!("\n将按方案生成缺失的系列: {}", missing.join(", ")));
+                    }
+                    let confirm = MessageDialog::new()
+                        .set_title("确认导入登记表")
+                        .set_description(format!("{}\n确认导入？", description))
+                        .set_buttons(MessageButtons::YesNo)
+                        .show();
+                    if !matches!(confirm, MessageDialogResult::Yes) {
+                        return;
+                    }
+                    let result = vm.import_register(&path, mode);
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match result {
+                            Ok(report) => {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(format!("已导入 {} 个案卷、{} 个文件", report.files_created, report.items_created).into());
+                            }
+                            Err(e) => {
+                                log::error!("Register import failed: {}", e);
+                                ui.invoke_show_toast(format!("导入失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        // Directory import callback: scan a folder tree in the background, review the mapping, then import it
+        // into the selected series; clicking again while a job runs offers to cancel it
+        {
+            let job: Rc<RefCell<Option<DirectoryJob>>> = Rc::new(RefCell::new(None));
+            let timer = Rc::new(slint::Timer::default());
+            ui_handle.on_import_directory({
+                let vm = Rc::clone(&vm);
+                let ui_weak = ui_weak.clone();
+                move || {
+                    use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
+                    if let Some(running) = job.borrow().as_ref() {
+                        let cancel = MessageDialog::new()
+                            .set_title("导入目录")
+                            .set_description("目录导入正在进行，是否取消？已导入的文件会保留。")
+                            .set_buttons(MessageButtons::YesNo)
+                            .show();
+                        if matches!(cancel, MessageDialogResult::Yes) {
+                            match running {
+                                DirectoryJob::Scan { job, .. } => job.cancel(),
+                                DirectoryJob::Import(job) => job.cancel(),
+                            }
+                        }
+                        return;
+                    }
+
+                    let Ok(home) = vm.try_borrow() else { return };
+                    let Some(series) = usize::try_from(home.selected_series_index).ok().and_then(|i| home.series_list.get(i)) else {
+                        if let Some(ui) = ui_weak.upgrade() {
+                            ui.invoke_show_toast("请先选择要导入到的系列".into());
+                        }
+                        return;
+                    };
+                    let series_id = series.id;
+                    drop(home);
+                    let Some(root) = FileDialog::new()
+                        .set_title("选择要导入的目录")
+                        .pick_folder() else { return };
+                    match DirectoryImportService::spawn_scan(root) {
+                        Ok(scan) => *job.borrow_mut() = Some(DirectoryJob::Scan { job: scan, series_id }),
+                        Err(e) => {
+                            log::error!("Failed to start directory scan: {}", e);
+                            return;
+                        }
+                    }
+
+                    let vm = Rc::clone(&vm);
+                    let ui_weak = ui_weak.clone();
+                    let job = Rc::clone(&job);
+                    let timer_weak = Rc::downgrade(&timer);
+                    let mut ticks = 0u32;
+                    timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(200), move || {
+                        let Some(ui) = ui_weak.upgrade() else { return };
+                        ticks += 1;
+                        let current = job.borrow_mut().take();
+                        match current {
+                            None => {
+                                if let Some(timer) = timer_weak.upgrade() {
+                                    timer.stop();
+                                }
+                            }
+                            Some(DirectoryJob::Scan { job: scan, series_id }) => match scan.try_finish() {
+                                None => {
+                                    if ticks.is_multiple_of(10) {
+                                        ui.invoke_show_toast(format!("正在扫描目录：已扫描 {} 个文件夹", scan.progress().done).into());
+                                    }
+                                    *job.borrow_mut() = Some(DirectoryJob::Scan { job: scan, series_id });
+                                }
+                                Some(Err(e)) => {
+                                    log::error!("Directory scan failed: {}", e);
+                                    ui.invoke_show_toast(format!("扫描失败: {}", e).into());
+                                }
+                                Some(Ok(mapping)) => {
+                                    let started = match HomeViewModel::review_directory_mapping(mapping) {
+                                        Ok(Some(mapping)) => match vm.try_borrow() {
+                                            Ok(vm) => vm.start_directory_import(mapping, series_id).map(Some),
+                                            Err(_) => Ok(None),
+                                        },
+                                        Ok(None) => Ok(None),
+                                        Err(e) => Err(e),
+                                    };
+                                    match started {
+                                        Ok(Some(import)) => *job.borrow_mut() = Some(DirectoryJob::Import(import)),
+                                        Ok(None) => {}
+                                        Err(e) => {
+                                            log::error!("Directory import failed: {}", e);
+                                            ui.invoke_show_toast(format!("导入失败: {}", e).into());
+                                        }
+                                    }
+                                }
+                            },
+                            Some(DirectoryJob::Import(import)) => match import.try_finish() {
+                                None => {
+                                    if ticks.is_multiple_of(10) {
+                                        let progress = import.progress();
+                                        ui.invoke_show_toast(format!("正在导入 {}/{}: {}", progress.done, progress.total, progress.current).into());
+                                    }
+                                    *job.borrow_mut() = Some(DirectoryJob::Import(import));
+                                }
+                                Some(result) => {
+                                    if let Ok(mut vm) = vm.try_borrow_mut() {
+                                        if let Err(e) = vm.load_fonds() {
+                                            log::error!("Failed to reload fonds after directory import: {}", e);
+                                        }
+                                        vm.init_ui(&ui);
+                                    }
+                                    match result {
+                                        Ok(report) => {
+                                            log::info!("Directory import finished: {}", report.summary());
+                                            ui.invoke_show_toast(report.summary().into());
+                                        }
+                                        Err(e) => {
+                                            log::error!("Directory import failed: {}", e);
+                                            ui.invoke_show_toast(format!("导入失败: {}", e).into());
+                                        }
+                                    }
+                                }
+                            },
+                        }
+                    });
+                }
+            });
+        }
+
+        // Recovery callback: rebuild the library database from its sidecars, or write sidecars for an older library
+        ui_handle.on_rebuild_library({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    use rfd::{MessageButtons, MessageDialog, MessageDialogResult};
+                    let found = match RecoveryService::new(&vm.last_opened_library).scan() {
+                        Ok(found) => found,
+                        Err(e) => {
+                            if let Some(ui) = ui_weak.upgrade() {
+                                ui.invoke_show_toast(format!("读取 sidecar 失败: {}", e).into());
+                            }
+                            return;
+                        }
+                    };
+                    let choice = MessageDialog::new()
+                        .set_title("重建数据库")
+                        .set_description(format!(
+                            "档案库目录中的 sidecar 记录了 {}。\n是否从 sidecar 重建数据库？当前数据库会被移到一旁保留。\n选择“否”为当前档案库的全部记录重新写入 sidecar。",
+                            found.summary()
+                        ))
+                        .set_buttons(MessageButtons::YesNoCancel)
+                        .show();
+                    let message = match choice {
+                        MessageDialogResult::Yes => vm.rebuild_library_database().map(|report| report.summary()),
+                        MessageDialogResult::No => vm.write_all_sidecars().map(|written| format!("已写入 {} 个 sidecar", written)),
+                        _ => return,
+                    };
+                    if let Some(ui) = ui_weak.upgrade() {
+                        match message {
+                            Ok(message) => {
+                                vm.init_ui(&ui);
+                                ui.invoke_show_toast(message.into());
+                            }
+                            Err(e) => {
+                                log::error!("Library recovery failed: {}", e);
+                                ui.invoke_show_toast(format!("操作失败: {}", e).into());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
         // Home page initialization callback
         ui_handle.on_initialize_home_page({
             let vm = Rc::clone(&vm);
@@ -1864,7 +3378,8 @@ impl HomeViewModel {
                 if let Ok(vm_ref) = vm.try_borrow() {
                     if let Some(file) = vm_ref.files_list.get(index as usize) {
                         if let Some(ref path) = file.path {
-                            if let Err(e) = open::that(path) {
+                            let path = vm_ref.resolve_path(path).to_string_lossy().to_string();
+                            if let Err(e) = open::that(&path) {
                                 log::error!("Failed to open file at path {}: {}", path, e);
                             } else {
                                 log::info!("Opened file: {}", path);
@@ -1884,7 +3399,8 @@ impl HomeViewModel {
                 if let Ok(vm_ref) = vm.try_borrow() {
                     if let Some(item) = vm_ref.items_list.get(index as usize) {
                         if let Some(ref path) = item.path {
-                            if let Err(e) = open::that(path) {
+                            let path = vm_ref.resolve_path(path).to_string_lossy().to_string();
+                            if let Err(e) = open::that(&path) {
                                 log::error!("Failed to open item at path {}: {}", path, e);
                             } else {
                                 log::info!("Opened item: {}", path);
@@ -1935,6 +3451,7 @@ impl Clone for HomeViewModel {
             selected_series_no: self.selected_series_no.clone(),
             files_list: self.files_list.clone(),
             selected_file: self.selected_file,
+            file_filter: self.file_filter.clone(),
             items_list: self.items_list.clone(),
             selected_item: self.selected_item,
             show_add_file_dialog: self.show_add_file_dialog,
@@ -1947,6 +3464,8 @@ impl Clone for HomeViewModel {
             rename_file_index: self.rename_file_index,
             show_rename_item_dialog: self.show_rename_item_dialog,
             rename_item_index: self.rename_item_index,
+            stocktake_active: self.stocktake_active,
+            stocktake_scans: self.stocktake_scans.clone(),
             settings_service: Rc::clone(&self.settings_service),
             db_connection: self.db_connection.as_ref().map(Rc::clone),
             current_db_path: self.current_db_path.clone(),
@@ -0,0 +1,309 @@
+use crate::core::{current_user, GenericRepository};
+use crate::models::borrower::Borrower;
+use crate::models::loan::{ApprovalStatus, Loan};
+use crate::persistence::{FilesRepository, ItemsRepository};
+use crate::services::LoanService;
+use crate::{AppWindow, CrudListItem, DialogField};
+use diesel::SqliteConnection;
+use slint::{ComponentHandle, Model, ModelRc, VecModel};
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+/// 借阅记录列表当前显示的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoanListMode {
+    /// 所选借阅人的借阅历史
+    History,
+    /// 全部逾期未还的借阅
+    Overdue,
+}
+
+/// Loan（借阅）管理ViewModel
+///
+/// 左侧为借阅人，右侧为所选借阅人的借阅历史或逾期清单；
+/// 登记借阅时按案卷号或文件号查找借出对象。
+pub struct LoanViewModel {
+    conn: Rc<RefCell<SqliteConnection>>,
+    pub borrowers: Vec<Borrower>,
+    pub loans: Vec<Loan>,
+    pub selected_borrower: i32,
+    pub selected_loan: i32,
+    pub mode: LoanListMode,
+}
+
+impl LoanViewModel {
+    /// 创建新的LoanViewModel实例
+    pub fn new(conn: Rc<RefCell<SqliteConnection>>) -> Self {
+        Self {
+            conn,
+            borrowers: Vec::new(),
+            loans: Vec::new(),
+            selected_borrower: -1,
+            selected_loan: -1,
+            mode: LoanListMode::History,
+        }
+    }
+
+    fn service(&self) -> LoanService {
+        LoanService::new(Rc::clone(&self.conn))
+    }
+
+    /// 更新数据库连接并重新加载数据
+    pub fn update_connection(&mut self, new_conn: Rc<RefCell<SqliteConnection>>) {
+        self.conn = new_conn;
+        self.selected_borrower = -1;
+        if let Err(e) = self.load() {
+            log::error!("LoanViewModel: Failed to load loans: {}", e);
+        }
+    }
+
+    /// 重新加载借阅人和当前借阅列表，保留所选借阅人
+    pub fn load(&mut self) -> Result<(), Box<dyn Error>> {
+        let selected_id = self.selected_borrower().map(|b| b.id);
+        self.borrowers = self.service().list_borrowers()?;
+        self.selected_borrower = match selected_id {
+            Some(id) => self.borrowers.iter().position(|b| b.id == id).map(|i| i as i32).unwrap_or(-1),
+            None if self.borrowers.is_empty() => -1,
+            None => 0,
+        };
+        self.load_loans()
+    }
+
+    fn load_loans(&mut self) -> Result<(), Box<dyn Error>> {
+        self.loans = match self.mode {
+            LoanListMode::Overdue => self.service().overdue(chrono::Utc::now().naive_utc())?,
+            LoanListMode::History => match self.selected_borrower() {
+                Some(borrower) => self.service().borrower_history(borrower.id)?,
+                None => Vec::new(),
+            },
+        };
+        self.selected_loan = -1;
+        Ok(())
+    }
+
+    pub fn selected_borrower(&self) -> Option<&Borrower> {
+        usize::try_from(self.selected_borrower).ok().and_then(|i| self.borrowers.get(i))
+    }
+
+    fn selected_loan(&self) -> Result<&Loan, Box<dyn Error>> {
+        Ok(usize::try_from(self.selected_loan).ok().and_then(|i| self.loans.get(i)).ok_or("请先选择借阅记录")?)
+    }
+
+    /// 选择借阅人并显示其借阅历史
+    pub fn select_borrower(&mut self, index: i32) -> Result<(), Box<dyn Error>> {
+        self.selected_borrower = index;
+        self.mode = LoanListMode::History;
+        self.load_loans()
+    }
+
+    /// 显示逾期清单，同时刷新借阅人以显示全部逾期借阅人的姓名
+    pub fn show_overdue(&mut self) -> Result<(), Box<dyn Error>> {
+        self.mode = LoanListMode::Overdue;
+        self.load()
+    }
+
+    /// 登记借阅人并选中
+    pub fn add_borrower(&mut self, name: &str, department: &str, contact: &str) -> Result<(), Box<dyn Error>> {
+        let id = self.service().add_borrower(name, department, contact)?;
+        self.borrowers = self.service().list_borrowers()?;
+        self.selected_borrower = self.borrowers.iter().position(|b| b.id == id).map(|i| i as i32).unwrap_or(-1);
+        self.mode = LoanListMode::History;
+        self.load_loans()
+    }
+
+    /// 为所选借阅人登记借阅，`number` 为案卷号或文件号，借期按天计
+    pub fn lend(&mut self, number: &str, purpose: &str, days: i64) -> Result<(), Box<dyn Error>> {
+        let borrower_id = self.selected_borrower().ok_or("请先选择借阅人")?.id;
+        let number = number.trim();
+        if days <= 0 {
+            return Err("借阅天数必须大于 0".into());
+        }
+        let now = chrono::Utc::now().naive_utc();
+        let due = now + chrono::Duration::days(days);
+        let service = self.service();
+        if let Some(file) = FilesRepository::new(Rc::clone(&self.conn)).find_by_file_no(number)? {
+            service.lend_file(borrower_id, file.id, purpose, now, due)?;
+        } else if let Some(item) = ItemsRepository::new(Rc::clone(&self.conn)).find_by_item_no(number)? {
+            service.lend_item(borrower_id, item.id, purpose, now, due)?;
+        } else {
+            return Err(format!("找不到案卷或文件 {}", number).into());
+        }
+        self.mode = LoanListMode::History;
+        self.load_loans()
+    }
+
+    /// 归还所选借阅
+    pub fn return_selected(&mut self) -> Result<(), Box<dyn Error>> {
+        let loan_id = self.selected_loan()?.id;
+        self.service().return_loan(loan_id, chrono::Utc::now().naive_utc())?;
+        self.load_loans()
+    }
+
+    /// 批准或驳回所选借阅申请
+    pub fn approve_selected(&mut self, approved: bool) -> Result<(), Box<dyn Error>> {
+        let loan_id = self.selected_loan()?.id;
+        let approver = current_user().unwrap_or_default();
+        if approved {
+            self.service().approve(loan_id, &approver)?;
+        } else {
+            self.service().reject(loan_id, &approver)?;
+        }
+        self.load_loans()
+    }
+
+    /// 借阅人列表
+    pub fn borrower_items(&self) -> Vec<CrudListItem> {
+        self.borrowers
+            .iter()
+            .map(|b| CrudListItem {
+                id: b.id,
+                title: b.name.clone().into(),
+                subtitle: b.department.clone().into(),
+                active: true,
+                detail: b.contact.clone().into(),
+            })
+            .collect()
+    }
+
+    /// 借阅记录列表：借出对象、借阅人与目的、日期和状态
+    pub fn loan_items(&self) -> Vec<CrudListItem> {
+        let now = chrono::Utc::now().naive_utc();
+        let mut files = FilesRepository::new(Rc::clone(&self.conn));
+        let mut items = ItemsRepository::new(Rc::clone(&self.conn));
+        self.loans
+            .iter()
+            .map(|loan| {
+                let target = match (loan.file_id, loan.item_id) {
+                    (Some(id), _) => files.find_by_id(id).ok().flatten().map(|f| format!("案卷 {} {}", f.file_no, f.name)),
+                    (_, Some(id)) => items.find_by_id(id).ok().flatten().map(|i| format!("文件 {} {}", i.item_no, i.name)),
+                    _ => None,
+                };
+                let borrower = self.borrowers.iter().find(|b| b.id == loan.borrower_id).map(|b| b.name.as_str()).unwrap_or("");
+                let status = match (loan.status(), loan.return_date) {
+                    (ApprovalStatus::Rejected, _) => "已驳回".to_string(),
+                    (_, Some(returned)) => format!("{} 归还", returned.format("%Y-%m-%d")),
+                    _ if loan.is_overdue(now) => "逾期未还".to_string(),
+                    (ApprovalStatus::Pending, None) => "待审批".to_string(),
+                    (ApprovalStatus::Approved, None) => "借出中".to_string(),
+                };
+                CrudListItem {
+                    id: loan.id,
+                    title: target.unwrap_or_else(|| "（已删除）".to_string()).into(),
+                    subtitle: format!("{} · {}", borrower, loan.purpose).into(),
+                    active: loan.is_open(),
+                    detail: format!("{} 借出 · {} 应还 · {}", loan.loan_date.format("%Y-%m-%d"), loan.due_date.format("%Y-%m-%d"), status).into(),
+                }
+            })
+            .collect()
+    }
+
+    /// 将借阅人和借阅记录写入UI
+    pub fn init_ui(&self, ui: &AppWindow) {
+        ui.set_loan_borrower_items(ModelRc::new(VecModel::from(self.borrower_items())));
+        ui.set_selected_loan_borrower(self.selected_borrower);
+        ui.set_loan_items(ModelRc::new(VecModel::from(self.loan_items())));
+        ui.set_selected_loan(self.selected_loan);
+        ui.set_loan_showing_overdue(self.mode == LoanListMode::Overdue);
+    }
+
+    /// 为UI设置借阅页回调
+    pub fn setup_callbacks(vm: Rc<RefCell<Self>>, ui_handle: &AppWindow) {
+        // 执行操作后刷新界面，失败时提示
+        fn run(vm: &Rc<RefCell<LoanViewModel>>, ui_weak: &slint::Weak<AppWindow>, action: &str, work: impl FnOnce(&mut LoanViewModel) -> Result<(), Box<dyn Error>>) -> bool {
+            let Some(ui) = ui_weak.upgrade() else { return false };
+            let Ok(mut vm) = vm.try_borrow_mut() else { return false };
+            let result = work(&mut vm);
+            vm.init_ui(&ui);
+            match result {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("LoanViewModel: {} failed: {}", action, e);
+                    ui.invoke_show_toast(format!("{}失败: {}", action, e).into());
+                    false
+                }
+            }
+        }
+        fn field(fields: &ModelRc<DialogField>, index: usize) -> String {
+            fields.row_data(index).map(|f| f.value.to_string()).unwrap_or_default()
+        }
+
+        let ui_weak = ui_handle.as_weak();
+
+        ui_handle.on_loan_borrower_selected({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move |index| {
+                run(&vm, &ui_weak, "加载借阅历史", |vm| vm.select_borrower(index));
+            }
+        });
+
+        ui_handle.on_loan_selected({
+            let vm = Rc::clone(&vm);
+            move |index| {
+                if let Ok(mut vm) = vm.try_borrow_mut() {
+                    vm.selected_loan = index;
+                }
+            }
+        });
+
+        ui_handle.on_show_overdue_loans({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                if run(&vm, &ui_weak, "加载逾期清单", LoanViewModel::show_overdue) {
+                    if let (Some(ui), Ok(vm)) = (ui_weak.upgrade(), vm.try_borrow()) {
+                        ui.invoke_show_toast(format!("逾期未还 {} 条", vm.loans.len()).into());
+                    }
+                }
+            }
+        });
+
+        ui_handle.on_confirm_add_borrower({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move |fields| {
+                let (name, department, contact) = (field(&fields, 0), field(&fields, 1), field(&fields, 2));
+                if run(&vm, &ui_weak, "登记借阅人", |vm| vm.add_borrower(&name, &department, &contact)) {
+                    if let Some(ui) = ui_weak.upgrade() {
+                        ui.set_show_add_borrower_dialog(false);
+                    }
+                }
+            }
+        });
+
+        ui_handle.on_confirm_add_loan({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move |fields| {
+                let (number, purpose, days) = (field(&fields, 0), field(&fields, 1), field(&fields, 2));
+                let lent = run(&vm, &ui_weak, "登记借阅", |vm| {
+                    let days = days.trim().parse::<i64>().map_err(|_| "借阅天数必须是整数")?;
+                    vm.lend(&number, &purpose, days)
+                });
+                if lent {
+                    if let Some(ui) = ui_weak.upgrade() {
+                        ui.set_show_add_loan_dialog(false);
+                        ui.invoke_show_toast("借阅已登记，待审批".into());
+                    }
+                }
+            }
+        });
+
+        ui_handle.on_return_loan({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move || {
+                run(&vm, &ui_weak, "归还", LoanViewModel::return_selected);
+            }
+        });
+
+        ui_handle.on_approve_loan({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_weak.clone();
+            move |approved| {
+                run(&vm, &ui_weak, if approved { "批准" } else { "驳回" }, |vm| vm.approve_selected(approved));
+            }
+        });
+    }
+}
@@ -15,3 +15,6 @@ pub use fond_vm::FondViewModel;
 
 pub mod fond_classification_vm;
 pub use fond_classification_vm::FondClassificationViewModel;
+
+pub mod loan_vm;
+pub use loan_vm::LoanViewModel;
@@ -1,7 +1,8 @@
 use crate::core::CrudViewModel;
 use crate::core::CrudViewModelBase;
 use crate::core::GenericRepository;
-use crate::models::schema::Schema;
+use crate::models::schema::{Schema, SchemaJson};
+use crate::services::{SchemaDiff, SchemaImportService, Usage, UsageService};
 use crate::persistence::schema_repository::SchemaRepository;
 use std::rc::Rc;
 use std::cell::RefCell;
@@ -85,6 +86,33 @@ impl SchemaViewModel {
         self.selected_index
     }
 
+    fn import_service(&self) -> SchemaImportService {
+        SchemaImportService::new(self.inner.borrow().get_repo().borrow().connection())
+    }
+
+    /// 导出方案及其条目到JSON或CSV文件（按扩展名，不含内置的 Year 方案）
+    pub fn export_schemas(&self, file_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let schemas = self.import_service().export()?;
+        SchemaImportService::write(std::path::Path::new(file_path), &schemas)?;
+        log::info!("Exported {} schemas to {}", schemas.len(), file_path);
+        Ok(schemas.len())
+    }
+
+    /// 读取导入文件并与现有方案比较，不写入数据库
+    pub fn preview_import(&self, file_path: &str) -> Result<(Vec<SchemaJson>, SchemaDiff), Box<dyn std::error::Error>> {
+        let schemas = SchemaImportService::read(std::path::Path::new(file_path))?;
+        let diff = self.import_service().preview(&schemas)?;
+        Ok((schemas, diff))
+    }
+
+    /// 按方案编号和条目编号合并导入的方案，不删除文件中没有的方案和条目
+    pub fn import_schemas(&mut self, schemas: &[SchemaJson]) -> Result<SchemaDiff, Box<dyn std::error::Error>> {
+        let diff = self.import_service().apply(schemas)?;
+        log::info!("Merged imported schemas: {}", diff.summary());
+        self.load();
+        Ok(diff)
+    }
+
     /// 为UI设置CRUD回调 - 标准实现在这里
     pub fn setup_callbacks(
         vm: Rc<RefCell<Self>>,
@@ -185,6 +213,86 @@ impl SchemaViewModel {
             }
         });
 
+        // Export schemas callback
+        let vm_clone = vm.clone();
+        let ui_weak = ui_handle.as_weak();
+        ui_handle.on_export_schemas(move || {
+            log::info!("SchemaViewModel::setup_callbacks: export schemas triggered");
+            use rfd::FileDialog;
+            if let Some(path) = FileDialog::new()
+                .add_filter("JSON files", &["json"])
+                .add_filter("CSV files", &["csv"])
+                .set_file_name("schemas_export.json")
+                .save_file() {
+                let message = match vm_clone.borrow().export_schemas(&path.to_string_lossy()) {
+                    Ok(count) => format!("已导出 {} 个方案", count),
+                    Err(e) => {
+                        log::error!("Failed to export schemas: {}", e);
+                        format!("方案导出失败: {}", e)
+                    }
+                };
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.invoke_show_toast(message.into());
+                }
+            }
+        });
+
+        // Import schemas callback - 先预览差异，确认后再合并
+        let vm_clone = vm.clone();
+        let schema_item_vm_clone = Rc::clone(&schema_item_vm);
+        let ui_weak = ui_handle.as_weak();
+        ui_handle.on_import_schemas(move || {
+            log::info!("SchemaViewModel::setup_callbacks: import schemas triggered");
+            use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+            let Some(path) = FileDialog::new()
+                .add_filter("Schema files", &["json", "csv"])
+                .pick_file() else { return };
+            let preview = vm_clone.borrow().preview_import(&path.to_string_lossy());
+            let result = match preview {
+                Ok((_, diff)) if diff.is_empty() => {
+                    MessageDialog::new()
+                        .set_level(MessageLevel::Info)
+                        .set_title("导入方案")
+                        .set_description("导入文件与现有方案一致，无需导入")
+                        .set_buttons(MessageButtons::Ok)
+                        .show();
+                    return;
+                }
+                Ok((schemas, diff)) => {
+                    const SHOWN: usize = 30;
+                    let mut lines: Vec<String> = diff.changes.iter().take(SHOWN).map(|c| c.describe()).collect();
+                    if diff.changes.len() > SHOWN {
+                        lines.push(format!("……另有 {} 项", diff.changes.len() - SHOWN));
+                    }
+                    let confirmed = MessageDialog::new()
+                        .set_level(MessageLevel::Info)
+                        .set_title("确认导入方案")
+                        .set_description(format!("{}\n\n{}\n\n是否应用以上变更？", diff.summary(), lines.join("\n")))
+                        .set_buttons(MessageButtons::YesNo)
+                        .show();
+                    if confirmed != MessageDialogResult::Yes {
+                        return;
+                    }
+                    vm_clone.borrow_mut().import_schemas(&schemas)
+                }
+                Err(e) => Err(e),
+            };
+            if let Some(ui) = ui_weak.upgrade() {
+                match result {
+                    Ok(diff) => {
+                        ui.set_schema_list_items(vm_clone.borrow().get_items());
+                        schema_item_vm_clone.borrow().load();
+                        ui.set_detail_list_items(schema_item_vm_clone.borrow().get_items());
+                        ui.invoke_show_toast(format!("方案导入成功: {}", diff.summary()).into());
+                    }
+                    Err(e) => {
+                        log::error!("Failed to import schemas: {}", e);
+                        ui.invoke_show_toast(format!("方案导入失败: {}", e).into());
+                    }
+                }
+            }
+        });
+
         // Delete callback
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
@@ -195,6 +303,20 @@ impl SchemaViewModel {
             let selected_index = vm_clone.borrow().get_selected_index();
             
             if let Some(index) = selected_index {
+                // 方案的条目一并删除，先确认
+                let item_count = vm_clone.borrow().item_count(index);
+                if item_count > 0 {
+                    let confirmed = rfd::MessageDialog::new()
+                        .set_level(rfd::MessageLevel::Warning)
+                        .set_title("删除方案")
+                        .set_description(format!("该方案有 {} 个条目，将一并删除。是否继续？", item_count))
+                        .set_buttons(rfd::MessageButtons::YesNo)
+                        .show();
+                    if confirmed != rfd::MessageDialogResult::Yes {
+                        return;
+                    }
+                }
+
                 // 执行删除操作
                 let delete_result = vm_clone.borrow().delete(index as i32);
                 
@@ -240,21 +362,29 @@ impl SchemaViewModel {
         });
     }
 
-    /// 自定义delete方法 - 检查是否是Year Schema
+    /// 第 `index` 行方案的条目数量，删除方案时一并删除
+    pub fn item_count(&self, index: usize) -> usize {
+        let Some(item) = self.inner.borrow().items.row_data(index) else { return 0 };
+        self.schema_items_repo.borrow_mut().find_by_schema_id(item.id).map(|items| items.len()).unwrap_or(0)
+    }
+
+    /// 自定义delete方法 - Year Schema 和仍被全宗使用的方案不能删除，方案的条目一并删除
     pub fn delete(&self, index: i32) -> Result<(), String> {
         let index_usize = index as usize;
-        
-        // 获取要删除的项
-        if let Some(item) = self.inner.borrow().items.row_data(index_usize) {
-            // 检查是否是Year Schema
-            if let Ok(Some(schema)) = self.inner.borrow().get_repo().borrow_mut().find_by_id(item.id) {
-                if schema.schema_no == "Year" {
-                    return Err("Cannot delete the schema with code 'Year'".to_string());
-                }
+        let item = self.inner.borrow().items.row_data(index_usize).ok_or("项目未找到")?;
+
+        // 检查是否是Year Schema
+        if let Ok(Some(schema)) = self.inner.borrow().get_repo().borrow_mut().find_by_id(item.id) {
+            if schema.schema_no == "Year" {
+                return Err("Cannot delete the schema with code 'Year'".to_string());
             }
         }
-        
-        self.inner.borrow_mut().delete(index_usize)
+
+        let conn = self.inner.borrow().get_repo().borrow().connection();
+        let deleted_items = UsageService::new(conn).delete_schema(item.id).map_err(|e| e.to_string())?;
+        log::info!("Deleted schema {} with {} items", item.id, deleted_items);
+        self.inner.borrow_mut().load();
+        Ok(())
     }
 }
 
@@ -284,8 +414,11 @@ impl SchemaViewModel {
 //     }
 // }
 
+use crate::core::tree_crud_vm::tree_title;
+use crate::core::{HierarchicalRepository, ToCrudListItem, TreeMove};
 use crate::models::schema_item::SchemaItem;
 use crate::persistence::schema_item_repository::SchemaItemRepository;
+use crate::persistence::TranslationsRepository;
 
 /// SchemaItem管理ViewModel
 pub struct SchemaItemViewModel {
@@ -362,15 +495,23 @@ impl SchemaItemViewModel {
                 }
 
                 if !code.is_empty() && !name.is_empty() {
-                    // 创建新的SchemaItem
+                    // 创建新的顶级SchemaItem（排在方案末尾，可再降级到上一个条目之下）
+                    let schema_id = _vm_clone.borrow().selected_schema_id.unwrap_or(0);
+                    let sort_order = _vm_clone.borrow().next_sort_order(schema_id);
                     let mut new_item = SchemaItem {
                         id: 0,
-                        schema_id: _vm_clone.borrow().selected_schema_id.unwrap_or(0),
+                        schema_id,
                         item_no: code,
                         item_name: name,
+                        parent_id: None,
+                        active: true,
+                        sort_order,
                         created_by: String::new(),
                         created_machine: String::new(),
                         created_at: chrono::Utc::now().naive_utc(),
+                        updated_at: None,
+                        updated_by: None,
+                        updated_machine: None,
                     };
 
                     // 添加到数据库
@@ -403,6 +544,46 @@ impl SchemaItemViewModel {
             vm_clone.borrow_mut().set_selected_index(Some(index as usize));
         });
 
+        // Activate / deactivate schema item callbacks (by id)
+        for active in [true, false] {
+            let vm_clone = vm.clone();
+            let ui_weak = ui_handle.as_weak();
+            let callback = move |id: i32| {
+                log::info!("SchemaItemViewModel::setup_callbacks: set schema item {} active={}", id, active);
+                if let Some(ui) = ui_weak.upgrade() {
+                    let result = vm_clone.borrow().set_item_active(id, active);
+                    match result {
+                        Ok(()) => ui.set_detail_list_items(vm_clone.borrow().get_items()),
+                        Err(e) => ui.invoke_show_toast(format!("条目状态修改失败: {}", e).into()),
+                    }
+                }
+            };
+            if active {
+                ui_handle.on_activate_schema_item(callback);
+            } else {
+                ui_handle.on_deactivate_schema_item(callback);
+            }
+        }
+
+        // Move schema item callback - 上移、下移、升级、降级（连同下级条目）
+        let vm_clone = vm.clone();
+        let ui_weak = ui_handle.as_weak();
+        ui_handle.on_move_schema_item(move |index, action| {
+            log::info!("SchemaItemViewModel::setup_callbacks: move schema item {} action {}", index, action);
+            let Some(movement) = TreeMove::from_action(action) else { return };
+            if let Some(ui) = ui_weak.upgrade() {
+                let result = vm_clone.borrow().move_item(index as usize, movement);
+                match result {
+                    Ok(position) => {
+                        ui.set_detail_list_items(vm_clone.borrow().get_items());
+                        ui.set_selected_schema_item(position as i32);
+                        vm_clone.borrow_mut().set_selected_index(Some(position));
+                    }
+                    Err(e) => ui.invoke_show_toast(format!("条目移动失败: {}", e).into()),
+                }
+            }
+        });
+
         // Delete callback
         let vm_clone = vm.clone();
         let ui_weak = ui_handle.as_weak();
@@ -413,8 +594,42 @@ impl SchemaItemViewModel {
             let selected_index = vm_clone.borrow().get_selected_index();
             
             if let Some(index) = selected_index {
-                // 执行删除操作
-                let delete_result = vm_clone.borrow().delete(index as i32);
+                // 仍被使用的条目不能删除，询问是否改为停用
+                let usage = vm_clone.borrow().usage_at(index);
+                let delete_result = match usage {
+                    Ok(usage) if !usage.is_empty() => {
+                        let confirmed = rfd::MessageDialog::new()
+                            .set_level(rfd::MessageLevel::Warning)
+                            .set_title("删除条目")
+                            .set_description(format!(
+                                "该条目仍被使用，删除会破坏已有案卷的编号：\n{}\n\n是否改为停用该条目？停用后不再生成新案卷，已有案卷保留。",
+                                usage.describe()
+                            ))
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show();
+                        if confirmed != rfd::MessageDialogResult::Yes {
+                            return;
+                        }
+                        vm_clone.borrow().deactivate_at(index)
+                    }
+                    Ok(_) => {
+                        // 下级条目一并删除，先确认
+                        let descendants = vm_clone.borrow().descendant_count(index);
+                        if descendants > 0 {
+                            let confirmed = rfd::MessageDialog::new()
+                                .set_level(rfd::MessageLevel::Warning)
+                                .set_title("删除条目")
+                                .set_description(format!("该条目有 {} 个下级条目，将一并删除。是否继续？", descendants))
+                                .set_buttons(rfd::MessageButtons::YesNo)
+                                .show();
+                            if confirmed != rfd::MessageDialogResult::Yes {
+                                return;
+                            }
+                        }
+                        vm_clone.borrow().delete(index as i32)
+                    }
+                    Err(e) => Err(e),
+                };
                 
                 match delete_result {
                     Ok(_) => {
@@ -470,17 +685,81 @@ impl SchemaItemViewModel {
                 schema_id,
                 item_no: format!("I{:03}", count),
                 item_name: "新条目".to_string(),
+                parent_id: None,
+                active: true,
+                sort_order: self.next_sort_order(schema_id),
                 created_by: String::new(),
                 created_machine: String::new(),
                 created_at: chrono::Utc::now().naive_utc(),
+                updated_at: None,
+                updated_by: None,
+                updated_machine: None,
             };
             self.inner.add(&mut item);
         }
     }
 
-    /// 自定义delete方法
+    fn next_sort_order(&self, schema_id: i32) -> i32 {
+        self.repo.borrow_mut().next_sort_order(schema_id).unwrap_or_else(|e| {
+            log::error!("Failed to read schema item order: {}", e);
+            0
+        })
+    }
+
+    /// 启用条目（连同上级条目）或停用条目（连同下级条目）；停用的条目不再生成新案卷，已有案卷不受影响
+    pub fn set_item_active(&self, id: i32, active: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if active {
+            self.repo.borrow_mut().activate_with_ancestors(id)?;
+        } else {
+            self.repo.borrow_mut().deactivate_subtree(id)?;
+        }
+        log::info!("Set schema item {} active={}", id, active);
+        self.load();
+        Ok(())
+    }
+
+    /// 移动第 `index` 行条目（连同其下级条目），返回移动后所在的行
+    pub fn move_item(&self, index: usize, movement: TreeMove) -> Result<usize, Box<dyn std::error::Error>> {
+        let id = self.id_at(index)?;
+        self.repo.borrow_mut().move_in_tree(id, movement)?;
+        self.load();
+        let items = self.inner.get_items();
+        Ok((0..items.row_count()).find(|&i| items.row_data(i).is_some_and(|item| item.id == id)).unwrap_or(index))
+    }
+
+    /// 第 `index` 行条目的下级条目数量
+    pub fn descendant_count(&self, index: usize) -> usize {
+        self.id_at(index)
+            .ok()
+            .and_then(|id| self.repo.borrow_mut().find_descendants(id).ok())
+            .map_or(0, |descendants| descendants.len())
+    }
+
+    fn usage_service(&self) -> UsageService {
+        UsageService::new(self.repo.borrow().connection())
+    }
+
+    /// 第 `index` 行条目的 id
+    fn id_at(&self, index: usize) -> Result<i32, String> {
+        self.inner.get_items().row_data(index).map(|item| item.id).ok_or_else(|| "项目未找到".to_string())
+    }
+
+    /// 使用第 `index` 行条目的全宗和案卷
+    pub fn usage_at(&self, index: usize) -> Result<Usage, String> {
+        self.usage_service().schema_item_usage(self.id_at(index)?).map_err(|e| e.to_string())
+    }
+
+    /// 停用第 `index` 行条目（仍被使用的条目不能删除时的替代做法）
+    pub fn deactivate_at(&self, index: usize) -> Result<(), String> {
+        self.set_item_active(self.id_at(index)?, false).map_err(|e| e.to_string())
+    }
+
+    /// 自定义delete方法（连同下级条目和它们的翻译）；仍被全宗或案卷使用的条目不能删除
     pub fn delete(&self, index: i32) -> Result<(), String> {
-        self.inner.delete(index as usize)
+        let id = self.id_at(index as usize)?;
+        self.usage_service().delete_schema_item(id).map_err(|e| e.to_string())?;
+        self.load();
+        Ok(())
     }
 
     /// 自定义get_items方法
@@ -488,11 +767,29 @@ impl SchemaItemViewModel {
         self.inner.get_items()
     }
 
-    /// 自定义load方法
+    /// 自定义load方法（条目按树的先序缩进显示，名称按当前界面语言显示）
     pub fn load(&self) {
         if let Some(schema_id) = self.selected_schema_id {
-            match self.repo.borrow_mut().find_by_schema_id(schema_id) {
-                Ok(items) => self.inner.set_items(items),
+            let loaded = self.repo.borrow_mut().find_tree_by_schema_id(schema_id);
+            match loaded {
+                Ok(tree) => {
+                    let (depths, mut items): (Vec<usize>, Vec<SchemaItem>) = tree.into_iter().unzip();
+                    let locale = crate::services::current_language();
+                    let mut translations = TranslationsRepository::new(self.repo.borrow().connection());
+                    if let Err(e) = translations.localize(&mut items, &locale) {
+                        log::error!("Failed to load {} schema item names: {}", locale, e);
+                    }
+                    let rows: Vec<CrudListItem> = depths
+                        .into_iter()
+                        .zip(&items)
+                        .map(|(depth, item)| {
+                            let mut row = item.to_crud_list_item();
+                            row.title = tree_title(depth, &row.title).into();
+                            row
+                        })
+                        .collect();
+                    self.inner.items.set_vec(rows);
+                }
                 Err(e) => log::error!("Failed to load schema items: {}", e),
             }
         } else {
@@ -1,11 +1,12 @@
 /// Settings View Model - MVVM architecture
 /// Manages the state and business logic for the settings page
-use crate::models::app_settings::ArchiveLibrary;
-use crate::services::SettingsService;
+use crate::models::app_settings::{ArchiveLibrary, IngestMode};
+use crate::services::{ChecksumAlgorithm, ClassificationStandard, LibraryStandard, SettingsService};
 use crate::{AppWindow, CrudListItem};
-use slint::{ComponentHandle, Model, ModelRc, VecModel};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel};
 use std::cell::RefCell;
 use std::error::Error;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 /// Archive library item for UI display
@@ -27,10 +28,17 @@ impl From<&ArchiveLibrary> for ArchiveLibraryUIItem {
 /// Settings ViewModel - handles state and business logic
 pub struct SettingsViewModel {
     pub selected_language: i32,
+    pub selected_ingest_mode: i32,
+    /// Index into `ChecksumAlgorithm::ALL`
+    pub selected_checksum_algorithm: i32,
     pub archive_libraries: Vec<ArchiveLibraryUIItem>,
     pub selected_archive_index: i32,
     pub new_archive_name: String,
     pub new_archive_path: String,
+    /// Classification standard id for the new library, empty for none
+    pub new_archive_standard: String,
+    /// Codes to pre-activate, separated by commas or spaces; empty keeps the standard's flags
+    pub new_archive_activate: String,
     pub show_add_archive_dialog: bool,
     pub show_rename_dialog: bool,
     pub rename_input: String,
@@ -41,10 +49,14 @@ impl Default for SettingsViewModel {
     fn default() -> Self {
         Self {
             selected_language: 0,
+            selected_ingest_mode: 0,
+            selected_checksum_algorithm: 0,
             archive_libraries: Vec::new(),
             selected_archive_index: -1,
             new_archive_name: String::new(),
             new_archive_path: String::new(),
+            new_archive_standard: String::new(),
+            new_archive_activate: String::new(),
             show_add_archive_dialog: false,
             show_rename_dialog: false,
             rename_input: String::new(),
@@ -58,10 +70,14 @@ impl SettingsViewModel {
     pub fn new(settings_service: Rc<SettingsService>) -> Self {
         Self {
             selected_language: 0,
+            selected_ingest_mode: 0,
+            selected_checksum_algorithm: 0,
             archive_libraries: Vec::new(),
             selected_archive_index: -1,
             new_archive_name: String::new(),
             new_archive_path: String::new(),
+            new_archive_standard: String::new(),
+            new_archive_activate: String::new(),
             show_add_archive_dialog: false,
             show_rename_dialog: false,
             rename_input: String::new(),
@@ -78,6 +94,12 @@ impl SettingsViewModel {
         // Set language (0 for Chinese, 1 for English)
         self.selected_language = if language.contains("en") { 1 } else { 0 };
 
+        // Set ingest mode (0 copy, 1 move, 2 link only)
+        self.selected_ingest_mode = Self::ingest_mode_to_index(self.settings_service.get_ingest_mode().unwrap_or_default());
+        self.selected_checksum_algorithm = Self::checksum_algorithm_to_index(
+            self.settings_service.get_checksum_algorithm().unwrap_or(ChecksumAlgorithm::Sha256),
+        );
+
         // Convert archive libraries to UI items
         self.archive_libraries = libraries.iter().map(ArchiveLibraryUIItem::from).collect();
 
@@ -108,9 +130,47 @@ impl SettingsViewModel {
             return Err("Archive name already exists".into());
         }
 
+        if self.new_archive_template().is_none() {
+            if let Some(standard) = self.new_archive_standard()? {
+                standard.classifications()?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Template named in the add archive dialog (standards take precedence over templates of the same name)
+    pub fn new_archive_template(&self) -> Option<String> {
+        let name = self.new_archive_standard.trim();
+        if name.is_empty() || ClassificationStandard::find(name).is_some() {
+            return None;
+        }
+        self.settings_service.template_service().exists(name).then(|| name.to_string())
+    }
+
+    /// Classification standard chosen in the add archive dialog
+    pub fn new_archive_standard(&self) -> Result<Option<LibraryStandard>, Box<dyn Error>> {
+        let id = self.new_archive_standard.trim();
+        if id.is_empty() {
+            return Ok(None);
+        }
+        let standard = ClassificationStandard::find(id).ok_or_else(|| {
+            let mut ids: Vec<String> = self.settings_service.list_classification_standards().iter().map(|s| s.id.to_string()).collect();
+            ids.extend(self.settings_service.template_service().list().unwrap_or_default());
+            format!("Unknown classification standard or template: {} (available: {})", id, ids.join(", "))
+        })?;
+        let codes: Vec<String> = self
+            .new_archive_activate
+            .split(|c: char| c == ',' || c == '，' || c.is_whitespace())
+            .filter(|code| !code.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Some(LibraryStandard {
+            standard: standard.id.to_string(),
+            activate: if codes.is_empty() { None } else { Some(codes) },
+        }))
+    }
+
     /// Validate rename form
     pub fn validate_rename(&self) -> Result<(), Box<dyn Error>> {
         if self.rename_input.trim().is_empty() {
@@ -175,6 +235,37 @@ impl SettingsViewModel {
         }
     }
 
+    /// Index of an ingest mode in the settings combo box
+    fn ingest_mode_to_index(mode: IngestMode) -> i32 {
+        match mode {
+            IngestMode::Copy => 0,
+            IngestMode::Move => 1,
+            IngestMode::Link => 2,
+        }
+    }
+
+    /// Get ingest mode setting for service
+    pub fn get_ingest_mode_for_service(&self) -> IngestMode {
+        match self.selected_ingest_mode {
+            1 => IngestMode::Move,
+            2 => IngestMode::Link,
+            _ => IngestMode::Copy,
+        }
+    }
+
+    /// Index of a checksum algorithm in the settings combo box
+    fn checksum_algorithm_to_index(algorithm: ChecksumAlgorithm) -> i32 {
+        ChecksumAlgorithm::ALL.iter().position(|a| *a == algorithm).unwrap_or(0) as i32
+    }
+
+    /// Get checksum algorithm setting for service
+    pub fn get_checksum_algorithm_for_service(&self) -> ChecksumAlgorithm {
+        ChecksumAlgorithm::ALL
+            .get(self.selected_checksum_algorithm.max(0) as usize)
+            .copied()
+            .unwrap_or(ChecksumAlgorithm::Sha256)
+    }
+
     /// Get archive libraries as service format
     pub fn get_archive_libraries_for_service(&self) -> Vec<ArchiveLibrary> {
         self.archive_libraries
@@ -186,8 +277,8 @@ impl SettingsViewModel {
             .collect()
     }
 
-    /// Add archive library
-    pub fn add_archive_library(&mut self, name: String, path: String) -> Result<(), Box<dyn Error>> {
+    /// Add archive library, installing `standard` into a newly created library
+    pub fn add_archive_library(&mut self, name: String, path: String, standard: Option<LibraryStandard>) -> Result<(), Box<dyn Error>> {
         // Validate input
         if name.is_empty() || path.is_empty() {
             return Err("Name and path cannot be empty".into());
@@ -199,7 +290,7 @@ impl SettingsViewModel {
         }
 
         // Add to service
-        self.settings_service.add_archive_library(name.clone(), path.clone())?;
+        self.settings_service.add_archive_library(name.clone(), path.clone(), standard.as_ref())?;
 
         // Add to VM
         self.archive_libraries.push(ArchiveLibraryUIItem { name, path });
@@ -208,6 +299,36 @@ impl SettingsViewModel {
         Ok(())
     }
 
+    /// Add archive library configured from a saved template
+    pub fn add_archive_library_from_template(&mut self, name: String, path: String, template: &str) -> Result<(), Box<dyn Error>> {
+        self.settings_service.add_archive_library_from_template(name.clone(), path.clone(), template)?;
+
+        // 模板中的设置已写入配置，同步到界面
+        self.selected_ingest_mode = Self::ingest_mode_to_index(self.settings_service.get_ingest_mode()?);
+        self.selected_checksum_algorithm = Self::checksum_algorithm_to_index(self.settings_service.get_checksum_algorithm()?);
+        self.archive_libraries.push(ArchiveLibraryUIItem { name, path });
+        self.selected_archive_index = (self.archive_libraries.len() - 1) as i32;
+
+        Ok(())
+    }
+
+    /// Save the selected archive library's configuration as a template named after the library
+    pub fn save_selected_as_template(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let library = self
+            .archive_libraries
+            .get(self.selected_archive_index.max(0) as usize)
+            .filter(|_| self.selected_archive_index >= 0)
+            .ok_or("Please select an archive library")?;
+        self.settings_service.save_library_as_template(self.selected_archive_index as usize, &library.name)
+    }
+
+    /// Whether a template with the selected archive library's name already exists
+    pub fn selected_template_exists(&self) -> bool {
+        self.archive_libraries
+            .get(self.selected_archive_index.max(0) as usize)
+            .is_some_and(|library| self.settings_service.template_service().exists(&library.name))
+    }
+
     /// Remove archive library
     pub fn remove_archive_library(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
         if index >= self.archive_libraries.len() {
@@ -255,6 +376,8 @@ impl SettingsViewModel {
         let libraries = self.get_archive_libraries_for_service();
 
         self.settings_service.apply_settings(language.clone(), libraries)?;
+        self.settings_service.set_ingest_mode(self.get_ingest_mode_for_service())?;
+        self.settings_service.set_checksum_algorithm(self.get_checksum_algorithm_for_service())?;
 
         // Apply language change immediately
         if !language.is_empty() {
@@ -262,6 +385,10 @@ impl SettingsViewModel {
                 Ok(_) => log::info!("Successfully selected bundled translation for: {}", language),
                 Err(e) => log::warn!("Failed to select bundled translation for {}: {}", language, e),
             }
+            // 分类、方案条目的翻译名称按当前语言显示
+            if let Err(e) = crate::services::set_language(&language) {
+                log::warn!("Failed to set language: {}, error: {}", language, e);
+            }
         }
 
         Ok(())
@@ -295,6 +422,7 @@ impl SettingsViewModel {
                 title: lib.name.clone().into(),
                 subtitle: lib.path.clone().into(),
                 active: true,
+                detail: "".into(),
             }
         }).collect();
         ModelRc::new(VecModel::from(ui_items))
@@ -304,6 +432,10 @@ impl SettingsViewModel {
     pub fn init_ui(&self, ui_handle: &AppWindow) {
         // 设置语言
         ui_handle.set_selected_language(self.selected_language);
+        ui_handle.set_selected_ingest_mode(self.selected_ingest_mode);
+        let algorithms: Vec<SharedString> = ChecksumAlgorithm::ALL.iter().map(|a| a.as_str().to_uppercase().into()).collect();
+        ui_handle.set_checksum_algorithms(ModelRc::new(VecModel::from(algorithms)));
+        ui_handle.set_selected_checksum_algorithm(self.selected_checksum_algorithm);
         
         // 设置档案库列表
         ui_handle.set_archive_libraries(Self::to_ui_items(&self.archive_libraries));
@@ -331,6 +463,10 @@ impl SettingsViewModel {
                         vm.new_archive_name = fields.row_data(0).unwrap().value.to_string();
                         vm.new_archive_path = fields.row_data(1).unwrap().value.to_string();
                     }
+                    if fields.row_count() >= 4 {
+                        vm.new_archive_standard = fields.row_data(2).unwrap().value.to_string();
+                        vm.new_archive_activate = fields.row_data(3).unwrap().value.to_string();
+                    }
                     
                     log::info!("Adding archive: name='{}', path='{}'", vm.new_archive_name, vm.new_archive_path);
                     
@@ -343,14 +479,20 @@ impl SettingsViewModel {
                     let new_name = vm.new_archive_name.clone();
                     let new_path = vm.new_archive_path.clone();
                     
-                    // 添加档案库
-                    if let Err(e) = vm.add_archive_library(new_name, new_path) {
+                    // 添加档案库（新建的档案库按模板配置，或导入选择的分类标准）
+                    let result = match vm.new_archive_template() {
+                        Some(template) => vm.add_archive_library_from_template(new_name, new_path, &template),
+                        None => vm.new_archive_standard().and_then(|standard| vm.add_archive_library(new_name, new_path, standard)),
+                    };
+                    if let Err(e) = result {
                         ui.invoke_show_toast(format!("Failed to add archive: {}", e).into());
                         return;
                     }
                     
                     vm.new_archive_name.clear();
                     vm.new_archive_path.clear();
+                    vm.new_archive_standard.clear();
+                    vm.new_archive_activate.clear();
                     
                     // 更新UI
                     ui.set_archive_libraries(Self::to_ui_items(&vm.archive_libraries));
@@ -387,6 +529,37 @@ impl SettingsViewModel {
             }
         });
         
+        // 将选中档案库的配置保存为模板
+        ui_handle.on_save_archive_template({
+            let vm = Rc::clone(&vm);
+            let ui_weak = ui_handle.as_weak();
+
+            move || {
+                if let Some(ui) = ui_weak.upgrade() {
+                    let vm = vm.borrow();
+                    if vm.selected_template_exists() {
+                        use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+                        let confirmed = MessageDialog::new()
+                            .set_level(MessageLevel::Warning)
+                            .set_title("保存为模板")
+                            .set_description("同名模板已存在，是否覆盖？")
+                            .set_buttons(MessageButtons::YesNo)
+                            .show();
+                        if confirmed != MessageDialogResult::Yes {
+                            return;
+                        }
+                    }
+                    match vm.save_selected_as_template() {
+                        Ok(path) => {
+                            log::info!("Saved library template to {}", path.display());
+                            ui.invoke_show_toast("Library saved as template".into());
+                        }
+                        Err(e) => ui.invoke_show_toast(format!("Failed to save template: {}", e).into()),
+                    }
+                }
+            }
+        });
+
         // 重命名档案库
         ui_handle.on_rename_archive_library({
             let vm = Rc::clone(&vm);
@@ -432,6 +605,8 @@ impl SettingsViewModel {
                     let mut vm = vm.borrow_mut();
                     
                     vm.selected_language = ui.get_selected_language();
+                    vm.selected_ingest_mode = ui.get_selected_ingest_mode();
+                    vm.selected_checksum_algorithm = ui.get_selected_checksum_algorithm();
                     
                     if let Err(e) = vm.apply_settings() {
                         ui.invoke_show_toast(format!("Failed to apply settings: {}", e).into());
@@ -458,6 +633,8 @@ impl SettingsViewModel {
                     }
                     
                     ui.set_selected_language(vm.selected_language);
+                    ui.set_selected_ingest_mode(vm.selected_ingest_mode);
+                    ui.set_selected_checksum_algorithm(vm.selected_checksum_algorithm);
                     ui.set_archive_libraries(Self::to_ui_items(&vm.archive_libraries));
                     ui.set_selected_archive(vm.selected_archive_index);
                     ui.invoke_show_toast("Settings cancelled".into());
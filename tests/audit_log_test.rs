@@ -0,0 +1,71 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    fond_classification_repository::FondClassificationsRepository,
+    AuditLogFilter, AuditLogRepository,
+};
+use fonds_pod_lib::models::fond_classification::FondClassification;
+use fonds_pod_lib::services::AuditService;
+use fonds_pod_lib::{ActiveableRepository, GenericRepository};
+use diesel::RunQueryDsl;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_repository_writes_are_audited() {
+    let db_path = setup_test_db("audit_log");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let mut repo = FondClassificationsRepository::new(Rc::clone(&conn));
+
+    let id = repo
+        .create(FondClassification { code: "GA".into(), name: "文化".into(), active: true, ..Default::default() })
+        .unwrap();
+
+    let mut classification = repo.find_by_id(id).unwrap().unwrap();
+    classification.name = "文化艺术".into();
+    repo.update(&classification).unwrap();
+    repo.deactivate(id).unwrap();
+    repo.delete(id).unwrap();
+
+    // 删除不存在的记录不产生审计日志
+    repo.delete(9999).unwrap();
+
+    let history = AuditLogRepository::new(Rc::clone(&conn))
+        .find_by_entity("fond_classifications", id)
+        .unwrap();
+    let actions: Vec<&str> = history.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(actions, vec!["insert", "update", "update", "delete"]);
+
+    assert!(history[0].before_json.is_none());
+    assert!(history[0].after_json.as_ref().unwrap().contains("\"文化\""));
+    assert!(history[1].before_json.as_ref().unwrap().contains("\"文化\""));
+    assert!(history[1].after_json.as_ref().unwrap().contains("文化艺术"));
+    assert!(history[2].after_json.as_ref().unwrap().contains("\"active\":false"));
+    assert!(history[3].after_json.is_none());
+
+    // 查询与导出
+    let service = AuditService::new(Rc::clone(&conn));
+    let deletes = service
+        .query(&AuditLogFilter { action: Some("delete".into()), ..Default::default() })
+        .unwrap();
+    assert_eq!(deletes.len(), 1);
+    assert!(AuditService::to_csv(&history).lines().count() == 5);
+
+    let export_path = format!("{}.audit.json", db_path);
+    let filter = AuditLogFilter { entity_type: Some("fond_classifications".into()), ..Default::default() };
+    assert_eq!(service.export(&filter, Path::new(&export_path)).unwrap(), 4);
+    assert!(fs::read_to_string(&export_path).unwrap().contains("fond_classifications"));
+    let _ = fs::remove_file(&export_path);
+
+    // 审计日志只追加，不允许修改或删除
+    assert!(diesel::sql_query("DELETE FROM audit_log").execute(&mut *conn.borrow_mut()).is_err());
+    assert!(diesel::sql_query("UPDATE audit_log SET action = 'x'").execute(&mut *conn.borrow_mut()).is_err());
+}
@@ -0,0 +1,116 @@
+use fonds_pod_lib::persistence::{establish_connection, FondsRepository};
+use fonds_pod_lib::models::fond::Fond;
+use fonds_pod_lib::services::{BackupService, BackupFormat};
+use fonds_pod_lib::GenericRepository;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_backup_verify_and_restore() {
+    let root = setup_test_dir("backup_service");
+    let library = root.join("Archive");
+    fs::create_dir_all(library.join("GA01").join("GA01-2025-01")).unwrap();
+    fs::write(library.join("GA01").join("GA01-2025-01").join("scan.tif"), "scan").unwrap();
+
+    // 备份时档案库保持打开
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+
+    let service = BackupService::new(&library);
+    for (name, format) in [("full.zip", BackupFormat::Zip), ("full.tar.zst", BackupFormat::TarZst)] {
+        let archive = root.join(name);
+        assert_eq!(BackupFormat::from_path(&archive), Some(format));
+        let manifest = service.backup(&archive, true).unwrap();
+        assert_eq!(manifest.library_name, "Archive");
+        assert!(manifest.entries.iter().any(|e| e.path == ".fondspod.db"));
+        assert!(manifest.entries.iter().any(|e| e.path == "GA01/GA01-2025-01/scan.tif"));
+        assert_eq!(BackupService::verify(&archive).unwrap(), manifest);
+
+        let restored = root.join(format!("restored-{}", format.extension()));
+        BackupService::restore(&archive, &restored, false).unwrap();
+        assert_eq!(fs::read_to_string(restored.join("GA01").join("GA01-2025-01").join("scan.tif")).unwrap(), "scan");
+        let restored_conn = establish_connection(&restored.join(".fondspod.db")).unwrap();
+        let fonds = FondsRepository::new(restored_conn).find_all().unwrap();
+        assert_eq!(fonds.len(), 1);
+        assert_eq!(fonds[0].fond_no, "GA01");
+
+        // 已有档案库需要显式覆盖
+        assert!(BackupService::restore(&archive, &restored, false).is_err());
+        BackupService::restore(&archive, &restored, true).unwrap();
+    }
+
+    // 仅数据库的备份不含档案文件
+    let db_only = service.backup(&root.join("db.zip"), false).unwrap();
+    assert_eq!(db_only.entries.len(), 1);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_scheduled_backups_and_retention() {
+    let root = setup_test_dir("backup_schedule");
+    let library = root.join("Archive");
+    fs::create_dir_all(&library).unwrap();
+    establish_connection(&library.join(".fondspod.db")).unwrap();
+    let backups = root.join("backups");
+    // 名称前缀相同的其他档案库的备份不受影响
+    fs::create_dir_all(&backups).unwrap();
+    fs::write(backups.join("Archive-2-20250101-000000.zip"), "other").unwrap();
+
+    let service = BackupService::new(&library);
+    let day = chrono::Duration::days(1);
+    let start = chrono::NaiveDate::from_ymd_opt(2025, 12, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+
+    assert!(service.run_scheduled(&backups, BackupFormat::Zip, false, day, 2, start).unwrap().is_some());
+    assert!(service.run_scheduled(&backups, BackupFormat::Zip, false, day, 2, start + chrono::Duration::hours(1)).unwrap().is_none());
+    for n in 1..=3 {
+        assert!(service.run_scheduled(&backups, BackupFormat::Zip, false, day, 2, start + day * n).unwrap().is_some());
+    }
+
+    let kept = service.list_backups(&backups).unwrap();
+    assert_eq!(kept.len(), 2);
+    assert!(kept[1].ends_with("Archive-20251204-090000.zip"));
+    assert!(backups.join("Archive-2-20250101-000000.zip").exists());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_restore_target_cannot_be_the_open_library() {
+    let root = setup_test_dir("backup_open_library");
+    let library = root.join("Archive");
+    fs::create_dir_all(library.join("GA01")).unwrap();
+    let _conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let archive = root.join("full.zip");
+    BackupService::new(&library).backup(&archive, false).unwrap();
+
+    // 同一目录的不同写法也视为当前打开的档案库
+    for target in [library.clone(), library.join("GA01").join(".."), root.join(".").join("Archive")] {
+        assert!(BackupService::check_restore_target(&target, Some(&library)).is_err());
+    }
+    #[cfg(unix)]
+    {
+        let link = root.join("Link");
+        std::os::unix::fs::symlink(&library, &link).unwrap();
+        assert!(BackupService::check_restore_target(&link, Some(&library)).is_err());
+    }
+
+    // 其他目录和尚不存在的目录可以恢复；没有打开的档案库时不限制
+    let other = root.join("Other");
+    assert!(BackupService::check_restore_target(&other, Some(&library)).is_ok());
+    BackupService::restore(&archive, &other, false).unwrap();
+    assert!(BackupService::check_restore_target(&library, None).is_ok());
+
+    let _ = fs::remove_dir_all(&root);
+}
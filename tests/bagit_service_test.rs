@@ -0,0 +1,147 @@
+use diesel::RunQueryDsl;
+use fonds_pod_lib::persistence::{establish_connection, FilesRepository, FondsRepository, ItemsRepository, SequencesRepository, SeriesRepository};
+use fonds_pod_lib::models::{file::File, fond::Fond, item::Item, series::Series};
+use fonds_pod_lib::services::{BagItService, BagScope, ChecksumAlgorithm};
+use fonds_pod_lib::GenericRepository;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Library with fond GA01 > series GA01-2025 > file GA01-2025-01 holding a file item and a folder item
+fn setup_source(library: &std::path::Path) -> (i32, i32) {
+    fs::create_dir_all(library).unwrap();
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let file_dir = library.join("GA01").join("GA01-2025-01");
+    fs::create_dir_all(file_dir.join("GA01-2025-01-001")).unwrap();
+    fs::write(file_dir.join("GA01-2025-01-001").join("letter.pdf"), "letter").unwrap();
+    fs::create_dir_all(file_dir.join("GA01-2025-01-002").join("pages")).unwrap();
+    fs::write(file_dir.join("GA01-2025-01-002").join("pages").join("1.tif"), "page 1").unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "通知".into(), path: Some("GA01/GA01-2025-01".into()), ..Default::default() })
+        .unwrap();
+    let mut items = ItemsRepository::new(Rc::clone(&conn));
+    items
+        .create(Item { file_id, item_no: "GA01-2025-01-001".into(), name: "函".into(), path: Some("GA01/GA01-2025-01/GA01-2025-01-001/letter.pdf".into()), ..Default::default() })
+        .unwrap();
+    items
+        .create(Item { file_id, item_no: "GA01-2025-01-002".into(), name: "扫描件".into(), path: Some("GA01/GA01-2025-01/GA01-2025-01-002".into()), ..Default::default() })
+        .unwrap();
+    (fond_id, file_id)
+}
+
+#[test]
+fn test_export_validate_and_import_with_remapping() {
+    let root = setup_test_dir("bagit_service");
+    let source = root.join("Source");
+    let (fond_id, _) = setup_source(&source);
+    let source_conn = establish_connection(&source.join(".fondspod.db")).unwrap();
+
+    let bag = BagItService::new(source_conn, &source).export(BagScope::Fond(fond_id), &root).unwrap();
+    assert_eq!(bag, root.join("GA01"));
+    assert!(bag.join("data/GA01/GA01-2025-01/GA01-2025-01-001/letter.pdf").is_file());
+    assert!(fs::read_to_string(bag.join("bag-info.txt")).unwrap().contains("Payload-Oxum: 12.2"));
+    assert!(BagItService::validate(&bag).unwrap().is_empty());
+    let metadata = BagItService::read_metadata(&bag).unwrap();
+    assert_eq!(metadata.scope, "fond");
+    assert_eq!(metadata.fond.series[0].files[0].items[1].payload.as_deref(), Some("data/GA01/GA01-2025-01/GA01-2025-01-002"));
+
+    // 接收方已有不同的 GA01 全宗，导入后整棵树改用新编号
+    let target = root.join("Target");
+    fs::create_dir_all(&target).unwrap();
+    let target_conn = establish_connection(&target.join(".fondspod.db")).unwrap();
+    FondsRepository::new(Rc::clone(&target_conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "档案局".into(), ..Default::default() })
+        .unwrap();
+    let report = BagItService::new(Rc::clone(&target_conn), &target).import(&bag, ChecksumAlgorithm::Sha256).unwrap();
+    assert_eq!((report.fonds, report.series, report.files, report.items), (1, 1, 1, 2));
+    assert!(report.remapped.contains(&("GA01".to_string(), "GA02".to_string())));
+    assert!(report.remapped.contains(&("GA01-2025-01-002".to_string(), "GA02-2025-01-002".to_string())));
+
+    let item = ItemsRepository::new(Rc::clone(&target_conn)).find_by_item_no("GA02-2025-01-001").unwrap().unwrap();
+    assert_eq!(item.path.as_deref(), Some("GA02/GA02-2025-01/GA02-2025-01-001/letter.pdf"));
+    assert_eq!(fs::read_to_string(target.join("GA02/GA02-2025-01/GA02-2025-01-002/pages/1.tif")).unwrap(), "page 1");
+    let file = FilesRepository::new(Rc::clone(&target_conn)).find_by_file_no("GA02-2025-01").unwrap().unwrap();
+    assert_eq!(file.path.as_deref(), Some("GA02/GA02-2025-01"));
+
+    // 序列已越过导入的编号
+    let mut sequences = SequencesRepository::new(Rc::clone(&target_conn));
+    assert_eq!(sequences.get_next_number("GA02-2025-01", Some(3)).unwrap(), "003");
+    assert_eq!(sequences.get_next_number("GA02-2025", Some(2)).unwrap(), "02");
+
+    // 被篡改的包不能导入
+    fs::write(bag.join("data/GA01/GA01-2025-01/GA01-2025-01-001/letter.pdf"), "forged").unwrap();
+    fs::write(bag.join("data/extra.txt"), "extra").unwrap();
+    let problems = BagItService::validate(&bag).unwrap();
+    assert!(problems.iter().any(|p| p.contains("GA01-2025-01-001/letter.pdf")));
+    assert!(problems.iter().any(|p| p.contains("data/extra.txt")));
+    assert!(problems.iter().any(|p| p.contains("Payload-Oxum")));
+    assert!(BagItService::new(target_conn, &target).import(&bag, ChecksumAlgorithm::Sha256).is_err());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_file_bag_carries_ancestry_and_reuses_matching_fond() {
+    let root = setup_test_dir("bagit_service_file");
+    let source = root.join("Source");
+    let (_, file_id) = setup_source(&source);
+    let source_conn = establish_connection(&source.join(".fondspod.db")).unwrap();
+
+    let bag = BagItService::new(source_conn, &source).export(BagScope::File(file_id), &root).unwrap();
+    assert_eq!(bag, root.join("GA01-2025-01"));
+    let metadata = BagItService::read_metadata(&bag).unwrap();
+    assert_eq!(metadata.scope, "file");
+    assert_eq!(metadata.fond.fond_no, "GA01");
+    assert_eq!(metadata.fond.series[0].series_no, "GA01-2025");
+
+    // 同号同名的全宗和系列直接沿用，案卷号冲突时在系列下取新号
+    let target = root.join("Target");
+    setup_source(&target);
+    let target_conn = establish_connection(&target.join(".fondspod.db")).unwrap();
+    let report = BagItService::new(Rc::clone(&target_conn), &target).import(&bag, ChecksumAlgorithm::Sha256).unwrap();
+    assert_eq!((report.fonds, report.series, report.files, report.items), (0, 0, 1, 2));
+    assert!(report.remapped.contains(&("GA01-2025-01".to_string(), "GA01-2025-02".to_string())));
+    assert!(ItemsRepository::new(target_conn).find_by_item_no("GA01-2025-02-001").unwrap().is_some());
+    assert!(target.join("GA01/GA01-2025-02/GA01-2025-02-001/letter.pdf").is_file());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_failed_import_leaves_no_rows_or_payloads() {
+    let root = setup_test_dir("bagit_service_rollback");
+    let source = root.join("Source");
+    let (fond_id, _) = setup_source(&source);
+    let source_conn = establish_connection(&source.join(".fondspod.db")).unwrap();
+    let bag = BagItService::new(source_conn, &source).export(BagScope::Fond(fond_id), &root).unwrap();
+
+    let target = root.join("Target");
+    fs::create_dir_all(&target).unwrap();
+    let target_conn = establish_connection(&target.join(".fondspod.db")).unwrap();
+    // 模拟第二个文件写入失败，第一个文件已复制
+    diesel::sql_query("CREATE TRIGGER reject_items BEFORE INSERT ON items WHEN NEW.name = '扫描件' BEGIN SELECT RAISE(ABORT, 'rejected'); END")
+        .execute(&mut *target_conn.borrow_mut())
+        .unwrap();
+    assert!(BagItService::new(Rc::clone(&target_conn), &target).import(&bag, ChecksumAlgorithm::Sha256).is_err());
+
+    assert!(FondsRepository::new(Rc::clone(&target_conn)).find_all().unwrap().is_empty());
+    assert!(ItemsRepository::new(target_conn).find_all().unwrap().is_empty());
+    assert!(!target.join("GA01").exists());
+
+    let _ = fs::remove_dir_all(&root);
+}
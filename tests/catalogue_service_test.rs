@@ -0,0 +1,122 @@
+use fonds_pod_lib::persistence::{
+    establish_connection, schema_item_repository::SchemaItemRepository, FilesRepository, FondSchemasRepository, FondsRepository,
+    ItemsRepository, SchemaRepository, SeriesRepository,
+};
+use fonds_pod_lib::models::{file::File, fond::Fond, fond_schema::FondSchema, item::Item, schema::Schema, schema_item::SchemaItem, series::Series};
+use fonds_pod_lib::services::{CatalogueColumn, CatalogueLevel, CatalogueQuery, CatalogueService};
+use fonds_pod_lib::GenericRepository;
+use std::fs;
+use std::io::Read;
+use std::rc::Rc;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_catalogue_queries_and_exports() {
+    let root = setup_test_dir("catalogue_service");
+    let conn = establish_connection(&root.join(".fondspod.db")).unwrap();
+
+    // 全宗 GA01 按年度（内置 Year 方案）和保管期限两个维度生成系列
+    let year_id = SchemaRepository::new(Rc::clone(&conn))
+        .find_by_predicate(|s| s.schema_no == "Year")
+        .unwrap()[0]
+        .id;
+    let retention_id = SchemaRepository::new(Rc::clone(&conn))
+        .create(Schema { schema_no: "Retention".into(), name: "保管期限".into(), sort_order: 1, ..Default::default() })
+        .unwrap();
+    SchemaItemRepository::new(Rc::clone(&conn))
+        .create(SchemaItem { schema_id: retention_id, item_no: "Y30".into(), item_name: "30年".into(), ..Default::default() })
+        .unwrap();
+
+    let mut fonds = FondsRepository::new(Rc::clone(&conn));
+    let ga01 = fonds.create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() }).unwrap();
+    let ga02 = fonds.create(Fond { fond_no: "GA02".into(), fond_classification_code: "GA".into(), name: "档案局".into(), ..Default::default() }).unwrap();
+    for (schema_id, sort_order) in [(year_id, 0), (retention_id, 1)] {
+        FondSchemasRepository::new(Rc::clone(&conn))
+            .create(FondSchema { fond_id: ga01, schema_id, sort_order, ..Default::default() })
+            .unwrap();
+    }
+
+    let mut series = SeriesRepository::new(Rc::clone(&conn));
+    let s1 = series.create(Series { fond_id: ga01, series_no: "GA01-2025-Y30".into(), name: "2025-30年".into(), ..Default::default() }).unwrap();
+    let s2 = series.create(Series { fond_id: ga02, series_no: "GA02-2025".into(), name: "2025".into(), ..Default::default() }).unwrap();
+    let mut files = FilesRepository::new(Rc::clone(&conn));
+    let f1 = files.create(File { series_id: s1, file_no: "GA01-2025-Y30-01".into(), name: "通知, 批复".into(), ..Default::default() }).unwrap();
+    files.create(File { series_id: s1, file_no: "GA01-2025-Y30-02".into(), name: "会议纪要".into(), ..Default::default() }).unwrap();
+    let f3 = files.create(File { series_id: s2, file_no: "GA02-2025-01".into(), name: "年报".into(), ..Default::default() }).unwrap();
+    let mut items = ItemsRepository::new(Rc::clone(&conn));
+    items.create(Item { file_id: f1, item_no: "GA01-2025-Y30-01-001".into(), name: "通知".into(), ..Default::default() }).unwrap();
+    items.create(Item { file_id: f1, item_no: "GA01-2025-Y30-01-002".into(), name: "批复".into(), ..Default::default() }).unwrap();
+    items.create(Item { file_id: f3, item_no: "GA02-2025-01-001".into(), name: "年报".into(), ..Default::default() }).unwrap();
+
+    // 界面列表使用的查询
+    let service = CatalogueService::new(Rc::clone(&conn));
+    assert_eq!(service.fonds(&CatalogueQuery::default()).unwrap().len(), 2);
+    assert_eq!(service.files(&CatalogueQuery::fond(ga01)).unwrap().len(), 2);
+    assert_eq!(service.items(&CatalogueQuery::file(f1)).unwrap().len(), 2);
+    assert_eq!(service.items(&CatalogueQuery::series(s2)).unwrap().len(), 1);
+
+    // 界面的案卷筛选同样作用于导出：只列出编号或题名匹配的案卷及其文件
+    let filtered = CatalogueQuery::series(s1).with_file_filter(" 通知 ");
+    assert_eq!(service.files(&filtered).unwrap().iter().map(|f| f.id).collect::<Vec<_>>(), vec![f1]);
+    assert_eq!(service.items(&CatalogueQuery::default().with_file_filter("y30-02")).unwrap().len(), 0);
+    let csv = service.to_csv(CatalogueLevel::Series, &filtered, &[CatalogueColumn::FileNo]).unwrap();
+    assert_eq!(csv.lines().skip(1).collect::<Vec<_>>(), vec!["GA01-2025-Y30-01"]);
+    assert_eq!(CatalogueQuery::fond(ga01).with_file_filter("  "), CatalogueQuery::fond(ga01));
+
+    // 案卷目录：自定义维度列取自系列号
+    let columns = vec![
+        CatalogueColumn::FileNo,
+        CatalogueColumn::FileName,
+        CatalogueColumn::Dimension("Retention".into()),
+        CatalogueColumn::Dimension("Year".into()),
+        CatalogueColumn::ItemCount,
+    ];
+    let csv = service.to_csv(CatalogueLevel::Series, &CatalogueQuery::default(), &columns).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "\u{feff}全宗号,案卷号,案卷题名,保管期限,Year,文件数");
+    assert_eq!(lines[1], "GA01,GA01-2025-Y30-01,\"通知, 批复\",30年,2025,2");
+    assert_eq!(lines.len(), 4);
+
+    // 筛选到单个全宗时不再补全宗号列
+    let csv = service.to_csv(CatalogueLevel::Fond, &CatalogueQuery::fond(ga01), &CatalogueLevel::Fond.default_columns()).unwrap();
+    assert_eq!(csv.lines().nth(1), Some("GA01-2025-Y30,2025-30年,2,2"));
+
+    // XLSX 每个全宗一个工作表
+    let workbook = service.workbook(CatalogueLevel::File, &CatalogueQuery::default(), &CatalogueLevel::File.default_columns()).unwrap();
+    let names: Vec<&str> = workbook.sheets().iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["GA01 文化局", "GA02 档案局"]);
+    assert_eq!(workbook.sheets()[0].rows.len(), 2);
+
+    let path = root.join("卷内目录.xlsx");
+    let rows = service.export(CatalogueLevel::File, &CatalogueQuery::default(), &CatalogueLevel::File.default_columns(), &path).unwrap();
+    assert_eq!(rows, 3);
+    let mut zip = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+    let mut workbook_xml = String::new();
+    zip.by_name("xl/workbook.xml").unwrap().read_to_string(&mut workbook_xml).unwrap();
+    assert!(workbook_xml.contains(r#"<sheet name="GA02 档案局" sheetId="2" r:id="rId2"/>"#));
+    let mut sheet_xml = String::new();
+    zip.by_name("xl/worksheets/sheet1.xml").unwrap().read_to_string(&mut sheet_xml).unwrap();
+    assert!(sheet_xml.contains("GA01-2025-Y30-01-002"));
+    assert!(sheet_xml.contains(r#"<autoFilter ref="A1:E3"/>"#));
+
+    assert!(service.export(CatalogueLevel::File, &CatalogueQuery::default(), &[], &root.join("empty.csv")).is_err());
+    assert!(service.export(CatalogueLevel::File, &CatalogueQuery::default(), &columns, &root.join("list.pdf")).is_err());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_catalogue_column_keys_round_trip() {
+    let keys = vec!["file_no".to_string(), "dimension:Year".to_string(), "unknown".to_string()];
+    let columns = CatalogueColumn::parse_list(&keys);
+    assert_eq!(columns, vec![CatalogueColumn::FileNo, CatalogueColumn::Dimension("Year".into())]);
+    assert_eq!(columns.iter().map(|c| c.key()).collect::<Vec<_>>(), keys[..2].to_vec());
+}
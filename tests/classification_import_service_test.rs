@@ -0,0 +1,167 @@
+use fonds_pod_lib::models::fond::Fond;
+use fonds_pod_lib::models::fond_classification::{ClassificationJson, FondClassification};
+use fonds_pod_lib::persistence::{establish_connection, FondClassificationsRepository, FondsRepository};
+use fonds_pod_lib::services::{ClassificationChange, ClassificationImportService};
+use fonds_pod_lib::persistence::TranslationsRepository;
+use fonds_pod_lib::GenericRepository;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> std::path::PathBuf {
+    let path = std::path::PathBuf::from(format!(".fondspod_test_{}.db", test_name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn node(code: &str, name: &str, children: Vec<ClassificationJson>) -> ClassificationJson {
+    ClassificationJson { code: code.into(), name: name.into(), active: true, children, replaces: Vec::new(), names: BTreeMap::new() }
+}
+
+/// G 政府 { GA 文化, GB 教育 }, H 企业 { HA 国企 }；GB 已被停用，全宗 GA01 使用 GA、HA01 使用 HA
+fn seed(conn: &Rc<std::cell::RefCell<diesel::SqliteConnection>>) {
+    let mut repo = FondClassificationsRepository::new(Rc::clone(conn));
+    let g = repo.create(FondClassification { code: "G".into(), name: "政府".into(), active: true, ..Default::default() }).unwrap();
+    repo.create(FondClassification { code: "GA".into(), name: "文化".into(), parent_id: Some(g), active: true, ..Default::default() }).unwrap();
+    repo.create(FondClassification { code: "GB".into(), name: "教育".into(), parent_id: Some(g), active: false, sort_order: 1, ..Default::default() }).unwrap();
+    let h = repo.create(FondClassification { code: "H".into(), name: "企业".into(), active: true, sort_order: 1, ..Default::default() }).unwrap();
+    repo.create(FondClassification { code: "HA".into(), name: "国企".into(), parent_id: Some(h), active: true, ..Default::default() }).unwrap();
+
+    let mut fonds = FondsRepository::new(Rc::clone(conn));
+    fonds.create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() }).unwrap();
+    fonds.create(Fond { fond_no: "HA01".into(), fond_classification_code: "HA".into(), name: "电力公司".into(), ..Default::default() }).unwrap();
+}
+
+#[test]
+fn test_preview_and_merge_by_code() {
+    let db_path = setup_test_db("classification_import_merge");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = ClassificationImportService::new(Rc::clone(&conn));
+
+    // GA 改名并移到 H 下，GC 新增，GB 在文件中为启用状态，HA 从文件中删除
+    let mut ga = node("GA", "文化旅游", Vec::new());
+    let imported = vec![node("G", "政府", vec![node("GB", "教育", Vec::new()), node("GC", "卫生", Vec::new())]), node("H", "企业", vec![ga.clone()])];
+    let diff = service.preview(&imported).unwrap();
+    assert_eq!(
+        diff.changes,
+        vec![
+            ClassificationChange::Added { code: "GC".into(), name: "卫生".into(), parent: Some("G".into()) },
+            ClassificationChange::Renamed { code: "GA".into(), from: "文化".into(), to: "文化旅游".into() },
+            ClassificationChange::Moved { code: "GA".into(), from: Some("G".into()), to: Some("H".into()) },
+            ClassificationChange::Removed { code: "HA".into(), name: "国企".into(), fonds: vec!["HA01".into()], replaced_by: None },
+        ]
+    );
+    assert_eq!(diff.unchanged, 3);
+    assert_eq!(diff.blocked().len(), 1);
+
+    // 被全宗使用的分类不能直接删除，且不写入任何变更
+    assert!(service.apply(&imported).is_err());
+    assert_eq!(FondClassificationsRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 5);
+
+    // 指定替代分类后合并
+    ga.replaces = vec!["HA".into()];
+    let imported = vec![node("G", "政府", vec![node("GB", "教育", Vec::new()), node("GC", "卫生", Vec::new())]), node("H", "企业", vec![ga])];
+    let applied = service.apply(&imported).unwrap();
+    assert!(applied.blocked().is_empty());
+
+    let mut repo = FondClassificationsRepository::new(Rc::clone(&conn));
+    let all = repo.find_all().unwrap();
+    let find = |code: &str| all.iter().find(|c| c.code == code).cloned();
+    let (g, h) = (find("G").unwrap(), find("H").unwrap());
+    let ga = find("GA").unwrap();
+    assert_eq!((ga.id, ga.name.as_str(), ga.parent_id), (2, "文化旅游", Some(h.id)));
+    assert_eq!(find("GC").unwrap().parent_id, Some(g.id));
+    assert!(!find("GB").unwrap().active, "curated inactive flag is kept");
+    assert!(find("HA").is_none());
+    let fond = FondsRepository::new(Rc::clone(&conn)).find_by_fond_no("HA01").unwrap().unwrap();
+    assert_eq!(fond.fond_classification_code, "GA");
+
+    // 再次导入同一文件没有差异
+    assert!(service.preview(&imported).unwrap().is_empty());
+
+    drop(repo);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_deactivate_and_reject_invalid_files() {
+    let db_path = setup_test_db("classification_import_invalid");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = ClassificationImportService::new(Rc::clone(&conn));
+
+    let mut ha = node("HA", "国企", Vec::new());
+    ha.active = false;
+    let imported = vec![node("G", "政府", vec![node("GA", "文化", Vec::new()), node("GB", "教育", Vec::new())]), node("H", "企业", vec![ha])];
+    let diff = service.apply(&imported).unwrap();
+    assert_eq!(diff.changes, vec![ClassificationChange::Deactivated { code: "HA".into() }]);
+    let ha = FondClassificationsRepository::new(Rc::clone(&conn)).find_by_predicate(|c| c.code == "HA").unwrap();
+    assert!(!ha[0].active);
+
+    // 重复代码、取代仍在文件中的代码均被拒绝
+    assert!(service.preview(&[node("G", "政府", vec![node("G", "重复", Vec::new())])]).is_err());
+    let mut gb = node("GB", "教育", Vec::new());
+    gb.replaces = vec!["GA".into()];
+    assert!(service.preview(&[node("G", "政府", vec![node("GA", "文化", Vec::new()), gb])]).is_err());
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_merge_translated_names() {
+    let db_path = setup_test_db("classification_import_names");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = ClassificationImportService::new(Rc::clone(&conn));
+    let id_of = |code: &str| FondClassificationsRepository::new(Rc::clone(&conn)).find_by_predicate(|c| c.code == code).unwrap()[0].id;
+
+    let translated = |code: &str, name: &str, en: &str, children| {
+        let mut n = node(code, name, children);
+        n.names = BTreeMap::from([("en_US".to_string(), en.to_string())]);
+        n
+    };
+    let imported = vec![
+        translated("G", "政府", "Government", vec![node("GA", "文化", Vec::new()), translated("GB", "教育", "Education", Vec::new())]),
+        node("H", "企业", vec![translated("HA", "国企", "State-owned", Vec::new())]),
+    ];
+    let diff = service.apply(&imported).unwrap();
+    assert_eq!(
+        diff.changes,
+        vec![
+            ClassificationChange::Translated { code: "G".into(), locales: vec!["en_US".into()] },
+            ClassificationChange::Translated { code: "GB".into(), locales: vec!["en_US".into()] },
+            ClassificationChange::Translated { code: "HA".into(), locales: vec!["en_US".into()] },
+        ]
+    );
+    let mut translations = TranslationsRepository::new(Rc::clone(&conn));
+    let english = translations.find_names_in("fond_classifications", "en_US").unwrap();
+    assert_eq!(english.get(&id_of("G")).map(String::as_str), Some("Government"));
+    assert_eq!(english.len(), 3);
+
+    // 没有 names 的分类保留现有翻译；names 中没有的语言被删除
+    let mut g = node("G", "政府", vec![node("GA", "文化", Vec::new()), node("GB", "教育", Vec::new())]);
+    g.names = BTreeMap::from([("ja_JP".to_string(), "政府".to_string())]);
+    let imported = vec![g, node("H", "企业", vec![node("HA", "国企", Vec::new())])];
+    let diff = service.apply(&imported).unwrap();
+    assert_eq!(diff.changes, vec![ClassificationChange::Translated { code: "G".into(), locales: vec!["en_US".into(), "ja_JP".into()] }]);
+    let g_names = translations.find_names("fond_classifications", id_of("G")).unwrap();
+    assert_eq!(g_names, BTreeMap::from([("ja_JP".to_string(), "政府".to_string())]));
+    assert_eq!(translations.find_names("fond_classifications", id_of("GB")).unwrap().len(), 1);
+
+    // 删除分类时一并删除其翻译
+    let mut ga = node("GA", "文化", Vec::new());
+    ga.replaces = vec!["HA".into()];
+    service.apply(&[node("G", "政府", vec![ga, node("GB", "教育", Vec::new())]), node("H", "企业", Vec::new())]).unwrap();
+    let remaining = translations.find_all_names("fond_classifications").unwrap();
+    assert_eq!(remaining.len(), 2, "translation of the removed HA is deleted");
+
+    let mut items = FondClassificationsRepository::new(Rc::clone(&conn)).find_all().unwrap();
+    translations.localize(&mut items, "en_US").unwrap();
+    assert_eq!(items.iter().find(|c| c.code == "GB").unwrap().name, "Education");
+    assert_eq!(items.iter().find(|c| c.code == "GA").unwrap().name, "文化");
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
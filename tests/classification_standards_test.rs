@@ -0,0 +1,44 @@
+use fonds_pod_lib::persistence::{establish_connection, FondClassificationsRepository};
+use fonds_pod_lib::services::{ClassificationStandard, LibraryStandard};
+use fonds_pod_lib::GenericRepository;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> std::path::PathBuf {
+    let path = std::path::PathBuf::from(format!(".fondspod_test_{}.db", test_name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_bundled_standards_parse() {
+    assert!(ClassificationStandard::all().len() >= 2);
+    for standard in ClassificationStandard::all() {
+        let tree = standard.classifications().unwrap();
+        assert!(!tree.is_empty(), "{} has classifications", standard.id);
+    }
+    assert!(ClassificationStandard::find("GBT15418-94").is_some());
+    assert!(ClassificationStandard::find("missing").is_none());
+}
+
+#[test]
+fn test_install_standard_with_activated_subset() {
+    let db_path = setup_test_db("classification_standards");
+    let conn = establish_connection(&db_path).unwrap();
+
+    // 未知代码在写入前被拒绝
+    let wrong = LibraryStandard { standard: "GBT15418-94".into(), activate: Some(vec!["BA".into(), "ZZ".into()]) };
+    assert!(wrong.classifications().unwrap_err().to_string().contains("ZZ"));
+    let unknown = LibraryStandard { standard: "missing".into(), activate: None };
+    assert!(unknown.install(Rc::clone(&conn)).is_err());
+
+    // 启用 BA 时连同上级 B 一起启用，其余分类停用
+    let standard = LibraryStandard { standard: "GBT15418-94".into(), activate: Some(vec!["BA".into()]) };
+    let diff = standard.install(Rc::clone(&conn)).unwrap();
+    let all = FondClassificationsRepository::new(Rc::clone(&conn)).find_all().unwrap();
+    assert_eq!(diff.changes.len(), all.len());
+    let active: Vec<&str> = all.iter().filter(|c| c.active).map(|c| c.code.as_str()).collect();
+    assert_eq!(active, vec!["B", "BA"]);
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
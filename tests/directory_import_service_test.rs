@@ -0,0 +1,186 @@
+use diesel::RunQueryDsl;
+use fonds_pod_lib::persistence::{establish_connection, FilesRepository, FondsRepository, ItemsRepository, SeriesRepository};
+use fonds_pod_lib::models::{app_settings::IngestMode, fond::Fond, series::Series};
+use fonds_pod_lib::services::{ChecksumAlgorithm, DirectoryImportService, DirectoryMapping, JobControl};
+use fonds_pod_lib::GenericRepository;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 2025/人事处/{任免.pdf, 调动.docx}, 2025/办公室/通知.pdf, 说明.txt, .DS_Store
+fn write_tree(root: &std::path::Path) {
+    fs::create_dir_all(root.join("2025/人事处")).unwrap();
+    fs::create_dir_all(root.join("2025/办公室")).unwrap();
+    fs::create_dir_all(root.join("空目录")).unwrap();
+    fs::write(root.join("2025/人事处/任免.pdf"), "a").unwrap();
+    fs::write(root.join("2025/人事处/调动.docx"), "b").unwrap();
+    fs::write(root.join("2025/办公室/通知.pdf"), "c").unwrap();
+    fs::write(root.join("说明.txt"), "d").unwrap();
+    fs::write(root.join(".DS_Store"), "").unwrap();
+}
+
+#[test]
+fn test_scan_proposes_files_for_folders_with_documents() {
+    let root = setup_test_dir("directory_scan");
+    let source = root.join("旧档案");
+    write_tree(&source);
+
+    let control = JobControl::default();
+    let mapping = DirectoryImportService::scan(&source, &control).unwrap();
+    let files: Vec<(&str, &str, usize)> = mapping.files.iter().map(|f| (f.folder.as_str(), f.name.as_str(), f.items.len())).collect();
+    assert_eq!(files, vec![("", "旧档案", 1), ("2025/人事处", "2025 / 人事处", 2), ("2025/办公室", "2025 / 办公室", 1)]);
+    assert_eq!(mapping.files[1].items[1].source, "2025/人事处/调动.docx");
+    assert_eq!(mapping.files[1].items[1].name, "调动");
+    assert_eq!((mapping.file_count(), mapping.item_count()), (3, 4));
+    assert_eq!(control.progress().done, 5);
+
+    // 编辑映射表：排除说明、改名，并把通知移入人事处案卷
+    let csv = mapping.to_csv();
+    assert!(csv.starts_with("\u{feff}include,folder,file_name,item,item_name\r\n1,,旧档案,说明.txt,说明\r\n"));
+    let edited = csv
+        .replace("1,,旧档案,说明.txt", "0,,旧档案,说明.txt")
+        .replace("2025 / 人事处", "人事任免")
+        .replace("1,2025/办公室,2025 / 办公室,", "1,2025/人事处,,");
+    let adjusted = DirectoryMapping::from_csv(&source, &edited).unwrap();
+    assert_eq!((adjusted.file_count(), adjusted.item_count()), (1, 3));
+    assert_eq!(adjusted.files[1].name, "人事任免");
+    assert_eq!(adjusted.files[1].items[2].source, "2025/办公室/通知.pdf");
+
+    assert!(DirectoryMapping::from_csv(&source, "include,folder,file_name,item,item_name\n1,,x,../secret.txt,\n").is_err());
+    assert!(DirectoryMapping::from_csv(&source, "include,folder,file_name,item,item_name\n1,,x,缺失.pdf,\n").is_err());
+    assert!(DirectoryMapping::from_csv(&source, "a,b\n").is_err());
+
+    let cancelled = JobControl::default();
+    cancelled.cancel();
+    assert!(DirectoryImportService::scan(&source, &cancelled).is_err());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_import_mapping_in_background_job() {
+    let root = setup_test_dir("directory_import");
+    let source = root.join("旧档案");
+    write_tree(&source);
+    let library = root.join("Archive");
+    fs::create_dir_all(&library).unwrap();
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+
+    let mapping = DirectoryImportService::spawn_scan(source.clone()).unwrap().wait().unwrap();
+    let job = DirectoryImportService::spawn_import(library.clone(), mapping, series_id, IngestMode::Copy, ChecksumAlgorithm::Sha256).unwrap();
+    let report = job.wait().unwrap();
+    assert_eq!((report.files_created, report.items_created, report.cancelled), (3, 4, false));
+
+    let file = FilesRepository::new(Rc::clone(&conn)).find_by_file_no("GA01-2025-02").unwrap().unwrap();
+    assert_eq!(file.name, "2025 / 人事处");
+    let item = ItemsRepository::new(Rc::clone(&conn)).find_by_item_no("GA01-2025-02-002").unwrap().unwrap();
+    assert_eq!(item.name, "调动");
+    assert_eq!(item.path.as_deref(), Some("GA01/GA01-2025-02/GA01-2025-02-002/调动.docx"));
+    assert!(library.join("GA01/GA01-2025-02/GA01-2025-02-002/调动.docx").is_file());
+    assert!(source.join("2025/人事处/调动.docx").exists());
+
+    // 取消后不再创建任何记录
+    let mapping = DirectoryImportService::scan(&source, &JobControl::default()).unwrap();
+    let control = JobControl::default();
+    control.cancel();
+    let service = DirectoryImportService::new(Rc::clone(&conn), &library);
+    let report = service.import(&mapping, series_id, IngestMode::Copy, ChecksumAlgorithm::Sha256, &control).unwrap();
+    assert_eq!((report.files_created, report.items_created, report.cancelled), (0, 0, true));
+    assert_eq!(FilesRepository::new(conn).find_all().unwrap().len(), 3);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_import_rolls_back_payload_when_item_row_fails() {
+    let root = setup_test_dir("directory_import_rollback");
+    let source = root.join("旧档案");
+    write_tree(&source);
+    let library = root.join("Archive");
+    fs::create_dir_all(&library).unwrap();
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    // 模拟写入档案项失败
+    diesel::sql_query("CREATE TRIGGER reject_items BEFORE INSERT ON items BEGIN SELECT RAISE(ABORT, 'rejected'); END")
+        .execute(&mut *conn.borrow_mut())
+        .unwrap();
+
+    let mapping = DirectoryImportService::scan(&source, &JobControl::default()).unwrap();
+    let service = DirectoryImportService::new(Rc::clone(&conn), &library);
+    assert!(service.import(&mapping, series_id, IngestMode::Move, ChecksumAlgorithm::Sha256, &JobControl::default()).is_err());
+
+    // 已移动的原文件放回原处，库中不留无记录的副本
+    assert!(source.join("说明.txt").is_file());
+    assert!(!library.join("GA01/GA01-2025-01/GA01-2025-01-001").exists());
+    assert!(ItemsRepository::new(conn).find_all().unwrap().is_empty());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_import_removes_a_file_that_fails_part_way() {
+    let root = setup_test_dir("directory_import_partial");
+    let source = root.join("旧档案");
+    write_tree(&source);
+    let library = root.join("Archive");
+    fs::create_dir_all(&library).unwrap();
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let service = DirectoryImportService::new(Rc::clone(&conn), &library);
+    let mapping = DirectoryImportService::scan(&source, &JobControl::default()).unwrap();
+
+    // 人事处案卷的第二个文件写入失败：第一个文件和案卷记录一并撤回，之前的案卷保留
+    diesel::sql_query("CREATE TRIGGER reject_items BEFORE INSERT ON items WHEN NEW.name = '调动' BEGIN SELECT RAISE(ABORT, 'rejected'); END")
+        .execute(&mut *conn.borrow_mut())
+        .unwrap();
+    assert!(service.import(&mapping, series_id, IngestMode::Move, ChecksumAlgorithm::Sha256, &JobControl::default()).is_err());
+    let files: Vec<String> = FilesRepository::new(Rc::clone(&conn)).find_all().unwrap().into_iter().map(|f| f.file_no).collect();
+    assert_eq!(files, vec!["GA01-2025-01"]);
+    let items: Vec<String> = ItemsRepository::new(Rc::clone(&conn)).find_all().unwrap().into_iter().map(|i| i.name).collect();
+    assert_eq!(items, vec!["说明"]);
+    assert!(source.join("2025/人事处/任免.pdf").is_file());
+    assert!(source.join("2025/人事处/调动.docx").is_file());
+    assert!(!library.join("GA01/GA01-2025-02").exists());
+
+    // 文件在扫描后被删除，导入时读取失败，同样撤回整个案卷
+    diesel::sql_query("DROP TRIGGER reject_items").execute(&mut *conn.borrow_mut()).unwrap();
+    let mapping = DirectoryImportService::scan(&source.join("2025"), &JobControl::default()).unwrap();
+    fs::remove_file(source.join("2025/人事处/调动.docx")).unwrap();
+    assert!(service.import(&mapping, series_id, IngestMode::Copy, ChecksumAlgorithm::Sha256, &JobControl::default()).is_err());
+    assert_eq!(FilesRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 1);
+    assert_eq!(ItemsRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 1);
+    let mut folders: Vec<String> = fs::read_dir(library.join("GA01"))
+        .unwrap()
+        .map(|e| e.unwrap())
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    folders.sort();
+    assert_eq!(folders, vec!["GA01-2025-01"]);
+
+    let _ = fs::remove_dir_all(&root);
+}
@@ -0,0 +1,85 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    FondsRepository, SeriesRepository, FilesRepository, ItemsRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File, item::Item};
+use fonds_pod_lib::services::{FixityService, ChecksumAlgorithm};
+use fonds_pod_lib::GenericRepository;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_fixity_detects_changed_missing_and_new_files() {
+    let db_path = setup_test_db("fixity_service");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    let payload = std::env::temp_dir().join("fondspod_test_fixity_payload");
+    let _ = fs::remove_dir_all(&payload);
+    fs::create_dir_all(payload.join("scans")).unwrap();
+    fs::write(payload.join("a.txt"), "alpha").unwrap();
+    fs::write(payload.join("b.txt"), "beta").unwrap();
+    fs::write(payload.join("scans").join("p1.tif"), "page one").unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "FX01".into(), fond_classification_code: "FX".into(), name: "校验".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "FX01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "FX01-2025-01".into(), name: "案卷".into(), ..Default::default() })
+        .unwrap();
+    let item_id = ItemsRepository::new(Rc::clone(&conn))
+        .create(Item {
+            file_id,
+            item_no: "FX01-2025-01-001".into(),
+            name: "文件".into(),
+            path: Some(payload.to_string_lossy().to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let service = FixityService::new(Rc::clone(&conn), std::env::temp_dir());
+    let now = chrono::Local::now().naive_local();
+    assert!(service.is_due(chrono::Duration::days(30), now).unwrap());
+
+    assert_eq!(service.record_item(item_id, ChecksumAlgorithm::Sha256).unwrap(), 3);
+    let manifest = service.manifest(item_id).unwrap();
+    assert!(manifest.iter().any(|e| e.relative_path == "scans/p1.tif"));
+
+    let report = service.verify_all().unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.checked, 3);
+    assert!(!service.is_due(chrono::Duration::days(30), now).unwrap());
+    assert!(service.is_due(chrono::Duration::days(30), now + chrono::Duration::days(31)).unwrap());
+
+    fs::write(payload.join("a.txt"), "tampered").unwrap();
+    fs::remove_file(payload.join("b.txt")).unwrap();
+    fs::write(payload.join("scans").join("p2.tif"), "page two").unwrap();
+
+    let report = service.verify_all().unwrap();
+    assert_eq!(report.checked, 2);
+    assert_eq!(report.changed.len(), 1);
+    assert_eq!(report.changed[0].relative_path, "a.txt");
+    assert_eq!(report.missing[0].relative_path, "b.txt");
+    assert_eq!(report.new_files[0].relative_path, "scans/p2.tif");
+    assert!(report.to_csv().contains("changed,FX01-2025-01-001,a.txt"));
+
+    // 接受变更后重新记录，校验恢复正常
+    service.record_item(item_id, ChecksumAlgorithm::Sha256).unwrap();
+    assert!(service.verify_all().unwrap().is_clean());
+
+    service.forget_item(item_id).unwrap();
+    assert!(service.manifest(item_id).unwrap().is_empty());
+
+    let _ = fs::remove_dir_all(&payload);
+}
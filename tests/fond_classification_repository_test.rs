@@ -2,7 +2,7 @@ use fonds_pod_lib::persistence::{
     establish_connection,
     fond_classification_repository::FondClassificationsRepository,
 };
-use fonds_pod_lib::{models::fond_classification::FondClassification, GenericRepository, ActiveableRepository, SortableRepository};
+use fonds_pod_lib::{models::fond_classification::FondClassification, GenericRepository, ActiveableRepository, HierarchicalRepository, SortableRepository};
 use std::path::Path;
 use std::fs;
 
@@ -85,4 +85,53 @@ fn test_fond_classifications_repository() {
     let resorted = repo.find_sorted().unwrap();
     assert_eq!(resorted[0].code, "GB"); // sort_order = 1
     assert_eq!(resorted[1].code, "GA"); // sort_order = 3
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_fond_classification_tree() {
+    let db_path = setup_test_db("fond_classification_tree");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let mut repo = FondClassificationsRepository::new(conn);
+    let add = |repo: &mut FondClassificationsRepository, code: &str, parent_id: Option<i32>, sort_order: i32| {
+        repo.create(FondClassification { code: code.into(), name: code.into(), parent_id, active: true, sort_order, ..Default::default() })
+            .unwrap()
+    };
+
+    // G { GA { GA1 { GA1a } }, GB }, H
+    let g = add(&mut repo, "G", None, 0);
+    let ga = add(&mut repo, "GA", Some(g), 0);
+    let gb = add(&mut repo, "GB", Some(g), 1);
+    let ga1 = add(&mut repo, "GA1", Some(ga), 0);
+    let ga1a = add(&mut repo, "GA1a", Some(ga1), 0);
+    let h = add(&mut repo, "H", None, 1);
+
+    let tree: Vec<(usize, String)> = repo.find_tree(None).unwrap().into_iter().map(|(d, c)| (d, c.code)).collect();
+    assert_eq!(tree, vec![(0, "G".into()), (1, "GA".into()), (2, "GA1".into()), (3, "GA1a".into()), (1, "GB".into()), (0, "H".into())]);
+    let ancestors: Vec<i32> = repo.find_ancestors(ga1a).unwrap().iter().map(|c| c.id).collect();
+    assert_eq!(ancestors, vec![g, ga, ga1]);
+    assert_eq!(repo.find_descendants(ga).unwrap().len(), 2);
+
+    // 不能移动到自身或下级分类之下
+    assert!(repo.move_subtree(ga, Some(ga1a), 0).is_err());
+    assert!(repo.move_subtree(ga, Some(ga), 0).is_err());
+
+    // GA 连同下级分类移到 H 下；H 上移到 G 之前
+    repo.move_subtree(ga, Some(h), 0).unwrap();
+    assert_eq!(repo.find_ancestors(ga1a).unwrap().first().unwrap().id, h);
+    repo.move_among_siblings(h, -1).unwrap();
+    let tops: Vec<String> = repo.find_by_parent_id(None).unwrap().into_iter().map(|c| c.code).collect();
+    assert_eq!(tops, vec!["H", "G"]);
+    assert_eq!(repo.find_by_parent_id(Some(g)).unwrap()[0].id, gb);
+
+    // 停用向下级传递，启用向上级传递
+    repo.deactivate_subtree(ga).unwrap();
+    assert!(!repo.find_by_id(ga1a).unwrap().unwrap().active);
+    repo.deactivate(h).unwrap();
+    repo.activate_with_ancestors(ga1).unwrap();
+    assert!(repo.find_by_id(h).unwrap().unwrap().active);
+    assert!(repo.find_by_id(ga).unwrap().unwrap().active);
+    assert!(!repo.find_by_id(ga1a).unwrap().unwrap().active);
+
+    assert_eq!(repo.delete_subtree(ga).unwrap(), 3);
+    assert_eq!(repo.find_all().unwrap().len(), 3);
+}
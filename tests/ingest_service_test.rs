@@ -0,0 +1,88 @@
+use fonds_pod_lib::models::app_settings::IngestMode;
+use fonds_pod_lib::services::IngestService;
+use std::fs;
+use std::path::Path;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_ingest_copy_move_and_link() {
+    let root = setup_test_dir("ingest_service");
+    let source = root.join("picked");
+    fs::create_dir_all(source.join("scans")).unwrap();
+    fs::write(source.join("a.txt"), "alpha").unwrap();
+    fs::write(source.join("scans").join("p1.tif"), "page one").unwrap();
+    let library = root.join("library");
+
+    // 复制：原文件保留，目标按全宗/案卷/档案项编号组织
+    let destination = IngestService::destination(&library, "GA01", "GA01-2025-01", "GA01-2025-01-001");
+    assert!(destination.ends_with(Path::new("GA01").join("GA01-2025-01").join("GA01-2025-01-001")));
+    let mut reported = Vec::new();
+    let outcome = IngestService::ingest(&source, &destination, IngestMode::Copy, |p| reported.push(p.files_done)).unwrap();
+    assert_eq!(outcome.files, 2);
+    assert_eq!(outcome.bytes, 13);
+    assert_eq!(reported, vec![1, 2]);
+    assert_eq!(outcome.source_path, source.to_string_lossy());
+    assert_eq!(fs::read_to_string(destination.join("scans").join("p1.tif")).unwrap(), "page one");
+    assert!(source.join("a.txt").exists());
+
+    // 目标目录已有内容时拒绝覆盖
+    assert!(IngestService::ingest(&source, &destination, IngestMode::Copy, |_| {}).is_err());
+
+    // 移动单个文件：档案项路径指向复制后的文件，原文件被删除
+    let single = root.join("letter.pdf");
+    fs::write(&single, "letter").unwrap();
+    let destination = IngestService::destination(&library, "GA01", "GA01-2025-01", "GA01-2025-01-002");
+    let outcome = IngestService::ingest(&single, &destination, IngestMode::Move, |_| {}).unwrap();
+    assert_eq!(Path::new(&outcome.path), destination.join("letter.pdf"));
+    assert!(!single.exists());
+    assert_eq!(fs::read_to_string(&outcome.path).unwrap(), "letter");
+
+    // 仅链接：不复制，路径与来源相同
+    let destination = IngestService::destination(&library, "GA01", "GA01-2025-01", "GA01-2025-01-003");
+    let outcome = IngestService::ingest(&source, &destination, IngestMode::Link, |_| {}).unwrap();
+    assert_eq!(outcome.path, outcome.source_path);
+    assert!(!destination.exists());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_rollback_restores_moved_payloads() {
+    let root = setup_test_dir("ingest_rollback");
+    let library = root.join("library");
+
+    // 移动文件夹后撤销：原位置恢复，库内副本删除
+    let source = root.join("picked");
+    fs::create_dir_all(source.join("scans")).unwrap();
+    fs::write(source.join("scans").join("p1.tif"), "page one").unwrap();
+    let destination = IngestService::destination(&library, "GA01", "GA01-2025-01", "GA01-2025-01-001");
+    let outcome = IngestService::ingest(&source, &destination, IngestMode::Move, |_| {}).unwrap();
+    assert!(!source.exists());
+    IngestService::rollback(&outcome, &destination, IngestMode::Move).unwrap();
+    assert_eq!(fs::read_to_string(source.join("scans").join("p1.tif")).unwrap(), "page one");
+    assert!(!destination.exists());
+
+    // 移动单个文件后撤销
+    let single = root.join("letter.pdf");
+    fs::write(&single, "letter").unwrap();
+    let outcome = IngestService::ingest(&single, &destination, IngestMode::Move, |_| {}).unwrap();
+    IngestService::rollback(&outcome, &destination, IngestMode::Move).unwrap();
+    assert_eq!(fs::read_to_string(&single).unwrap(), "letter");
+    assert!(!destination.exists());
+
+    // 复制后撤销只删除副本
+    let outcome = IngestService::ingest(&single, &destination, IngestMode::Copy, |_| {}).unwrap();
+    IngestService::rollback(&outcome, &destination, IngestMode::Copy).unwrap();
+    assert!(single.exists());
+    assert!(!destination.exists());
+
+    let _ = fs::remove_dir_all(&root);
+}
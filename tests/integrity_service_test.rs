@@ -0,0 +1,149 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    FondsRepository, SeriesRepository, FilesRepository, ItemsRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File, item::Item};
+use fonds_pod_lib::services::{IntegrityService, IntegrityIssue, IntegrityRepair, RepairChoice};
+use fonds_pod_lib::GenericRepository;
+use diesel::RunQueryDsl;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_integrity_check_and_repair() {
+    let db_path = setup_test_db("integrity_service");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    let library = std::env::temp_dir().join("fondspod_test_integrity_library");
+    let _ = fs::remove_dir_all(&library);
+    let fond_dir = library.join("GA01");
+    fs::create_dir_all(fond_dir.join("GA01-2025-01")).unwrap();
+    fs::create_dir_all(fond_dir.join("GA01-2025-02")).unwrap();
+    fs::create_dir_all(fond_dir.join("stray")).unwrap();
+    fs::create_dir_all(library.join("ZZ99")).unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let mut series_repo = SeriesRepository::new(Rc::clone(&conn));
+    let series_id = series_repo
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let bad_series = series_repo
+        .create(Series { fond_id, series_no: "XX-2024".into(), name: "2024".into(), ..Default::default() })
+        .unwrap();
+    let mut files_repo = FilesRepository::new(Rc::clone(&conn));
+    files_repo
+        .create(File {
+            series_id,
+            file_no: "GA01-2025-01".into(),
+            name: "案卷一".into(),
+            path: Some(fond_dir.join("GA01-2025-01").to_string_lossy().to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    // 目录被移动：数据库仍指向旧位置
+    let moved = files_repo
+        .create(File {
+            series_id,
+            file_no: "GA01-2025-02".into(),
+            name: "案卷二".into(),
+            path: Some(library.join("old").join("GA01-2025-02").to_string_lossy().to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    // 模拟在应用外部关闭外键约束后写入的悬空记录
+    diesel::sql_query("PRAGMA foreign_keys = OFF").execute(&mut *conn.borrow_mut()).unwrap();
+    let orphan_item = ItemsRepository::new(Rc::clone(&conn))
+        .create(Item { file_id: 9999, item_no: "GA01-2025-09-001".into(), name: "孤立文件".into(), ..Default::default() })
+        .unwrap();
+
+    let service = IntegrityService::new(Rc::clone(&conn), &library);
+    let report = service.check().unwrap();
+    assert_eq!(report.count("dangling_reference"), 1);
+    assert_eq!(report.count("number_mismatch"), 1);
+    assert_eq!(report.count("missing_path"), 1);
+    assert_eq!(report.count("orphan_directory"), 2);
+    assert!(report.issues.iter().any(|i| matches!(i, IntegrityIssue::NumberMismatch { entity_id, .. } if *entity_id == bad_series)));
+    assert!(report.issues.iter().any(|i| matches!(i, IntegrityIssue::OrphanDirectory { path } if path.ends_with("stray"))));
+    assert!(report.to_csv().contains("missing_path"));
+
+    let missing = report.issues.iter().find(|i| i.kind() == "missing_path").unwrap();
+    assert!(matches!(missing.repairs().first(), Some(IntegrityRepair::RelinkPath { entity_id, .. }) if *entity_id == moved));
+
+    // 删除记录的问题先询问
+    let mut asked = Vec::new();
+    let applied = service
+        .repair_each(|issue, repairs, _| {
+            asked.push(issue.kind());
+            RepairChoice::Apply(repairs[0].clone())
+        })
+        .unwrap();
+    assert_eq!(applied, 2);
+    assert_eq!(asked, vec!["dangling_reference", "missing_path"]);
+    let report = service.check().unwrap();
+    assert_eq!(report.count("dangling_reference"), 0);
+    assert_eq!(report.count("missing_path"), 0);
+    // 编号不符和孤立目录需要人工处理
+    assert_eq!(report.count("number_mismatch"), 1);
+    assert_eq!(report.count("orphan_directory"), 2);
+    assert!(ItemsRepository::new(Rc::clone(&conn)).find_by_id(orphan_item).unwrap().is_none());
+    let relinked = files_repo.find_by_id(moved).unwrap().unwrap();
+    assert!(relinked.path.unwrap().ends_with("GA01-2025-02"));
+
+    let _ = fs::remove_dir_all(&library);
+}
+
+#[test]
+fn test_guided_repair_lists_child_rows_and_honours_skip() {
+    let db_path = setup_test_db("integrity_guided");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let library = std::env::temp_dir().join("fondspod_test_integrity_guided");
+    let _ = fs::remove_dir_all(&library);
+    fs::create_dir_all(&library).unwrap();
+
+    // 全宗已不存在的系列，其下还有案卷和文件
+    diesel::sql_query("PRAGMA foreign_keys = OFF").execute(&mut *conn.borrow_mut()).unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id: 9999, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷".into(), ..Default::default() })
+        .unwrap();
+    ItemsRepository::new(Rc::clone(&conn))
+        .create(Item { file_id, item_no: "GA01-2025-01-001".into(), name: "文件".into(), ..Default::default() })
+        .unwrap();
+    diesel::sql_query("PRAGMA foreign_keys = ON").execute(&mut *conn.borrow_mut()).unwrap();
+
+    let service = IntegrityService::new(Rc::clone(&conn), &library);
+    let mut asked = 0;
+    assert_eq!(service.repair_each(|_, _, _| { asked += 1; RepairChoice::Skip }).unwrap(), 0);
+    assert_eq!(asked, 1);
+    assert_eq!(SeriesRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 1);
+
+    let mut described = String::new();
+    let applied = service
+        .repair_each(|_, repairs, deleted| {
+            described = deleted.describe();
+            RepairChoice::Apply(repairs[0].clone())
+        })
+        .unwrap();
+    assert_eq!(applied, 1);
+    assert_eq!(described, "1 个案卷、1 个文件");
+    assert!(FilesRepository::new(Rc::clone(&conn)).find_all().unwrap().is_empty());
+    assert!(ItemsRepository::new(Rc::clone(&conn)).find_all().unwrap().is_empty());
+
+    drop(service);
+    drop(conn);
+    let _ = fs::remove_file(&db_path);
+    let _ = fs::remove_dir_all(&library);
+}
@@ -0,0 +1,108 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    FondsRepository, SeriesRepository, FilesRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File};
+use fonds_pod_lib::services::{JournalService, JournalDivergence};
+use fonds_pod_lib::GenericRepository;
+use diesel::RunQueryDsl;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+fn exec(conn: &Rc<std::cell::RefCell<diesel::SqliteConnection>>, sql: &str) {
+    diesel::sql_query(sql).execute(&mut *conn.borrow_mut()).unwrap();
+}
+
+#[test]
+fn test_journal_detects_tampering() {
+    let db_path = setup_test_db("journal_service");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let mut files_repo = FilesRepository::new(Rc::clone(&conn));
+    let file_id = files_repo
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷一".into(), ..Default::default() })
+        .unwrap();
+    let mut file = files_repo.find_by_id(file_id).unwrap().unwrap();
+    file.name = "案卷一（修订）".into();
+    files_repo.update(&file).unwrap();
+
+    let service = JournalService::new(Rc::clone(&conn));
+    let report = service.verify(None).unwrap();
+    assert!(report.is_intact(), "{:?}", report.divergence);
+    assert_eq!(report.entries_checked, 5);
+
+    // 检查点导出与读取
+    let checkpoint_path = format!("{}.checkpoint.json", db_path);
+    let checkpoint = service.export_checkpoint(Path::new(&checkpoint_path)).unwrap();
+    let loaded = JournalService::load_checkpoint(Path::new(&checkpoint_path)).unwrap();
+    assert_eq!(loaded, checkpoint);
+    let _ = fs::remove_file(&checkpoint_path);
+    assert!(service.verify(Some(&checkpoint)).unwrap().is_intact());
+
+    // 绕过应用修改记录
+    exec(&conn, "UPDATE files SET name = '改名' WHERE file_no = 'GA01-2025-01'");
+    assert_eq!(
+        service.verify(None).unwrap().divergence,
+        Some(JournalDivergence::RowModified { entity_type: "files".into(), entity_id: file_id })
+    );
+    exec(&conn, "UPDATE files SET name = '案卷一（修订）' WHERE file_no = 'GA01-2025-01'");
+    assert!(service.verify(None).unwrap().is_intact());
+
+    // 绕过应用插入记录
+    exec(&conn, "INSERT INTO files (series_id, name, file_no, created_by, created_machine, created_at) VALUES (1, '私加', 'GA01-2025-99', 'x', 'x', '2025-01-01 00:00:00')");
+    assert!(matches!(
+        service.verify(None).unwrap().divergence,
+        Some(JournalDivergence::RowUntracked { .. })
+    ));
+    exec(&conn, "DELETE FROM files WHERE file_no = 'GA01-2025-99'");
+
+    // 篡改日志本身（先去掉只追加触发器）
+    exec(&conn, "DROP TRIGGER audit_log_no_update");
+    exec(&conn, "UPDATE audit_log SET created_by = 'someone' WHERE entity_type = 'series'");
+    assert!(matches!(
+        service.verify(None).unwrap().divergence,
+        Some(JournalDivergence::HashMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_checkpoint_detects_truncation_and_seal_adopts_legacy_rows() {
+    let db_path = setup_test_db("journal_checkpoint");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let service = JournalService::new(Rc::clone(&conn));
+    let checkpoint = service.checkpoint().unwrap();
+
+    // 日志启用前已存在的记录：先报告未纳入日志，封存后校验通过
+    exec(&conn, "INSERT INTO fonds (fond_no, fond_classification_code, name, created_by, created_machine, created_at) VALUES ('GB01', 'GB', '旧全宗', 'x', 'x', '2024-01-01 00:00:00')");
+    assert!(!service.verify(Some(&checkpoint)).unwrap().is_intact());
+    assert_eq!(service.seal_untracked().unwrap(), 1);
+    assert!(service.verify(Some(&checkpoint)).unwrap().is_intact());
+
+    // 删除检查点之后的日志尾部，再删除对应记录：链本身仍连贯，但检查点不再匹配
+    exec(&conn, "DROP TRIGGER audit_log_no_delete");
+    exec(&conn, &format!("DELETE FROM audit_log WHERE id >= {}", checkpoint.entry_id));
+    exec(&conn, "DELETE FROM fonds");
+    assert_eq!(
+        service.verify(Some(&checkpoint)).unwrap().divergence,
+        Some(JournalDivergence::CheckpointMismatch { entry_id: checkpoint.entry_id })
+    );
+}
@@ -0,0 +1,147 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    FondsRepository, SeriesRepository, FilesRepository, ItemsRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File, item::Item};
+use fonds_pod_lib::services::{LibraryPathService, FixityService, ChecksumAlgorithm};
+use fonds_pod_lib::GenericRepository;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_relative_paths_migration_and_relink() {
+    let db_path = setup_test_db("library_path_service");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    let root = std::env::temp_dir().join("fondspod_test_library_paths");
+    let _ = fs::remove_dir_all(&root);
+    let library = root.join("library");
+    let file_dir = library.join("GA01").join("GA01-2025-01");
+    fs::create_dir_all(&file_dir).unwrap();
+    fs::write(file_dir.join("scan.tif"), "scan").unwrap();
+
+    assert_eq!(LibraryPathService::to_stored(&library, &file_dir), "GA01/GA01-2025-01");
+    assert_eq!(LibraryPathService::resolve(&library, "GA01/GA01-2025-01"), file_dir);
+    let outside = root.join("elsewhere");
+    assert_eq!(LibraryPathService::to_stored(&library, &outside), outside.to_string_lossy());
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷".into(), path: Some(file_dir.to_string_lossy().to_string()), ..Default::default() })
+        .unwrap();
+    let mut items_repo = ItemsRepository::new(Rc::clone(&conn));
+    let inside_item = items_repo
+        .create(Item { file_id, item_no: "GA01-2025-01-001".into(), name: "扫描件".into(), path: Some(file_dir.join("scan.tif").to_string_lossy().to_string()), ..Default::default() })
+        .unwrap();
+    // 外部移动硬盘上的路径，硬盘已改挂载到其他位置
+    let external_item = items_repo
+        .create(Item { file_id, item_no: "GA01-2025-01-002".into(), name: "外部".into(), path: Some("/fondspod-missing-mount/scans/GA01-2025-01-002".into()), ..Default::default() })
+        .unwrap();
+
+    let service = LibraryPathService::new(Rc::clone(&conn), &library);
+    assert_eq!(service.migrate_absolute_paths().unwrap(), 2);
+    assert_eq!(service.migrate_absolute_paths().unwrap(), 0);
+    let file = FilesRepository::new(Rc::clone(&conn)).find_by_id(file_id).unwrap().unwrap();
+    assert_eq!(file.path.as_deref(), Some("GA01/GA01-2025-01"));
+
+    // 相对路径在运行时解析，校验服务照常工作
+    let fixity = FixityService::new(Rc::clone(&conn), &library);
+    assert_eq!(fixity.record_item(inside_item, ChecksumAlgorithm::Sha256).unwrap(), 1);
+
+    let external = service.external_paths().unwrap();
+    assert_eq!(external.len(), 1);
+    assert!(!external[0].exists);
+
+    let new_mount = root.join("mnt");
+    fs::create_dir_all(new_mount.join("scans").join("GA01-2025-01-002")).unwrap();
+    assert_eq!(service.relink(&new_mount).unwrap().relinked, 1);
+    let item = items_repo.find_by_id(external_item).unwrap().unwrap();
+    assert_eq!(Path::new(item.path.as_deref().unwrap()), new_mount.join("scans").join("GA01-2025-01-002"));
+    assert!(service.external_paths().unwrap().iter().all(|e| e.exists));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_relink_needs_two_components_or_matching_checksums() {
+    let db_path = setup_test_db("library_path_relink");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let root = std::env::temp_dir().join("fondspod_test_library_relink");
+    let _ = fs::remove_dir_all(&root);
+    let library = root.join("library");
+    fs::create_dir_all(&library).unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷".into(), ..Default::default() })
+        .unwrap();
+
+    // 三个库外档案项，前两个在原位置时记录了校验值，之后原位置失效
+    let fixity = FixityService::new(Rc::clone(&conn), &library);
+    let mut items_repo = ItemsRepository::new(Rc::clone(&conn));
+    let mut external = |n: usize, content: &str, record: bool| {
+        let old = root.join("old").join(format!("dir{}", n)).join(format!("scan{}.tif", n));
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, content).unwrap();
+        let id = items_repo
+            .create(Item { file_id, item_no: format!("GA01-2025-01-00{}", n), name: "外部".into(), path: Some(old.to_string_lossy().to_string()), ..Default::default() })
+            .unwrap();
+        if record {
+            fixity.record_item(id, ChecksumAlgorithm::Sha256).unwrap();
+        }
+        id
+    };
+    let verified = external(1, "one", true);
+    let changed = external(2, "two", true);
+    let ambiguous = external(3, "three", false);
+    fs::remove_dir_all(root.join("old")).unwrap();
+
+    // 只有文件名相同：校验值一致的采用，不一致的不采用；两个同样匹配的位置都不采用
+    let mount = root.join("mnt");
+    fs::create_dir_all(mount.join("other")).unwrap();
+    fs::write(mount.join("other").join("scan1.tif"), "one").unwrap();
+    fs::write(mount.join("other").join("scan2.tif"), "changed").unwrap();
+    for copy in ["a", "b"] {
+        fs::create_dir_all(mount.join(copy).join("dir3")).unwrap();
+        fs::write(mount.join(copy).join("dir3").join("scan3.tif"), "three").unwrap();
+    }
+
+    let service = LibraryPathService::new(Rc::clone(&conn), &library);
+    let report = service.relink(&mount).unwrap();
+    assert_eq!(report.relinked, 1);
+    assert_eq!(report.not_found.iter().map(|e| e.entity_id).collect::<Vec<_>>(), vec![changed]);
+    assert_eq!(report.ambiguous.len(), 1);
+    assert_eq!(report.ambiguous[0].0.entity_id, ambiguous);
+    assert_eq!(report.ambiguous[0].1, vec![mount.join("a/dir3/scan3.tif"), mount.join("b/dir3/scan3.tif")]);
+
+    let item = items_repo.find_by_id(verified).unwrap().unwrap();
+    assert_eq!(Path::new(item.path.as_deref().unwrap()), mount.join("other").join("scan1.tif"));
+    for id in [changed, ambiguous] {
+        let item = items_repo.find_by_id(id).unwrap().unwrap();
+        assert!(item.path.unwrap().contains("old"));
+    }
+
+    drop(items_repo);
+    let _ = fs::remove_dir_all(&root);
+    drop(conn);
+    let _ = fs::remove_file(&db_path);
+}
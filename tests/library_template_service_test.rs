@@ -0,0 +1,117 @@
+use diesel::{sql_query, RunQueryDsl};
+use fonds_pod_lib::models::app_settings::{AppSettings, IngestMode};
+use fonds_pod_lib::models::fond_classification::FondClassification;
+use fonds_pod_lib::models::schema::Schema;
+use fonds_pod_lib::models::schema_item::SchemaItem;
+use fonds_pod_lib::persistence::schema_item_repository::SchemaItemRepository;
+use fonds_pod_lib::persistence::{establish_connection, FondClassificationsRepository, SchemaRepository, SequencesRepository};
+use fonds_pod_lib::services::{LibraryTemplate, LibraryTemplateService, NumberingConvention};
+use fonds_pod_lib::GenericRepository;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> std::path::PathBuf {
+    let path = std::path::PathBuf::from(format!(".fondspod_test_{}.db", test_name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_capture_save_and_install_template() {
+    let source_path = setup_test_db("template_source");
+    let target_path = setup_test_db("template_target");
+    let dir = std::env::temp_dir().join("fondspod_test_templates");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // 来源库：分类 GA、档案模式 D { 01 办公室 }、全宗号序列 GA 四位
+    let source = establish_connection(&source_path).unwrap();
+    FondClassificationsRepository::new(Rc::clone(&source))
+        .create(FondClassification { code: "GA".into(), name: "文化".into(), active: true, ..Default::default() })
+        .unwrap();
+    let d = SchemaRepository::new(Rc::clone(&source))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), sort_order: 1, ..Default::default() })
+        .unwrap();
+    SchemaItemRepository::new(Rc::clone(&source))
+        .create(SchemaItem { schema_id: d, item_no: "01".into(), item_name: "办公室".into(), ..Default::default() })
+        .unwrap();
+    let mut sequences = SequencesRepository::new(Rc::clone(&source));
+    sequences.insert("GA", 7, 4).unwrap();
+    sequences.insert("GA0001-D01", 3, 3).unwrap();
+
+    let settings = AppSettings { ingest_mode: IngestMode::Move, fixity_interval_days: 30, ..Default::default() };
+    let template = LibraryTemplate::capture(Rc::clone(&source), "文化馆", "来源库", &settings).unwrap();
+    // 只带分类代码前缀的序列，且从 1 开始
+    assert_eq!(template.numbering, vec![NumberingConvention { prefix: "GA".into(), digits: 4 }]);
+
+    let service = LibraryTemplateService::new(dir.clone());
+    assert!(service.list().unwrap().is_empty());
+    service.save(&template).unwrap();
+    assert_eq!(service.list().unwrap(), vec!["文化馆".to_string()]);
+    assert!(service.exists("文化馆"));
+    let loaded = service.load("文化馆").unwrap();
+    assert_eq!(loaded.settings.ingest_mode, IngestMode::Move);
+    assert_eq!(loaded.settings.fixity_interval_days, 30);
+    assert_eq!(loaded.settings.checksum_algorithm.as_deref(), Some("sha256"));
+
+    let target = establish_connection(&target_path).unwrap();
+    loaded.install(Rc::clone(&target)).unwrap();
+    let codes: Vec<String> = FondClassificationsRepository::new(Rc::clone(&target))
+        .find_all()
+        .unwrap()
+        .into_iter()
+        .map(|c| c.code)
+        .collect();
+    assert_eq!(codes, vec!["GA".to_string()]);
+    let schemas = SchemaRepository::new(Rc::clone(&target)).find_all().unwrap();
+    assert!(schemas.iter().any(|s| s.schema_no == "D" && s.name == "部门"));
+    let items = SchemaItemRepository::new(Rc::clone(&target)).find_all().unwrap();
+    assert!(items.iter().any(|i| i.item_no == "01" && i.item_name == "办公室"));
+    let mut target_sequences = SequencesRepository::new(Rc::clone(&target));
+    let ga = target_sequences.find_by_prefix("GA").unwrap().unwrap();
+    assert_eq!((ga.next_value, ga.digits), (1, 4));
+    assert!(target_sequences.find_by_prefix("GA0001-D01").unwrap().is_none());
+
+    service.delete("文化馆").unwrap();
+    assert!(!service.exists("文化馆"));
+
+    drop(source);
+    drop(target);
+    let _ = std::fs::remove_file(&source_path);
+    let _ = std::fs::remove_file(&target_path);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_failed_install_leaves_no_template_rows() {
+    let source_path = setup_test_db("template_rollback_source");
+    let target_path = setup_test_db("template_rollback_target");
+
+    let source = establish_connection(&source_path).unwrap();
+    FondClassificationsRepository::new(Rc::clone(&source))
+        .create(FondClassification { code: "GA".into(), name: "文化".into(), active: true, ..Default::default() })
+        .unwrap();
+    SequencesRepository::new(Rc::clone(&source)).insert("GA", 7, 4).unwrap();
+    let template = LibraryTemplate::capture(Rc::clone(&source), "文化馆", "来源库", &AppSettings::default()).unwrap();
+
+    // 写入编号序列时失败，之前安装的分类也应撤回
+    let target = establish_connection(&target_path).unwrap();
+    sql_query("CREATE TRIGGER reject_sequence BEFORE INSERT ON sequences BEGIN SELECT RAISE(ABORT, 'rejected'); END")
+        .execute(&mut *target.borrow_mut())
+        .unwrap();
+    assert!(template.install(Rc::clone(&target)).is_err());
+    assert!(FondClassificationsRepository::new(Rc::clone(&target)).find_all().unwrap().is_empty());
+
+    drop(source);
+    drop(target);
+    let _ = std::fs::remove_file(&source_path);
+    let _ = std::fs::remove_file(&target_path);
+}
+
+#[test]
+fn test_reject_template_names_with_path_characters() {
+    let service = LibraryTemplateService::new(std::env::temp_dir().join("fondspod_test_template_names"));
+    for name in ["", "  ", "../a", "a/b", "a\\b", ".hidden"] {
+        assert!(service.load(name).is_err(), "{:?} rejected", name);
+        assert!(!service.exists(name));
+    }
+    assert!(service.load("不存在").unwrap_err().to_string().contains("不存在"));
+}
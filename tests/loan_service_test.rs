@@ -0,0 +1,78 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    FondsRepository, SeriesRepository, FilesRepository, ItemsRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File, item::Item};
+use fonds_pod_lib::services::LoanService;
+use fonds_pod_lib::GenericRepository;
+use chrono::{Duration, NaiveDate};
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_lend_return_and_overdue() {
+    let db_path = setup_test_db("loan_service");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷一".into(), ..Default::default() })
+        .unwrap();
+    let item_id = ItemsRepository::new(Rc::clone(&conn))
+        .create(Item { file_id, item_no: "GA01-2025-01-001".into(), name: "文件一".into(), ..Default::default() })
+        .unwrap();
+
+    let service = LoanService::new(Rc::clone(&conn));
+    let alice = service.add_borrower("张三", "办公室", "").unwrap();
+    let bob = service.add_borrower("李四", "档案室", "").unwrap();
+    assert!(service.add_borrower("  ", "", "").is_err());
+
+    let day1 = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    let due = day1 + Duration::days(7);
+
+    // 借出文件后，其所属案卷和该文件本身都不能再借
+    let item_loan = service.lend_item(alice, item_id, "查阅", day1, due).unwrap();
+    assert!(service.lend_file(bob, file_id, "查阅", day1, due).is_err());
+    assert!(service.lend_item(bob, item_id, "查阅", day1, due).is_err());
+
+    service.approve(item_loan, "管理员").unwrap();
+    assert!(service.approve(item_loan, "管理员").is_err());
+
+    // 逾期列表
+    assert!(service.overdue(day1 + Duration::days(1)).unwrap().is_empty());
+    let overdue = service.overdue(due + Duration::days(1)).unwrap();
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue[0].id, item_loan);
+
+    // 归还后可以借出整个案卷
+    service.return_loan(item_loan, day1 + Duration::days(3)).unwrap();
+    assert!(service.return_loan(item_loan, day1 + Duration::days(3)).is_err());
+    assert!(service.overdue(due + Duration::days(1)).unwrap().is_empty());
+
+    let file_loan = service.lend_file(bob, file_id, "编研", day1 + Duration::days(4), due + Duration::days(4)).unwrap();
+    assert!(service.lend_item(alice, item_id, "查阅", day1, due).is_err());
+
+    // 驳回后案卷释放
+    service.reject(file_loan, "管理员").unwrap();
+    assert!(service.lend_item(alice, item_id, "查阅", day1 + Duration::days(5), due + Duration::days(5)).is_ok());
+
+    let history = service.borrower_history(alice).unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].loan_date > history[1].loan_date);
+    assert_eq!(service.borrower_history(bob).unwrap().len(), 1);
+    assert_eq!(service.open_loans().unwrap().len(), 1);
+}
@@ -0,0 +1,80 @@
+use fonds_pod_lib::persistence::{establish_connection, FilesRepository, FondsRepository, ItemsRepository, SeriesRepository};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File, item::Item};
+use fonds_pod_lib::services::LoanService;
+use fonds_pod_lib::viewmodels::loan_vm::LoanListMode;
+use fonds_pod_lib::viewmodels::LoanViewModel;
+use fonds_pod_lib::GenericRepository;
+use chrono::Duration;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_record_loans_by_number_and_list_history_and_overdue() {
+    let db_path = setup_test_db("loan_vm");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let file_id = FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷一".into(), ..Default::default() })
+        .unwrap();
+    ItemsRepository::new(Rc::clone(&conn))
+        .create(Item { file_id, item_no: "GA01-2025-01-001".into(), name: "文件一".into(), ..Default::default() })
+        .unwrap();
+
+    let mut vm = LoanViewModel::new(Rc::clone(&conn));
+    vm.load().unwrap();
+    assert!(vm.lend("GA01-2025-01", "查档", 7).unwrap_err().to_string().contains("借阅人"));
+
+    vm.add_borrower("张三", "办公室", "").unwrap();
+    vm.lend("GA01-2025-01-001", "编史修志", 7).unwrap();
+    assert!(vm.lend("GA01-2025-01", "查档", 7).unwrap_err().to_string().contains("GA01-2025-01-001"));
+    assert!(vm.lend("GA01-2025-99", "查档", 7).unwrap_err().to_string().contains("找不到"));
+    assert_eq!(vm.loans.len(), 1);
+    let rows = vm.loan_items();
+    assert_eq!(rows[0].title, "文件 GA01-2025-01-001 文件一");
+    assert!(rows[0].detail.contains("待审批"));
+
+    // 批准、归还后记录留在借阅历史中
+    vm.selected_loan = 0;
+    vm.approve_selected(true).unwrap();
+    vm.selected_loan = 0;
+    vm.return_selected().unwrap();
+    assert!(vm.loan_items()[0].detail.contains("归还"));
+    assert!(!vm.loan_items()[0].active);
+
+    // 逾期清单包含所有借阅人的逾期借阅
+    let service = LoanService::new(Rc::clone(&conn));
+    let other = service.add_borrower("李四", "", "").unwrap();
+    let past = chrono::Utc::now().naive_utc() - Duration::days(30);
+    service.lend_file(other, file_id, "查档", past, past + Duration::days(7)).unwrap();
+    vm.show_overdue().unwrap();
+    assert_eq!(vm.mode, LoanListMode::Overdue);
+    assert_eq!(vm.loans.len(), 1);
+    assert!(vm.loan_items()[0].subtitle.starts_with("李四"));
+    assert!(vm.loan_items()[0].detail.contains("逾期未还"));
+
+    // 重新加载后仍选中原借阅人，并回到借阅历史
+    vm.load().unwrap();
+    assert_eq!(vm.selected_borrower().unwrap().name, "张三");
+    vm.select_borrower(0).unwrap();
+    assert_eq!(vm.mode, LoanListMode::History);
+    assert_eq!(vm.loans.len(), 1);
+
+    drop(vm);
+    drop(service);
+    drop(conn);
+    let _ = fs::remove_file(&db_path);
+}
@@ -0,0 +1,174 @@
+use fonds_pod_lib::persistence::sidecars::{self, Sidecar, SidecarDocument, SIDECAR_NAME};
+use fonds_pod_lib::persistence::{
+    establish_connection, with_transaction, schema_item_repository::SchemaItemRepository, FilesRepository, FondSchemasRepository, FondsRepository,
+    ItemChecksumsRepository, ItemsRepository, SchemaRepository, SequencesRepository, SeriesRepository,
+};
+use fonds_pod_lib::models::{file::File, fond::Fond, fond_schema::FondSchema, item::Item, schema::Schema, schema_item::SchemaItem, series::Series};
+use fonds_pod_lib::services::RecoveryService;
+use fonds_pod_lib::GenericRepository;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_sidecars_follow_repository_writes() {
+    let library = setup_test_dir("sidecars");
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    assert_eq!(sidecars::library_dir(&mut conn.borrow_mut()), Some(library.clone()));
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let mut files = FilesRepository::new(Rc::clone(&conn));
+    let file_id = files.create(File { series_id, file_no: "GA01-2025-01".into(), name: "通知".into(), ..Default::default() }).unwrap();
+    let mut items = ItemsRepository::new(Rc::clone(&conn));
+    let item_id = items.create(Item { file_id, item_no: "GA01-2025-01-001".into(), name: "正文".into(), ..Default::default() }).unwrap();
+
+    // 全宗 sidecar 包含系列，修改后立即更新
+    let fond_sidecar = SidecarDocument::read(&library.join("GA01").join(SIDECAR_NAME)).unwrap();
+    let Sidecar::Fond { series, .. } = fond_sidecar.sidecar else { panic!("expected a fond sidecar") };
+    assert_eq!(series[0].series_no, "GA01-2025");
+    let mut file = files.find_by_id(file_id).unwrap().unwrap();
+    file.name = "通知（修订）".into();
+    files.update(&file).unwrap();
+    let Sidecar::File { series_no, file } = SidecarDocument::read(&library.join("GA01/GA01-2025-01").join(SIDECAR_NAME)).unwrap().sidecar else {
+        panic!("expected a file sidecar")
+    };
+    assert_eq!((series_no.as_str(), file.name.as_str()), ("GA01-2025", "通知（修订）"));
+    assert!(file.updated_at.is_some());
+
+    let item_sidecar = library.join("GA01/GA01-2025-01/GA01-2025-01-001").join(SIDECAR_NAME);
+    assert!(item_sidecar.is_file());
+    items.delete(item_id).unwrap();
+    assert!(!item_sidecar.exists());
+    assert!(library.join(SIDECAR_NAME).is_file());
+
+    // 非档案库数据库不写 sidecar
+    let other = library.join("other.db");
+    let other_conn = establish_connection(&other).unwrap();
+    assert_eq!(sidecars::library_dir(&mut other_conn.borrow_mut()), None);
+
+    let _ = fs::remove_dir_all(&library);
+}
+
+#[test]
+fn test_library_sidecar_is_written_once_a_transaction_ends() {
+    let library = setup_test_dir("sidecars_transaction");
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let schema_nos = || {
+        let Sidecar::Library { schemas, .. } = SidecarDocument::read(&library.join(SIDECAR_NAME)).unwrap().sidecar else {
+            panic!("expected a library sidecar")
+        };
+        schemas.into_iter().map(|s| s.schema.schema_no).collect::<Vec<_>>()
+    };
+    SchemaRepository::new(Rc::clone(&conn)).create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() }).unwrap();
+    let mut expected = schema_nos();
+    assert!(expected.contains(&"D".to_string()));
+
+    // 事务中的写入不立即改写库级 sidecar，提交后写一次
+    with_transaction(&conn, || {
+        let mut schemas = SchemaRepository::new(Rc::clone(&conn));
+        schemas.create(Schema { schema_no: "Y".into(), name: "年度".into(), ..Default::default() })?;
+        schemas.create(Schema { schema_no: "R".into(), name: "保管期限".into(), ..Default::default() })?;
+        assert_eq!(schema_nos(), expected);
+        Ok(())
+    })
+    .unwrap();
+    expected.extend(["Y".to_string(), "R".to_string()]);
+    assert_eq!(schema_nos(), expected);
+
+    // 回滚后 sidecar 仍与数据库一致
+    let result: Result<(), _> = with_transaction(&conn, || {
+        SchemaRepository::new(Rc::clone(&conn)).create(Schema { schema_no: "X".into(), name: "临时".into(), ..Default::default() })?;
+        Err("rejected".into())
+    });
+    assert!(result.is_err());
+    assert_eq!(schema_nos(), expected);
+
+    drop(conn);
+    let _ = fs::remove_dir_all(&library);
+}
+
+#[test]
+fn test_rebuild_database_from_sidecars() {
+    let library = setup_test_dir("recovery_service");
+    {
+        let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+        let schema_id = SchemaRepository::new(Rc::clone(&conn))
+            .create(Schema { schema_no: "Retention".into(), name: "保管期限".into(), sort_order: 1, ..Default::default() })
+            .unwrap();
+        SchemaItemRepository::new(Rc::clone(&conn))
+            .create(SchemaItem { schema_id, item_no: "Y30".into(), item_name: "30年".into(), ..Default::default() })
+            .unwrap();
+        let fond_id = FondsRepository::new(Rc::clone(&conn))
+            .create(Fond { fond_no: "GA03".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+            .unwrap();
+        FondSchemasRepository::new(Rc::clone(&conn))
+            .create(FondSchema { fond_id, schema_id, sort_order: 0, ..Default::default() })
+            .unwrap();
+        let series_id = SeriesRepository::new(Rc::clone(&conn))
+            .create(Series { fond_id, series_no: "GA03-Y30".into(), name: "30年".into(), ..Default::default() })
+            .unwrap();
+        let file_id = FilesRepository::new(Rc::clone(&conn))
+            .create(File { series_id, file_no: "GA03-Y30-04".into(), name: "会议纪要".into(), path: Some("GA03/GA03-Y30-04".into()), ..Default::default() })
+            .unwrap();
+        let payload = library.join("GA03/GA03-Y30-04/GA03-Y30-04-002");
+        fs::create_dir_all(&payload).unwrap();
+        fs::write(payload.join("纪要.pdf"), "minutes").unwrap();
+        ItemsRepository::new(Rc::clone(&conn))
+            .create(Item {
+                file_id,
+                item_no: "GA03-Y30-04-002".into(),
+                name: "纪要".into(),
+                path: Some("GA03/GA03-Y30-04/GA03-Y30-04-002/纪要.pdf".into()),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    // 数据库损坏
+    fs::write(library.join(".fondspod.db"), "not a database").unwrap();
+    fs::create_dir_all(library.join("GA09")).unwrap();
+    fs::write(library.join("GA09").join(SIDECAR_NAME), "{").unwrap();
+
+    let service = RecoveryService::new(&library);
+    let scanned = service.scan().unwrap();
+    assert_eq!(scanned.fonds.len(), 1);
+    assert_eq!(scanned.problems.len(), 1);
+
+    let report = service.rebuild().unwrap();
+    assert_eq!((report.fonds, report.series, report.files, report.items), (1, 1, 1, 1));
+    assert_eq!(report.problems.len(), 1);
+    let previous = report.previous_database.unwrap();
+    assert_eq!(fs::read_to_string(previous).unwrap(), "not a database");
+
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let fond = FondsRepository::new(Rc::clone(&conn)).find_by_fond_no("GA03").unwrap().unwrap();
+    let dimensions = FondSchemasRepository::new(Rc::clone(&conn)).find_by_predicate(|fs| fs.fond_id == fond.id).unwrap();
+    let schema = SchemaRepository::new(Rc::clone(&conn)).find_by_id(dimensions[0].schema_id).unwrap().unwrap();
+    assert_eq!(schema.schema_no, "Retention");
+    let file = FilesRepository::new(Rc::clone(&conn)).find_by_file_no("GA03-Y30-04").unwrap().unwrap();
+    assert_eq!(file.name, "会议纪要");
+    let item = ItemsRepository::new(Rc::clone(&conn)).find_by_item_no("GA03-Y30-04-002").unwrap().unwrap();
+    assert_eq!(item.file_id, file.id);
+    assert_eq!(ItemChecksumsRepository::new(Rc::clone(&conn)).find_by_predicate(|c| c.item_id == item.id).unwrap().len(), 1);
+
+    // 计数器越过已恢复的编号
+    let mut sequences = SequencesRepository::new(Rc::clone(&conn));
+    assert_eq!(sequences.get_next_number("GA", Some(2)).unwrap(), "04");
+    assert_eq!(sequences.get_next_number("GA03-Y30", Some(2)).unwrap(), "05");
+    assert_eq!(sequences.get_next_number("GA03-Y30-04", Some(3)).unwrap(), "003");
+
+    let _ = fs::remove_dir_all(&library);
+}
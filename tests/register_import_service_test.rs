@@ -0,0 +1,221 @@
+use diesel::RunQueryDsl;
+use fonds_pod_lib::persistence::{establish_connection, FilesRepository, FondsRepository, ItemsRepository, SequencesRepository, SeriesRepository};
+use fonds_pod_lib::models::{app_settings::IngestMode, file::File, fond::Fond, series::Series};
+use fonds_pod_lib::services::xlsx::{Cell, Workbook};
+use fonds_pod_lib::services::{ChecksumAlgorithm, NumberMode, RegisterField, RegisterImportService, RegisterMapping, RegisterTable};
+use fonds_pod_lib::GenericRepository;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_dir(test_name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fondspod_test_{}", test_name));
+    if dir.exists() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Library with fond GA01, series GA01-2025 and file GA01-2025-01
+fn setup_library(library: &std::path::Path) -> (std::rc::Rc<std::cell::RefCell<diesel::SqliteConnection>>, i32) {
+    fs::create_dir_all(library).unwrap();
+    let conn = establish_connection(&library.join(".fondspod.db")).unwrap();
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    FilesRepository::new(Rc::clone(&conn))
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "年报".into(), ..Default::default() })
+        .unwrap();
+    (conn, fond_id)
+}
+
+#[test]
+fn test_preview_and_import_csv_keeping_numbers() {
+    let root = setup_test_dir("register_import");
+    let library = root.join("Archive");
+    let (conn, fond_id) = setup_library(&library);
+    let scan = root.join("scan.pdf");
+    fs::write(&scan, "scan").unwrap();
+
+    let register = root.join("register.csv");
+    fs::write(
+        &register,
+        format!(
+            "\u{feff}系列号,案卷号,案卷题名,文件号,文件题名,路径\r\n\
+             GA01-2025,GA01-2025-05,通知,GA01-2025-05-001,通知正文,{}\r\n\
+             GA01-2025,GA01-2025-05,通知,,\"附件, 一\",\r\n\
+             GA01-2026,,会议纪要,,纪要,\r\n\
+             ,GA01-2025-01,,,补充件,\r\n\
+             ,,,,,\r\n",
+            scan.display()
+        ),
+    )
+    .unwrap();
+
+    let table = RegisterTable::read(&register).unwrap();
+    let mapping = RegisterMapping::detect(&table.header);
+    assert_eq!(mapping.column(RegisterField::SeriesNo), Some(0));
+    assert_eq!(mapping.column(RegisterField::Path), Some(5));
+    assert_eq!(mapping.column(RegisterField::FondNo), None);
+
+    let service = RegisterImportService::new(Rc::clone(&conn), &library);
+    let preview = service.preview(&table, &mapping, NumberMode::Keep).unwrap();
+    assert!(preview.is_valid(), "{}", preview.to_csv());
+    assert_eq!(preview.rows.len(), 4);
+    assert_eq!((preview.new_files(), preview.new_items()), (2, 4));
+    assert!(preview.missing_series.contains("GA01-2026"));
+    assert_eq!(preview.rows[3].series_no, "GA01-2025");
+
+    // 由调用方按方案生成缺失的系列
+    let mut generated = Vec::new();
+    let report = service
+        .import(&table, &mapping, NumberMode::Keep, IngestMode::Copy, ChecksumAlgorithm::Sha256, |id| {
+            generated.push(id);
+            SeriesRepository::new(Rc::clone(&conn))
+                .create(Series { fond_id: id, series_no: "GA01-2026".into(), name: "2026".into(), ..Default::default() })
+                .map(|_| ())
+        })
+        .unwrap();
+    assert_eq!(generated, vec![fond_id]);
+    assert_eq!((report.series_generated, report.files_created, report.items_created), (1, 2, 4));
+
+    let mut items = ItemsRepository::new(Rc::clone(&conn));
+    let kept = items.find_by_item_no("GA01-2025-05-001").unwrap().unwrap();
+    assert_eq!(kept.path.as_deref(), Some("GA01/GA01-2025-05/GA01-2025-05-001/scan.pdf"));
+    assert_eq!(items.find_by_item_no("GA01-2025-05-002").unwrap().unwrap().name, "附件, 一");
+    assert!(items.find_by_item_no("GA01-2025-01-001").unwrap().is_some());
+    assert!(FilesRepository::new(Rc::clone(&conn)).find_by_file_no("GA01-2026-01").unwrap().is_some());
+    assert!(library.join("GA01/GA01-2025-05/GA01-2025-05-001/scan.pdf").is_file());
+    assert!(scan.exists());
+
+    // 保留的编号已推进计数器
+    let mut sequences = SequencesRepository::new(Rc::clone(&conn));
+    assert_eq!(sequences.get_next_number("GA01-2025", Some(2)).unwrap(), "06");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_invalid_rows_block_the_import() {
+    let root = setup_test_dir("register_import_errors");
+    let library = root.join("Archive");
+    let (conn, _) = setup_library(&library);
+
+    let table = RegisterTable {
+        header: ["全宗号", "系列号", "案卷号", "案卷题名", "文件号", "文件题名", "路径"].iter().map(|h| h.to_string()).collect(),
+        rows: vec![
+            vec!["", "GA01-2025", "GA01-2025-07", "", "", "无题名案卷", ""],
+            vec!["", "GA01-2025", "GA01-2025-X", "编号错误", "", "", ""],
+            vec!["", "GB09-2025", "", "未知全宗", "", "", ""],
+            vec!["GA02", "GA01-2025", "", "全宗不符", "", "", ""],
+            vec!["", "GA01-2025", "GA01-2025-08", "重复", "GA01-2025-08-001", "甲", "/nonexistent/scan.pdf"],
+            vec!["", "GA01-2025", "GA01-2025-08", "重复", "GA01-2025-08-001", "乙", ""],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(str::to_string).collect())
+        .collect(),
+    };
+    let mapping = RegisterMapping::detect(&table.header);
+    let service = RegisterImportService::new(Rc::clone(&conn), &library);
+    let preview = service.preview(&table, &mapping, NumberMode::Keep).unwrap();
+    assert!(!preview.is_valid());
+    let errors: Vec<String> = preview.rows.iter().map(|r| r.errors.join("; ")).collect();
+    assert!(errors[0].contains("案卷题名"));
+    assert!(errors[1].contains("GA01-2025-X"));
+    assert!(errors[2].contains("GB09-2025"));
+    assert!(errors[3].contains("GA02"));
+    assert!(errors[4].contains("路径不存在"));
+    assert!(errors[5].contains("已存在"));
+    assert!(preview.to_csv().starts_with("line,series_no,file_no,item_no,errors,notes\n2,"));
+
+    assert!(service.import(&table, &mapping, NumberMode::Keep, IngestMode::Copy, ChecksumAlgorithm::Sha256, |_| Ok(())).is_err());
+    assert_eq!(FilesRepository::new(conn).find_all().unwrap().len(), 1);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_import_xlsx_allocating_numbers() {
+    let root = setup_test_dir("register_import_xlsx");
+    let library = root.join("Archive");
+    let (conn, _) = setup_library(&library);
+
+    let register = root.join("register.xlsx");
+    let mut workbook = Workbook::new();
+    workbook.add_sheet(
+        "登记表",
+        vec!["案卷号".into(), "案卷题名".into(), "文件号".into(), "文件题名".into()],
+        vec![
+            vec![Cell::Text("GA01-2025-42".into()), Cell::Text("批复 & 通知".into()), Cell::Number(7.0), Cell::Text("批复".into())],
+            vec![Cell::Text("GA01-2025-42".into()), Cell::Text("批复 & 通知".into()), Cell::Text(String::new()), Cell::Text("通知".into())],
+        ],
+    );
+    workbook.save(&register).unwrap();
+
+    let table = RegisterTable::read(&register).unwrap();
+    assert_eq!(table.rows[0], vec!["GA01-2025-42", "批复 & 通知", "7", "批复"]);
+    assert_eq!(table.rows[1].len(), 4);
+
+    let mapping = RegisterMapping::detect(&table.header);
+    let service = RegisterImportService::new(Rc::clone(&conn), &library);
+    let preview = service.preview(&table, &mapping, NumberMode::Allocate).unwrap();
+    assert!(preview.is_valid(), "{}", preview.to_csv());
+    assert!(preview.rows[0].notes.iter().any(|n| n.contains("重新分配")));
+
+    let report = service.import(&table, &mapping, NumberMode::Allocate, IngestMode::Copy, ChecksumAlgorithm::Sha256, |_| Ok(())).unwrap();
+    assert_eq!((report.files_created, report.items_created), (1, 2));
+    // 新案卷从计数器取号，不沿用登记表中的 42
+    let file = FilesRepository::new(Rc::clone(&conn)).find_by_file_no("GA01-2025-02").unwrap().unwrap();
+    assert_eq!(file.name, "批复 & 通知");
+    let mut items = ItemsRepository::new(conn);
+    assert_eq!(items.find_by_item_no("GA01-2025-02-002").unwrap().unwrap().name, "通知");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_failed_import_rolls_back_rows_and_payloads() {
+    let root = setup_test_dir("register_import_rollback");
+    let library = root.join("Archive");
+    let (conn, _) = setup_library(&library);
+    let scan = root.join("scan.pdf");
+    fs::write(&scan, "scan").unwrap();
+    let register = root.join("register.csv");
+    fs::write(
+        &register,
+        format!(
+            "系列号,案卷号,案卷题名,文件题名,路径\n\
+             GA01-2025,GA01-2025-05,通知,通知正文,{}\n\
+             GA01-2026,GA01-2026-01,会议纪要,坏,\n",
+            scan.display()
+        ),
+    )
+    .unwrap();
+    // 模拟第二个文件写入失败
+    diesel::sql_query("CREATE TRIGGER reject_items BEFORE INSERT ON items WHEN NEW.name = '坏' BEGIN SELECT RAISE(ABORT, 'rejected'); END")
+        .execute(&mut *conn.borrow_mut())
+        .unwrap();
+
+    let table = RegisterTable::read(&register).unwrap();
+    let mapping = RegisterMapping::detect(&table.header);
+    let service = RegisterImportService::new(Rc::clone(&conn), &library);
+    let result = service.import(&table, &mapping, NumberMode::Keep, IngestMode::Move, ChecksumAlgorithm::Sha256, |id| {
+        SeriesRepository::new(Rc::clone(&conn))
+            .create(Series { fond_id: id, series_no: "GA01-2026".into(), name: "2026".into(), ..Default::default() })
+            .map(|_| ())
+    });
+    assert!(result.is_err());
+
+    // 生成的系列、案卷和文件都不保留，移动的原文件放回原处
+    assert_eq!(SeriesRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 1);
+    assert_eq!(FilesRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 1);
+    assert!(ItemsRepository::new(Rc::clone(&conn)).find_all().unwrap().is_empty());
+    assert!(scan.is_file());
+    assert!(!library.join("GA01/GA01-2025-05").exists());
+    assert!(!fs::read_to_string(library.join("GA01/.fondspod.json")).unwrap().contains("GA01-2026"));
+
+    let _ = fs::remove_dir_all(&root);
+}
@@ -0,0 +1,63 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    FondsRepository, SeriesRepository, FilesRepository, ItemsRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, series::Series, file::File, item::Item};
+use fonds_pod_lib::services::{ScanService, ScanTarget};
+use fonds_pod_lib::GenericRepository;
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_scan_resolve_and_stocktake() {
+    let db_path = setup_test_db("scan_service");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA01-2025".into(), name: "2025".into(), ..Default::default() })
+        .unwrap();
+    let mut files_repo = FilesRepository::new(Rc::clone(&conn));
+    let file1 = files_repo
+        .create(File { series_id, file_no: "GA01-2025-01".into(), name: "案卷一".into(), ..Default::default() })
+        .unwrap();
+    let _file2 = files_repo
+        .create(File { series_id, file_no: "GA01-2025-02".into(), name: "案卷二".into(), ..Default::default() })
+        .unwrap();
+    ItemsRepository::new(Rc::clone(&conn))
+        .create(Item { file_id: file1, item_no: "GA01-2025-01-001".into(), name: "文件一".into(), ..Default::default() })
+        .unwrap();
+
+    let service = ScanService::new(Rc::clone(&conn));
+
+    // 扫码枪输入通常带换行符
+    match service.resolve("GA01-2025-01\r\n").unwrap() {
+        Some(ScanTarget::File(file)) => assert_eq!(file.id, file1),
+        other => panic!("expected file, got {:?}", other),
+    }
+    assert!(matches!(service.resolve("ga01-2025-01-001").unwrap(), Some(ScanTarget::Item(_))));
+    assert!(matches!(service.resolve("GA01-2025").unwrap(), Some(ScanTarget::Series(_))));
+    assert!(matches!(service.resolve("GA01").unwrap(), Some(ScanTarget::Fond(_))));
+    assert!(service.resolve("NOPE").unwrap().is_none());
+
+    // 扫描文件条码也视为其所属案卷在架
+    let scans = vec!["GA01-2025-01-001".to_string(), "XX99".to_string()];
+    let report = service.stocktake(fond_id, None, &scans).unwrap();
+    assert_eq!(report.expected.len(), 2);
+    assert_eq!(report.found.len(), 1);
+    assert_eq!(report.missing.len(), 1);
+    assert_eq!(report.missing[0].file_no, "GA01-2025-02");
+    assert_eq!(report.unknown, vec!["XX99".to_string()]);
+    assert!(report.to_csv().contains("missing,GA01-2025-02,案卷二"));
+}
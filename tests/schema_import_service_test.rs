@@ -0,0 +1,192 @@
+use fonds_pod_lib::models::schema::{Schema, SchemaJson};
+use fonds_pod_lib::models::schema_item::{SchemaItem, SchemaItemJson};
+use fonds_pod_lib::persistence::schema_item_repository::SchemaItemRepository;
+use fonds_pod_lib::persistence::{establish_connection, SchemaRepository, TranslationsRepository};
+use fonds_pod_lib::services::{SchemaChange, SchemaImportService};
+use fonds_pod_lib::GenericRepository;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> std::path::PathBuf {
+    let path = std::path::PathBuf::from(format!(".fondspod_test_{}.db", test_name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn item(item_no: &str, item_name: &str) -> SchemaItemJson {
+    SchemaItemJson { item_no: item_no.into(), item_name: item_name.into(), parent_no: None, active: true, names: BTreeMap::new() }
+}
+
+fn child(item_no: &str, item_name: &str, parent_no: &str) -> SchemaItemJson {
+    SchemaItemJson { parent_no: Some(parent_no.into()), ..item(item_no, item_name) }
+}
+
+/// D 部门 { 01 办公室, 02 财务处 }
+fn seed(conn: &Rc<std::cell::RefCell<diesel::SqliteConnection>>) {
+    let d = SchemaRepository::new(Rc::clone(conn))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), sort_order: 1, ..Default::default() })
+        .unwrap();
+    let mut items = SchemaItemRepository::new(Rc::clone(conn));
+    items.create(SchemaItem { schema_id: d, item_no: "01".into(), item_name: "办公室".into(), ..Default::default() }).unwrap();
+    items.create(SchemaItem { schema_id: d, item_no: "02".into(), item_name: "财务处".into(), ..Default::default() }).unwrap();
+}
+
+#[test]
+fn test_export_and_merge_by_number() {
+    let db_path = setup_test_db("schema_import_merge");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = SchemaImportService::new(Rc::clone(&conn));
+
+    // Year 不导出
+    let exported = service.export().unwrap();
+    assert_eq!(exported, vec![SchemaJson {
+        schema_no: "D".into(),
+        name: "部门".into(),
+        sort_order: 1,
+        items: vec![item("01", "办公室"), item("02", "财务处")],
+    }]);
+    assert!(service.preview(&exported).unwrap().is_empty());
+
+    let mut english = item("02", "财务科");
+    english.names = BTreeMap::from([("en_US".to_string(), "Finance".to_string())]);
+    let imported = vec![
+        SchemaJson { schema_no: "D".into(), name: "部门".into(), sort_order: 1, items: vec![english, item("03", "档案室")] },
+        SchemaJson { schema_no: "P".into(), name: "保管期限".into(), sort_order: 2, items: vec![item("Y", "永久")] },
+    ];
+    let diff = service.apply(&imported).unwrap();
+    assert_eq!(
+        diff.changes,
+        vec![
+            SchemaChange::RenamedItem { schema_no: "D".into(), item_no: "02".into(), from: "财务处".into(), to: "财务科".into() },
+            SchemaChange::TranslatedItem { schema_no: "D".into(), item_no: "02".into(), locales: vec!["en_US".into()] },
+            SchemaChange::AddedItem { schema_no: "D".into(), item_no: "03".into(), name: "档案室".into() },
+            SchemaChange::AddedSchema { schema_no: "P".into(), name: "保管期限".into() },
+            SchemaChange::AddedItem { schema_no: "P".into(), item_no: "Y".into(), name: "永久".into() },
+        ]
+    );
+    assert_eq!(diff.unchanged, 1);
+
+    // 文件中没有的条目 01 被保留
+    let items = SchemaItemRepository::new(Rc::clone(&conn)).find_all().unwrap();
+    assert_eq!(items.len(), 4);
+    assert_eq!(TranslationsRepository::new(Rc::clone(&conn)).find_all_names("schema_items").unwrap().len(), 1);
+
+    // CSV 往返保持内容
+    let exported = service.export().unwrap();
+    let csv = SchemaImportService::to_csv(&exported);
+    assert!(csv.contains("item_name.en_US"));
+    assert_eq!(SchemaImportService::from_csv(&csv).unwrap(), exported);
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_reject_invalid_files_and_year_items() {
+    let db_path = setup_test_db("schema_import_invalid");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = SchemaImportService::new(Rc::clone(&conn));
+
+    let year = SchemaJson { schema_no: "Year".into(), name: "年度".into(), sort_order: 0, items: vec![item("2020", "2020")] };
+    assert!(service.apply(std::slice::from_ref(&year)).is_err());
+    // 没有条目的 Year 被忽略，不会改名
+    let diff = service.apply(&[SchemaJson { items: Vec::new(), ..year }]).unwrap();
+    assert!(diff.is_empty());
+    let names: Vec<String> = SchemaRepository::new(Rc::clone(&conn)).find_all().unwrap().into_iter().map(|s| s.name).collect();
+    assert!(names.contains(&"Year".to_string()));
+
+    let duplicate = SchemaJson { schema_no: "D".into(), name: "部门".into(), sort_order: 1, items: vec![item("01", "甲"), item("01", "乙")] };
+    assert!(service.preview(&[duplicate]).is_err());
+    assert!(SchemaImportService::from_csv("schema_no,schema_name,sort_order,item_no,item_name\nD,部门,1,01,甲\nD,部门二,1,02,乙\n").is_err());
+    assert!(SchemaImportService::from_csv("schema_no,item_no\nD,01\n").is_err());
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_import_order_and_deactivation() {
+    let db_path = setup_test_db("schema_import_active");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = SchemaImportService::new(Rc::clone(&conn));
+
+    // 01 在文件中停用；本地停用的条目不会被导入重新启用
+    let mut office = item("01", "办公室");
+    office.active = false;
+    let imported = vec![SchemaJson {
+        schema_no: "D".into(),
+        name: "部门".into(),
+        sort_order: 1,
+        items: vec![item("04", "后勤处"), office, item("03", "档案室")],
+    }];
+    let diff = service.apply(&imported).unwrap();
+    assert!(diff.changes.contains(&SchemaChange::DeactivatedItem { schema_no: "D".into(), item_no: "01".into() }));
+
+    // 新条目按文件顺序排在已有条目之后
+    let exported = service.export().unwrap();
+    let order: Vec<(&str, bool)> = exported[0].items.iter().map(|i| (i.item_no.as_str(), i.active)).collect();
+    assert_eq!(order, vec![("01", false), ("02", true), ("04", true), ("03", true)]);
+    let csv = SchemaImportService::to_csv(&exported);
+    assert_eq!(SchemaImportService::from_csv(&csv).unwrap(), exported);
+    assert!(service.preview(&[SchemaJson { items: vec![item("01", "办公室")], ..imported[0].clone() }]).unwrap().is_empty());
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_import_nested_items_by_parent_number() {
+    let db_path = setup_test_db("schema_import_nested");
+    let conn = establish_connection(&db_path).unwrap();
+    seed(&conn);
+    let service = SchemaImportService::new(Rc::clone(&conn));
+    let schema = |items| vec![SchemaJson { schema_no: "D".into(), name: "部门".into(), sort_order: 1, items }];
+
+    // 上下级关系不能成环；不存在的上级条目使整个导入失败
+    assert!(service.preview(&schema(vec![child("A", "甲", "B"), child("B", "乙", "A")])).is_err());
+    assert!(service.preview(&schema(vec![child("A", "甲", "A")])).is_err());
+    assert!(service.apply(&schema(vec![child("A", "甲", "99")])).is_err());
+    assert_eq!(SchemaItemRepository::new(Rc::clone(&conn)).find_all().unwrap().len(), 2);
+
+    // 上级条目可以在文件中排在后面，也可以是库中已有的条目；条目按编号路径匹配，
+    // 01 下的 02 是新条目，与顶级的 02 不同
+    let diff = service
+        .apply(&schema(vec![
+            child("C", "整理科", "03"),
+            child("A", "文书科", "01"),
+            child("B", "机要科", "01"),
+            child("02", "财务处", "01"),
+            item("03", "档案室"),
+            child("01", "收发室", "01.A"),
+        ]))
+        .unwrap();
+    assert!(diff.changes.contains(&SchemaChange::AddedItem { schema_no: "D".into(), item_no: "01.02".into(), name: "财务处".into() }));
+    assert!(diff.changes.contains(&SchemaChange::AddedItem { schema_no: "D".into(), item_no: "01.A.01".into(), name: "收发室".into() }));
+
+    // 按树的先序导出，上级条目用编号路径表示，CSV 往返保持不变
+    let exported = service.export().unwrap();
+    let tree: Vec<(&str, Option<&str>)> = exported[0].items.iter().map(|i| (i.item_no.as_str(), i.parent_no.as_deref())).collect();
+    assert_eq!(
+        tree,
+        vec![
+            ("01", None),
+            ("A", Some("01")),
+            ("01", Some("01.A")),
+            ("B", Some("01")),
+            ("02", Some("01")),
+            ("02", None),
+            ("03", None),
+            ("C", Some("03")),
+        ]
+    );
+    let csv = SchemaImportService::to_csv(&exported);
+    assert!(csv.lines().next().unwrap().ends_with(",parent_no"));
+    assert_eq!(SchemaImportService::from_csv(&csv).unwrap(), exported);
+    assert!(service.preview(&exported).unwrap().is_empty());
+
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
@@ -0,0 +1,213 @@
+use fonds_pod_lib::models::schema::Schema;
+use fonds_pod_lib::models::schema_item::SchemaItem;
+use fonds_pod_lib::persistence::schema_item_repository::SchemaItemRepository;
+use fonds_pod_lib::core::TreeMove;
+use fonds_pod_lib::persistence::{establish_connection, ActiveableRepository, HierarchicalRepository, SchemaRepository};
+use fonds_pod_lib::GenericRepository;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> std::path::PathBuf {
+    let path = std::path::PathBuf::from(format!(".fondspod_test_{}.db", test_name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn item_nos(items: &[SchemaItem]) -> Vec<&str> {
+    items.iter().map(|i| i.item_no.as_str()).collect()
+}
+
+#[test]
+fn test_schema_items_ordered_and_deactivated_within_schema() {
+    let db_path = setup_test_db("schema_item_order");
+    let conn = establish_connection(&db_path).unwrap();
+    let mut schemas = SchemaRepository::new(Rc::clone(&conn));
+    let d = schemas.create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() }).unwrap();
+    let p = schemas.create(Schema { schema_no: "P".into(), name: "保管期限".into(), ..Default::default() }).unwrap();
+
+    let mut repo = SchemaItemRepository::new(Rc::clone(&conn));
+    let mut ids = Vec::new();
+    for (schema_id, item_no) in [(d, "03"), (p, "Y"), (d, "01"), (d, "02")] {
+        let sort_order = repo.next_sort_order(schema_id).unwrap();
+        ids.push(repo.create(SchemaItem { schema_id, item_no: item_no.into(), item_name: item_no.into(), sort_order, ..Default::default() }).unwrap());
+    }
+    assert_eq!(item_nos(&repo.find_by_schema_id(d).unwrap()), vec!["03", "01", "02"]);
+
+    // 移动只影响同一方案的条目
+    repo.move_within_schema(ids[0], 2).unwrap();
+    repo.move_within_schema(ids[3], -10).unwrap();
+    assert_eq!(item_nos(&repo.find_by_schema_id(d).unwrap()), vec!["02", "01", "03"]);
+    assert_eq!(repo.find_by_id(ids[1]).unwrap().unwrap().sort_order, 0);
+
+    // 新条目默认启用；停用的条目保留，但不再用于生成新案卷
+    assert!(repo.find_all().unwrap().iter().all(|i| i.active));
+    repo.deactivate(ids[2]).unwrap();
+    assert_eq!(item_nos(&repo.find_active_by_schema_id(d).unwrap()), vec!["02", "03"]);
+    assert_eq!(repo.find_by_schema_id(d).unwrap().len(), 3);
+    repo.activate(ids[2]).unwrap();
+    assert_eq!(repo.find_active_by_schema_id(d).unwrap().len(), 3);
+
+    drop(repo);
+    drop(schemas);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_nested_items_expand_to_leaves_or_level() {
+    let db_path = setup_test_db("schema_item_tree");
+    let conn = establish_connection(&db_path).unwrap();
+    let mut schemas = SchemaRepository::new(Rc::clone(&conn));
+    let d = schemas.create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() }).unwrap();
+    let p = schemas.create(Schema { schema_no: "P".into(), name: "保管期限".into(), ..Default::default() }).unwrap();
+
+    // D: 01 办公室 { A 文书科, B 机要科 }, 02 财务处, 03 档案室 { C 整理科（停用） }
+    let mut repo = SchemaItemRepository::new(Rc::clone(&conn));
+    let mut create = |item_no: &str, item_name: &str, parent_id: Option<i32>, sort_order: i32| {
+        repo.create(SchemaItem { schema_id: d, item_no: item_no.into(), item_name: item_name.into(), parent_id, sort_order, ..Default::default() })
+            .unwrap()
+    };
+    let office = create("01", "办公室", None, 0);
+    let a = create("A", "文书科", Some(office), 0);
+    create("B", "机要科", Some(office), 1);
+    let finance = create("02", "财务处", None, 1);
+    let archive = create("03", "档案室", None, 2);
+    let c = create("C", "整理科", Some(archive), 0);
+    let y = repo.create(SchemaItem { schema_id: p, item_no: "Y".into(), item_name: "永久".into(), ..Default::default() }).unwrap();
+    repo.deactivate(c).unwrap();
+
+    let expanded = |repo: &mut SchemaItemRepository, root, level| -> Vec<String> {
+        repo.find_expanded(d, root, level).unwrap().into_iter().map(|i| i.item_no).collect()
+    };
+    assert_eq!(item_nos(&repo.find_by_schema_id(d).unwrap()), vec!["01", "A", "B", "02", "03", "C"]);
+    // 叶子条目按路径编号；档案室只有停用的下级条目，不生成案卷
+    assert_eq!(expanded(&mut repo, None, None), vec!["01.A", "01.B", "02"]);
+    assert_eq!(repo.find_expanded(d, None, None).unwrap()[0].item_name, "办公室/文书科");
+    assert_eq!(expanded(&mut repo, None, Some(0)), vec!["01", "02", "03"]);
+    // 指定条目时只展开其下级；比所选层级更深的条目只生成自身
+    assert_eq!(expanded(&mut repo, Some(office), None), vec!["01.A", "01.B"]);
+    assert_eq!(expanded(&mut repo, Some(a), Some(0)), vec!["01.A"]);
+    assert_eq!(repo.find_path_nos(office).unwrap(), vec!["01", "01.A", "01.B"]);
+    assert_eq!(item_nos(&repo.find_active_by_schema_id(d).unwrap()), vec!["01", "A", "B", "02", "03"]);
+
+    // 降级、升级只在同一方案内调整，不影响其他方案的顶级条目
+    repo.move_in_tree(finance, TreeMove::Indent).unwrap();
+    assert_eq!(expanded(&mut repo, None, None), vec!["01.A", "01.B", "01.02"]);
+    repo.move_in_tree(finance, TreeMove::Outdent).unwrap();
+    repo.move_in_tree(finance, TreeMove::Up).unwrap();
+    assert_eq!(item_nos(&repo.find_by_schema_id(d).unwrap()), vec!["02", "01", "A", "B", "03", "C"]);
+    assert_eq!(repo.find_by_id(y).unwrap().unwrap().sort_order, 0);
+    assert!(repo.move_to_parent(office, Some(a), 0).is_err());
+    assert!(repo.move_to_parent(y, Some(office), 0).is_err());
+
+    // 启用下级条目时连同上级条目
+    repo.deactivate_subtree(archive).unwrap();
+    repo.activate_with_ancestors(c).unwrap();
+    assert_eq!(expanded(&mut repo, None, None), vec!["02", "01.A", "01.B", "03.C"]);
+
+    drop(repo);
+    drop(schemas);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_path_numbers_do_not_collide() {
+    let db_path = setup_test_db("schema_item_paths");
+    let conn = establish_connection(&db_path).unwrap();
+    let d = SchemaRepository::new(Rc::clone(&conn))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() })
+        .unwrap();
+
+    // 1 → 12 和 11 → 2 直接拼接都是 112
+    let mut repo = SchemaItemRepository::new(Rc::clone(&conn));
+    let mut create = |item_no: &str, parent_id: Option<i32>| {
+        repo.create(SchemaItem { schema_id: d, item_no: item_no.into(), item_name: item_no.into(), parent_id, ..Default::default() })
+            .unwrap()
+    };
+    let one = create("1", None);
+    create("12", Some(one));
+    let eleven = create("11", None);
+    create("2", Some(eleven));
+    let leaves: Vec<String> = repo.find_expanded(d, None, None).unwrap().into_iter().map(|i| i.item_no).collect();
+    assert_eq!(leaves, vec!["1.12", "11.2"]);
+
+    // 编号中带分隔符造成的重复路径报错，而不是静默跳过案卷
+    let dotted = repo.create(SchemaItem { schema_id: d, item_no: "1.12".into(), item_name: "1.12".into(), ..Default::default() }).unwrap();
+    assert!(repo.find_expanded(d, None, None).unwrap_err().to_string().contains("1.12"));
+    repo.delete(dotted).unwrap();
+    assert_eq!(repo.find_expanded(d, None, None).unwrap().len(), 2);
+
+    drop(repo);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_item_numbers_unique_under_their_parent() {
+    let db_path = setup_test_db("schema_item_parent_scope");
+    let conn = establish_connection(&db_path).unwrap();
+    let d = SchemaRepository::new(Rc::clone(&conn))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() })
+        .unwrap();
+
+    // 不同上级下可以有相同编号的条目
+    let mut repo = SchemaItemRepository::new(Rc::clone(&conn));
+    let mut create = |item_no: &str, parent_id: Option<i32>| {
+        repo.create(SchemaItem { schema_id: d, item_no: item_no.into(), item_name: item_no.into(), parent_id, ..Default::default() })
+    };
+    let a = create("A", None).unwrap();
+    let b = create("B", None).unwrap();
+    create("01", Some(a)).unwrap();
+    create("01", Some(b)).unwrap();
+
+    // 同一上级下、以及顶级条目之间的编号仍然唯一
+    assert!(create("01", Some(a)).is_err());
+    assert!(create("A", None).is_err());
+
+    let leaves: Vec<String> = repo.find_expanded(d, None, None).unwrap().into_iter().map(|i| i.item_no).collect();
+    assert_eq!(leaves, vec!["A.01", "B.01"]);
+
+    drop(repo);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_rebuild_schema_items_unique_per_schema() {
+    use diesel::{sql_query, Connection, RunQueryDsl, SqliteConnection};
+
+    // 旧版本的条目编号在整个方案内唯一
+    let db_path = setup_test_db("schema_item_old_unique");
+    let mut old = SqliteConnection::establish(&db_path.to_string_lossy()).unwrap();
+    sql_query(
+        "CREATE TABLE schema_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            schema_id INTEGER NOT NULL,
+            item_no TEXT NOT NULL,
+            item_name TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_machine TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE (schema_id, item_no),
+            FOREIGN KEY (schema_id) REFERENCES schemas(id)
+        )",
+    )
+    .execute(&mut old)
+    .unwrap();
+    drop(old);
+
+    let conn = establish_connection(&db_path).unwrap();
+    let d = SchemaRepository::new(Rc::clone(&conn))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() })
+        .unwrap();
+    let mut repo = SchemaItemRepository::new(Rc::clone(&conn));
+    let a = repo.create(SchemaItem { schema_id: d, item_no: "A".into(), item_name: "A".into(), ..Default::default() }).unwrap();
+    let b = repo.create(SchemaItem { schema_id: d, item_no: "B".into(), item_name: "B".into(), ..Default::default() }).unwrap();
+    repo.create(SchemaItem { schema_id: d, item_no: "01".into(), item_name: "01".into(), parent_id: Some(a), ..Default::default() }).unwrap();
+    repo.create(SchemaItem { schema_id: d, item_no: "01".into(), item_name: "01".into(), parent_id: Some(b), ..Default::default() }).unwrap();
+    assert!(repo.create(SchemaItem { schema_id: d, item_no: "A".into(), item_name: "A".into(), ..Default::default() }).is_err());
+
+    drop(repo);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
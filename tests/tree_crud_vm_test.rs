@@ -0,0 +1,78 @@
+use fonds_pod_lib::core::{TreeCrudViewModel, TreeMove, TreeScope};
+use fonds_pod_lib::persistence::{establish_connection, FondClassificationsRepository};
+use fonds_pod_lib::{models::fond_classification::FondClassification, GenericRepository};
+use slint::Model;
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+fn classification(code: &str) -> FondClassification {
+    FondClassification {
+        code: code.to_string(),
+        name: code.to_string(),
+        active: true,
+        ..Default::default()
+    }
+}
+
+fn titles(vm: &TreeCrudViewModel<FondClassification, FondClassificationsRepository>) -> Vec<String> {
+    vm.items.iter().map(|item| item.title.to_string()).collect()
+}
+
+#[test]
+fn test_tree_crud_view_model() {
+    let db_path = setup_test_db("tree_crud_vm");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let repo = Rc::new(RefCell::new(FondClassificationsRepository::new(conn)));
+
+    // Level 范围：添加的记录排在同级最后
+    let tops = TreeCrudViewModel::new(Rc::clone(&repo), TreeScope::Level(None));
+    let mut a = classification("A");
+    tops.add(&mut a);
+    tops.add(&mut classification("B"));
+    assert!(a.id > 0);
+    assert_eq!(titles(&tops), vec!["A", "B"]);
+
+    // Subtree 范围：第 0 行是根记录本身，下级记录按深度缩进
+    let tree = TreeCrudViewModel::new(Rc::clone(&repo), TreeScope::Subtree(a.id));
+    let a1 = tree.add_child(0, classification("A1")).unwrap();
+    tree.add_child(0, classification("A2")).unwrap();
+    tree.add_child(tree.index_of(a1).unwrap(), classification("A11")).unwrap();
+    assert_eq!(titles(&tree), vec!["A", "└ A1", "　└ A11", "└ A2"]);
+    assert_eq!(tree.depth_at(2), Some(2));
+
+    // 降一级：A2 归入 A1，排在 A11 之后；再上移到 A11 之前
+    let (top_id, a2) = tree.move_node(3, TreeMove::Indent).unwrap();
+    assert_eq!(top_id, a.id);
+    assert_eq!(titles(&tree), vec!["A", "└ A1", "　└ A11", "　└ A2"]);
+    tree.move_node(tree.index_of(a2).unwrap(), TreeMove::Up).unwrap();
+    assert_eq!(titles(&tree), vec!["A", "└ A1", "　└ A2", "　└ A11"]);
+    assert!(tree.move_node(0, TreeMove::Outdent).is_err());
+
+    // 停用带下级记录，启用带上级记录
+    tree.deactivate(a1);
+    assert!(repo.borrow_mut().find_all().unwrap().iter().filter(|c| c.code.starts_with("A1") || c.code == "A2").all(|c| !c.active));
+    tree.activate(a2);
+    assert!(repo.borrow_mut().find_by_id(a1).unwrap().unwrap().active);
+    assert!(!repo.borrow_mut().find_by_predicate(|c| c.code == "A11").unwrap()[0].active);
+
+    // 删除带下级记录
+    assert_eq!(tree.descendant_count(1), 2);
+    assert_eq!(tree.delete_subtree(1).unwrap(), 3);
+    assert_eq!(titles(&tree), vec!["A"]);
+
+    tops.load();
+    tops.delete(0).unwrap();
+    assert_eq!(titles(&tops), vec!["B"]);
+
+    let _ = fs::remove_file(&db_path);
+}
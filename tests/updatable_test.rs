@@ -0,0 +1,68 @@
+use fonds_pod_lib::persistence::{
+    establish_connection,
+    fond_classification_repository::FondClassificationsRepository,
+    FondsRepository,
+};
+use fonds_pod_lib::models::{fond::Fond, fond_classification::FondClassification};
+use fonds_pod_lib::{ActiveableRepository, GenericRepository, Updatable, UpdatableRepository};
+use std::path::Path;
+use std::fs;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+fn setup_test_db(test_name: &str) -> String {
+    let path = format!(".fondspod_test_{}.db", test_name);
+    if Path::new(&path).exists() {
+        let _ = fs::remove_file(&path);
+    }
+    path
+}
+
+#[test]
+fn test_update_stamps_and_recently_changed() {
+    let db_path = setup_test_db("updatable");
+    let conn = establish_connection(Path::new(&db_path)).unwrap();
+    let mut fonds_repo = FondsRepository::new(Rc::clone(&conn));
+
+    let first = fonds_repo
+        .create(Fond { fond_no: "GA01".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    thread::sleep(Duration::from_millis(5));
+    let second = fonds_repo
+        .create(Fond { fond_no: "GA02".into(), fond_classification_code: "GA".into(), name: "体育局".into(), ..Default::default() })
+        .unwrap();
+
+    // 新建记录没有修改信息
+    let fond = fonds_repo.find_by_id(first).unwrap().unwrap();
+    assert!(fond.updated_at.is_none());
+    assert_eq!(fond.change_summary(), "");
+    assert_eq!(fond.last_changed_at(), fond.created_at);
+
+    // 修改后自动填写，调用方传入的值被忽略
+    thread::sleep(Duration::from_millis(5));
+    let mut fond = fond;
+    fond.name = "文化和旅游局".into();
+    fond.updated_by = Some("forged".into());
+    fonds_repo.update(&fond).unwrap();
+    let fond = fonds_repo.find_by_id(first).unwrap().unwrap();
+    assert!(fond.updated_at.unwrap() > fond.created_at);
+    assert_ne!(fond.updated_by.as_deref(), Some("forged"));
+    assert!(fond.updated_machine.is_some());
+    assert!(fond.change_summary().starts_with('✎'));
+
+    // 最近变动：修改过的记录排在前面
+    let recent = fonds_repo.find_recently_changed(1).unwrap();
+    assert_eq!(recent[0].id, first);
+    let since = fonds_repo.find_by_id(second).unwrap().unwrap().created_at;
+    let changed: Vec<i32> = fonds_repo.find_changed_since(since).unwrap().iter().map(|f| f.id).collect();
+    assert_eq!(changed, vec![first, second]);
+
+    // 激活/停用同样记录修改信息
+    let mut classifications = FondClassificationsRepository::new(Rc::clone(&conn));
+    let id = classifications
+        .create(FondClassification { code: "GA".into(), name: "文化".into(), active: true, ..Default::default() })
+        .unwrap();
+    classifications.deactivate(id).unwrap();
+    assert!(classifications.find_by_id(id).unwrap().unwrap().updated_at.is_some());
+}
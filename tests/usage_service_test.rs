@@ -0,0 +1,254 @@
+use fonds_pod_lib::models::fond::Fond;
+use fonds_pod_lib::models::fond_classification::FondClassification;
+use fonds_pod_lib::models::fond_schema::FondSchema;
+use fonds_pod_lib::models::schema::Schema;
+use fonds_pod_lib::models::schema_item::SchemaItem;
+use fonds_pod_lib::models::series::Series;
+use fonds_pod_lib::models::{file::File, item::Item, series_schema_item::SeriesSchemaItem};
+use fonds_pod_lib::persistence::schema_item_repository::SchemaItemRepository;
+use fonds_pod_lib::persistence::{
+    establish_connection, FilesRepository, FondClassificationsRepository, FondSchemasRepository, FondsRepository, ItemsRepository,
+    SchemaRepository, SeriesRepository, SeriesSchemaItemsRepository,
+};
+use fonds_pod_lib::services::{LoanService, Usage, UsageService};
+use fonds_pod_lib::GenericRepository;
+use std::rc::Rc;
+
+fn setup_test_db(test_name: &str) -> std::path::PathBuf {
+    let path = std::path::PathBuf::from(format!(".fondspod_test_{}.db", test_name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_schema_and_item_deletes_are_checked() {
+    let db_path = setup_test_db("usage_schemas");
+    let conn = establish_connection(&db_path).unwrap();
+    let mut schemas = SchemaRepository::new(Rc::clone(&conn));
+    let d = schemas.create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() }).unwrap();
+    let p = schemas.create(Schema { schema_no: "P".into(), name: "保管期限".into(), ..Default::default() }).unwrap();
+    let mut items = SchemaItemRepository::new(Rc::clone(&conn));
+    let office = items.create(SchemaItem { schema_id: d, item_no: "01".into(), item_name: "办公室".into(), ..Default::default() }).unwrap();
+    let finance = items.create(SchemaItem { schema_id: d, item_no: "02".into(), item_name: "财务处".into(), ..Default::default() }).unwrap();
+    items.create(SchemaItem { schema_id: p, item_no: "Y".into(), item_name: "永久".into(), ..Default::default() }).unwrap();
+
+    // 全宗 GA0001 以 D 为唯一维度，已生成案卷 GA0001-01
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA0001".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    FondSchemasRepository::new(Rc::clone(&conn)).create(FondSchema { fond_id, schema_id: d, ..Default::default() }).unwrap();
+    SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA0001-01".into(), name: "办公室".into(), ..Default::default() })
+        .unwrap();
+
+    let service = UsageService::new(Rc::clone(&conn));
+    let used = Usage { fonds: vec!["GA0001".into()], series: vec!["GA0001-01".into()] };
+    assert_eq!(service.schema_usage(d).unwrap(), used);
+    assert_eq!(service.schema_item_usage(office).unwrap(), used);
+    assert!(service.schema_item_usage(finance).unwrap().is_empty());
+    assert!(service.schema_usage(p).unwrap().is_empty());
+
+    // 仍被使用的方案和条目不能删除，仓储层也拒绝删除被全宗使用的方案
+    assert!(service.delete_schema(d).unwrap_err().to_string().contains("GA0001"));
+    assert!(schemas.delete(d).is_err());
+    assert!(service.delete_schema_item(office).unwrap_err().to_string().contains("GA0001-01"));
+
+    // 未使用的条目可以删除；未使用的方案连同条目一起删除
+    service.delete_schema_item(finance).unwrap();
+    assert_eq!(service.delete_schema(p).unwrap(), 1);
+    let remaining: Vec<String> = items.find_all().unwrap().into_iter().map(|i| i.item_no).collect();
+    assert_eq!(remaining, vec!["01".to_string()]);
+
+    // 外键在每个连接上都生效
+    assert!(items.create(SchemaItem { schema_id: 999, item_no: "X".into(), item_name: "X".into(), ..Default::default() }).is_err());
+
+    drop(items);
+    drop(schemas);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_classification_delete_checked_or_remapped_to_parent() {
+    let db_path = setup_test_db("usage_classifications");
+    let conn = establish_connection(&db_path).unwrap();
+    let mut classifications = FondClassificationsRepository::new(Rc::clone(&conn));
+    let b = classifications
+        .create(FondClassification { code: "B".into(), name: "经济".into(), active: true, ..Default::default() })
+        .unwrap();
+    let ba = classifications
+        .create(FondClassification { code: "BA".into(), name: "财政".into(), parent_id: Some(b), active: true, ..Default::default() })
+        .unwrap();
+    let mut fonds = FondsRepository::new(Rc::clone(&conn));
+    fonds
+        .create(Fond { fond_no: "BA0001".into(), fond_classification_code: "BA".into(), name: "财政局".into(), ..Default::default() })
+        .unwrap();
+
+    let service = UsageService::new(Rc::clone(&conn));
+    assert_eq!(service.classification_usage(b).unwrap().fonds, vec!["BA0001".to_string()]);
+    assert!(service.delete_classification(b).is_err());
+    assert!(service.remap_and_delete_classification(b, ba).is_err());
+
+    assert_eq!(service.remap_and_delete_classification(ba, b).unwrap(), 1);
+    assert_eq!(fonds.find_by_fond_no("BA0001").unwrap().unwrap().fond_classification_code, "B");
+    assert!(classifications.find_by_id(ba).unwrap().is_none());
+
+    // 没有全宗时可以删除
+    let fond_id = fonds.find_by_fond_no("BA0001").unwrap().unwrap().id;
+    fonds.delete(fond_id).unwrap();
+    assert_eq!(service.delete_classification(b).unwrap(), 1);
+
+    drop(fonds);
+    drop(classifications);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_nested_item_usage_follows_path_numbers() {
+    let db_path = setup_test_db("usage_nested_items");
+    let conn = establish_connection(&db_path).unwrap();
+    let d = SchemaRepository::new(Rc::clone(&conn))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() })
+        .unwrap();
+    let mut items = SchemaItemRepository::new(Rc::clone(&conn));
+    let office = items.create(SchemaItem { schema_id: d, item_no: "01".into(), item_name: "办公室".into(), ..Default::default() }).unwrap();
+    let a = items
+        .create(SchemaItem { schema_id: d, item_no: "A".into(), item_name: "文书科".into(), parent_id: Some(office), ..Default::default() })
+        .unwrap();
+    let b = items
+        .create(SchemaItem { schema_id: d, item_no: "B".into(), item_name: "机要科".into(), parent_id: Some(office), ..Default::default() })
+        .unwrap();
+
+    // 案卷 GA0001-01.A 使用文书科，也就使用了其上级办公室
+    let mut fonds = FondsRepository::new(Rc::clone(&conn));
+    let fond_id = fonds
+        .create(Fond { fond_no: "GA0001".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let mut fond_schemas = FondSchemasRepository::new(Rc::clone(&conn));
+    let fond_schema_id = fond_schemas.create(FondSchema { fond_id, schema_id: d, ..Default::default() }).unwrap();
+    let mut series = SeriesRepository::new(Rc::clone(&conn));
+    let series_id = series
+        .create(Series { fond_id, series_no: "GA0001-01.A".into(), name: "办公室/文书科".into(), ..Default::default() })
+        .unwrap();
+
+    let service = UsageService::new(Rc::clone(&conn));
+    assert_eq!(service.schema_item_usage(a).unwrap().series, vec!["GA0001-01.A".to_string()]);
+    assert_eq!(service.schema_item_usage(office).unwrap().series, vec!["GA0001-01.A".to_string()]);
+    assert!(service.schema_item_usage(b).unwrap().is_empty());
+    assert!(service.delete_schema_item(office).is_err());
+    assert_eq!(service.delete_schema_item(b).unwrap(), 1);
+
+    // 不再使用后，方案连同多级条目一起删除（下级条目先于上级删除）
+    series.delete(series_id).unwrap();
+    fond_schemas.delete(fond_schema_id).unwrap();
+    fonds.delete(fond_id).unwrap();
+    assert_eq!(service.delete_schema(d).unwrap(), 2);
+    assert!(items.find_all().unwrap().is_empty());
+
+    drop(items);
+    drop(fonds);
+    drop(fond_schemas);
+    drop(series);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_catalogue_deletes_cascade_and_keep_loaned_records() {
+    let db_path = setup_test_db("usage_catalogue");
+    let conn = establish_connection(&db_path).unwrap();
+    let d = SchemaRepository::new(Rc::clone(&conn))
+        .create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() })
+        .unwrap();
+    let mut fonds = FondsRepository::new(Rc::clone(&conn));
+    let fond_id = fonds
+        .create(Fond { fond_no: "GA0001".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    FondSchemasRepository::new(Rc::clone(&conn)).create(FondSchema { fond_id, schema_id: d, ..Default::default() }).unwrap();
+    let series_id = SeriesRepository::new(Rc::clone(&conn))
+        .create(Series { fond_id, series_no: "GA0001-01".into(), name: "办公室".into(), ..Default::default() })
+        .unwrap();
+    let mut files = FilesRepository::new(Rc::clone(&conn));
+    let file_id = files.create(File { series_id, file_no: "GA0001-01-01".into(), name: "案卷一".into(), ..Default::default() }).unwrap();
+    let loaned_file = files.create(File { series_id, file_no: "GA0001-01-02".into(), name: "案卷二".into(), ..Default::default() }).unwrap();
+    let mut items = ItemsRepository::new(Rc::clone(&conn));
+    for no in ["001", "002"] {
+        items.create(Item { file_id, item_no: format!("GA0001-01-01-{}", no), name: no.into(), ..Default::default() }).unwrap();
+    }
+    let loans = LoanService::new(Rc::clone(&conn));
+    let borrower = loans.add_borrower("张三", "办公室", "").unwrap();
+    let now = chrono::Utc::now().naive_utc();
+    loans.lend_file(borrower, loaned_file, "查阅", now, now).unwrap();
+
+    let service = UsageService::new(Rc::clone(&conn));
+    let contents = service.fond_contents(fond_id).unwrap();
+    assert_eq!(contents.describe(), "1 个系列、2 个案卷、2 个文件");
+    assert_eq!(contents.loans, 1);
+
+    // 有借阅记录的记录及其上级不能删除，外键错误不会直接暴露给用户
+    assert!(service.delete_file(loaned_file).unwrap_err().to_string().contains("借阅记录"));
+    assert!(service.delete_fond(fond_id).unwrap_err().to_string().contains("借阅记录"));
+    assert_eq!(items.find_all().unwrap().len(), 2);
+
+    // 案卷连同文件一起删除
+    assert_eq!(service.file_contents(file_id).unwrap().items.len(), 2);
+    assert_eq!(service.delete_file(file_id).unwrap().items.len(), 2);
+    assert!(items.find_all().unwrap().is_empty());
+    assert!(files.find_by_id(file_id).unwrap().is_none());
+
+    drop(items);
+    drop(files);
+    drop(fonds);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_item_usage_is_structural() {
+    let db_path = setup_test_db("usage_structural");
+    let conn = establish_connection(&db_path).unwrap();
+    let mut schemas = SchemaRepository::new(Rc::clone(&conn));
+    let d = schemas.create(Schema { schema_no: "D".into(), name: "部门".into(), ..Default::default() }).unwrap();
+    let p = schemas.create(Schema { schema_no: "P".into(), name: "保管期限".into(), ..Default::default() }).unwrap();
+    let year = schemas.find_all().unwrap().into_iter().find(|s| s.schema_no == "Year").unwrap().id;
+    let mut items = SchemaItemRepository::new(Rc::clone(&conn));
+    let a1 = items.create(SchemaItem { schema_id: d, item_no: "A-1".into(), item_name: "一科".into(), ..Default::default() }).unwrap();
+    let a = items.create(SchemaItem { schema_id: d, item_no: "A".into(), item_name: "A".into(), ..Default::default() }).unwrap();
+    let permanent = items.create(SchemaItem { schema_id: p, item_no: "Y".into(), item_name: "永久".into(), ..Default::default() }).unwrap();
+    let fond_id = FondsRepository::new(Rc::clone(&conn))
+        .create(Fond { fond_no: "GA0001".into(), fond_classification_code: "GA".into(), name: "文化局".into(), ..Default::default() })
+        .unwrap();
+    let mut fond_schemas = FondSchemasRepository::new(Rc::clone(&conn));
+    fond_schemas.create(FondSchema { fond_id, schema_id: year, sort_order: 0, ..Default::default() }).unwrap();
+    fond_schemas.create(FondSchema { fond_id, schema_id: d, sort_order: 1, ..Default::default() }).unwrap();
+    let mut series = SeriesRepository::new(Rc::clone(&conn));
+
+    // 没有生成记录的案卷按全宗维度的取值匹配，条目编号中的 '-' 不影响匹配
+    series.create(Series { fond_id, series_no: "GA0001-2025-A-1".into(), name: "2025-一科".into(), ..Default::default() }).unwrap();
+    let service = UsageService::new(Rc::clone(&conn));
+    assert_eq!(service.schema_item_usage(a1).unwrap().series, vec!["GA0001-2025-A-1".to_string()]);
+    assert!(service.schema_item_usage(a).unwrap().is_empty());
+
+    // 有生成记录的案卷以记录为准，全宗维度事后改变也不影响
+    let linked = series.create(Series { fond_id, series_no: "GA0001-2025-A-Y".into(), name: "2025-A-永久".into(), ..Default::default() }).unwrap();
+    let mut links = SeriesSchemaItemsRepository::new(Rc::clone(&conn));
+    links.create(SeriesSchemaItem { series_id: linked, schema_item_id: a, ..Default::default() }).unwrap();
+    links.create(SeriesSchemaItem { series_id: linked, schema_item_id: permanent, ..Default::default() }).unwrap();
+    assert_eq!(service.schema_item_usage(a).unwrap().series, vec!["GA0001-2025-A-Y".to_string()]);
+    assert_eq!(service.schema_item_usage(permanent).unwrap().series, vec!["GA0001-2025-A-Y".to_string()]);
+    assert!(service.delete_schema_item(permanent).is_err());
+
+    // 无法匹配的案卷编号视为可能使用该全宗维度方案的任何条目
+    series.create(Series { fond_id, series_no: "GA0001-手工编号".into(), name: "手工".into(), ..Default::default() }).unwrap();
+    let b = items.create(SchemaItem { schema_id: d, item_no: "B".into(), item_name: "B".into(), ..Default::default() }).unwrap();
+    assert_eq!(service.schema_item_usage(b).unwrap().series, vec!["GA0001-手工编号".to_string()]);
+
+    drop(links);
+    drop(series);
+    drop(fond_schemas);
+    drop(items);
+    drop(schemas);
+    drop(conn);
+    let _ = std::fs::remove_file(&db_path);
+}